@@ -1,5 +1,6 @@
-use std::{net::IpAddr, path::PathBuf};
+use std::{net::IpAddr, path::PathBuf, time::Duration};
 
+use ambient_network::link_conditioner::LinkConditions;
 use clap::{Args, Parser, Subcommand};
 
 pub mod new_project;
@@ -31,6 +32,8 @@ pub enum Commands {
         host_args: HostCli,
         #[command(flatten)]
         run_args: RunCli,
+        #[command(flatten)]
+        link_args: LinkConditionerCli,
     },
     /// Builds the project
     Build {
@@ -58,6 +61,8 @@ pub enum Commands {
         project_args: ProjectCli,
         #[command(flatten)]
         host_args: HostCli,
+        #[command(flatten)]
+        link_args: LinkConditionerCli,
     },
     /// View an asset
     View {
@@ -70,9 +75,34 @@ pub enum Commands {
     Join {
         #[command(flatten)]
         run_args: RunCli,
+        #[command(flatten)]
+        link_args: LinkConditionerCli,
+        /// The server to connect to; defaults to localhost
+        host: Option<String>,
+    },
+    /// Connect headless bots to a server, to load test it
+    Bots {
+        #[command(flatten)]
+        bots_args: BotsCli,
+        #[command(flatten)]
+        link_args: LinkConditionerCli,
         /// The server to connect to; defaults to localhost
         host: Option<String>,
     },
+    /// Play back a session recorded by a server started with `--record`
+    Replay {
+        /// The recording to play back
+        file: PathBuf,
+        /// The player whose messages are played back; defaults to the first one in the recording
+        #[arg(short, long)]
+        user_id: Option<String>,
+        /// Where to load the assets from, if the server which recorded the session is not running
+        #[arg(long)]
+        content_base_url: Option<String>,
+        /// If set, show a debugger that can be used to investigate the state of the replay
+        #[arg(short, long)]
+        debugger: bool,
+    },
     /// Asset manipulation and migration
     Assets {
         #[command(subcommand)]
@@ -122,6 +152,39 @@ pub struct RunCli {
     #[clap(short, long)]
     pub user_id: Option<String>,
 
+    /// The credential to present to the server when joining, such as an authentication token
+    #[clap(long)]
+    pub credential: Option<String>,
+
+    /// Specify a trusted certificate authority
+    #[arg(long)]
+    pub ca: Option<PathBuf>,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct BotsCli {
+    /// How many bots to connect
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub count: usize,
+
+    /// How many seconds to run for; runs until interrupted if not specified
+    #[arg(long)]
+    pub duration: Option<u64>,
+
+    /// The bots join with this prefix followed by their number as their user ID
+    #[arg(long, default_value = "bot")]
+    pub user_id_prefix: String,
+
+    /// The credential to present to the server when joining, such as an authentication token.
+    /// `{i}` is replaced by the number of the bot
+    #[clap(long)]
+    pub credential: Option<String>,
+
+    /// File with one credential per line, of which each bot presents the one on the line of its
+    /// number
+    #[arg(long, conflicts_with("credential"))]
+    pub credentials_file: Option<PathBuf>,
+
     /// Specify a trusted certificate authority
     #[arg(long)]
     pub ca: Option<PathBuf>,
@@ -179,6 +242,115 @@ pub struct HostCli {
     /// Private key for the certificate
     #[arg(long)]
     pub key: Option<PathBuf>,
+
+    /// Directory to periodically save a snapshot of the world to
+    ///
+    /// If a snapshot already exists in this directory, it is loaded when the server starts
+    #[arg(long)]
+    pub save_dir: Option<PathBuf>,
+    /// How often to save the world snapshot, in seconds
+    #[arg(long, default_value_t = 60., value_parser = parse_seconds)]
+    pub save_interval: f32,
+
+    /// File containing a secret shared with the service which issues credentials to clients
+    ///
+    /// Clients must join with a credential signed with this secret (see `SharedSecretVerifier`)
+    #[arg(long, conflicts_with_all(["auth_jwt_key", "auth_module"]))]
+    pub auth_secret_file: Option<PathBuf>,
+    /// Public key (PEM or DER) used to verify the JSON Web Tokens clients must join with
+    #[arg(long, conflicts_with("auth_module"))]
+    pub auth_jwt_key: Option<PathBuf>,
+    /// Let the modules of the project verify the credentials of clients
+    #[arg(long)]
+    pub auth_module: bool,
+
+    /// Compress the world diffs sent to the clients with zstd at this level (1 to 22)
+    ///
+    /// Trades server CPU time for bandwidth
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..=22))]
+    pub diff_compression: Option<i32>,
+
+    /// How long to keep the player of a client whose connection was lost, in seconds
+    ///
+    /// If the client reconnects within this time, it resumes controlling the same player entity
+    #[arg(long, default_value_t = 30., value_parser = parse_seconds)]
+    pub reconnect_grace_period: f32,
+
+    /// Record the session to this file, to be played back with `ambient replay`
+    #[arg(long)]
+    pub record: Option<PathBuf>,
+
+    /// How many times per second the server steps the world
+    #[arg(long, default_value_t = 60.)]
+    pub tick_rate: f32,
+
+    /// How long a server module can run for when handling a single message, in milliseconds
+    ///
+    /// Modules which run for longer, such as those stuck in an infinite loop, are unloaded
+    #[arg(long, default_value_t = 1000)]
+    pub wasm_max_exec_time: u64,
+    /// How much memory each server module can use, in MiB
+    ///
+    /// Modules which try to use more are unloaded
+    #[arg(long, default_value_t = 512)]
+    pub wasm_max_memory: usize,
+
+    /// Directory the server modules store their data in, which is kept across restarts
+    ///
+    /// Defaults to `storage` in the project directory, if the project is local
+    #[arg(long)]
+    pub storage_dir: Option<PathBuf>,
+    /// How much data each ember can store, in MiB
+    ///
+    /// Defaults to no limit other than the disk
+    #[arg(long)]
+    pub storage_limit: Option<u64>,
+
+    /// A built ember of type `Mod` to load when the server starts, as a path or a URL. Can be repeated
+    #[arg(long = "mod")]
+    pub mods: Vec<String>,
+
+    /// A URL that mods can send HTTP requests to, if their `[http]` allowlist asks for it too. Can be repeated
+    #[arg(long)]
+    pub mod_http_allow: Vec<String>,
+}
+
+/// Simulates a bad network link; each endpoint delays, reorders and drops the traffic it receives
+#[derive(Args, Clone, Debug)]
+pub struct LinkConditionerCli {
+    /// Delay added to every datagram and stream frame received, in milliseconds
+    #[arg(long, default_value_t = 0)]
+    pub sim_latency: u64,
+    /// Maximum random delay added on top of the latency, in milliseconds
+    #[arg(long, default_value_t = 0)]
+    pub sim_jitter: u64,
+    /// Chance of a received datagram being dropped, from 0 to 1
+    #[arg(long, default_value_t = 0.)]
+    pub sim_loss: f32,
+    /// Chance of a received datagram being held back so that later ones overtake it, from 0 to 1
+    #[arg(long, default_value_t = 0.)]
+    pub sim_reorder: f32,
+}
+
+impl LinkConditionerCli {
+    /// The simulated conditions, if any were requested
+    pub fn link_conditions(&self) -> Option<LinkConditions> {
+        let conditions = LinkConditions {
+            latency: Duration::from_millis(self.sim_latency),
+            jitter: Duration::from_millis(self.sim_jitter),
+            loss: self.sim_loss,
+            reorder: self.sim_reorder,
+        };
+        (!conditions.is_ideal()).then_some(conditions)
+    }
+}
+
+/// Parses a number of seconds which can be turned into a [Duration]
+fn parse_seconds(value: &str) -> Result<f32, String> {
+    let seconds = value.parse::<f32>().map_err(|err| err.to_string())?;
+    Duration::try_from_secs_f32(seconds)
+        .map_err(|_| format!("{value} is not a valid number of seconds"))?;
+    Ok(seconds)
 }
 
 impl Cli {
@@ -193,6 +365,8 @@ impl Cli {
             Commands::Serve { .. } => None,
             Commands::View { .. } => None,
             Commands::Join { run_args, .. } => Some(run_args),
+            Commands::Bots { .. } => None,
+            Commands::Replay { .. } => None,
             Commands::Assets { .. } => None,
         }
     }
@@ -207,9 +381,21 @@ impl Cli {
             Commands::Serve { project_args, .. } => Some(project_args),
             Commands::View { project_args, .. } => Some(project_args),
             Commands::Join { .. } => None,
+            Commands::Bots { .. } => None,
+            Commands::Replay { .. } => None,
             Commands::Assets { .. } => None,
         }
     }
+    /// Extract the simulated network conditions, if any
+    pub fn link_conditions(&self) -> Option<LinkConditions> {
+        match &self.command {
+            Commands::Run { link_args, .. } => link_args.link_conditions(),
+            Commands::Serve { link_args, .. } => link_args.link_conditions(),
+            Commands::Join { link_args, .. } => link_args.link_conditions(),
+            Commands::Bots { link_args, .. } => link_args.link_conditions(),
+            _ => None,
+        }
+    }
     /// Extract host-relevant state only
     pub fn host(&self) -> Option<&HostCli> {
         match &self.command {
@@ -221,6 +407,8 @@ impl Cli {
             Commands::Serve { host_args, .. } => Some(host_args),
             Commands::View { .. } => None,
             Commands::Join { .. } => None,
+            Commands::Bots { .. } => None,
+            Commands::Replay { .. } => None,
             Commands::Assets { .. } => None,
         }
     }
@@ -0,0 +1,211 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use ambient_input::PlayerRawInput;
+use ambient_network::{
+    link_conditioner::LinkConditions,
+    native::{
+        bot::{Bot, BotConfig, BotStats},
+        client::ResolvedAddr,
+    },
+};
+use ambient_shared_types::VirtualKeyCode;
+use ambient_std::{asset_cache::AssetCache, to_byte_unit};
+use anyhow::Context;
+use parking_lot::Mutex;
+
+use super::load_cert;
+use crate::cli::BotsCli;
+
+/// How often the bots send their input
+const INPUT_INTERVAL: Duration = Duration::from_millis(33);
+/// How long a bot walks in one direction
+const WANDER_INTERVAL: u32 = 60;
+/// Spreads out the connections, so that the bots don't all join at once
+const CONNECT_INTERVAL: Duration = Duration::from_millis(20);
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Connects bots to the server which wander around until the time is up or the process is
+/// interrupted, and periodically logs their aggregated statistics
+pub async fn run(
+    assets: AssetCache,
+    server_addr: ResolvedAddr,
+    args: &BotsCli,
+    link_conditions: Option<LinkConditions>,
+) -> anyhow::Result<()> {
+    let cert = load_cert(args.ca.as_deref());
+    let credentials = credentials(args)?;
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stats = Arc::new(Mutex::new(vec![None::<BotStats>; args.count]));
+
+    tracing::info!("Connecting {} bots to {server_addr:?}", args.count);
+    let mut tasks = Vec::new();
+    for (index, credential) in credentials.into_iter().enumerate() {
+        let config = BotConfig {
+            server_addr: server_addr.clone(),
+            cert: cert.clone(),
+            user_id: format!("{}_{index}", args.user_id_prefix),
+            credential,
+            link_conditions,
+        };
+
+        tasks.push(tokio::spawn(run_bot(
+            assets.clone(),
+            config,
+            index,
+            stopped.clone(),
+            stats.clone(),
+        )));
+        tokio::time::sleep(CONNECT_INTERVAL).await;
+    }
+
+    let report = async {
+        let mut interval = tokio::time::interval(REPORT_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            report_stats(&stats.lock());
+        }
+    };
+    let duration = async {
+        match args.duration {
+            Some(secs) => tokio::time::sleep(Duration::from_secs(secs)).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        _ = report => {}
+        _ = duration => {}
+        result = tokio::signal::ctrl_c() => result?,
+    }
+
+    stopped.store(true, Ordering::Relaxed);
+    let mut failed = 0;
+    for task in tasks {
+        if let Err(err) = task.await? {
+            tracing::error!("{err:?}");
+            failed += 1;
+        }
+    }
+    report_stats(&stats.lock());
+
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} bots failed", args.count);
+    }
+    Ok(())
+}
+
+/// The credential of every bot, from either the credentials file or the credential template
+fn credentials(args: &BotsCli) -> anyhow::Result<Vec<Option<String>>> {
+    if let Some(path) = &args.credentials_file {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read credentials from {path:?}"))?;
+        let credentials = contents
+            .lines()
+            .map(|line| Some(line.trim().to_string()))
+            .take(args.count)
+            .collect::<Vec<_>>();
+        anyhow::ensure!(
+            credentials.len() == args.count,
+            "{path:?} has {} credentials, but there are {} bots",
+            credentials.len(),
+            args.count
+        );
+        return Ok(credentials);
+    }
+
+    Ok((0..args.count)
+        .map(|index| {
+            args.credential
+                .as_ref()
+                .map(|credential| credential.replace("{i}", &index.to_string()))
+        })
+        .collect())
+}
+
+async fn run_bot(
+    assets: AssetCache,
+    config: BotConfig,
+    index: usize,
+    stopped: Arc<AtomicBool>,
+    stats: Arc<Mutex<Vec<Option<BotStats>>>>,
+) -> anyhow::Result<()> {
+    let mut bot = Bot::connect(assets, config).await?;
+    let mut interval = tokio::time::interval(INPUT_INTERVAL);
+
+    let mut frame = 0;
+    while !stopped.load(Ordering::Relaxed) {
+        interval.tick().await;
+        if !bot.is_connected() {
+            anyhow::bail!("Bot {:?} lost its connection", bot.user_id());
+        }
+
+        bot.send_input(wander(index, frame), INPUT_INTERVAL.as_secs_f32())
+            .await?;
+        // Nothing is done with the messages, but they are counted
+        bot.messages();
+        stats.lock()[index] = Some(bot.stats());
+        frame += 1;
+    }
+
+    bot.disconnect().await
+}
+
+/// Walks in a different direction every few seconds
+fn wander(index: usize, frame: u32) -> PlayerRawInput {
+    const DIRECTIONS: [&[VirtualKeyCode]; 4] = [
+        &[VirtualKeyCode::W],
+        &[VirtualKeyCode::A, VirtualKeyCode::W],
+        &[VirtualKeyCode::S],
+        &[VirtualKeyCode::D],
+    ];
+
+    let direction = (index + (frame / WANDER_INTERVAL) as usize) % DIRECTIONS.len();
+    PlayerRawInput {
+        keys: DIRECTIONS[direction].iter().copied().collect(),
+        ..Default::default()
+    }
+}
+
+fn report_stats(stats: &[Option<BotStats>]) {
+    let connected = stats.iter().flatten().collect::<Vec<_>>();
+    let network = connected
+        .iter()
+        .filter_map(|stats| stats.network.as_ref())
+        .collect::<Vec<_>>();
+
+    let average_latency = if network.is_empty() {
+        0
+    } else {
+        network.iter().map(|stats| stats.latency_ms).sum::<u64>() / network.len() as u64
+    };
+    let bytes_sent = network.iter().map(|stats| stats.bytes_sent).sum::<u64>();
+    let bytes_received = network
+        .iter()
+        .map(|stats| stats.bytes_received)
+        .sum::<u64>();
+    let max_entities = connected
+        .iter()
+        .map(|stats| stats.remote_entities)
+        .max()
+        .unwrap_or_default();
+    let messages = connected
+        .iter()
+        .map(|stats| stats.messages_received)
+        .sum::<usize>();
+
+    tracing::info!(
+        "{}/{} bots connected, {average_latency} ms average rtt, {}/s out, {}/s in, \
+         {max_entities} entities, {messages} messages received",
+        connected.len(),
+        stats.len(),
+        to_byte_unit(bytes_sent),
+        to_byte_unit(bytes_received),
+    );
+}
@@ -1,4 +1,9 @@
-use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use ambient_app::{fps_stats, window_title, AppBuilder};
 use ambient_cameras::UICamera;
@@ -18,6 +23,7 @@ use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
 use ambient_network::{
     client::{client_network_stats, GameClient, GameClientRenderTarget, GameClientWorld},
     hooks::use_remote_resource,
+    link_conditioner::LinkConditions,
     native::client::{GameClientView, ResolvedAddr},
 };
 use ambient_shared_types::CursorIcon;
@@ -36,7 +42,9 @@ use crate::{
 use ambient_ecs_editor::{ECSEditor, InspectableAsyncWorld};
 use ambient_layout::{docking, margin, padding, width, Borders};
 
+pub mod bots;
 pub mod player;
+pub mod replay;
 mod wasm;
 
 /// Construct an app and enter the main client view
@@ -44,12 +52,14 @@ pub async fn run(
     assets: AssetCache,
     server_addr: ResolvedAddr,
     run: &RunCli,
+    link_conditions: Option<LinkConditions>,
     golden_image_output_dir: Option<PathBuf>,
 ) -> ExitStatus {
     let user_id = run
         .user_id
         .clone()
         .unwrap_or_else(|| format!("user_{}", friendly_id()));
+    let credential = run.credential.clone();
     let headless = if run.headless {
         Some(uvec2(600, 600))
     } else {
@@ -58,24 +68,7 @@ pub async fn run(
 
     let is_debug = std::env::var("AMBIENT_DEBUGGER").is_ok() || run.debugger;
 
-    let cert = if let Some(ca) = &run.ca {
-        match std::fs::read(ca) {
-            Ok(v) => Some(v),
-            Err(err) => {
-                tracing::error!("Failed to load certificate from file: {}", err);
-                None
-            }
-        }
-    } else {
-        #[cfg(not(feature = "no_bundled_certs"))]
-        {
-            Some(super::CERT.to_vec())
-        }
-        #[cfg(feature = "no_bundled_certs")]
-        {
-            None
-        }
-    };
+    let cert = load_cert(run.ca.as_deref());
 
     AppBuilder::new()
         .ui_renderer(true)
@@ -87,6 +80,8 @@ pub async fn run(
             MainApp {
                 server_addr,
                 user_id,
+                credential,
+                link_conditions,
                 show_debug: is_debug,
                 golden_image_cmd: run.golden_image,
                 golden_image_output_dir,
@@ -98,6 +93,28 @@ pub async fn run(
         .await
 }
 
+/// Loads the trusted certificate authority, if specified, or the bundled certificate
+pub(crate) fn load_cert(ca: Option<&Path>) -> Option<Vec<u8>> {
+    if let Some(ca) = ca {
+        match std::fs::read(ca) {
+            Ok(v) => Some(v),
+            Err(err) => {
+                tracing::error!("Failed to load certificate from file: {}", err);
+                None
+            }
+        }
+    } else {
+        #[cfg(not(feature = "no_bundled_certs"))]
+        {
+            Some(super::CERT.to_vec())
+        }
+        #[cfg(feature = "no_bundled_certs")]
+        {
+            None
+        }
+    }
+}
+
 #[element_component]
 fn TitleUpdater(hooks: &mut Hooks) -> Element {
     let (net, _) = use_remote_resource(hooks, client_network_stats()).expect("No game client");
@@ -129,6 +146,8 @@ fn MainApp(
     server_addr: ResolvedAddr,
     golden_image_output_dir: Option<PathBuf>,
     user_id: String,
+    credential: Option<String>,
+    link_conditions: Option<LinkConditions>,
     show_debug: bool,
     golden_image_cmd: Option<GoldenImageCommand>,
     cert: Option<Vec<u8>>,
@@ -141,6 +160,8 @@ fn MainApp(
         WindowSized::el([GameClientView {
             server_addr,
             user_id,
+            credential,
+            link_conditions,
             on_loaded: cb(move |client| {
                 let mut game_state = client.game_state.lock();
                 let world = &mut game_state.world;
@@ -154,26 +175,7 @@ fn MainApp(
                     log::info!("Disconnecting client");
                 }))
             }),
-            systems_and_resources: cb(|| {
-                let mut resources = Entity::new();
-
-                let bistream_handlers = HashMap::new();
-                resources.set(
-                    ambient_network::client::bi_stream_handlers(),
-                    bistream_handlers,
-                );
-
-                let unistream_handlers = HashMap::new();
-                resources.set(
-                    ambient_network::client::uni_stream_handlers(),
-                    unistream_handlers,
-                );
-
-                let dgram_handlers = HashMap::new();
-                resources.set(ambient_network::client::datagram_handlers(), dgram_handlers);
-
-                (systems(), resources)
-            }),
+            systems_and_resources: cb(systems_and_resources),
             cert,
             create_rpc_registry: cb(shared::create_server_rpc_registry),
             inner: Dock::el(vec![
@@ -534,6 +536,33 @@ fn GameView(hooks: &mut Hooks, show_debug: bool) -> Element {
     ])
 }
 
+fn systems_and_resources() -> (SystemGroup, Entity) {
+    let mut resources = Entity::new();
+
+    let bistream_handlers = HashMap::new();
+    resources.set(
+        ambient_network::client::bi_stream_handlers(),
+        bistream_handlers,
+    );
+
+    let unistream_handlers = HashMap::new();
+    resources.set(
+        ambient_network::client::uni_stream_handlers(),
+        unistream_handlers,
+    );
+
+    let dgram_handlers = HashMap::new();
+    resources.set(ambient_network::client::datagram_handlers(), dgram_handlers);
+
+    let replayed_unistream_handlers = HashMap::new();
+    resources.set(
+        ambient_network::client::replayed_uni_stream_handlers(),
+        replayed_unistream_handlers,
+    );
+
+    (systems(), resources)
+}
+
 fn systems() -> SystemGroup {
     SystemGroup::new(
         "client",
@@ -545,6 +574,7 @@ fn systems() -> SystemGroup {
             Box::new(ambient_water::systems()),
             Box::new(ambient_physics::client_systems()),
             Box::new(wasm::systems()),
+            Box::new(ambient_world_audio::systems::audio_bus_systems()),
             Box::new(player::systems_final()),
         ],
     )
@@ -0,0 +1,254 @@
+use std::{fs::File, io::BufReader, path::Path, sync::Arc, time::Duration};
+
+use ambient_app::{window_title, AppBuilder};
+use ambient_cameras::UICamera;
+use ambient_core::{asset_cache, dtime, gpu, window::ExitStatus};
+use ambient_ecs::{world_events, World};
+use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
+use ambient_layout::{docking, padding, Borders, Docking};
+use ambient_network::{
+    client::{GameClient, GameClientRenderTarget},
+    client_game_state::ClientGameState,
+    replay::{ReplayConnection, SessionReplay},
+};
+use ambient_renderer::RenderTarget;
+use ambient_shared_types::VirtualKeyCode;
+use ambient_std::{
+    asset_cache::{AssetCache, SyncAssetKeyExt},
+    asset_url::{AbsAssetUrl, ContentBaseUrlKey},
+};
+use ambient_ui_native::{
+    space_between_items, Button, ButtonStyle, Dock, FlowRow, FocusRoot, FontAwesomeIcon, Text,
+    UIExt, WindowSized, STREET,
+};
+use anyhow::Context;
+use glam::{uvec2, vec4};
+use parking_lot::Mutex;
+
+use super::{systems_and_resources, wasm, GameView};
+use crate::shared;
+
+/// How far the seek buttons jump
+const SEEK_STEP: Duration = Duration::from_secs(5);
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 8.0;
+
+/// Construct an app which plays back a recorded session
+pub async fn run(
+    assets: AssetCache,
+    path: &Path,
+    user_id: Option<String>,
+    content_base_url: Option<String>,
+    debugger: bool,
+) -> anyhow::Result<ExitStatus> {
+    let file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let mut replay = SessionReplay::read(BufReader::new(file))
+        .with_context(|| format!("Failed to read the recording {path:?}"))?;
+
+    let content_base_url = match content_base_url {
+        Some(url) => AbsAssetUrl::parse(url).context("Invalid content base url")?,
+        None => replay.server_info().content_base_url.clone(),
+    };
+    ContentBaseUrlKey.insert(&assets, content_base_url);
+
+    if user_id.is_some() {
+        replay.user_id = user_id;
+    }
+    tracing::info!(
+        "Replaying {:?} as {:?}, recorded players: {:?}",
+        replay.duration(),
+        replay.user_id,
+        replay.user_ids()
+    );
+
+    let title = format!("{} (replay)", replay.server_info().project_name);
+    let show_debug = std::env::var("AMBIENT_DEBUGGER").is_ok() || debugger;
+
+    Ok(AppBuilder::new()
+        .ui_renderer(true)
+        .with_asset_cache(assets)
+        .run(move |app, _runtime| {
+            *app.world.resource_mut(window_title()) = title;
+            ReplayApp {
+                replay: Arc::new(Mutex::new(replay)),
+                show_debug,
+            }
+            .el()
+            .spawn_interactive(&mut app.world);
+        })
+        .await)
+}
+
+#[element_component]
+fn ReplayApp(hooks: &mut Hooks, replay: Arc<Mutex<SessionReplay>>, show_debug: bool) -> Element {
+    let gpu = hooks.world.resource(gpu()).clone();
+
+    hooks.provide_context(|| {
+        GameClientRenderTarget(Arc::new(RenderTarget::new(&gpu, uvec2(1, 1), None)))
+    });
+    let (render_target, _) = hooks.consume_context::<GameClientRenderTarget>().unwrap();
+
+    // There is no server to talk to; the client only sees what the recording holds
+    let assets = hooks.world.resource(asset_cache()).clone();
+    let game_client = hooks.use_ref_with(|world| {
+        let user_id = replay.lock().user_id.clone().unwrap_or_default();
+        let (systems, resources) = systems_and_resources();
+        let mut game_state = ClientGameState::new(
+            &gpu,
+            world,
+            assets.clone(),
+            user_id.clone(),
+            render_target.0.clone(),
+            systems,
+            resources,
+        );
+
+        wasm::initialize(&mut game_state.world).unwrap();
+        UICamera.el().spawn_static(&mut game_state.world);
+
+        GameClient::new(
+            Arc::new(ReplayConnection),
+            Arc::new(shared::create_server_rpc_registry()),
+            Arc::new(Mutex::new(game_state)),
+            user_id,
+        )
+    });
+    let game_client = game_client.lock().clone();
+    hooks.provide_context(|| game_client.clone());
+
+    {
+        let game_state = game_client.game_state.clone();
+        let replay = replay.clone();
+        let world_event_reader = Mutex::new(hooks.world.resource(world_events()).reader());
+
+        hooks.use_frame(move |app_world| {
+            let mut game_state = game_state.lock();
+
+            // Pipe events from app world to game world
+            for (_, event) in world_event_reader
+                .lock()
+                .iter(app_world.resource(world_events()))
+            {
+                game_state
+                    .world
+                    .resource_mut(world_events())
+                    .add_event(event.clone());
+            }
+
+            let dt = Duration::from_secs_f32(*app_world.resource(dtime()));
+            replay.lock().update(&mut game_state.world, dt);
+            game_state.on_frame(&gpu, &render_target.0);
+        });
+    }
+
+    FocusRoot::el([
+        UICamera.el(),
+        WindowSized::el([Dock::el([
+            ReplayControls {
+                replay,
+                game_client,
+            }
+            .el()
+            .with(docking(), Docking::Bottom)
+            .with_background(vec4(0., 0., 0., 1.))
+            .with(padding(), Borders::even(STREET).into()),
+            GameView { show_debug }.el(),
+        ])]),
+    ])
+}
+
+#[element_component]
+fn ReplayControls(
+    hooks: &mut Hooks,
+    replay: Arc<Mutex<SessionReplay>>,
+    game_client: GameClient,
+) -> Element {
+    let status = |replay: &SessionReplay| (replay.time().as_secs(), replay.speed, replay.paused);
+
+    // Only re-render when what is shown changes
+    let (shown, set_shown) = hooks.use_state(status(&replay.lock()));
+    hooks.use_frame({
+        let replay = replay.clone();
+        move |_| {
+            let current = status(&replay.lock());
+            if current != shown {
+                set_shown(current);
+            }
+        }
+    });
+    let (time, speed, paused) = shown;
+    let duration = replay.lock().duration().as_secs();
+
+    let seek = {
+        let replay = replay.clone();
+        move |forward: bool| {
+            let replay = replay.clone();
+            let game_state = game_client.game_state.clone();
+            move |_: &mut World| {
+                // Locked in the same order as when playing
+                let mut game_state = game_state.lock();
+                let mut replay = replay.lock();
+                let time = if forward {
+                    replay.time() + SEEK_STEP
+                } else {
+                    replay.time().saturating_sub(SEEK_STEP)
+                };
+                replay.seek(&mut game_state.world, time);
+            }
+        }
+    };
+    let set_speed = |factor: f32| {
+        let replay = replay.clone();
+        move |_: &mut World| {
+            let mut replay = replay.lock();
+            replay.speed = (replay.speed * factor).clamp(MIN_SPEED, MAX_SPEED);
+        }
+    };
+
+    FlowRow::el([
+        Button::new(FontAwesomeIcon::el(0xf04a, true), seek(false))
+            .hotkey(VirtualKeyCode::Left)
+            .tooltip("Back 5 seconds")
+            .style(ButtonStyle::Flat)
+            .el(),
+        Button::new(
+            FontAwesomeIcon::el(if paused { 0xf04b } else { 0xf04c }, true),
+            {
+                let replay = replay.clone();
+                move |_| {
+                    let mut replay = replay.lock();
+                    replay.paused = !replay.paused;
+                }
+            },
+        )
+        .hotkey(VirtualKeyCode::Space)
+        .tooltip(if paused { "Play" } else { "Pause" })
+        .style(ButtonStyle::Flat)
+        .el(),
+        Button::new(FontAwesomeIcon::el(0xf04e, true), seek(true))
+            .hotkey(VirtualKeyCode::Right)
+            .tooltip("Forward 5 seconds")
+            .style(ButtonStyle::Flat)
+            .el(),
+        Button::new(FontAwesomeIcon::el(0xf068, true), set_speed(0.5))
+            .hotkey(VirtualKeyCode::Down)
+            .tooltip("Slower")
+            .style(ButtonStyle::Flat)
+            .el(),
+        Button::new(FontAwesomeIcon::el(0xf067, true), set_speed(2.0))
+            .hotkey(VirtualKeyCode::Up)
+            .tooltip("Faster")
+            .style(ButtonStyle::Flat)
+            .el(),
+        Text::el(format!(
+            "{} / {}  {speed}x",
+            format_time(time),
+            format_time(duration)
+        )),
+    ])
+    .with(space_between_items(), STREET)
+}
+
+fn format_time(secs: u64) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -206,6 +206,18 @@ async fn load_manifest(
     }
 }
 
+/// Resolves the server to connect to, which defaults to localhost
+async fn resolve_host(host: Option<String>) -> anyhow::Result<ResolvedAddr> {
+    if let Some(mut host) = host {
+        if !host.contains(':') {
+            host = format!("{host}:{QUIC_INTERFACE_PORT}");
+        }
+        ResolvedAddr::lookup_host(&host).await
+    } else {
+        Ok(ResolvedAddr::localhost_with_port(QUIC_INTERFACE_PORT))
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     setup_logging()?;
@@ -258,6 +270,36 @@ async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    if let Commands::Replay {
+        file,
+        user_id,
+        content_base_url,
+        debugger,
+    } = &cli.command
+    {
+        let exit_status = client::replay::run(
+            assets,
+            file,
+            user_id.clone(),
+            content_base_url.clone(),
+            *debugger,
+        )
+        .await?;
+        if exit_status == ExitStatus::FAILURE {
+            bail!("client::replay::run failed with {exit_status:?}");
+        }
+
+        return Ok(());
+    }
+
+    if let Commands::Bots {
+        bots_args, host, ..
+    } = &cli.command
+    {
+        let server_addr = resolve_host(host.clone()).await?;
+        return client::bots::run(assets, server_addr, bots_args, cli.link_conditions()).await;
+    }
+
     // If a project was specified, assume that assets need to be built
     let manifest = match project {
         Some(_) => Some(load_manifest(&assets, &project_path).await?),
@@ -341,15 +383,9 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // Otherwise, either connect to a server or host one
+    let mut server_task = None;
     let server_addr = if let Commands::Join { host, .. } = &cli.command {
-        if let Some(mut host) = host.clone() {
-            if !host.contains(':') {
-                host = format!("{host}:{QUIC_INTERFACE_PORT}");
-            }
-            ResolvedAddr::lookup_host(&host).await?
-        } else {
-            ResolvedAddr::localhost_with_port(QUIC_INTERFACE_PORT)
-        }
+        resolve_host(host.clone()).await?
     } else if let Some(host) = &cli.host() {
         let crypto = if let (Some(cert_file), Some(key_file)) = (&host.cert, &host.key) {
             let raw_cert = std::fs::read(cert_file).context("Failed to read certificate file")?;
@@ -390,7 +426,7 @@ async fn main() -> anyhow::Result<()> {
             }
         };
 
-        let addr = server::start(
+        let (addr, task) = server::start(
             &runtime,
             assets.clone(),
             cli.clone(),
@@ -399,7 +435,8 @@ async fn main() -> anyhow::Result<()> {
             metadata.as_ref().expect("no build metadata"),
             crypto,
         )
-        .await;
+        .await?;
+        server_task = Some(task);
 
         ResolvedAddr::localhost_with_port(addr.port())
     } else {
@@ -410,10 +447,22 @@ async fn main() -> anyhow::Result<()> {
 
     if let Some(run) = cli.run() {
         // If we have run parameters, start a client and join a server
-        let exit_status = client::run(assets, server_addr, run, project_path.fs_path).await;
+        let exit_status = client::run(
+            assets,
+            server_addr,
+            run,
+            cli.link_conditions(),
+            project_path.fs_path,
+        )
+        .await;
         if exit_status == ExitStatus::FAILURE {
             bail!("client::run failed with {exit_status:?}");
         }
+    } else if let Some(server_task) = server_task {
+        // Otherwise, wait for the server to shut down on Ctrl+C or SIGTERM, and save the world
+        server_task
+            .await
+            .context("The server stopped unexpectedly")?;
     } else {
         // Otherwise, wait for the Ctrl+C signal
         match tokio::signal::ctrl_c().await {
@@ -1,8 +1,10 @@
 use std::{
     collections::HashMap,
+    fs::File,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::Path,
     sync::Arc,
+    time::Duration,
 };
 
 use ambient_core::{abs_time, app_start_time, asset_cache, dtime, name, no_sync, project_name};
@@ -11,10 +13,21 @@ use ambient_ecs::{
     World, WorldEventsSystem, WorldStreamCompEvent,
 };
 use ambient_network::{
-    native::server::{Crypto, GameServer},
+    auth::{ConnectionVerifier, ModuleVerifier},
+    codec::Compression,
+    native::{
+        auth::{JwtVerifier, SharedSecretVerifier},
+        server::{Crypto, GameServer},
+    },
     persistent_resources,
-    server::{ForkingEvent, ProxySettings, ShutdownEvent},
+    replay::SessionRecorder,
+    server::{
+        connection_verifier, diff_compression, link_conditions, reconnect_grace_period,
+        session_recorder, ForkingEvent, ProxySettings, ShutdownEvent, MAIN_INSTANCE_ID,
+    },
     synced_resources,
+    tick::tick_rate,
+    ServerWorldExt,
 };
 use ambient_prefab::PrefabFromUrl;
 use ambient_std::{
@@ -22,13 +35,23 @@ use ambient_std::{
     asset_url::{AbsAssetUrl, ServerBaseUrlKey},
 };
 use ambient_sys::{task::RuntimeHandle, time::SystemTime};
+use ambient_wasm::{
+    server::{
+        mods::mod_loader,
+        storage::{storage_dir, storage_limit},
+    },
+    shared::{module_limits, ModuleLimits},
+};
 use anyhow::Context;
 use axum::{
+    extract::Path as UrlPath,
     http::{Method, StatusCode},
     response::IntoResponse,
     routing::{get, get_service},
     Router,
 };
+use parking_lot::Mutex;
+use tokio::task::JoinHandle;
 use tower_http::{cors::CorsLayer, services::ServeDir};
 
 use crate::{
@@ -36,6 +59,8 @@ use crate::{
     shared,
 };
 
+mod mods;
+mod snapshot;
 pub mod wasm;
 
 pub async fn start(
@@ -46,8 +71,29 @@ pub async fn start(
     manifest: &ambient_project::Manifest,
     metadata: &ambient_build::Metadata,
     crypto: Crypto,
-) -> SocketAddr {
+) -> anyhow::Result<(SocketAddr, JoinHandle<()>)> {
     let host_cli = cli.host().unwrap();
+    let save_dir = host_cli.save_dir.clone();
+    let save_interval = Duration::from_secs_f32(host_cli.save_interval);
+    let verifier =
+        create_connection_verifier(host_cli).context("Failed to set up connection verification")?;
+    let compression_level = host_cli.diff_compression;
+    let conditions = cli.link_conditions();
+    let grace_period = Duration::from_secs_f32(host_cli.reconnect_grace_period);
+    let ticks_per_second = host_cli.tick_rate;
+    let limits = ModuleLimits {
+        max_exec_time: Duration::from_millis(host_cli.wasm_max_exec_time),
+        // Limits too large to be represented are as good as no limit
+        max_memory: host_cli.wasm_max_memory.saturating_mul(1024 * 1024),
+    };
+    let module_storage_limit = host_cli
+        .storage_limit
+        .map(|limit| limit.saturating_mul(1024 * 1024));
+    let module_storage_dir = host_cli.storage_dir.clone().or_else(|| {
+        let project_path_fs = project_path.to_file_path().ok().flatten()?;
+        Some(project_path_fs.join("storage"))
+    });
+    let mod_urls = host_cli.mods.clone();
     let quic_interface_port = host_cli.quic_interface_port;
     let proxy_settings = (!host_cli.no_proxy).then(|| {
         ProxySettings {
@@ -70,8 +116,7 @@ pub async fn start(
             &crypto,
         )
         .await
-        .context("failed to create game server with port")
-        .unwrap()
+        .context("failed to create game server with port")?
     } else {
         GameServer::new_with_port_in_range(
             host_cli.bind_address,
@@ -81,8 +126,7 @@ pub async fn start(
             &crypto,
         )
         .await
-        .context("failed to create game server with port in range")
-        .unwrap()
+        .context("failed to create game server with port in range")?
     };
 
     let addr = server.local_addr();
@@ -113,20 +157,44 @@ pub async fn start(
     };
 
     // here the key is inserted into the asset cache
+    let mut mod_state = mods::ModState {
+        project_id: manifest.ember.id.to_string(),
+        http_allowlist: host_cli.mod_http_allow.clone(),
+        ..Default::default()
+    };
     if let Ok(Some(project_path_fs)) = project_path.to_file_path() {
         let key = format!("http://{public_host}:{http_interface_port}/content/");
         ServerBaseUrlKey.insert(&assets, AbsAssetUrl::parse(key).unwrap());
-        start_http_interface(runtime, &project_path_fs, http_interface_port);
+        mod_state.base_url = Some(
+            AbsAssetUrl::parse(format!("http://{public_host}:{http_interface_port}/mods/"))
+                .unwrap(),
+        );
+        start_http_interface(
+            runtime,
+            &project_path_fs,
+            http_interface_port,
+            mod_state.clone(),
+        );
     } else {
         ServerBaseUrlKey.insert(&assets, project_path.push("build/").unwrap());
     }
 
+    let recorder = match &host_cli.record {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create the session recording {path:?}"))?;
+            log::info!("Recording the session to {path:?}");
+            Some(SessionRecorder::new(file, ServerBaseUrlKey.get(&assets)))
+        }
+        None => None,
+    };
+
     ComponentRegistry::get_mut()
         .add_external(ambient_project_native::all_defined_components(manifest, false).unwrap());
 
     let manifest = manifest.clone();
     let metadata = metadata.clone();
-    runtime.spawn(async move {
+    let task = runtime.spawn(async move {
         let mut server_world = World::new_with_config("server", true);
         server_world.init_shape_change_tracking();
 
@@ -136,6 +204,26 @@ pub async fn start(
                 create_resources(assets.clone()),
             )
             .unwrap();
+        if let Some(verifier) = verifier {
+            server_world.add_resource(connection_verifier(), verifier);
+        }
+        if let Some(level) = compression_level {
+            server_world.add_resource(diff_compression(), Compression::Zstd(level));
+        }
+        if let Some(conditions) = conditions {
+            server_world.add_resource(link_conditions(), conditions);
+        }
+        server_world.add_resource(reconnect_grace_period(), grace_period);
+        server_world.add_resource(module_limits(), limits);
+        if let Some(dir) = module_storage_dir {
+            server_world.add_resource(storage_dir(), dir);
+        }
+        if let Some(limit) = module_storage_limit {
+            server_world.add_resource(storage_limit(), limit);
+        }
+        if let Some(recorder) = recorder {
+            server_world.add_resource(session_recorder(), Arc::new(Mutex::new(recorder)));
+        }
 
         // Keep track of the project name
         let name = manifest
@@ -153,13 +241,28 @@ pub async fn start(
         Entity::new()
             .with(ambient_core::name(), "Synced resources".to_string())
             .with(synced_resources(), ())
+            .with(tick_rate(), ticks_per_second)
             .with(dont_store(), ())
             .spawn(&mut server_world);
-        // Note: this should not be reset every time the server is created. Remove this when it becomes possible to load/save worlds.
-        Entity::new()
-            .with(ambient_core::name(), "Persistent resources".to_string())
-            .with(persistent_resources(), ())
-            .spawn(&mut server_world);
+
+        if let Some(save_dir) = &save_dir {
+            if let Err(err) = snapshot::load(&mut server_world, save_dir) {
+                log::error!("{err:?}");
+                // Keep the snapshot from being overwritten by the fresh world
+                match snapshot::set_aside(save_dir) {
+                    Ok(path) => {
+                        log::warn!("Starting with a fresh world; moved the snapshot to {path:?}")
+                    }
+                    Err(err) => log::error!("{err:?}"),
+                }
+            }
+        }
+        if server_world.persisted_resource_entity().is_none() {
+            Entity::new()
+                .with(ambient_core::name(), "Persistent resources".to_string())
+                .with(persistent_resources(), ())
+                .spawn(&mut server_world);
+        }
 
         wasm::initialize(
             &mut server_world,
@@ -171,6 +274,16 @@ pub async fn start(
         .await
         .unwrap();
 
+        server_world.add_resource(
+            mod_loader(),
+            mods::loader(assets.clone(), mod_state.clone()),
+        );
+        for url in &mod_urls {
+            if let Err(err) = mods::load(&mut server_world, &assets, url, &mod_state).await {
+                log::error!("{err:?}");
+            }
+        }
+
         if let Commands::View { asset_path, .. } = cli.command.clone() {
             let asset_path = project_path
                 .push("build")
@@ -183,19 +296,96 @@ pub async fn start(
             let obj = PrefabFromUrl(asset_path.into()).get(&assets).await.unwrap();
             obj.spawn_into_world(&mut server_world, None);
         }
+        let create_server_systems: Arc<dyn Fn(&str, &mut World) -> SystemGroup + Sync + Send> =
+            match save_dir.clone() {
+                Some(save_dir) => {
+                    // Only the main instance is saved
+                    Arc::new(move |instance_id: &str, world: &mut World| {
+                        let mut systems = systems(world);
+                        if instance_id == MAIN_INSTANCE_ID {
+                            systems
+                                .add(Box::new(snapshot::system(save_dir.clone(), save_interval)));
+                        }
+                        systems
+                    })
+                }
+                None => Arc::new(|_: &str, world: &mut World| systems(world)),
+            };
+
         log::info!("Starting server");
-        server
+        let state = server
+            .with_shutdown_signal(shutdown_signal())
             .run(
                 server_world,
-                Arc::new(systems),
+                create_server_systems,
                 Arc::new(on_forking_systems),
                 Arc::new(on_shutdown_systems),
                 Arc::new(is_sync_component),
             )
             .await;
+
+        if let Some(save_dir) = &save_dir {
+            let state = state.lock();
+            if let Some(instance) = state.instances.get(MAIN_INSTANCE_ID) {
+                if let Err(err) = snapshot::save(&instance.world, save_dir) {
+                    log::error!("{err:?}");
+                }
+            }
+        }
     });
 
-    addr
+    Ok((addr, task))
+}
+
+/// Completes when the process is asked to shut down, with Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            log::error!("Unable to listen for Ctrl+C: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                log::error!("Unable to listen for SIGTERM: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+fn create_connection_verifier(
+    host_cli: &HostCli,
+) -> anyhow::Result<Option<Arc<dyn ConnectionVerifier>>> {
+    if let Some(path) = &host_cli.auth_secret_file {
+        let secret = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the secret from {path:?}"))?;
+        Ok(Some(Arc::new(SharedSecretVerifier::new(
+            secret.trim().as_bytes(),
+        ))))
+    } else if let Some(path) = &host_cli.auth_jwt_key {
+        let key =
+            std::fs::read(path).with_context(|| format!("Failed to read the key from {path:?}"))?;
+        Ok(Some(Arc::new(JwtVerifier::from_public_key(&key)?)))
+    } else if host_cli.auth_module {
+        Ok(Some(Arc::new(ModuleVerifier::default())))
+    } else {
+        Ok(None)
+    }
 }
 
 fn systems(_world: &mut World) -> SystemGroup {
@@ -206,6 +396,7 @@ fn systems(_world: &mut World) -> SystemGroup {
             // Can happen *during* the physics step
             Box::new(ambient_core::async_ecs::async_ecs_systems()),
             Box::new(ambient_prefab::systems()),
+            Box::new(ambient_network::prediction::server_systems()),
             // Happens after the physics step
             ambient_physics::fetch_simulation_system(),
             Box::new(ambient_physics::physx::sync_ecs_physics()),
@@ -275,7 +466,8 @@ fn create_resources(assets: AssetCache) -> Entity {
         unistream_handlers,
     );
 
-    let dgram_handlers = HashMap::new();
+    let mut dgram_handlers = HashMap::new();
+    ambient_network::prediction::register_datagram_handler(&mut dgram_handlers);
     server_resources.set(ambient_network::server::datagram_handlers(), dgram_handlers);
 
     server_resources
@@ -287,9 +479,18 @@ fn start_http_interface(
     runtime: &tokio::runtime::Handle,
     project_path: &Path,
     http_interface_port: u16,
+    mod_state: mods::ModState,
 ) {
     let router = Router::new()
         .route("/ping", get(|| async move { "ok" }))
+        .route(
+            "/mods/:ember_id/*path",
+            get(
+                move |UrlPath((ember_id, path)): UrlPath<(String, String)>| {
+                    mods::serve(mod_state.clone(), ember_id, path)
+                },
+            ),
+        )
         .nest_service(
             "/content",
             get_service(ServeDir::new(project_path.join("build"))).handle_error(handle_error),
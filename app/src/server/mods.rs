@@ -0,0 +1,402 @@
+//! Loading built embers into the running server as mods.
+//!
+//! A mod is downloaded from its URL, which is either the URL or the path of its ember. The
+//! clients download the client modules and assets of local mods from the HTTP interface of the
+//! server, and those of remote mods from the URL of the mod.
+//!
+//! The manifest of a mod is not trusted: a mod cannot take the ID of the project or of another
+//! mod, nor define components in the namespace of another ember, and it can only send HTTP
+//! requests to the URLs which the server operator allows.
+
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use ambient_core::{async_ecs::async_run, runtime};
+use ambient_ecs::{ComponentRegistry, ExternalComponentDesc, World};
+use ambient_project::{EmberType, Manifest};
+use ambient_std::{asset_cache::AssetCache, asset_url::AbsAssetUrl};
+use ambient_wasm::{
+    server::{
+        http::restrict_allowlist,
+        mods::{is_mod_module, ModLoaded, ModLoader},
+    },
+    shared::module_asset_base_url,
+};
+use anyhow::Context;
+use axum::http::StatusCode;
+use parking_lot::RwLock;
+
+use super::wasm::{self, EmberModules};
+
+/// What the mods are allowed to do, and where the clients download their files from
+#[derive(Clone, Default)]
+pub struct ModState {
+    /// The ID of the project's ember, which mods cannot take
+    pub project_id: String,
+    /// The URLs the server operator allows mods to send HTTP requests to, if their manifest asks
+    /// for them too
+    pub http_allowlist: Vec<String>,
+    /// The URL the HTTP interface serves local mods at, if it is running
+    pub base_url: Option<AbsAssetUrl>,
+    /// The build directory of each local mod, by ember ID
+    pub dirs: Arc<RwLock<HashMap<String, PathBuf>>>,
+    /// The URL each mod was loaded from, by ember ID. An ID stays taken by the mod which was
+    /// loaded with it until the server restarts, as its components stay registered.
+    pub urls: Arc<RwLock<HashMap<String, AbsAssetUrl>>>,
+}
+
+/// A downloaded mod which is ready to be spawned
+struct Mod {
+    manifest: Manifest,
+    modules: EmberModules,
+    /// The URL the assets of the mod are relative to
+    base_url: AbsAssetUrl,
+}
+
+/// Creates the loader the server modules use to load mods
+pub fn loader(assets: AssetCache, mod_state: ModState) -> ModLoader {
+    Arc::new(
+        move |world: &mut World, url: AbsAssetUrl, loaded: ModLoaded| {
+            let assets = assets.clone();
+            let mod_state = mod_state.clone();
+            let async_run = world.resource(async_run()).clone();
+            world.resource(runtime()).spawn(async move {
+                let result = download(&assets, &url, &mod_state).await;
+                async_run.run(move |world| {
+                    let result = result.and_then(|m| spawn(world, m, &mod_state));
+                    if let Err(err) = &result {
+                        log::error!("Failed to load the mod at {url}: {err:?}");
+                    }
+                    loaded(world, result);
+                });
+            });
+        },
+    )
+}
+
+/// Loads the built ember at `url` as a mod, and waits until its modules are spawned
+pub async fn load(
+    world: &mut World,
+    assets: &AssetCache,
+    url: &str,
+    mod_state: &ModState,
+) -> anyhow::Result<()> {
+    let url = AbsAssetUrl::parse(url)?;
+    let m = download(assets, &url, mod_state)
+        .await
+        .with_context(|| format!("Failed to load the mod at {url}"))?;
+    spawn(world, m, mod_state)?;
+    Ok(())
+}
+
+async fn download(
+    assets: &AssetCache,
+    url: &AbsAssetUrl,
+    mod_state: &ModState,
+) -> anyhow::Result<Mod> {
+    let build_dir = url.push("build/")?;
+    let manifest = build_dir
+        .push("ambient.toml")?
+        .download_string(assets)
+        .await
+        .context("Failed to download the manifest of the mod; has it been built?")?;
+    let mut manifest = Manifest::parse(&manifest)?;
+    if manifest.ember.type_ != EmberType::Mod {
+        anyhow::bail!("{} is not a mod", manifest.ember.id);
+    }
+    resolve_dependencies(&mut manifest, url)?;
+    let metadata = build_dir
+        .push("metadata.toml")?
+        .download_string(assets)
+        .await
+        .context("Failed to download the build metadata of the mod")?;
+    let metadata = ambient_build::Metadata::parse(&metadata)?;
+
+    let ember_id = manifest.ember.id.to_string();
+    if ember_id == mod_state.project_id {
+        anyhow::bail!("The mod has the same ID as the project, {ember_id}");
+    }
+    let components = {
+        // Held until the ID is taken, so that two mods cannot take it at the same time
+        let mut urls = mod_state.urls.write();
+        let reloading = match urls.get(&ember_id) {
+            Some(loaded_url) if loaded_url != url => {
+                anyhow::bail!("Another mod with the ID {ember_id} was loaded from {loaded_url}")
+            }
+            loaded_url => loaded_url.is_some(),
+        };
+        let components = new_components(&manifest, reloading)?;
+        urls.insert(ember_id.clone(), url.clone());
+        components
+    };
+
+    let base_url = match url.to_file_path()? {
+        Some(path) => {
+            let hosting_url = mod_state
+                .base_url
+                .as_ref()
+                .context("Local mods can only be loaded when the server hosts its project")?;
+            mod_state
+                .dirs
+                .write()
+                .insert(ember_id.clone(), path.join("build"));
+            hosting_url.push(format!("{ember_id}/"))?
+        }
+        None => build_dir.clone(),
+    };
+    let modules = wasm::download_modules(assets, &build_dir, &metadata, |path| {
+        Ok(base_url.push(path)?.to_string())
+    })
+    .await?;
+
+    // Registered before the modules are spawned, so that the clients are sent the components of
+    // the mod before anything that uses them
+    ComponentRegistry::get_mut().add_external(components);
+
+    Ok(Mod {
+        manifest,
+        modules,
+        base_url,
+    })
+}
+
+/// Loads the manifests of the dependencies of the mod at `url`.
+///
+/// The paths of the dependencies are relative to the mod's source, which is only available for
+/// local mods, so remote mods cannot have dependencies.
+fn resolve_dependencies(manifest: &mut Manifest, url: &AbsAssetUrl) -> anyhow::Result<()> {
+    if manifest.dependencies.is_empty() {
+        return Ok(());
+    }
+    let Some(path) = url.to_file_path()? else {
+        let dependencies = manifest
+            .dependencies
+            .keys()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        anyhow::bail!(
+            "Only local mods can have dependencies, but {} depends on {}",
+            manifest.ember.id,
+            dependencies.join(", ")
+        );
+    };
+    manifest
+        .resolve_all_dependencies(&path)
+        .context("Failed to resolve the dependencies of the mod")
+}
+
+/// The components defined by the mod and its dependencies which are not registered yet.
+///
+/// Components which are already registered are left as they are. The namespace of the mod must
+/// not be used by another ember, unless the mod is `reloading`, and the mod can only add the
+/// components of dependencies whose namespace no other ember uses.
+fn new_components(
+    manifest: &Manifest,
+    reloading: bool,
+) -> anyhow::Result<Vec<ExternalComponentDesc>> {
+    let registry = ComponentRegistry::get();
+    let mut components = vec![];
+    for (index, ember) in std::iter::once(manifest)
+        .chain(manifest.all_dependencies())
+        .enumerate()
+    {
+        let is_mod = index == 0;
+        let namespace = ember.project_path().to_string();
+        let prefix = format!("{namespace}::");
+        let namespace_taken = registry
+            .component_paths
+            .keys()
+            .any(|path| path.starts_with(&prefix));
+        let new = ambient_project_native::defined_components(ember, false)
+            .map_err(anyhow::Error::msg)?
+            .into_iter()
+            .filter(|desc| !registry.component_paths.contains_key(&desc.path))
+            .collect::<Vec<_>>();
+
+        // The mod must have a namespace of its own, even if it defines no components
+        if namespace_taken && !(is_mod && reloading) && (is_mod || !new.is_empty()) {
+            anyhow::bail!("The namespace {namespace} already belongs to another ember");
+        }
+        components.extend(new);
+    }
+    Ok(components)
+}
+
+/// Spawns the modules of the mod, returning its ember ID
+fn spawn(world: &mut World, m: Mod, mod_state: &ModState) -> anyhow::Result<String> {
+    let ember_id = m.manifest.ember.id.to_string();
+    // Loading a mod again reloads it
+    ambient_wasm::server::mods::unload(world, &ember_id);
+
+    let allowlist = restrict_allowlist(&m.manifest.http.allow, &mod_state.http_allowlist);
+    for id in wasm::spawn_modules(world, &m.manifest, m.modules, allowlist)? {
+        world.add_component(id, is_mod_module(), ())?;
+        world.add_component(id, module_asset_base_url(), m.base_url.to_string())?;
+    }
+    log::info!("Loaded mod {ember_id}");
+    Ok(ember_id)
+}
+
+/// Serves `path` from the build directory of the local mod `ember_id`
+pub(super) async fn serve(
+    mod_state: ModState,
+    ember_id: String,
+    path: String,
+) -> Result<Vec<u8>, StatusCode> {
+    let dir = mod_state.dirs.read().get(&ember_id).cloned();
+    let Some(dir) = dir else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let path = Path::new(path.trim_start_matches('/'));
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    ambient_sys::fs::read(dir.join(path))
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn serves_only_files_in_the_build_directory() {
+        let dir = std::env::temp_dir().join(format!("ambient_mod_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("build/client")).unwrap();
+        std::fs::write(dir.join("build/client/module.wasm"), b"wasm").unwrap();
+        std::fs::write(dir.join("secret.txt"), b"secret").unwrap();
+
+        let mod_state = ModState::default();
+        mod_state
+            .dirs
+            .write()
+            .insert("my_mod".to_string(), dir.join("build"));
+        let serve = |ember_id: &str, path: &str| {
+            serve(mod_state.clone(), ember_id.to_string(), path.to_string())
+        };
+
+        assert_eq!(
+            serve("my_mod", "/client/module.wasm").await,
+            Ok(b"wasm".to_vec())
+        );
+        assert_eq!(
+            serve("my_mod", "client/missing.wasm").await,
+            Err(StatusCode::NOT_FOUND)
+        );
+        assert_eq!(
+            serve("other_mod", "client/module.wasm").await,
+            Err(StatusCode::NOT_FOUND)
+        );
+        assert_eq!(
+            serve("my_mod", "../secret.txt").await,
+            Err(StatusCode::BAD_REQUEST)
+        );
+        // Absolute paths are relative to the build directory
+        assert_eq!(
+            serve("my_mod", "//secret.txt").await,
+            Err(StatusCode::NOT_FOUND)
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn manifest(id: &str) -> Manifest {
+        Manifest::parse(&format!(
+            r#"
+            [ember]
+            id = "{id}"
+            name = "Mod"
+            version = "0.0.1"
+            type = "Mod"
+
+            [components]
+            score = {{ type = "I32", name = "Score", description = "" }}
+            "#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn mods_cannot_take_the_namespace_of_another_ember() {
+        ambient_ecs::init_components();
+        ComponentRegistry::get_mut().add_external(
+            ambient_project_native::all_defined_components(&manifest("mods_test_game"), false)
+                .unwrap(),
+        );
+
+        let err = new_components(&manifest("mods_test_game"), false).unwrap_err();
+        assert!(err.to_string().contains("already belongs to another ember"));
+
+        let components = new_components(&manifest("mods_test_mod"), false).unwrap();
+        assert_eq!(
+            components
+                .iter()
+                .map(|desc| desc.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["mods_test_mod::score"]
+        );
+
+        // When reloading, the components which are already registered are kept
+        ComponentRegistry::get_mut().add_external(components);
+        assert!(new_components(&manifest("mods_test_mod"), true)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn local_mods_can_have_dependencies() {
+        ambient_ecs::init_components();
+        let dir = std::env::temp_dir().join(format!("ambient_mod_deps_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("deps/mods_test_dependency")).unwrap();
+        std::fs::write(
+            dir.join("deps/mods_test_dependency/ambient.toml"),
+            r#"
+            [ember]
+            id = "mods_test_dependency"
+            name = "Dependency"
+            version = "0.0.1"
+
+            [components]
+            health = { type = "F32", name = "Health", description = "" }
+            "#,
+        )
+        .unwrap();
+        let dependent = r#"
+            [ember]
+            id = "mods_test_dependent"
+            name = "Mod"
+            version = "0.0.1"
+            type = "Mod"
+
+            [dependencies]
+            mods_test_dependency = { path = "deps/mods_test_dependency" }
+            "#;
+
+        let mut manifest = Manifest::parse(dependent).unwrap();
+        resolve_dependencies(&mut manifest, &AbsAssetUrl::from_directory_path(&dir)).unwrap();
+        let components = new_components(&manifest, false).unwrap();
+        assert_eq!(
+            components
+                .iter()
+                .map(|desc| desc.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["mods_test_dependency::health"]
+        );
+
+        let mut manifest = Manifest::parse(dependent).unwrap();
+        let url = AbsAssetUrl::parse("https://example.com/mods_test_dependent/").unwrap();
+        let err = resolve_dependencies(&mut manifest, &url).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Only local mods can have dependencies"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ambient_core::player::{player, user_id};
+use ambient_ecs::{ComponentDesc, DeserWorldWithWarnings, FnSystem, World, WorldSnapshotMigration};
+use ambient_sys::time::Instant;
+use anyhow::Context;
+
+/// The version of the world snapshots written by the server.
+///
+/// When the layout of stored components changes, bump this and add a migration to [MIGRATIONS]
+/// so that existing snapshots can still be loaded.
+const SNAPSHOT_VERSION: u32 = 1;
+const MIGRATIONS: &[WorldSnapshotMigration] = &[];
+
+const SNAPSHOT_FILE_NAME: &str = "world.json";
+
+/// Entities with any of these components belong to a player's session: the player entity itself,
+/// and the entities owned by it. They are spawned again when the player reconnects, so they are
+/// neither saved nor loaded.
+fn session_components() -> [ComponentDesc; 2] {
+    [player().desc(), user_id().desc()]
+}
+
+/// Loads the snapshot in `save_dir` into `world`, keeping the original entity ids.
+///
+/// This includes the entities spawned by modules, so modules must check for existing entities
+/// before spawning them on startup. Returns `false` if there is no snapshot to load.
+pub fn load(world: &mut World, save_dir: &Path) -> anyhow::Result<bool> {
+    let path = save_dir.join(SNAPSHOT_FILE_NAME);
+    let content = match std::fs::read(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to read world snapshot {path:?}"))
+        }
+    };
+
+    let DeserWorldWithWarnings {
+        world: snapshot,
+        warnings,
+    } = World::from_snapshot(&content, SNAPSHOT_VERSION, MIGRATIONS)
+        .with_context(|| format!("Failed to load world snapshot {path:?}"))?;
+    warnings.log_warnings();
+
+    let session_components = session_components();
+    let entities = snapshot
+        .entities()
+        .into_iter()
+        .filter(|(_, entity)| {
+            !session_components
+                .iter()
+                .any(|&comp| entity.get_entry(comp).is_some())
+        })
+        .collect::<Vec<_>>();
+    let count = entities.len();
+    for (id, entity) in entities {
+        world.spawn_with_id(id, entity);
+    }
+    log::info!("Loaded {count} entities from {path:?}");
+
+    Ok(true)
+}
+
+/// Moves the snapshot in `save_dir` out of the way, so that it is neither loaded nor overwritten,
+/// and returns where it was moved to.
+pub fn set_aside(save_dir: &Path) -> anyhow::Result<PathBuf> {
+    let path = save_dir.join(SNAPSHOT_FILE_NAME);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let new_path = path.with_extension(format!("{timestamp}.json"));
+    std::fs::rename(&path, &new_path)
+        .with_context(|| format!("Failed to move world snapshot {path:?} to {new_path:?}"))?;
+
+    Ok(new_path)
+}
+
+/// Saves a snapshot of `world` to `save_dir`.
+///
+/// The snapshot is written to a temporary file first so that a crash during saving does not
+/// corrupt the previous snapshot.
+pub fn save(world: &World, save_dir: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(save_dir)
+        .with_context(|| format!("Failed to create save directory {save_dir:?}"))?;
+
+    let path = save_dir.join(SNAPSHOT_FILE_NAME);
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(
+        &temp_path,
+        world.to_snapshot(SNAPSHOT_VERSION, &session_components())?,
+    )
+    .with_context(|| format!("Failed to write world snapshot {temp_path:?}"))?;
+    std::fs::rename(&temp_path, &path)
+        .with_context(|| format!("Failed to replace world snapshot {path:?}"))?;
+
+    Ok(())
+}
+
+/// Saves a snapshot of the world to `save_dir` every `interval`.
+pub fn system(save_dir: PathBuf, interval: Duration) -> FnSystem {
+    let mut last_save = Instant::now();
+    FnSystem::new(move |world, _| {
+        if last_save.elapsed() < interval {
+            return;
+        }
+        last_save = Instant::now();
+
+        if let Err(err) = save(world, &save_dir) {
+            log::error!("{err:?}");
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_core::name;
+    use ambient_ecs::{Entity, EntityId};
+
+    use super::*;
+
+    #[test]
+    fn player_entities_are_not_saved() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+
+        let save_dir =
+            std::env::temp_dir().join(format!("ambient_snapshot_{}", std::process::id()));
+        let mut world = World::new("test");
+        let player_id = Entity::new()
+            .with(player(), ())
+            .with(user_id(), "user".to_string())
+            .spawn(&mut world);
+        let body_id = Entity::new()
+            .with(user_id(), "user".to_string())
+            .with(name(), "body".to_string())
+            .spawn(&mut world);
+        let tree_id = Entity::new()
+            .with(name(), "tree".to_string())
+            .spawn(&mut world);
+        save(&world, &save_dir).unwrap();
+
+        let mut loaded = World::new("test");
+        assert!(load(&mut loaded, &save_dir).unwrap());
+        let ids = |world: &World| -> Vec<EntityId> {
+            let resource_entity = world.resource_entity();
+            world
+                .entities()
+                .into_iter()
+                .map(|(id, _)| id)
+                .filter(|&id| id != resource_entity)
+                .collect()
+        };
+        assert_eq!(ids(&loaded), [tree_id]);
+        assert_eq!(loaded.get_ref(tree_id, name()).unwrap(), "tree");
+        assert!(!loaded.exists(player_id));
+        assert!(!loaded.exists(body_id));
+
+        std::fs::remove_dir_all(&save_dir).unwrap();
+    }
+}
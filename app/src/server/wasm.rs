@@ -1,12 +1,15 @@
 use std::{collections::HashMap, sync::Arc};
 
-use ambient_ecs::{EntityId, SystemGroup, World};
+use ambient_ecs::{dont_store, EntityId, SystemGroup, World};
 use ambient_project::Identifier;
 use ambient_std::{asset_cache::AssetCache, asset_url::AbsAssetUrl};
 pub use ambient_wasm::server::{on_forking_systems, on_shutdown_systems};
-use ambient_wasm::shared::{
-    client_bytecode_from_url, get_module_name, module_bytecode, remote_paired_id, spawn_module,
-    MessageType, ModuleBytecode,
+use ambient_wasm::{
+    server::http::http_allowlist,
+    shared::{
+        client_bytecode_from_url, get_module_name, module_bytecode, module_ember_id,
+        remote_paired_id, spawn_module, MessageType, ModuleBytecode,
+    },
 };
 use anyhow::Context;
 
@@ -43,14 +46,38 @@ pub async fn initialize(
     ambient_wasm::server::initialize(world, messenger)?;
 
     let build_dir = project_path.push("build").unwrap();
+    let modules = download_modules(&assets, &build_dir, build_metadata, |path| {
+        Ok(AbsAssetUrl::from_asset_key(path)?.to_string())
+    })
+    .await?;
+    spawn_modules(world, manifest, modules, manifest.http.allow.clone())?;
 
-    let mut modules_to_entity_ids = HashMap::new();
-    for target in ["client", "server"] {
-        let wasm_component_paths: &[String] = build_metadata.component_paths(target);
+    Ok(())
+}
 
-        let is_sole_module = wasm_component_paths.len() == 1;
-        for path in wasm_component_paths {
-            let component_url = build_dir.push(path).unwrap();
+/// The WASM modules of a built ember, ready to be spawned
+pub struct EmberModules {
+    /// The name of each client module, with the URL the clients download its bytecode from
+    client: Vec<(Identifier, String)>,
+    /// The name of each server module, with its bytecode
+    server: Vec<(Identifier, Vec<u8>)>,
+}
+
+/// Downloads the server modules of the ember built to `build_dir`. The URL of each client module
+/// is built from its path in `build_dir` by `client_url`.
+pub async fn download_modules(
+    assets: &AssetCache,
+    build_dir: &AbsAssetUrl,
+    build_metadata: &ambient_build::Metadata,
+    client_url: impl Fn(&str) -> anyhow::Result<String>,
+) -> anyhow::Result<EmberModules> {
+    let mut modules = EmberModules {
+        client: vec![],
+        server: vec![],
+    };
+    for target in ["client", "server"] {
+        for path in build_metadata.component_paths(target) {
+            let component_url = build_dir.push(path)?;
             let name = Identifier::new(
                 component_url
                     .file_stem()
@@ -58,37 +85,73 @@ pub async fn initialize(
             )
             .map_err(anyhow::Error::msg)?;
 
-            let description = manifest.ember.description.clone().unwrap_or_default();
-            let description = if is_sole_module {
-                description
-            } else {
-                format!("{description} ({name})")
-            };
-
-            let id = spawn_module(world, &name, description, true);
-            modules_to_entity_ids.insert(
-                (
-                    target,
-                    // Support `client_module`, `module_client` and `module`
-                    name.as_ref()
-                        .strip_prefix(target)
-                        .or_else(|| name.as_ref().strip_suffix(target))
-                        .unwrap_or(name.as_ref())
-                        .trim_matches('_')
-                        .to_string(),
-                ),
-                id,
-            );
-
             if target == "client" {
-                let bytecode_url = AbsAssetUrl::from_asset_key(path)?.to_string();
-                world.add_component(id, client_bytecode_from_url(), bytecode_url)?;
+                modules.client.push((name, client_url(path)?));
             } else {
-                let bytecode = component_url.download_bytes(&assets).await?;
-                world.add_component(id, module_bytecode(), ModuleBytecode(bytecode))?;
+                let bytecode = component_url.download_bytes(assets).await?;
+                modules.server.push((name, bytecode));
             }
         }
     }
+    Ok(modules)
+}
+
+/// Spawns the modules of the ember described by `manifest`, returning their IDs. The server
+/// modules can send HTTP requests to the URLs in `allowlist`.
+pub fn spawn_modules(
+    world: &mut World,
+    manifest: &ambient_project::Manifest,
+    modules: EmberModules,
+    allowlist: Vec<String>,
+) -> anyhow::Result<Vec<EntityId>> {
+    let description = manifest.ember.description.clone().unwrap_or_default();
+    let spawn = |world: &mut World, name: &Identifier, is_sole_module: bool| {
+        let description = if is_sole_module {
+            description.clone()
+        } else {
+            format!("{description} ({name})")
+        };
+
+        let id = spawn_module(world, name, description, true);
+        // The ember's modules are recreated on every start, so they should not be saved
+        world.add_component(id, dont_store(), ()).unwrap();
+        world
+            .add_component(id, module_ember_id(), manifest.ember.id.to_string())
+            .unwrap();
+        id
+    };
+
+    let mut modules_to_entity_ids = HashMap::new();
+    let mut add = |target: &'static str, name: &Identifier, id: EntityId| {
+        modules_to_entity_ids.insert(
+            (
+                target,
+                // Support `client_module`, `module_client` and `module`
+                name.as_ref()
+                    .strip_prefix(target)
+                    .or_else(|| name.as_ref().strip_suffix(target))
+                    .unwrap_or(name.as_ref())
+                    .trim_matches('_')
+                    .to_string(),
+            ),
+            id,
+        );
+    };
+
+    let is_sole_module = modules.client.len() == 1;
+    for (name, bytecode_url) in modules.client {
+        let id = spawn(world, &name, is_sole_module);
+        world.add_component(id, client_bytecode_from_url(), bytecode_url)?;
+        add("client", &name, id);
+    }
+
+    let is_sole_module = modules.server.len() == 1;
+    for (name, bytecode) in modules.server {
+        let id = spawn(world, &name, is_sole_module);
+        world.add_component(id, module_bytecode(), ModuleBytecode(bytecode))?;
+        world.add_component(id, http_allowlist(), allowlist.clone())?;
+        add("server", &name, id);
+    }
 
     for ((target, name), id) in modules_to_entity_ids.iter() {
         let corresponding = match *target {
@@ -101,5 +164,5 @@ pub async fn initialize(
         }
     }
 
-    Ok(())
+    Ok(modules_to_entity_ids.into_values().collect())
 }
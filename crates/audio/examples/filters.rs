@@ -23,12 +23,14 @@ fn main() {
 
     let source = source
         .decode()
+        .unwrap()
         .low_pass(1000.0, 6.0)
         .repeat()
         .take(Duration::from_secs(5))
         .chain(
             source
                 .decode()
+                .unwrap()
                 .high_pass(1000.0, 6.0)
                 .repeat()
                 .take(Duration::from_secs(5)),
@@ -36,6 +38,7 @@ fn main() {
         .chain(
             source
                 .decode()
+                .unwrap()
                 .blt(Constant(Bpf {
                     freq: 1000.0,
                     bandwidth: 3.0,
@@ -26,11 +26,13 @@ fn main() {
 
     let source = source
         .decode()
+        .unwrap()
         .repeat()
         .take(Duration::from_secs(5))
         .chain(
             source
                 .decode()
+                .unwrap()
                 .high_pass(2800.0, 3.0)
                 .repeat()
                 .take(Duration::from_secs(5)),
@@ -26,19 +26,19 @@ fn main() -> color_eyre::Result<()> {
         let a = sources
             .choose(&mut rng)
             .unwrap()
-            .decode()
+            .decode()?
             .take(Duration::from_secs(5));
 
         let b = sources
             .choose(&mut rng)
             .unwrap()
-            .decode()
+            .decode()?
             .take(Duration::from_secs(5));
 
         let c = sources
             .choose(&mut rng)
             .unwrap()
-            .decode()
+            .decode()?
             .take(Duration::from_secs(5));
 
         let source = a.crossfade(b, 200.ms()).crossfade(c, 200.ms());
@@ -22,7 +22,7 @@ fn main() -> color_eyre::Result<()> {
     ];
 
     for (i, slice) in slices.iter().enumerate().cycle() {
-        let source = track.decode().slice(slice.clone());
+        let source = track.decode()?.slice(slice.clone());
 
         eprintln!("--------------\nPlaying slice {i} {:?}", source.duration());
 
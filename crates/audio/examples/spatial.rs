@@ -169,6 +169,7 @@ async fn main() {
     )
     .unwrap()
     .decode()
+    .unwrap()
     .repeat();
 
     let chord_source = SineWave::new(523.25);
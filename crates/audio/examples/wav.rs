@@ -12,7 +12,7 @@ fn main() {
 
     let stream = AudioStream::new().unwrap();
 
-    let source = track.decode();
+    let source = track.decode().unwrap();
     eprintln!("Duration: {:?}", source.duration());
     let sound = stream.mixer().play(source);
     let now = Instant::now();
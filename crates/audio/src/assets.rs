@@ -29,6 +29,8 @@ impl AsyncAssetKey<Result<Arc<Track>, Arc<Error>>> for AudioFromUrl {
         let format = match self.url.extension().as_ref().map(|x| x as &str) {
             Some("wav") => AudioFormat::Wav,
             Some("ogg") => AudioFormat::Vorbis,
+            Some("flac") => AudioFormat::Flac,
+            Some("mp3") => AudioFormat::Mp3,
             v => {
                 return Err(Arc::new(Error::UnsupportedFormat(
                     v.unwrap_or_default().to_string(),
@@ -1,6 +1,7 @@
 use std::{f32::consts::TAU, fmt::Debug};
 
 use glam::Vec2;
+use serde::{Deserialize, Serialize};
 
 use crate::{value::Value, SampleRate, Source};
 
@@ -13,7 +14,7 @@ pub struct BltCoeffs {
     b2: f32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Hpf {
     pub freq: f32,
     // In dB/octave
@@ -46,7 +47,7 @@ impl TransferFunction for Hpf {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Lpf {
     pub freq: f32,
     // In dB/octave
@@ -80,7 +81,7 @@ impl TransferFunction for Lpf {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Bpf {
     pub freq: f32,
     pub bandwidth: f32,
@@ -116,6 +117,34 @@ pub trait TransferFunction {
     fn get_coeffs(&self, sample_freq: SampleRate) -> BltCoeffs;
 }
 
+/// A filter whose kind can be changed while it is playing
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum Filter {
+    /// Lets the source through unchanged
+    #[default]
+    None,
+    LowPass(Lpf),
+    HighPass(Hpf),
+    BandPass(Bpf),
+}
+
+impl TransferFunction for Filter {
+    fn get_coeffs(&self, sample_freq: SampleRate) -> BltCoeffs {
+        match self {
+            Filter::None => BltCoeffs {
+                b0: 1.0,
+                b1: 0.0,
+                b2: 0.0,
+                a1: 0.0,
+                a2: 0.0,
+            },
+            Filter::LowPass(v) => v.get_coeffs(sample_freq),
+            Filter::HighPass(v) => v.get_coeffs(sample_freq),
+            Filter::BandPass(v) => v.get_coeffs(sample_freq),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BilinearTransform<S, H, Vh>
 where
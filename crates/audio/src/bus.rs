@@ -0,0 +1,284 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blt::Filter, value::Constant, CompressorParams, DelayParams, Frame, ReverbParams, SampleRate,
+    Source,
+};
+
+/// How long it takes for volume, mute and ducking changes to take effect, in seconds.
+///
+/// Changing the gain instantly would cause audible clicks.
+const GAIN_SMOOTHING: f32 = 0.05;
+
+/// An effect applied to everything played on a bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Effect {
+    Filter(Filter),
+    Delay(DelayParams),
+    Reverb(ReverbParams),
+    Compressor(CompressorParams),
+}
+
+impl Effect {
+    pub fn apply(&self, source: Box<dyn Source>) -> Box<dyn Source> {
+        match *self {
+            Effect::Filter(ref filter) => Box::new(source.blt(Constant(filter.clone()))),
+            Effect::Delay(params) => Box::new(source.delay(params)),
+            Effect::Reverb(params) => Box::new(source.reverb(params)),
+            Effect::Compressor(params) => Box::new(source.compress(params)),
+        }
+    }
+}
+
+/// Lowers the volume of a bus while sounds are playing on another bus, e.g. to keep dialogue
+/// audible over music
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ducking {
+    /// The bus which causes this bus to be ducked
+    pub trigger: String,
+    /// The gain applied to this bus while the trigger bus is playing
+    pub gain: f32,
+}
+
+/// The settings of a named mixer bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusSettings {
+    pub volume: f32,
+    pub muted: bool,
+    /// Applied in order to the mix of all sounds on the bus, before the volume
+    pub effects: Vec<Effect>,
+    pub ducking: Option<Ducking>,
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+            effects: Vec::new(),
+            ducking: None,
+        }
+    }
+}
+
+impl BusSettings {
+    fn gain(&self, ducked: bool) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+
+        match &self.ducking {
+            Some(ducking) if ducked => self.volume * ducking.gain,
+            _ => self.volume,
+        }
+    }
+}
+
+/// Feeds the mix of the sounds on a bus into its effect chain
+struct BusInput {
+    queue: Arc<Mutex<VecDeque<Frame>>>,
+    sample_rate: SampleRate,
+}
+
+impl Source for BusInput {
+    fn next_sample(&mut self) -> Option<Frame> {
+        // The bus keeps running when nothing is playing, so that effect tails can decay
+        Some(self.queue.lock().pop_front().unwrap_or(Frame::ZERO))
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn sample_count(&self) -> Option<u64> {
+        None
+    }
+}
+
+pub(crate) struct Bus {
+    settings: BusSettings,
+    sample_rate: SampleRate,
+    queue: Arc<Mutex<VecDeque<Frame>>>,
+    chain: Box<dyn Source>,
+    /// The mix of the sounds on the bus for the current buffer
+    input: Vec<Frame>,
+    output: Vec<Frame>,
+    gain: f32,
+    /// The number of sounds which played on the bus during the current buffer
+    pub(crate) playing: usize,
+}
+
+impl Bus {
+    pub(crate) fn new(settings: BusSettings, sample_rate: SampleRate) -> Self {
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        Self {
+            chain: Self::build_chain(&settings.effects, queue.clone(), sample_rate),
+            gain: settings.gain(false),
+            settings,
+            sample_rate,
+            queue,
+            input: Vec::new(),
+            output: Vec::new(),
+            playing: 0,
+        }
+    }
+
+    fn build_chain(
+        effects: &[Effect],
+        queue: Arc<Mutex<VecDeque<Frame>>>,
+        sample_rate: SampleRate,
+    ) -> Box<dyn Source> {
+        effects.iter().fold(
+            Box::new(BusInput { queue, sample_rate }) as Box<dyn Source>,
+            |source, effect| effect.apply(source),
+        )
+    }
+
+    pub(crate) fn settings(&self) -> &BusSettings {
+        &self.settings
+    }
+
+    pub(crate) fn set_settings(&mut self, settings: BusSettings) {
+        if settings.effects != self.settings.effects {
+            self.queue = Arc::new(Mutex::new(VecDeque::new()));
+            self.chain = Self::build_chain(&settings.effects, self.queue.clone(), self.sample_rate);
+        }
+        self.settings = settings;
+    }
+
+    /// Prepares the bus for mixing `len` frames
+    pub(crate) fn begin(&mut self, len: usize) {
+        self.input.clear();
+        self.input.resize(len, Frame::ZERO);
+        self.playing = 0;
+    }
+
+    pub(crate) fn input_mut(&mut self) -> &mut [Frame] {
+        &mut self.input
+    }
+
+    /// The ducking trigger of this bus
+    pub(crate) fn trigger(&self) -> Option<&str> {
+        self.settings
+            .ducking
+            .as_ref()
+            .map(|ducking| ducking.trigger.as_str())
+    }
+
+    /// Runs the mixed input through the effects and adds the result to `output`
+    pub(crate) fn process(&mut self, output: &mut [Frame], ducked: bool) {
+        self.queue.lock().extend(self.input.iter().copied());
+
+        self.output.clear();
+        self.output.resize(output.len(), Frame::ZERO);
+        self.chain.sample_buffered(&mut self.output);
+
+        let target = self.settings.gain(ducked);
+        let smoothing = 1.0 - (-1.0 / (GAIN_SMOOTHING * self.sample_rate as f32)).exp();
+        for (out, sample) in output.iter_mut().zip(&self.output) {
+            self.gain += (target - self.gain) * smoothing;
+            *out += *sample * self.gain;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AudioMixer, BufferedSource};
+
+    fn mix(mixer: &mut AudioMixer, len: usize) -> Vec<f32> {
+        let mut output = vec![Frame::ZERO; len];
+        mixer.sample_buffered(&mut output);
+        output.iter().map(|v| v.x).collect()
+    }
+
+    #[test]
+    fn bus_volume() {
+        let mut mixer = AudioMixer::new(100);
+        mixer.set_limiter(None);
+        mixer.set_bus(
+            "music",
+            BusSettings {
+                volume: 0.5,
+                ..Default::default()
+            },
+        );
+        mixer.set_bus(
+            "sfx",
+            BusSettings {
+                muted: true,
+                ..Default::default()
+            },
+        );
+
+        mixer.play_on_bus("music", BufferedSource::new([1.0; 4], 1, 100));
+        mixer.play_on_bus("sfx", BufferedSource::new([1.0; 4], 1, 100));
+        mixer.play(BufferedSource::new([0.25; 4], 1, 100));
+
+        assert_eq!(mix(&mut mixer, 4), [0.75; 4]);
+        assert_eq!(mix(&mut mixer, 1), [0.0]);
+        assert_eq!(mixer.playing_sinks(), 0);
+    }
+
+    #[test]
+    fn removed_bus() {
+        let mut mixer = AudioMixer::new(100);
+        mixer.set_limiter(None);
+        mixer.set_bus(
+            "music",
+            BusSettings {
+                volume: 0.5,
+                ..Default::default()
+            },
+        );
+
+        mixer.play_on_bus("music", BufferedSource::new([1.0; 4], 1, 100));
+        assert_eq!(mix(&mut mixer, 2), [0.5; 2]);
+
+        assert!(mixer.remove_bus("music"));
+        assert!(!mixer.remove_bus("music"));
+        assert_eq!(mixer.bus_settings("music"), None);
+        assert_eq!(mix(&mut mixer, 2), [1.0; 2]);
+    }
+
+    #[test]
+    fn ducking() {
+        let mut mixer = AudioMixer::new(100);
+        mixer.set_limiter(None);
+        mixer.set_bus(
+            "music",
+            BusSettings {
+                ducking: Some(Ducking {
+                    trigger: "voice".into(),
+                    gain: 0.0,
+                }),
+                ..Default::default()
+            },
+        );
+        mixer.set_bus(
+            "voice",
+            BusSettings {
+                volume: 0.0,
+                ..Default::default()
+            },
+        );
+
+        mixer.play_on_bus("music", BufferedSource::new(vec![1.0; 100], 1, 100));
+        assert_eq!(mix(&mut mixer, 10), [1.0; 10]);
+
+        // The music fades out while the voice is playing
+        mixer.play_on_bus("voice", BufferedSource::new(vec![1.0; 50], 1, 100));
+        let samples = mix(&mut mixer, 50);
+        assert!(samples.windows(2).all(|v| v[1] < v[0]));
+        assert!(samples[49] < 0.01);
+
+        // And back in once it has finished
+        let samples = mix(&mut mixer, 40);
+        assert!(samples.windows(2).all(|v| v[1] > v[0]));
+        assert!(samples[39] > 0.99);
+    }
+}
@@ -29,6 +29,10 @@ pub enum Error {
     ContentDownload(#[from] AssetError),
     #[error("Failed to decode vorbis")]
     Vorbis(#[from] VorbisError),
+    #[error("Failed to decode audio")]
+    Symphonia(#[from] symphonia::core::errors::Error),
+    #[error("No audio track with a known sample rate was found")]
+    NoAudioTrack,
     #[error("Invalid channel configuration: {0}")]
     InvalidChannelCount(u16),
     #[error("Failed to load IR sphere for spatial audio")]
@@ -1,6 +1,8 @@
 mod assets;
+mod bus;
 mod error;
 mod mixer;
+mod offline;
 // mod sink;
 mod stream;
 
@@ -11,6 +13,7 @@ pub mod hrtf;
 pub mod signal;
 pub mod source;
 mod spatial;
+pub mod symphonia_track;
 pub mod track;
 pub mod utils;
 pub mod value;
@@ -18,8 +21,10 @@ pub mod vorbis;
 pub mod wav;
 
 pub use assets::*;
+pub use bus::*;
 pub use error::*;
 pub use mixer::*;
+pub use offline::*;
 // pub use sink::*;
 pub use source::*;
 pub use spatial::*;
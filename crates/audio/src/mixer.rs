@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     future::Future,
     sync::{Arc, Weak},
     task::Poll,
@@ -10,10 +11,14 @@ use parking_lot::Mutex;
 use slotmap::{new_key_type, SlotMap};
 
 use crate::{
+    bus::Bus,
     signal::{AsyncSignal, BlockingSignal, Signal},
-    Frame, SampleConversion, SampleRate, Source,
+    BusSettings, CompressorParams, DynamicsProcessor, Frame, SampleConversion, SampleRate, Source,
 };
 
+/// The threshold of the master limiter, in dBFS
+pub const MASTER_LIMITER_THRESHOLD: f32 = -1.0;
+
 new_key_type! {
     pub struct SoundId;
 }
@@ -24,6 +29,8 @@ struct PlayingSound {
     #[allow(dead_code)]
     cursor: usize,
     source: Box<dyn Source>,
+    /// The bus the sound is played on, or None if it is played directly on the mixer
+    bus: Option<String>,
 }
 
 /// Handle to a playing sound
@@ -104,6 +111,9 @@ struct AudioMixerInner {
     sample_rate: SampleRate,
     waiters: Mutex<SignalVec>,
     sources: Mutex<SlotMap<SoundId, PlayingSound>>,
+    buses: Mutex<HashMap<String, Bus>>,
+    /// Applied to the sum of all sources, to prevent clipping when many sounds play at once
+    limiter: Mutex<Option<DynamicsProcessor>>,
 }
 
 impl std::fmt::Debug for AudioMixerInner {
@@ -111,6 +121,7 @@ impl std::fmt::Debug for AudioMixerInner {
         f.debug_struct("AudioMixerInner")
             .field("sample_rate", &self.sample_rate)
             .field("sources", &self.sources.lock().len())
+            .field("buses", &self.buses.lock().keys().collect::<Vec<_>>())
             .finish()
     }
 }
@@ -121,7 +132,12 @@ impl AudioMixer {
             inner: Arc::new(AudioMixerInner {
                 sample_rate,
                 sources: Mutex::default(),
+                buses: Mutex::default(),
                 waiters: Default::default(),
+                limiter: Mutex::new(Some(DynamicsProcessor::new(
+                    CompressorParams::limiter(MASTER_LIMITER_THRESHOLD),
+                    sample_rate,
+                ))),
             }),
         }
     }
@@ -134,6 +150,22 @@ impl AudioMixer {
 
     /// Play a source on the mixer, returning a handle which can be used to control it
     pub fn play<S: Source + 'static>(&self, source: S) -> Sound {
+        self.play_inner(source, None)
+    }
+
+    /// Play a source on the named bus, creating the bus with the default settings if it does not
+    /// exist yet
+    pub fn play_on_bus<S: Source + 'static>(&self, bus: &str, source: S) -> Sound {
+        self.inner
+            .buses
+            .lock()
+            .entry(bus.to_string())
+            .or_insert_with(|| Bus::new(BusSettings::default(), self.inner.sample_rate));
+
+        self.play_inner(source, Some(bus.to_string()))
+    }
+
+    fn play_inner<S: Source + 'static>(&self, source: S, bus: Option<String>) -> Sound {
         let sample_rate = source.sample_rate();
 
         let source = if sample_rate == self.inner.sample_rate {
@@ -142,17 +174,53 @@ impl AudioMixer {
             Box::new(SampleConversion::new(source, self.inner.sample_rate as _)) as Box<dyn Source>
         };
 
-        let id = self
-            .inner
-            .sources
-            .lock()
-            .insert(PlayingSound { cursor: 0, source });
+        let id = self.inner.sources.lock().insert(PlayingSound {
+            cursor: 0,
+            source,
+            bus,
+        });
         Sound {
             id,
             mixer: self.clone(),
         }
     }
 
+    /// Changes the settings of the named bus, creating it if it does not exist yet.
+    ///
+    /// The effects of the bus are only rebuilt if they have changed, so that volume changes do not
+    /// interrupt effect tails.
+    pub fn set_bus(&self, bus: &str, settings: BusSettings) {
+        let mut buses = self.inner.buses.lock();
+        match buses.get_mut(bus) {
+            Some(existing) => existing.set_settings(settings),
+            None => {
+                buses.insert(bus.to_string(), Bus::new(settings, self.inner.sample_rate));
+            }
+        }
+    }
+
+    /// Removes the named bus, returning whether it existed.
+    ///
+    /// Sounds which are still playing on the bus are mixed directly into the output.
+    pub fn remove_bus(&self, bus: &str) -> bool {
+        self.inner.buses.lock().remove(bus).is_some()
+    }
+
+    /// Returns the settings of the named bus, if it exists
+    pub fn bus_settings(&self, bus: &str) -> Option<BusSettings> {
+        self.inner
+            .buses
+            .lock()
+            .get(bus)
+            .map(|bus| bus.settings().clone())
+    }
+
+    /// Replaces the master limiter applied to the mixed output, or disables it if `None`
+    pub fn set_limiter(&self, params: Option<CompressorParams>) {
+        *self.inner.limiter.lock() =
+            params.map(|params| DynamicsProcessor::new(params, self.inner.sample_rate));
+    }
+
     pub fn stop(&self, key: SoundId) {
         self.inner.sources.lock().remove(key);
     }
@@ -177,22 +245,9 @@ impl AudioMixer {
 
 impl Source for AudioMixer {
     fn next_sample(&mut self) -> Option<crate::Frame> {
-        let mut sources = self.inner.sources.lock();
-        let mut res = Frame::ZERO;
-        sources.retain(|id, source| {
-            let sample = match source.source.next_sample() {
-                Some(v) => v,
-                None => {
-                    self.terminate_source(id, source);
-                    return false;
-                }
-            };
-            res += sample;
-
-            true
-        });
-
-        Some(res)
+        let mut res = [Frame::ZERO];
+        self.sample_buffered(&mut res);
+        Some(res[0])
     }
 
     fn sample_rate(&self) -> crate::SampleRate {
@@ -200,12 +255,27 @@ impl Source for AudioMixer {
     }
 
     fn sample_buffered(&mut self, output: &mut [Frame]) -> usize {
+        let len = output.len();
         let mut sources = self.inner.sources.lock();
+        let mut buses = self.inner.buses.lock();
+        for bus in buses.values_mut() {
+            bus.begin(len);
+        }
+
         sources.retain(|id, source| {
-            let written = source.source.sample_buffered(output);
+            let written = match source.bus.as_ref().and_then(|bus| buses.get_mut(bus)) {
+                Some(bus) => {
+                    let written = source.source.sample_buffered(bus.input_mut());
+                    if written > 0 {
+                        bus.playing += 1;
+                    }
+                    written
+                }
+                None => source.source.sample_buffered(output),
+            };
 
             // No more samples in source
-            if written != output.len() {
+            if written != len {
                 self.terminate_source(id, source);
                 return false;
             }
@@ -213,7 +283,26 @@ impl Source for AudioMixer {
             true
         });
 
-        output.len()
+        let ducked = buses
+            .values()
+            .map(|bus| {
+                bus.trigger()
+                    .and_then(|trigger| buses.get(trigger))
+                    .map(|trigger| trigger.playing > 0)
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        for (bus, ducked) in buses.values_mut().zip(ducked) {
+            bus.process(output, ducked);
+        }
+
+        if let Some(limiter) = &mut *self.inner.limiter.lock() {
+            for sample in output.iter_mut() {
+                *sample = limiter.process(*sample);
+            }
+        }
+
+        len
     }
 
     fn sample_count(&self) -> Option<u64> {
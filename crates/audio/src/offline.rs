@@ -0,0 +1,145 @@
+use std::{
+    io::{Seek, Write},
+    time::Duration,
+};
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+use crate::{error::Result, AudioMixer, Frame, SampleRate, Source};
+
+/// The number of frames mixed at a time, similar to the buffer size of an output device
+pub const OFFLINE_BLOCK_SIZE: usize = 512;
+
+/// A mixer which renders into memory rather than an output device.
+///
+/// Unlike [AudioStream](crate::AudioStream) this does not need a sound card, and the output only
+/// depends on what is played on the mixer, which makes it suitable for tests.
+#[derive(Debug)]
+pub struct OfflineStream {
+    mixer: AudioMixer,
+    rendered: u64,
+}
+
+impl OfflineStream {
+    pub fn new(sample_rate: SampleRate) -> Self {
+        Self {
+            mixer: AudioMixer::new(sample_rate),
+            rendered: 0,
+        }
+    }
+
+    /// Get a reference to the audio stream's mixer.
+    #[must_use]
+    pub fn mixer(&self) -> &AudioMixer {
+        &self.mixer
+    }
+
+    pub fn sample_rate(&self) -> SampleRate {
+        self.mixer.sample_rate()
+    }
+
+    /// The amount of audio which has been rendered so far
+    pub fn position(&self) -> Duration {
+        Duration::from_secs_f64(self.rendered as f64 / self.sample_rate() as f64)
+    }
+
+    /// Renders the next `count` frames of the mix
+    pub fn render_frames(&mut self, count: usize) -> Vec<Frame> {
+        let mut output = vec![Frame::ZERO; count];
+        for block in output.chunks_mut(OFFLINE_BLOCK_SIZE) {
+            self.mixer.sample_buffered(block);
+        }
+
+        self.rendered += count as u64;
+        output
+    }
+
+    /// Renders the next `duration` of the mix
+    pub fn render(&mut self, duration: Duration) -> Vec<Frame> {
+        let count = (duration.as_secs_f64() * self.sample_rate() as f64).round() as usize;
+        self.render_frames(count)
+    }
+}
+
+/// Writes stereo frames as a 32-bit float WAV file
+pub fn write_wav<W: Write + Seek>(
+    writer: W,
+    frames: &[Frame],
+    sample_rate: SampleRate,
+) -> Result<()> {
+    let mut writer = WavWriter::new(
+        writer,
+        WavSpec {
+            channels: 2,
+            sample_rate: sample_rate as u32,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        },
+    )?;
+
+    for frame in frames {
+        writer.write_sample(frame.x)?;
+        writer.write_sample(frame.y)?;
+    }
+
+    writer.finalize()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use hound::WavReader;
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{BufferedSource, SineWave};
+
+    fn render_scene() -> Vec<Frame> {
+        let mut stream = OfflineStream::new(44100);
+        stream
+            .mixer()
+            .play(SineWave::new(440.0).take(Duration::from_millis(300)));
+        stream
+            .mixer()
+            .play(BufferedSource::new(vec![0.25; 1000], 1, 22050));
+
+        let frames = stream.render(Duration::from_millis(500));
+        assert_eq!(stream.position(), Duration::from_millis(500));
+        frames
+    }
+
+    #[test]
+    fn deterministic() {
+        let frames = render_scene();
+        assert_eq!(frames.len(), 22050);
+        assert_eq!(frames, render_scene());
+
+        // Both sounds have finished by the end
+        assert!(frames[frames.len() - 100..]
+            .iter()
+            .all(|v| *v == Frame::ZERO));
+    }
+
+    #[test]
+    fn wav() {
+        let frames = render_scene();
+
+        let mut bytes = Cursor::new(Vec::new());
+        write_wav(&mut bytes, &frames, 44100).unwrap();
+        bytes.set_position(0);
+
+        let reader = WavReader::new(bytes).unwrap();
+        assert_eq!(reader.spec().channels, 2);
+        assert_eq!(reader.spec().sample_rate, 44100);
+
+        let samples = reader
+            .into_samples::<f32>()
+            .map(|v| v.unwrap())
+            .tuples()
+            .map(|(l, r)| Frame::new(l, r))
+            .collect_vec();
+        assert_eq!(samples, frames);
+    }
+}
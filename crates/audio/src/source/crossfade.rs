@@ -19,8 +19,9 @@ impl<L: Source, R: Source> Crossfade<L, R> {
 
         // in number of frames
         let dur = (left.sample_rate() * dur.as_nanos() as u64) / 1_000_000_000;
-        let start = left.sample_count().unwrap_or(u64::MAX) - dur;
+        // Fades over the whole of `left` if it is shorter than the crossfade
         let end = left.sample_count().unwrap_or(u64::MAX);
+        let start = end.saturating_sub(dur);
 
         Self {
             left,
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{to_sample_index, Frame, SampleRate, Source};
+
+/// The level below which echoes are considered inaudible, -60 dB
+const SILENCE: f32 = 0.001;
+/// Upper bound on the number of echoes, so that a feedback close to 1 still has a finite tail
+const MAX_ECHOES: u64 = 256;
+/// The longest time between echoes of a [Delay]. Longer delays are clamped to this, as the delay
+/// buffer holds this many seconds of samples.
+pub const MAX_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DelayParams {
+    /// The time between each echo, up to [MAX_DELAY]
+    pub delay: Duration,
+    /// How much of each echo is fed back into the next one, in `0..1`
+    pub feedback: f32,
+    /// The volume of the echoes relative to the input
+    pub wet: f32,
+}
+
+impl Default for DelayParams {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(300),
+            feedback: 0.4,
+            wet: 0.5,
+        }
+    }
+}
+
+/// A feedback delay, which repeats the source as a series of decaying echoes.
+///
+/// After the source has finished the echoes keep playing until they have decayed.
+#[derive(Debug, Clone)]
+pub struct Delay<S> {
+    source: S,
+    buffer: Box<[Frame]>,
+    cursor: usize,
+    feedback: f32,
+    wet: f32,
+    tail_len: u64,
+    /// Remaining samples of the tail once the source has finished
+    tail: Option<u64>,
+}
+
+impl<S: Source> Delay<S> {
+    pub fn new(source: S, params: DelayParams) -> Self {
+        let len = to_sample_index(source.sample_rate(), params.delay.min(MAX_DELAY)).max(1);
+        let feedback = params.feedback.clamp(0.0, 0.99);
+
+        let echoes = if feedback > 0.0 {
+            1 + ((SILENCE.ln() / feedback.ln()).ceil() as u64).min(MAX_ECHOES)
+        } else {
+            1
+        };
+
+        Self {
+            source,
+            buffer: vec![Frame::ZERO; len as usize].into_boxed_slice(),
+            cursor: 0,
+            feedback,
+            wet: params.wet,
+            tail_len: len * echoes,
+            tail: None,
+        }
+    }
+}
+
+impl<S: Source> Source for Delay<S> {
+    fn next_sample(&mut self) -> Option<Frame> {
+        let input = match self.tail {
+            None => match self.source.next_sample() {
+                Some(sample) => sample,
+                None => {
+                    self.tail = Some(self.tail_len);
+                    Frame::ZERO
+                }
+            },
+            Some(_) => Frame::ZERO,
+        };
+
+        if let Some(tail) = &mut self.tail {
+            if *tail == 0 {
+                return None;
+            }
+            *tail -= 1;
+        }
+
+        let delayed = self.buffer[self.cursor];
+        self.buffer[self.cursor] = input + delayed * self.feedback;
+        self.cursor = (self.cursor + 1) % self.buffer.len();
+
+        Some(input + delayed * self.wet)
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.source.sample_rate()
+    }
+
+    fn sample_count(&self) -> Option<u64> {
+        Some(self.source.sample_count()? + self.tail_len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::BufferedSource;
+
+    #[test]
+    fn delay() {
+        let source = BufferedSource::new([1.0, 0.0], 1, 2);
+        let samples = Delay::new(
+            source,
+            DelayParams {
+                delay: Duration::from_secs(1),
+                feedback: 0.5,
+                wet: 1.0,
+            },
+        )
+        .samples_iter()
+        .map(|v| v.x)
+        .collect_vec();
+
+        // An echo every 2 samples, halving each time, until below -60 dB
+        assert_eq!(samples.len(), 2 + 2 * 11);
+        assert_eq!(&samples[..8], [1.0, 0.0, 1.0, 0.0, 0.5, 0.0, 0.25, 0.0]);
+        assert!(samples.last().unwrap().abs() < SILENCE);
+    }
+
+    #[test]
+    fn delay_is_clamped() {
+        let source = BufferedSource::new([1.0, 0.0], 1, 2);
+        let delay = Delay::new(
+            source,
+            DelayParams {
+                delay: Duration::MAX,
+                feedback: 0.0,
+                wet: 1.0,
+            },
+        );
+
+        assert_eq!(delay.buffer.len(), 2 * MAX_DELAY.as_secs() as usize);
+    }
+}
@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Frame, SampleRate, Source};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CompressorParams {
+    /// The level above which the signal is compressed, in dBFS
+    pub threshold: f32,
+    /// How much the signal above the threshold is reduced, e.g. `4.0` for 4:1.
+    ///
+    /// An infinite ratio makes the compressor a limiter.
+    pub ratio: f32,
+    /// How quickly the compressor reacts to the signal rising above the threshold
+    pub attack: Duration,
+    /// How quickly the compressor recovers once the signal falls below the threshold
+    pub release: Duration,
+    /// Gain applied after compression, in dB
+    pub makeup_gain: f32,
+}
+
+impl CompressorParams {
+    /// A limiter which keeps the peaks of the signal below `threshold` dBFS
+    pub fn limiter(threshold: f32) -> Self {
+        Self {
+            threshold,
+            ratio: f32::INFINITY,
+            attack: Duration::ZERO,
+            release: Duration::from_millis(100),
+            makeup_gain: 0.0,
+        }
+    }
+}
+
+impl Default for CompressorParams {
+    fn default() -> Self {
+        Self {
+            threshold: -18.0,
+            ratio: 4.0,
+            attack: Duration::from_millis(5),
+            release: Duration::from_millis(100),
+            makeup_gain: 0.0,
+        }
+    }
+}
+
+/// The envelope follower and gain computer of a compressor.
+///
+/// This processes one frame at a time, so it can be used both by [Compressor] and by sources
+/// which apply dynamics to their own output, such as the mixer.
+#[derive(Debug, Clone)]
+pub struct DynamicsProcessor {
+    threshold: f32,
+    slope: f32,
+    makeup_gain: f32,
+    attack: f32,
+    release: f32,
+    envelope: f32,
+}
+
+impl DynamicsProcessor {
+    pub fn new(params: CompressorParams, sample_rate: SampleRate) -> Self {
+        let coefficient = |time: Duration| {
+            let samples = time.as_secs_f32() * sample_rate as f32;
+            if samples > 0.0 {
+                (-1.0 / samples).exp()
+            } else {
+                0.0
+            }
+        };
+
+        Self {
+            threshold: params.threshold,
+            slope: 1.0 - 1.0 / params.ratio.max(1.0),
+            makeup_gain: params.makeup_gain,
+            attack: coefficient(params.attack),
+            release: coefficient(params.release),
+            envelope: 0.0,
+        }
+    }
+
+    #[inline]
+    pub fn process(&mut self, frame: Frame) -> Frame {
+        let peak = frame.x.abs().max(frame.y.abs());
+        let coefficient = if peak > self.envelope {
+            self.attack
+        } else {
+            self.release
+        };
+        self.envelope = coefficient * self.envelope + (1.0 - coefficient) * peak;
+
+        let level = 20.0 * self.envelope.max(1e-9).log10();
+        let reduction = (level - self.threshold).max(0.0) * self.slope;
+
+        frame * db_to_gain(self.makeup_gain - reduction)
+    }
+}
+
+#[inline]
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Reduces the dynamic range of a source by attenuating it when it is above a threshold
+#[derive(Debug, Clone)]
+pub struct Compressor<S> {
+    source: S,
+    processor: DynamicsProcessor,
+}
+
+impl<S: Source> Compressor<S> {
+    pub fn new(source: S, params: CompressorParams) -> Self {
+        let processor = DynamicsProcessor::new(params, source.sample_rate());
+        Self { source, processor }
+    }
+}
+
+impl<S: Source> Source for Compressor<S> {
+    #[inline]
+    fn next_sample(&mut self) -> Option<Frame> {
+        Some(self.processor.process(self.source.next_sample()?))
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.source.sample_rate()
+    }
+
+    fn sample_count(&self) -> Option<u64> {
+        self.source.sample_count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::BufferedSource;
+
+    #[test]
+    fn limiter() {
+        let source = BufferedSource::new([0.25, 1.0, -2.0, 0.5], 1, 4);
+        let samples = Compressor::new(source, CompressorParams::limiter(-6.0))
+            .samples_iter()
+            .map(|v| v.x)
+            .collect_vec();
+
+        let threshold = db_to_gain(-6.0);
+        assert_eq!(samples[0], 0.25);
+        approx::assert_relative_eq!(samples[1], threshold, epsilon = 1e-4);
+        approx::assert_relative_eq!(samples[2], -threshold, epsilon = 1e-4);
+        assert!(samples.iter().all(|v| v.abs() <= threshold + 1e-4));
+    }
+
+    #[test]
+    fn compressor() {
+        let source = BufferedSource::new(vec![1.0; 100], 1, 100);
+        let params = CompressorParams {
+            threshold: -12.0,
+            ratio: 2.0,
+            attack: Duration::ZERO,
+            ..Default::default()
+        };
+        let samples = Compressor::new(source, params).samples_iter().collect_vec();
+
+        // 12 dB above the threshold at 2:1 is reduced by 6 dB
+        approx::assert_relative_eq!(samples[99].x, db_to_gain(-6.0), epsilon = 1e-4);
+    }
+}
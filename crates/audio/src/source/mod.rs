@@ -1,14 +1,19 @@
 mod buffered;
 mod chain;
 mod crossfade;
+mod delay;
 pub(crate) mod dynamic_delay;
+mod dynamics;
 pub mod gain;
 pub mod history;
 mod mix;
 mod oscilloscope;
 mod pad_to;
 mod peek;
+mod playback_rate;
+mod progress;
 mod repeat;
+mod reverb;
 mod sample_bufferer;
 mod sample_rate;
 mod slice;
@@ -28,11 +33,16 @@ pub use buffered::*;
 pub use chain::*;
 use circular_queue::CircularQueue;
 pub use crossfade::*;
+pub use delay::*;
+pub use dynamics::*;
 pub use gain::*;
 pub use mix::*;
 use parking_lot::Mutex;
 pub use peek::*;
+pub use playback_rate::*;
+pub use progress::*;
 pub use repeat::*;
+pub use reverb::*;
 pub use sample_rate::*;
 pub use slice::*;
 pub use spatial::*;
@@ -194,6 +204,51 @@ pub trait Source: Send {
         BilinearTransform::new(self, transfer)
     }
 
+    fn playback_rate<V>(self, rate: V) -> PlaybackRate<Self, V>
+    where
+        Self: Sized,
+        V: for<'x> Value<'x, Item = f32>,
+    {
+        PlaybackRate::new(self, rate)
+    }
+
+    fn progress(self, state: Arc<ProgressState>, start: Duration) -> Progress<Self>
+    where
+        Self: Sized,
+    {
+        Progress::new(self, state, start)
+    }
+
+    /// Repeats the source as a series of decaying echoes
+    fn delay(self, params: DelayParams) -> Delay<Self>
+    where
+        Self: Sized,
+    {
+        Delay::new(self, params)
+    }
+
+    fn reverb(self, params: ReverbParams) -> Reverb<Self>
+    where
+        Self: Sized,
+    {
+        Reverb::new(self, params)
+    }
+
+    fn compress(self, params: CompressorParams) -> Compressor<Self>
+    where
+        Self: Sized,
+    {
+        Compressor::new(self, params)
+    }
+
+    /// Keeps the peaks of the source below `threshold` dBFS
+    fn limit(self, threshold: f32) -> Compressor<Self>
+    where
+        Self: Sized,
+    {
+        Compressor::new(self, CompressorParams::limiter(threshold))
+    }
+
     fn history(self, freq: f32, buf: Arc<Mutex<CircularQueue<Frame>>>) -> History<Self>
     where
         Self: Sized,
@@ -0,0 +1,125 @@
+use crate::{value::Value, Frame, SampleRate, Source};
+
+/// The highest rate a [PlaybackRate] plays at. Higher rates are clamped to this, which bounds the
+/// number of samples read from the source for each output sample.
+pub const MAX_PLAYBACK_RATE: f32 = 16.0;
+
+/// Changes the speed, and thereby the pitch, of a source.
+///
+/// A rate of `2.0` plays the source twice as fast and an octave higher. The rate is clamped to
+/// `0.0..=MAX_PLAYBACK_RATE`.
+#[derive(Debug, Clone)]
+pub struct PlaybackRate<S, V> {
+    source: S,
+    rate: V,
+    prev: Frame,
+    next: Option<Frame>,
+    /// Position between `prev` and `next`
+    t: f32,
+    started: bool,
+}
+
+impl<S, V> PlaybackRate<S, V>
+where
+    S: Source,
+    V: for<'x> Value<'x, Item = f32>,
+{
+    pub fn new(source: S, rate: V) -> Self {
+        Self {
+            source,
+            rate,
+            prev: Frame::ZERO,
+            next: None,
+            t: 0.0,
+            started: false,
+        }
+    }
+}
+
+impl<S, V> Source for PlaybackRate<S, V>
+where
+    S: Source,
+    V: for<'x> Value<'x, Item = f32>,
+{
+    fn next_sample(&mut self) -> Option<Frame> {
+        if !self.started {
+            self.prev = self.source.next_sample()?;
+            self.next = self.source.next_sample();
+            self.started = true;
+        }
+
+        while self.t >= 1.0 {
+            self.prev = self.next?;
+            self.next = self.source.next_sample();
+            self.t -= 1.0;
+        }
+
+        let sample = match self.next {
+            Some(next) => self.prev.lerp(next, self.t),
+            None => self.prev,
+        };
+        self.t += (*self.rate.get()).max(0.0).min(MAX_PLAYBACK_RATE);
+        Some(sample)
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.source.sample_rate()
+    }
+
+    fn sample_count(&self) -> Option<u64> {
+        // The rate may change while playing
+        None
+    }
+
+    fn duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{value::Constant, BufferedSource};
+
+    #[test]
+    fn playback_rate() {
+        let source = BufferedSource::new([0.0, 1.0, 2.0, 3.0, 4.0], 1, 4);
+
+        let samples = PlaybackRate::new(source.clone(), Constant(2.0))
+            .samples_iter()
+            .collect_vec();
+        assert_eq!(
+            samples,
+            [Frame::splat(0.0), Frame::splat(2.0), Frame::splat(4.0)]
+        );
+
+        let samples = PlaybackRate::new(source, Constant(0.5))
+            .samples_iter()
+            .take(4)
+            .collect_vec();
+        assert_eq!(
+            samples,
+            [
+                Frame::splat(0.0),
+                Frame::splat(0.5),
+                Frame::splat(1.0),
+                Frame::splat(1.5)
+            ]
+        );
+    }
+
+    #[test]
+    fn playback_rate_is_clamped() {
+        let source = BufferedSource::new((0..40).map(|v| v as f32).collect_vec(), 1, 4);
+
+        let samples = PlaybackRate::new(source, Constant(f32::INFINITY))
+            .samples_iter()
+            .collect_vec();
+        assert_eq!(
+            samples,
+            [Frame::splat(0.0), Frame::splat(16.0), Frame::splat(32.0)]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{to_sample_index, Frame, SampleRate, Source};
+
+/// The playback position of a source, which can be read from other threads while it plays
+#[derive(Debug, Default)]
+pub struct ProgressState {
+    samples: AtomicU64,
+    sample_rate: AtomicU64,
+    /// The length of a repeating source, or 0 if the source does not repeat
+    period: AtomicU64,
+    finished: AtomicBool,
+}
+
+impl ProgressState {
+    /// Returns how far into the source playback has come.
+    ///
+    /// For repeating sources, this is the position within the current repetition.
+    pub fn position(&self) -> Duration {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        if sample_rate == 0 {
+            return Duration::ZERO;
+        }
+
+        let mut samples = self.samples.load(Ordering::Relaxed);
+        let period = self.period.load(Ordering::Relaxed);
+        if period > 0 {
+            samples %= period;
+        }
+        Duration::from_nanos(samples * 1_000_000_000 / sample_rate)
+    }
+
+    /// Returns true if the source has run out of samples, or has been marked as finished
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    pub fn set_finished(&self) {
+        self.finished.store(true, Ordering::Relaxed);
+    }
+
+    /// Sets the length of one repetition of a repeating source
+    pub fn set_period(&self, period: Duration) {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        self.period
+            .store(to_sample_index(sample_rate, period), Ordering::Relaxed);
+    }
+}
+
+/// Reports the playback position of a source to a shared [ProgressState]
+#[derive(Debug, Clone)]
+pub struct Progress<S> {
+    source: S,
+    state: Arc<ProgressState>,
+}
+
+impl<S: Source> Progress<S> {
+    /// Starts tracking the source, which begins at `start` into the underlying audio
+    pub fn new(source: S, state: Arc<ProgressState>, start: Duration) -> Self {
+        let sample_rate = source.sample_rate();
+        state.sample_rate.store(sample_rate, Ordering::Relaxed);
+        state
+            .samples
+            .store(to_sample_index(sample_rate, start), Ordering::Relaxed);
+        state.finished.store(false, Ordering::Relaxed);
+
+        Self { source, state }
+    }
+}
+
+impl<S: Source> Source for Progress<S> {
+    #[inline]
+    fn next_sample(&mut self) -> Option<Frame> {
+        match self.source.next_sample() {
+            Some(sample) => {
+                self.state.samples.fetch_add(1, Ordering::Relaxed);
+                Some(sample)
+            }
+            None => {
+                self.state.set_finished();
+                None
+            }
+        }
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.source.sample_rate()
+    }
+
+    fn sample_count(&self) -> Option<u64> {
+        self.source.sample_count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::BufferedSource;
+
+    #[test]
+    fn progress() {
+        let state = Arc::new(ProgressState::default());
+        let source = BufferedSource::new([0.0, 1.0, 2.0, 3.0], 1, 2);
+        let mut source = Progress::new(source, state.clone(), Duration::from_secs(1));
+
+        assert_eq!(state.position(), Duration::from_secs(1));
+        source.next_sample();
+        assert_eq!(state.position(), Duration::from_millis(1500));
+        assert!(!state.is_finished());
+
+        while source.next_sample().is_some() {}
+        assert_eq!(state.position(), Duration::from_secs(3));
+        assert!(state.is_finished());
+
+        state.set_period(Duration::from_secs(2));
+        assert_eq!(state.position(), Duration::from_secs(1));
+    }
+}
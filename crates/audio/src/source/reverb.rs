@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Frame, SampleRate, Source};
+
+/// Comb filter lengths in samples at 44.1 kHz, from Freeverb
+const COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+/// Allpass filter lengths in samples at 44.1 kHz, from Freeverb
+const ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
+/// Offset of the right channel filters, which decorrelates the channels
+const STEREO_SPREAD: usize = 23;
+const TUNING_SAMPLE_RATE: f32 = 44100.0;
+
+const INPUT_GAIN: f32 = 0.015;
+const ALLPASS_FEEDBACK: f32 = 0.5;
+/// The level at which the tail is considered to have decayed, -60 dB
+const SILENCE: f32 = 0.001;
+/// Upper bound on the tail, in seconds
+const MAX_TAIL: f32 = 30.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReverbParams {
+    /// The size of the simulated room in `0..1`. Larger rooms have a longer tail
+    pub room_size: f32,
+    /// How quickly high frequencies are absorbed, in `0..1`
+    pub damping: f32,
+    /// The volume of the reverberated signal
+    pub wet: f32,
+    /// The volume of the unprocessed signal
+    pub dry: f32,
+    /// The stereo width of the reverb in `0..1`
+    pub width: f32,
+}
+
+impl Default for ReverbParams {
+    fn default() -> Self {
+        Self {
+            room_size: 0.5,
+            damping: 0.5,
+            wet: 0.3,
+            dry: 1.0,
+            width: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Comb {
+    buffer: Box<[f32]>,
+    cursor: usize,
+    store: f32,
+}
+
+impl Comb {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len.max(1)].into_boxed_slice(),
+            cursor: 0,
+            store: 0.0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.cursor];
+        self.store = output * (1.0 - damping) + self.store * damping;
+        self.buffer[self.cursor] = input + self.store * feedback;
+        self.cursor = (self.cursor + 1) % self.buffer.len();
+        output
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Allpass {
+    buffer: Box<[f32]>,
+    cursor: usize,
+}
+
+impl Allpass {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len.max(1)].into_boxed_slice(),
+            cursor: 0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.cursor];
+        self.buffer[self.cursor] = input + delayed * ALLPASS_FEEDBACK;
+        self.cursor = (self.cursor + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Channel {
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+}
+
+impl Channel {
+    fn new(scale: f32, spread: usize) -> Self {
+        let len = |tuning: usize| ((tuning + spread) as f32 * scale) as usize;
+        Self {
+            combs: COMB_TUNING.iter().map(|&v| Comb::new(len(v))).collect(),
+            allpasses: ALLPASS_TUNING
+                .iter()
+                .map(|&v| Allpass::new(len(v)))
+                .collect(),
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self
+            .combs
+            .iter_mut()
+            .map(|comb| comb.process(input, feedback, damping))
+            .sum();
+
+        self.allpasses
+            .iter_mut()
+            .fold(output, |acc, allpass| allpass.process(acc))
+    }
+}
+
+/// An algorithmic reverb, based on the Schroeder-Moorer design of Freeverb.
+///
+/// Each channel runs the input through parallel lowpass-feedback comb filters followed by
+/// allpass filters in series. After the source has finished the tail keeps playing until it has
+/// decayed.
+#[derive(Debug, Clone)]
+pub struct Reverb<S> {
+    source: S,
+    left: Channel,
+    right: Channel,
+    feedback: f32,
+    damping: f32,
+    wet1: f32,
+    wet2: f32,
+    dry: f32,
+    tail_len: u64,
+    /// Remaining samples of the tail once the source has finished
+    tail: Option<u64>,
+}
+
+impl<S: Source> Reverb<S> {
+    pub fn new(source: S, params: ReverbParams) -> Self {
+        let sample_rate = source.sample_rate();
+        let scale = sample_rate as f32 / TUNING_SAMPLE_RATE;
+
+        let feedback = params.room_size.clamp(0.0, 1.0) * 0.28 + 0.7;
+        let damping = params.damping.clamp(0.0, 1.0) * 0.4;
+        let width = params.width.clamp(0.0, 1.0);
+        // Scaled to match the original Freeverb levels
+        let wet = params.wet * 3.0;
+
+        // Each pass through the longest comb filter decays the signal by `feedback`
+        let passes = SILENCE.ln() / feedback.ln();
+        let longest = (COMB_TUNING[COMB_TUNING.len() - 1] + STEREO_SPREAD) as f32 * scale;
+        let tail_len = (passes * longest).min(MAX_TAIL * sample_rate as f32) as u64;
+
+        Self {
+            source,
+            left: Channel::new(scale, 0),
+            right: Channel::new(scale, STEREO_SPREAD),
+            feedback,
+            damping,
+            wet1: wet * (width / 2.0 + 0.5),
+            wet2: wet * ((1.0 - width) / 2.0),
+            dry: params.dry,
+            tail_len,
+            tail: None,
+        }
+    }
+}
+
+impl<S: Source> Source for Reverb<S> {
+    fn next_sample(&mut self) -> Option<Frame> {
+        let input = match self.tail {
+            None => match self.source.next_sample() {
+                Some(sample) => sample,
+                None => {
+                    self.tail = Some(self.tail_len);
+                    Frame::ZERO
+                }
+            },
+            Some(_) => Frame::ZERO,
+        };
+
+        if let Some(tail) = &mut self.tail {
+            if *tail == 0 {
+                return None;
+            }
+            *tail -= 1;
+        }
+
+        let mono = (input.x + input.y) * INPUT_GAIN;
+        let left = self.left.process(mono, self.feedback, self.damping);
+        let right = self.right.process(mono, self.feedback, self.damping);
+
+        Some(Frame::new(
+            left * self.wet1 + right * self.wet2 + input.x * self.dry,
+            right * self.wet1 + left * self.wet2 + input.y * self.dry,
+        ))
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.source.sample_rate()
+    }
+
+    fn sample_count(&self) -> Option<u64> {
+        Some(self.source.sample_count()? + self.tail_len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::BufferedSource;
+
+    #[test]
+    fn reverb_tail() {
+        let mut input = vec![0.0; 1000];
+        input[0] = 1.0;
+        let source = BufferedSource::new(input, 1, 44100);
+
+        let samples = Reverb::new(
+            source,
+            ReverbParams {
+                dry: 0.0,
+                ..Default::default()
+            },
+        )
+        .samples_iter()
+        .collect_vec();
+
+        assert!(samples.len() > 1000);
+        // The first reflection arrives after the shortest comb filter
+        assert!(samples[..COMB_TUNING[0]].iter().all(|v| *v == Frame::ZERO));
+        assert!(samples.iter().any(|v| v.x.abs() > 0.0));
+
+        let end = &samples[samples.len() - 100..];
+        assert!(end
+            .iter()
+            .all(|v| v.x.abs() < SILENCE && v.y.abs() < SILENCE));
+    }
+}
@@ -0,0 +1,257 @@
+use std::{io::Cursor, sync::Arc};
+
+use derivative::Derivative;
+use glam::vec2;
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader},
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+use crate::{Error, Frame, Result, SampleRate, Source};
+
+/// A track in a format decoded by symphonia, such as FLAC or MP3
+#[derive(Derivative, Clone)]
+#[derivative(Debug)]
+pub struct SymphoniaTrack {
+    #[derivative(Debug = "ignore")]
+    bytes: Arc<[u8]>,
+    /// The file extension, which is used as a hint for the container format
+    extension: &'static str,
+    /// The number of frames in the fully decoded audio
+    decoded_len: usize,
+}
+
+impl SymphoniaTrack {
+    pub fn new(bytes: impl Into<Arc<[u8]>>, extension: &'static str) -> Result<Self> {
+        // Decode once to ensure there are no encoding errors, and acquire the duration
+        let bytes = bytes.into();
+        let mut stream = SymphoniaDecodeStream::open(bytes.clone(), extension, 0)?;
+
+        let mut len = 0;
+        while stream.read_packet()? {
+            len += stream.packet.len();
+        }
+
+        Ok(Self {
+            bytes,
+            extension,
+            decoded_len: len,
+        })
+    }
+
+    pub fn decode(&self) -> Result<SymphoniaDecodeStream> {
+        SymphoniaDecodeStream::open(self.bytes.clone(), self.extension, self.decoded_len)
+    }
+}
+
+/// Audio source which decodes a stream using symphonia
+pub struct SymphoniaDecodeStream {
+    bytes: Arc<[u8]>,
+    extension: &'static str,
+    /// `None` if the stream could not be reopened when it was cloned, which ends the stream
+    reader: Option<Reader>,
+    sample_rate: SampleRate,
+    decoded_len: usize,
+    sample_buf: Option<SampleBuffer<f32>>,
+    packet: Vec<Frame>,
+    cursor: usize,
+}
+
+struct Reader {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+}
+
+impl SymphoniaDecodeStream {
+    fn open(bytes: Arc<[u8]>, extension: &'static str, decoded_len: usize) -> Result<Self> {
+        let mut hint = Hint::new();
+        hint.with_extension(extension);
+
+        let source =
+            MediaSourceStream::new(Box::new(Cursor::new(bytes.clone())), Default::default());
+        let format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                source,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )?
+            .format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or(Error::NoAudioTrack)?;
+        let sample_rate = track.codec_params.sample_rate.ok_or(Error::NoAudioTrack)?;
+        let track_id = track.id;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+
+        Ok(Self {
+            bytes,
+            extension,
+            reader: Some(Reader {
+                format,
+                decoder,
+                track_id,
+            }),
+            sample_rate: sample_rate as _,
+            decoded_len,
+            sample_buf: None,
+            packet: Vec::new(),
+            cursor: 0,
+        })
+    }
+
+    /// Decodes the next packet of the track into `self.packet`.
+    ///
+    /// Returns false at the end of the stream.
+    fn read_packet(&mut self) -> Result<bool> {
+        let Some(reader) = &mut self.reader else {
+            return Ok(false);
+        };
+
+        loop {
+            let packet = match reader.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(err))
+                    if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(false)
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            if packet.track_id() != reader.track_id {
+                continue;
+            }
+
+            let decoded = match reader.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // Corrupt packets are skipped rather than ending the stream
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            let spec = *decoded.spec();
+            let sample_buf = match &mut self.sample_buf {
+                Some(buf) if buf.capacity() >= decoded.capacity() * spec.channels.count() => buf,
+                buf => buf.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+            };
+            sample_buf.copy_interleaved_ref(decoded);
+            let samples = sample_buf.samples();
+
+            self.packet.clear();
+            self.cursor = 0;
+            match spec.channels.count() {
+                1 => self
+                    .packet
+                    .extend(samples.iter().copied().map(Frame::splat)),
+                2 => self.packet.extend(
+                    samples
+                        .chunks_exact(2)
+                        .map(|frame| vec2(frame[0], frame[1])),
+                ),
+                channels => return Err(Error::InvalidChannelCount(channels as _)),
+            }
+
+            if !self.packet.is_empty() {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+impl Clone for SymphoniaDecodeStream {
+    fn clone(&self) -> Self {
+        match Self::open(self.bytes.clone(), self.extension, self.decoded_len) {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::error!("Failed to reopen audio stream: {err}");
+                Self {
+                    bytes: self.bytes.clone(),
+                    extension: self.extension,
+                    reader: None,
+                    sample_rate: self.sample_rate,
+                    decoded_len: self.decoded_len,
+                    sample_buf: None,
+                    packet: Vec::new(),
+                    cursor: 0,
+                }
+            }
+        }
+    }
+}
+
+impl Source for SymphoniaDecodeStream {
+    #[inline]
+    fn next_sample(&mut self) -> Option<Frame> {
+        if let Some(&s) = self.packet.get(self.cursor) {
+            self.cursor += 1;
+            Some(s)
+        } else {
+            match self.read_packet() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(err) => {
+                    log::error!("Failed to decode audio: {err}");
+                    return None;
+                }
+            }
+            self.cursor = 1;
+            Some(self.packet[0])
+        }
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn sample_count(&self) -> Option<u64> {
+        Some(self.decoded_len as _)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hound::{SampleFormat, WavSpec, WavWriter};
+    use itertools::Itertools;
+
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(
+            &mut bytes,
+            WavSpec {
+                channels: 2,
+                sample_rate: 100,
+                bits_per_sample: 32,
+                sample_format: SampleFormat::Float,
+            },
+        )
+        .unwrap();
+        for i in 0..300 {
+            writer.write_sample(i as f32 / 300.0).unwrap();
+            writer.write_sample(-(i as f32) / 300.0).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let track = SymphoniaTrack::new(bytes.into_inner(), "wav").unwrap();
+        let source = track.decode().unwrap();
+        assert_eq!(source.sample_rate(), 100);
+        assert_eq!(source.sample_count(), Some(300));
+
+        let samples = source.samples_iter().collect_vec();
+        assert_eq!(samples.len(), 300);
+        assert_eq!(samples[150], vec2(0.5, -0.5));
+    }
+}
@@ -4,6 +4,7 @@ use derive_more::From;
 
 use crate::{
     error::Result,
+    symphonia_track::{SymphoniaDecodeStream, SymphoniaTrack},
     vorbis::{VorbisDecodeStream, VorbisTrack},
     wav::{WavDecodeStream, WavTrack},
     Source,
@@ -13,6 +14,8 @@ use crate::{
 pub enum AudioFormat {
     Wav,
     Vorbis,
+    Flac,
+    Mp3,
 }
 
 #[derive(From, Debug, Clone)]
@@ -22,6 +25,8 @@ pub enum AudioFormat {
 pub enum Track {
     Vorbis(VorbisTrack),
     Wav(WavTrack),
+    /// FLAC or MP3
+    Symphonia(SymphoniaTrack),
 }
 
 impl Track {
@@ -33,18 +38,29 @@ impl Track {
         Ok(Self::Vorbis(VorbisTrack::new(bytes.into())?))
     }
 
+    pub fn from_flac(bytes: impl Into<Arc<[u8]>>) -> Result<Self> {
+        Ok(Self::Symphonia(SymphoniaTrack::new(bytes, "flac")?))
+    }
+
+    pub fn from_mp3(bytes: impl Into<Arc<[u8]>>) -> Result<Self> {
+        Ok(Self::Symphonia(SymphoniaTrack::new(bytes, "mp3")?))
+    }
+
     pub fn from_format(bytes: impl Into<Arc<[u8]>>, format: AudioFormat) -> Result<Self> {
         match format {
             AudioFormat::Wav => Self::from_wav(bytes.into()),
             AudioFormat::Vorbis => Self::from_vorbis(bytes.into()),
+            AudioFormat::Flac => Self::from_flac(bytes.into()),
+            AudioFormat::Mp3 => Self::from_mp3(bytes.into()),
         }
     }
 
-    pub fn decode(&self) -> TrackDecodeStream {
-        match self {
+    pub fn decode(&self) -> Result<TrackDecodeStream> {
+        Ok(match self {
             Track::Vorbis(v) => TrackDecodeStream::Vorbis(Box::new(v.decode())),
             Track::Wav(v) => TrackDecodeStream::Wav(v.decode()),
-        }
+            Track::Symphonia(v) => TrackDecodeStream::Symphonia(Box::new(v.decode()?)),
+        })
     }
 }
 
@@ -52,6 +68,7 @@ impl Track {
 pub enum TrackDecodeStream {
     Vorbis(Box<VorbisDecodeStream>),
     Wav(WavDecodeStream),
+    Symphonia(Box<SymphoniaDecodeStream>),
 }
 
 impl Source for TrackDecodeStream {
@@ -59,6 +76,7 @@ impl Source for TrackDecodeStream {
         match self {
             TrackDecodeStream::Vorbis(v) => v.next_sample(),
             TrackDecodeStream::Wav(v) => v.next_sample(),
+            TrackDecodeStream::Symphonia(v) => v.next_sample(),
         }
     }
 
@@ -66,6 +84,7 @@ impl Source for TrackDecodeStream {
         match self {
             TrackDecodeStream::Vorbis(v) => v.sample_buffered(output),
             TrackDecodeStream::Wav(v) => v.sample_buffered(output),
+            TrackDecodeStream::Symphonia(v) => v.sample_buffered(output),
         }
     }
 
@@ -73,6 +92,7 @@ impl Source for TrackDecodeStream {
         match self {
             TrackDecodeStream::Vorbis(v) => v.sample_rate(),
             TrackDecodeStream::Wav(v) => v.sample_rate(),
+            TrackDecodeStream::Symphonia(v) => v.sample_rate(),
         }
     }
 
@@ -80,6 +100,7 @@ impl Source for TrackDecodeStream {
         match self {
             TrackDecodeStream::Vorbis(v) => v.sample_count(),
             TrackDecodeStream::Wav(v) => v.sample_count(),
+            TrackDecodeStream::Symphonia(v) => v.sample_count(),
         }
     }
 }
@@ -267,6 +267,9 @@ impl From<json_pipeline::AudioPipeline> for AudioPipeline {
     fn from(value: json_pipeline::AudioPipeline) -> Self {
         Self {
             convert: value.convert,
+            quality: None,
+            sample_rate: None,
+            normalize_loudness: None,
         }
     }
 }
@@ -9,83 +9,91 @@ use super::{
     out_asset::{asset_id_from_url, OutAsset, OutAssetContent, OutAssetPreview},
 };
 
+mod processing;
+
 pub const SOUND_GRAPH_EXTENSION: &str = "sgr";
 
+/// The Vorbis quality used when the pipeline does not specify one
+const DEFAULT_QUALITY: f32 = 0.9;
+
 pub async fn pipeline(ctx: &PipelineCtx, config: AudioPipeline) -> Vec<OutAsset> {
     ctx.process_files(
         |file| {
             matches!(
                 file.extension().as_deref(),
-                Some("ogg") | Some("wav") | Some("mp3")
+                Some("ogg") | Some("wav") | Some("mp3") | Some("flac")
             )
         },
-        move |ctx, file| async move {
-            let contents = file.download_bytes(ctx.assets()).await?;
+        move |ctx, file| {
+            let config = config.clone();
+            async move {
+                let contents = file.download_bytes(ctx.assets()).await?;
 
-            let filename = file.decoded_path().file_name().unwrap().to_string();
+                let filename = file.decoded_path().file_name().unwrap().to_string();
 
-            let rel_path = ctx.in_root().relative_path(file.decoded_path());
+                let rel_path = ctx.in_root().relative_path(file.decoded_path());
 
-            let content_url = match file.extension().as_deref() {
-                Some("wav") => {
-                    if config.convert {
-                        tracing::info!("Processing wav file");
-                        let contents = symphonia_convert("wav", contents).await?;
+                let content_url = match file.extension().as_deref() {
+                    Some("wav") => {
+                        if config.convert {
+                            tracing::info!("Processing wav file");
+                            let contents = symphonia_convert("wav", contents, &config).await?;
+                            ctx.write_file(rel_path.with_extension("ogg"), contents)
+                                .await
+                        } else {
+                            ctx.write_file(&rel_path, contents).await
+                        }
+                    }
+                    Some("ogg") => ctx.write_file(&rel_path, contents).await,
+                    Some(ext @ ("mp3" | "flac")) => {
+                        tracing::info!("Processing {ext} file");
+                        // Make sure to take the contents, to avoid having both the input and output in
+                        // memory at once
+                        let contents = symphonia_convert(ext, contents, &config).await?;
                         ctx.write_file(rel_path.with_extension("ogg"), contents)
                             .await
-                    } else {
-                        ctx.write_file(&rel_path, contents).await
                     }
-                }
-                Some("ogg") => ctx.write_file(&rel_path, contents).await,
-                Some(ext @ "mp3") => {
-                    tracing::info!("Processing mp3 file");
-                    // Make sure to take the contents, to avoid having both the input and output in
-                    // memory at once
-                    let contents = symphonia_convert(ext, contents).await?;
-                    ctx.write_file(rel_path.with_extension("ogg"), contents)
-                        .await
-                }
-                other => anyhow::bail!(
-                    "Audio filetype {:?} is not yet supported",
-                    other.unwrap_or_default()
-                ),
-            };
-
-            let root_node = AudioNode::Vorbis {
-                url: content_url.to_string(),
-            };
-            let graph_url = ctx
-                .write_file(
-                    &rel_path.with_extension(SOUND_GRAPH_EXTENSION),
-                    save_audio_graph(root_node).unwrap(),
-                )
-                .await;
-
-            Ok(vec![
-                OutAsset {
-                    id: asset_id_from_url(&file),
-                    type_: AssetType::VorbisTrack,
-                    hidden: false,
-                    name: filename.clone(),
-                    tags: Vec::new(),
-                    categories: Default::default(),
-                    preview: OutAssetPreview::None,
-                    content: OutAssetContent::Content(content_url),
-                    source: Some(file.clone()),
-                },
-                OutAsset {
-                    id: asset_id_from_url(&file.push("graph").unwrap()),
-                    type_: AssetType::SoundGraph,
-                    hidden: false,
-                    name: filename,
-                    tags: Vec::new(),
-                    categories: Default::default(),
-                    preview: OutAssetPreview::None,
-                    content: OutAssetContent::Content(graph_url),
-                    source: None,
-                },
-            ])
+                    other => anyhow::bail!(
+                        "Audio filetype {:?} is not yet supported",
+                        other.unwrap_or_default()
+                    ),
+                };
+
+                let root_node = AudioNode::Vorbis {
+                    url: content_url.to_string(),
+                };
+                let graph_url = ctx
+                    .write_file(
+                        &rel_path.with_extension(SOUND_GRAPH_EXTENSION),
+                        save_audio_graph(root_node).unwrap(),
+                    )
+                    .await;
+
+                Ok(vec![
+                    OutAsset {
+                        id: asset_id_from_url(&file),
+                        type_: AssetType::VorbisTrack,
+                        hidden: false,
+                        name: filename.clone(),
+                        tags: Vec::new(),
+                        categories: Default::default(),
+                        preview: OutAssetPreview::None,
+                        content: OutAssetContent::Content(content_url),
+                        source: Some(file.clone()),
+                    },
+                    OutAsset {
+                        id: asset_id_from_url(&file.push("graph").unwrap()),
+                        type_: AssetType::SoundGraph,
+                        hidden: false,
+                        name: filename,
+                        tags: Vec::new(),
+                        categories: Default::default(),
+                        preview: OutAssetPreview::None,
+                        content: OutAssetContent::Content(graph_url),
+                        source: None,
+                    },
+                ])
+            }
         },
     )
     .instrument(info_span!("audio_pipeline"))
@@ -98,8 +106,12 @@ fn save_audio_graph(root: AudioNode) -> anyhow::Result<Vec<u8>> {
         .into_bytes())
 }
 
-#[tracing::instrument(level = "info", skip(input))]
-async fn symphonia_convert(ext: &str, input: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+#[tracing::instrument(level = "info", skip(input, config))]
+async fn symphonia_convert(
+    ext: &str,
+    input: Vec<u8>,
+    config: &AudioPipeline,
+) -> anyhow::Result<Vec<u8>> {
     use std::num::{NonZeroU32, NonZeroU8};
 
     use symphonia::core::{
@@ -140,6 +152,7 @@ async fn symphonia_convert(ext: &str, input: Vec<u8>) -> anyhow::Result<Vec<u8>>
         .iter()
         .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
         .context("Failed to select default audio track")?;
+    let track_id = track.id;
 
     // init an audio decoder with default options
     let dec_opts = DecoderOptions::default();
@@ -147,50 +160,30 @@ async fn symphonia_convert(ext: &str, input: Vec<u8>) -> anyhow::Result<Vec<u8>>
         .make(&track.codec_params, &dec_opts)
         .context("Failed to create audio decoder")?;
 
-    // randomize an ogg stream serial number
-    let stream_serial: i32 = rand::random();
-
     // retrieve the sampling rate from the input file
-    let sampling_rate: NonZeroU32 = decoder
+    let source_rate = decoder
         .codec_params()
         .sample_rate
-        .context("Expected audio to have sample rate")?
-        .try_into()
-        .context("Audio must have >0 sampling rate")?;
+        .context("Expected audio to have sample rate")?;
 
     // retrieve the channel count from the input file
-    let channels = decoder
+    let channel_count = decoder
         .codec_params()
         .channels
         .context("Audio does not have any channels")?
         .count();
-    let channels: NonZeroU8 = (channels as u8)
-        .try_into()
-        .context("Audio must have >0 channels")?;
-
-    // select a bitrate
-    let bitrate = VorbisBitrateManagementStrategy::QualityVbr {
-        target_quality: 0.9,
-    };
-
-    // create the ogg Vorbis encoder
-    let mut encoder = VorbisEncoder::new(
-        stream_serial,
-        [("", ""); 0], // no tags
-        sampling_rate,
-        channels,
-        bitrate,
-        None,
-        Vec::new(),
-    )?;
 
-    // process all packets in the input file
+    // decode the whole file, as normalization needs to know the loudness of all of it
+    let mut channels = vec![Vec::new(); channel_count];
     let result = loop {
         // read the next packet
         let packet = match format.next_packet() {
             Ok(packet) => packet,
             Err(err) => break err,
         };
+        if packet.track_id() != track_id {
+            continue;
+        }
 
         // decode the packet's samples
         let decoded = match decoder.decode(&packet) {
@@ -202,12 +195,9 @@ async fn symphonia_convert(ext: &str, input: Vec<u8>) -> anyhow::Result<Vec<u8>>
         let mut block = decoded.make_equivalent::<f32>();
         decoded.convert(&mut block);
 
-        // get the samples as &[&[f32]]
-        let planes = block.planes();
-        let plane_samples = planes.planes();
-
-        // feed the samples into the encoder
-        encoder.encode_audio_block(plane_samples)?;
+        for (channel, plane) in channels.iter_mut().zip(block.planes().planes()) {
+            channel.extend_from_slice(plane);
+        }
     };
 
     // process the error returned by the loop
@@ -220,6 +210,61 @@ async fn symphonia_convert(ext: &str, input: Vec<u8>) -> anyhow::Result<Vec<u8>>
         err => return Err(err.into()),
     }
 
+    let sample_rate = match config.sample_rate {
+        Some(sample_rate) if sample_rate != source_rate => {
+            tracing::info!("Resampling from {source_rate} Hz to {sample_rate} Hz");
+            for channel in &mut channels {
+                *channel = processing::resample(channel, source_rate, sample_rate);
+            }
+            sample_rate
+        }
+        _ => source_rate,
+    };
+
+    if let Some(target) = config.normalize_loudness {
+        tracing::info!("Normalizing loudness to {target} LUFS");
+        processing::normalize_loudness(&mut channels, sample_rate, target);
+    }
+
+    // randomize an ogg stream serial number
+    let stream_serial: i32 = rand::random();
+
+    let sampling_rate: NonZeroU32 = sample_rate
+        .try_into()
+        .context("Audio must have >0 sampling rate")?;
+    let channel_count: NonZeroU8 = u8::try_from(channel_count)
+        .context("Audio has too many channels")?
+        .try_into()
+        .context("Audio must have >0 channels")?;
+
+    // select a bitrate
+    let bitrate = VorbisBitrateManagementStrategy::QualityVbr {
+        target_quality: config.quality.unwrap_or(DEFAULT_QUALITY).clamp(-0.1, 1.0),
+    };
+
+    // create the ogg Vorbis encoder
+    let mut encoder = VorbisEncoder::new(
+        stream_serial,
+        [("", ""); 0], // no tags
+        sampling_rate,
+        channel_count,
+        bitrate,
+        None,
+        Vec::new(),
+    )?;
+
+    // feed the samples into the encoder in blocks
+    const BLOCK_SIZE: usize = 4096;
+    let len = channels.first().map_or(0, |channel| channel.len());
+    for start in (0..len).step_by(BLOCK_SIZE) {
+        let end = (start + BLOCK_SIZE).min(len);
+        let block = channels
+            .iter()
+            .map(|channel| &channel[start..end])
+            .collect::<Vec<_>>();
+        encoder.encode_audio_block(&block)?;
+    }
+
     // finish encoding
     let output = encoder.finish()?;
     tracing::info!("Encoded {} samples", output.len());
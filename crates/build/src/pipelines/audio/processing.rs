@@ -0,0 +1,224 @@
+//! Processing applied to audio while it is transcoded to Vorbis
+
+use std::f64::consts::PI;
+
+/// Number of lobes on each side of the resampling filter
+const RESAMPLE_TAPS: f64 = 8.0;
+
+/// Peak level that loudness normalization will not push the audio above, in dBFS
+const NORMALIZE_PEAK_LIMIT: f32 = -1.0;
+
+/// Resamples one channel of audio from `from` Hz to `to` Hz, using a Lanczos-windowed sinc filter
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = from as f64 / to as f64;
+    // Lower the cutoff when downsampling to avoid aliasing
+    let cutoff = (1.0 / ratio).min(1.0);
+    let radius = (RESAMPLE_TAPS / cutoff).ceil() as isize;
+    let len = (samples.len() as f64 / ratio).round() as usize;
+
+    (0..len)
+        .map(|i| {
+            let t = i as f64 * ratio;
+            let center = t.floor() as isize;
+
+            let mut sum = 0.0;
+            let mut weights = 0.0;
+            for j in
+                (center - radius + 1).max(0)..=(center + radius).min(samples.len() as isize - 1)
+            {
+                let x = (t - j as f64) * cutoff;
+                if x.abs() >= RESAMPLE_TAPS {
+                    continue;
+                }
+                let weight = sinc(x) * sinc(x / RESAMPLE_TAPS);
+                sum += samples[j as usize] as f64 * weight;
+                weights += weight;
+            }
+
+            if weights.abs() > 1e-9 {
+                (sum / weights) as f32
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// A second order IIR filter
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The K-weighting filter of ITU-R BS.1770, which approximates how loud frequencies are perceived
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    // High shelf, boosting frequencies above ~1.5 kHz by 4 dB
+    let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    // High pass, removing frequencies below ~40 Hz
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+/// Measures the integrated loudness of the audio in LUFS, as specified by ITU-R BS.1770-4.
+///
+/// Returns `None` if the audio is silent.
+pub fn integrated_loudness(channels: &[Vec<f32>], sample_rate: u32) -> Option<f32> {
+    let len = channels.first()?.len();
+    let block_len = (sample_rate as usize * 4 / 10).min(len).max(1);
+    let step = (sample_rate as usize / 10).max(1);
+
+    // The squared K-weighted samples of every channel, summed
+    let mut power = vec![0.0f64; len];
+    for channel in channels {
+        let [mut shelf, mut high_pass] = k_weighting(sample_rate);
+        for (power, &sample) in power.iter_mut().zip(channel) {
+            let weighted = high_pass.process(shelf.process(sample as f64));
+            *power += weighted * weighted;
+        }
+    }
+
+    // Mean power of overlapping 400 ms blocks
+    let blocks = (0..=len.saturating_sub(block_len))
+        .step_by(step)
+        .map(|start| power[start..start + block_len].iter().sum::<f64>() / block_len as f64)
+        .collect::<Vec<_>>();
+
+    let loudness = |power: f64| -0.691 + 10.0 * power.log10();
+    let gated_mean = |threshold: f64| {
+        let gated = blocks
+            .iter()
+            .copied()
+            .filter(|&power| loudness(power) > threshold)
+            .collect::<Vec<_>>();
+        if gated.is_empty() {
+            None
+        } else {
+            Some(gated.iter().sum::<f64>() / gated.len() as f64)
+        }
+    };
+
+    let absolute = gated_mean(-70.0)?;
+    let relative = gated_mean(loudness(absolute) - 10.0)?;
+    Some(loudness(relative) as f32)
+}
+
+/// Changes the gain of the audio so that its integrated loudness is `target` LUFS.
+///
+/// The gain is reduced if the peaks of the audio would otherwise exceed -1 dBFS.
+pub fn normalize_loudness(channels: &mut [Vec<f32>], sample_rate: u32, target: f32) {
+    let Some(loudness) = integrated_loudness(channels, sample_rate) else {
+        return;
+    };
+
+    let peak = channels
+        .iter()
+        .flatten()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    let headroom = NORMALIZE_PEAK_LIMIT - 20.0 * peak.log10();
+
+    let gain = 10f32.powf((target - loudness).min(headroom) / 20.0);
+    for sample in channels.iter_mut().flatten() {
+        *sample *= gain;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sine(freq: f32, amplitude: f32, sample_rate: u32, secs: f32) -> Vec<f32> {
+        (0..(sample_rate as f32 * secs) as usize)
+            .map(|i| {
+                amplitude * (std::f32::consts::TAU * freq * i as f32 / sample_rate as f32).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loudness() {
+        // A full scale 997 Hz sine on one channel is defined to be -3.01 LUFS
+        let channels = [sine(997.0, 1.0, 48000, 2.0)];
+        let loudness = integrated_loudness(&channels, 48000).unwrap();
+        assert!((loudness + 3.01).abs() < 0.05, "{loudness}");
+
+        let channels = [sine(997.0, 0.1, 44100, 2.0), sine(997.0, 0.1, 44100, 2.0)];
+        let loudness = integrated_loudness(&channels, 44100).unwrap();
+        assert!((loudness + 20.0).abs() < 0.05, "{loudness}");
+
+        assert_eq!(integrated_loudness(&[vec![0.0; 48000]], 48000), None);
+    }
+
+    #[test]
+    fn normalize() {
+        let mut channels = [sine(997.0, 0.01, 48000, 2.0)];
+        normalize_loudness(&mut channels, 48000, -23.0);
+        let loudness = integrated_loudness(&channels, 48000).unwrap();
+        assert!((loudness + 23.0).abs() < 0.05, "{loudness}");
+
+        // The peaks are not pushed above -1 dBFS
+        normalize_loudness(&mut channels, 48000, 0.0);
+        let peak = channels[0].iter().fold(0.0f32, |peak, v| peak.max(v.abs()));
+        assert!(peak <= 10f32.powf(-1.0 / 20.0) + 1e-4, "{peak}");
+    }
+
+    #[test]
+    fn resampling() {
+        let samples = sine(440.0, 1.0, 48000, 1.0);
+        let resampled = resample(&samples, 48000, 24000);
+        assert_eq!(resampled.len(), 24000);
+
+        let expected = sine(440.0, 1.0, 24000, 1.0);
+        // Ignore the edges, where the filter runs out of samples
+        for (a, b) in resampled[100..23900].iter().zip(&expected[100..23900]) {
+            assert!((a - b).abs() < 0.01, "{a} != {b}");
+        }
+
+        assert_eq!(resample(&[0.5; 100], 22050, 44100), vec![0.5; 200]);
+    }
+}
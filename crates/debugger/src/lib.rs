@@ -5,23 +5,34 @@ use ambient_core::{
     bounding::world_bounding_sphere,
     camera::shadow_cameras_from_world,
     hierarchy::{dump_world_hierarchy, dump_world_hierarchy_to_tmp_file},
-    main_scene,
+    main_scene, name,
     player::local_user_id,
     runtime,
 };
-use ambient_ecs::{query, World};
+use ambient_ecs::{
+    generated::components::core::wasm::{
+        module_memory, profile_host_calls, profile_message_calls, profile_message_max_time,
+        profile_message_time, profile_messages, profile_time,
+    },
+    query, World,
+};
 use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
 use ambient_gizmos::{gizmos, GizmoPrimitive};
-use ambient_network::{client::GameClient, server::RpcArgs as ServerRpcArgs};
+use ambient_network::{
+    client::GameClient,
+    server::RpcArgs as ServerRpcArgs,
+    stats::{NetworkReport, STATS_INTERVAL},
+};
 use ambient_renderer::{RenderTarget, Renderer};
 use ambient_rpc::RpcRegistry;
 use ambient_shared_types::{ModifiersState, VirtualKeyCode};
 use ambient_std::{
-    asset_cache::SyncAssetKeyExt, color::Color, download_asset::AssetsCacheDir, line_hash, Cb,
+    asset_cache::SyncAssetKeyExt, color::Color, download_asset::AssetsCacheDir, line_hash,
+    to_byte_unit, Cb,
 };
 use ambient_ui_native::{
     fit_horizontal, height, space_between_items, width, Button, ButtonStyle, Dropdown, Fit,
-    FlowColumn, FlowRow, Image, UIExt,
+    FlowColumn, FlowRow, Image, StylesExt, Text, UIExt,
 };
 use glam::Vec3;
 
@@ -36,13 +47,24 @@ pub async fn rpc_dump_world_hierarchy(args: ServerRpcArgs, _: ()) -> Option<Stri
     Some(String::from_utf8(res).unwrap())
 }
 
+/// The statistics of the connection of the player, as measured by the server
+pub async fn rpc_network_report(args: ServerRpcArgs, _: ()) -> Option<NetworkReport> {
+    let state = args.state.lock();
+    let world = state.get_player_world(&args.user_id)?;
+    NetworkReport::read(world, args.get_player(world)?)
+}
+
 pub fn register_server_rpcs(reg: &mut RpcRegistry<ServerRpcArgs>) {
     reg.register(rpc_dump_world_hierarchy);
+    reg.register(rpc_network_report);
+    reg.register(rpc_wasm_profiles);
 }
 
 #[element_component]
 pub fn Debugger(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
     let (show_shadows, set_show_shadows) = hooks.use_state(false);
+    let (show_network, set_show_network) = hooks.use_state(false);
+    let (show_wasm, set_show_wasm) = hooks.use_state(false);
     let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
     FlowColumn::el([
         FlowRow(vec![
@@ -154,6 +176,20 @@ pub fn Debugger(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
             .hotkey(VirtualKeyCode::F6)
             .style(ButtonStyle::Flat)
             .el(),
+            Button::new("Show Network Stats", move |_| {
+                set_show_network(!show_network);
+            })
+            .hotkey_modifier(ModifiersState::SHIFT)
+            .hotkey(VirtualKeyCode::F7)
+            .style(ButtonStyle::Flat)
+            .el(),
+            Button::new("Show WASM Profile", move |_| {
+                set_show_wasm(!show_wasm);
+            })
+            .hotkey_modifier(ModifiersState::SHIFT)
+            .hotkey(VirtualKeyCode::F8)
+            .style(ButtonStyle::Flat)
+            .el(),
             ShaderDebug {
                 get_state: get_state.clone(),
             }
@@ -176,6 +212,22 @@ pub fn Debugger(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
         } else {
             Element::new()
         },
+        if show_network {
+            NetworkStatsViz {
+                get_state: get_state.clone(),
+            }
+            .el()
+        } else {
+            Element::new()
+        },
+        if show_wasm {
+            WasmProfileViz {
+                get_state: get_state.clone(),
+            }
+            .el()
+        } else {
+            Element::new()
+        },
     ])
     .with_background(Color::rgba(0., 0., 0., 1.).into())
     .with(fit_horizontal(), Fit::Parent)
@@ -227,6 +279,213 @@ fn ShadowMapViz(hooks: &mut Hooks, get_state: GetDebuggerState, cascade: u32) ->
         .with(height(), 200.)
 }
 
+#[element_component]
+fn NetworkStatsViz(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
+    let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
+    let (client, set_client) = hooks.use_state(None);
+    let (server, set_server) = hooks.use_state(None);
+
+    let runtime = hooks.world.resource(runtime()).clone();
+    hooks.use_interval(STATS_INTERVAL.as_secs_f32(), move || {
+        get_state(&mut |_, _, world| {
+            set_client(NetworkReport::read(world, world.resource_entity()));
+        });
+
+        let game_client = game_client.clone();
+        let set_server = set_server.clone();
+        runtime.spawn(async move {
+            if let Ok(report) = game_client.rpc(rpc_network_report, ()).await {
+                set_server(report);
+            }
+        });
+    });
+
+    FlowRow::el([
+        NetworkReportViz {
+            title: "Received by the client".to_string(),
+            report: client,
+        }
+        .el(),
+        NetworkReportViz {
+            title: "Sent by the server".to_string(),
+            report: server,
+        }
+        .el(),
+    ])
+    .with(space_between_items(), 20.)
+}
+
+#[element_component]
+fn NetworkReportViz(_hooks: &mut Hooks, title: String, report: Option<NetworkReport>) -> Element {
+    let Some(report) = report else {
+        return FlowColumn::el([
+            Text::el(title).section_style(),
+            Text::el("No statistics yet"),
+        ]);
+    };
+
+    let bytes = |bytes: u64| format!("{}/s", to_byte_unit(bytes));
+    FlowColumn::el(
+        [
+            Text::el(title).section_style(),
+            Text::el(report.stats.to_string()),
+            Text::el(format!(
+                "Diffs: {} before compression",
+                bytes(report.diff_bytes_per_second)
+            )),
+            Text::el("By component").small_style(),
+        ]
+        .into_iter()
+        .chain(
+            report
+                .components
+                .iter()
+                .map(|(path, size)| Text::el(format!("{} {path}", bytes(*size)))),
+        )
+        .chain([Text::el("By entity").small_style()])
+        .chain(
+            report
+                .entities
+                .iter()
+                .map(|(id, size)| Text::el(format!("{} {id}", bytes(*size)))),
+        ),
+    )
+    .with(space_between_items(), 2.)
+}
+
+/// The profile of a WASM module, as shown by the debugger
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WasmModuleProfile {
+    pub name: String,
+    /// Milliseconds per second spent handling messages
+    pub time: f32,
+    pub host_calls: u64,
+    pub memory: u64,
+    /// The messages the module spent the most time on, in descending order
+    pub messages: Vec<WasmMessageProfile>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WasmMessageProfile {
+    pub name: String,
+    pub calls: u64,
+    /// Milliseconds per second spent handling the message
+    pub time: f32,
+    /// The longest it took to handle the message once, in milliseconds
+    pub max_time: f32,
+}
+
+/// Reads the profiles of the modules in the world, by the time spent in them in descending order
+fn read_wasm_profiles(world: &World) -> Vec<WasmModuleProfile> {
+    /// The most messages listed for each module
+    const MAX_MESSAGES: usize = 5;
+
+    let mut modules = query((name(), profile_time(), module_memory()))
+        .iter(world, None)
+        .map(|(id, (name, &time, &memory))| {
+            let messages = world.get_cloned(id, profile_messages()).unwrap_or_default();
+            let calls = world
+                .get_cloned(id, profile_message_calls())
+                .unwrap_or_default();
+            let times = world
+                .get_cloned(id, profile_message_time())
+                .unwrap_or_default();
+            let max_times = world
+                .get_cloned(id, profile_message_max_time())
+                .unwrap_or_default();
+
+            WasmModuleProfile {
+                name: name.clone(),
+                time,
+                host_calls: world.get(id, profile_host_calls()).unwrap_or_default(),
+                memory,
+                messages: messages
+                    .into_iter()
+                    .zip(calls)
+                    .zip(times)
+                    .zip(max_times)
+                    .take(MAX_MESSAGES)
+                    .map(|(((name, calls), time), max_time)| WasmMessageProfile {
+                        name,
+                        calls,
+                        time,
+                        max_time,
+                    })
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+    modules.sort_by(|a, b| b.time.total_cmp(&a.time));
+    modules
+}
+
+/// The profiles of the modules on the server, which are not replicated to the clients
+pub async fn rpc_wasm_profiles(args: ServerRpcArgs, _: ()) -> Option<Vec<WasmModuleProfile>> {
+    let state = args.state.lock();
+    let world = state.get_player_world(&args.user_id)?;
+    Some(read_wasm_profiles(world))
+}
+
+#[element_component]
+fn WasmProfileViz(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
+    let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
+    let (client, set_client) = hooks.use_state(Vec::new());
+    let (server, set_server) = hooks.use_state(Vec::new());
+
+    let runtime = hooks.world.resource(runtime()).clone();
+    // The profiles are updated once per second
+    hooks.use_interval(1., move || {
+        get_state(&mut |_, _, world| {
+            set_client(read_wasm_profiles(world));
+        });
+
+        let game_client = game_client.clone();
+        let set_server = set_server.clone();
+        runtime.spawn(async move {
+            if let Ok(Some(profiles)) = game_client.rpc(rpc_wasm_profiles, ()).await {
+                set_server(profiles);
+            }
+        });
+    });
+
+    FlowRow::el([
+        WasmProfilesViz {
+            title: "Client modules".to_string(),
+            profiles: client,
+        }
+        .el(),
+        WasmProfilesViz {
+            title: "Server modules".to_string(),
+            profiles: server,
+        }
+        .el(),
+    ])
+    .with(space_between_items(), 20.)
+}
+
+#[element_component]
+fn WasmProfilesViz(_hooks: &mut Hooks, title: String, profiles: Vec<WasmModuleProfile>) -> Element {
+    let mut lines = vec![Text::el(title).section_style()];
+    for module in profiles {
+        lines.push(Text::el(format!(
+            "{}: {:.2} ms/s, {} host calls/s, {}",
+            module.name,
+            module.time,
+            module.host_calls,
+            to_byte_unit(module.memory)
+        )));
+        lines.extend(module.messages.into_iter().map(|message| {
+            Text::el(format!(
+                "{:.2} ms/s {} ({} calls/s, longest {:.2} ms)",
+                message.time, message.name, message.calls, message.max_time
+            ))
+            .small_style()
+        }));
+    }
+
+    FlowColumn::el(lines).with(space_between_items(), 2.)
+}
+
 #[element_component]
 fn ShaderDebug(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
     let (show, set_show) = hooks.use_state(false);
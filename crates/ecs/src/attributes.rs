@@ -244,6 +244,38 @@ where
     }
 }
 
+/// Round the component to a multiple of `precision` when it is sent over the network, which
+/// takes less bandwidth than the full value
+///
+/// Applies to components of type `f32`, `Vec2`, `Vec3`, `Vec4` and `Quat`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantized {
+    pub precision: f32,
+}
+impl Quantized {
+    /// Parses the `Quantized[<precision>]` attribute of a component defined in a manifest.
+    ///
+    /// Returns `None` if `attribute` is another attribute.
+    pub fn parse(attribute: &str) -> Option<Result<Self, &'static str>> {
+        let params = attribute.strip_prefix("Quantized")?;
+        Some(
+            params
+                .strip_prefix('[')
+                .and_then(|params| params.strip_suffix(']'))
+                .and_then(|precision| precision.trim().parse::<f32>().ok())
+                .filter(|&precision| precision > 0.0 && precision.is_finite())
+                .map(|precision| Self { precision })
+                .ok_or("Quantized must have a positive precision, such as Quantized[0.01]"),
+        )
+    }
+}
+impl ComponentAttribute for Quantized {}
+impl<T: ComponentValue> AttributeConstructor<T, f32> for Quantized {
+    fn construct(store: &mut AttributeStore, precision: f32) {
+        store.set(Self { precision })
+    }
+}
+
 pub(crate) struct ComponentPath(pub String);
 impl ComponentAttribute for ComponentPath {}
 
@@ -46,6 +46,8 @@ pub struct ExternalComponentDesc {
 pub struct ExternalComponentAttributes {
     pub name: Option<String>,
     pub description: Option<String>,
+    /// The precision of the [Quantized] attribute, if the component has it
+    pub quantized: Option<f32>,
     pub flags: ExternalComponentFlagAttributes,
 }
 impl ExternalComponentAttributes {
@@ -53,6 +55,7 @@ impl ExternalComponentAttributes {
         Self {
             name: desc.attribute::<Name>().map(|n| n.0.clone()),
             description: desc.attribute::<Description>().map(|n| n.0.clone()),
+            quantized: desc.attribute::<Quantized>().map(|q| q.precision),
             flags: ExternalComponentFlagAttributes::from_existing_component(desc),
         }
     }
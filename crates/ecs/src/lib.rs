@@ -41,6 +41,7 @@ mod location;
 mod primitive_component;
 mod query;
 mod serialization;
+mod snapshot;
 mod stream;
 pub use ambient_project_rt::message_serde::*;
 pub use archetype::*;
@@ -57,6 +58,7 @@ pub use location::*;
 pub use primitive_component::*;
 pub use query::*;
 pub use serialization::*;
+pub use snapshot::*;
 pub use stream::*;
 
 pub struct DebugWorldArchetypes<'a> {
@@ -6,13 +6,13 @@ use paste::paste;
 
 use crate::{
     AttributeConstructor, AttributeStore, ComponentDesc, ComponentRegistry, ComponentVTable,
-    Description, EntityId, ExternalComponentAttributes, Name,
+    Description, EntityId, ExternalComponentAttributes, Name, Quantized,
 };
 
-use ambient_shared_types::primitive_component_definitions;
+use ambient_shared_types::stored_component_definitions;
 use ambient_shared_types::{
     ProceduralMaterialHandle, ProceduralMeshHandle, ProceduralSamplerHandle,
-    ProceduralTextureHandle,
+    ProceduralTextureHandle, StructValue,
 };
 
 // implementation
@@ -24,6 +24,9 @@ macro_rules! build_attribute_registration {
         if let Some(description) = $attributes.description {
             <Description as AttributeConstructor<$type, _>>::construct(&mut $store, &description);
         }
+        if let Some(precision) = $attributes.quantized {
+            <Quantized as AttributeConstructor<$type, _>>::construct(&mut $store, precision);
+        }
         $attributes.flags.construct_for_store::<$type>(&mut $store);
 
         static VTABLE: &ComponentVTable<$type> = &ComponentVTable::construct_external();
@@ -32,7 +35,13 @@ macro_rules! build_attribute_registration {
 }
 
 macro_rules! make_primitive_component {
-    ($(($value:ident, $type:ty)),*) => {
+    ($(($primitive_value:ident, $primitive_type:ty)),*; $(($internal_value:ident, $internal_type:ty)),*) => {
+        make_primitive_component!(
+            [$($primitive_value),*],
+            $(($primitive_value, $primitive_type)),*, $(($internal_value, $internal_type)),*
+        );
+    };
+    ([$($primitive_value:ident),*], $(($value:ident, $type:ty)),*) => {
         paste! {
             #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
             pub struct PrimitiveComponent {
@@ -68,7 +77,7 @@ macro_rules! make_primitive_component {
 
                 fn try_from(value: &str) -> Result<Self, Self::Error> {
                     match value {
-                        $(stringify!($value) => Ok(Self::$value),)*
+                        $(stringify!($primitive_value) => Ok(Self::$primitive_value),)*
                         "Vec" | "Option" => Err("The specified type is a container type, not primitive"),
                         _ => Err("Unsupported type")
                     }
@@ -82,10 +91,11 @@ macro_rules! make_primitive_component {
             }
 
             impl PrimitiveComponentType {
-                /// Not defined for the container types; use [Self::decompose_container_type].
+                /// Not defined for the container types, or for the types that can't be named in a
+                /// manifest; use [Self::decompose_container_type] for the former.
                 pub fn as_str(&self) -> Option<&'static str> {
                     match self {
-                        $(Self::$value => Some(stringify!($value)),)*
+                        $(Self::$primitive_value => Some(stringify!($primitive_value)),)*
                         _ => None,
                     }
                 }
@@ -148,4 +158,4 @@ macro_rules! make_primitive_component {
     }
 }
 
-primitive_component_definitions!(make_primitive_component);
+stored_component_definitions!(make_primitive_component);
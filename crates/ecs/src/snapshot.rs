@@ -0,0 +1,241 @@
+use itertools::Itertools;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+use crate::{
+    dont_store, query, ComponentDesc, DeserWorldWithWarnings, EntityId, Serializable, Store, World,
+};
+
+/// A migration that upgrades a world snapshot from `from_version` to `from_version + 1`.
+///
+/// Migrations operate on the raw JSON of the snapshot's entities, which is a map of
+/// entity ids to maps of component paths to values.
+#[derive(Clone, Copy)]
+pub struct WorldSnapshotMigration {
+    pub from_version: u32,
+    pub migrate: fn(&mut serde_json::Value) -> anyhow::Result<()>,
+}
+
+impl World {
+    /// Serializes the [Store] components of every entity in this world, tagged with `version`.
+    ///
+    /// The resource entity, entities marked with [dont_store], entities with any of the `exclude`
+    /// components and entities without any [Store] components are skipped.
+    pub fn to_snapshot(&self, version: u32, exclude: &[ComponentDesc]) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&SerWorldSnapshot {
+            version,
+            entities: SerStoredEntities {
+                world: self,
+                exclude,
+            },
+        })?)
+    }
+
+    /// Deserializes a snapshot produced by [World::to_snapshot].
+    ///
+    /// If the snapshot is older than `version`, `migrations` are applied in order until it is
+    /// up to date. Components that could not be deserialized are returned as warnings instead
+    /// of failing the load.
+    pub fn from_snapshot(
+        content: &[u8],
+        version: u32,
+        migrations: &[WorldSnapshotMigration],
+    ) -> anyhow::Result<DeserWorldWithWarnings> {
+        let mut snapshot: serde_json::Value = serde_json::from_slice(content)?;
+        let mut snapshot_version = snapshot
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| anyhow::anyhow!("World snapshot is missing a version"))?
+            as u32;
+        let entities = snapshot
+            .get_mut("entities")
+            .ok_or_else(|| anyhow::anyhow!("World snapshot is missing its entities"))?;
+
+        if snapshot_version > version {
+            anyhow::bail!(
+                "World snapshot version {snapshot_version} is newer than the supported version {version}"
+            );
+        }
+        while snapshot_version < version {
+            let migration = migrations
+                .iter()
+                .find(|m| m.from_version == snapshot_version)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("No migration for world snapshot version {snapshot_version}")
+                })?;
+            (migration.migrate)(entities)?;
+            snapshot_version += 1;
+        }
+
+        Ok(serde_json::from_value(entities.take())?)
+    }
+}
+
+#[derive(Serialize)]
+struct SerWorldSnapshot<'a> {
+    version: u32,
+    entities: SerStoredEntities<'a>,
+}
+
+struct SerStoredEntities<'a> {
+    world: &'a World,
+    exclude: &'a [ComponentDesc],
+}
+impl<'a> Serialize for SerStoredEntities<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let resource_entity = self.world.resource_entity();
+        let entities = query(())
+            .excl(dont_store())
+            .iter(self.world, None)
+            .map(|(id, _)| id)
+            .filter(|&id| id != resource_entity)
+            .filter(|&id| {
+                !self
+                    .exclude
+                    .iter()
+                    .any(|&comp| self.world.has_component(id, comp))
+            })
+            .map(|id| (id, stored_components(self.world, id)))
+            .filter(|(_, comps)| !comps.is_empty())
+            .collect_vec();
+
+        let mut map = serializer.serialize_map(Some(entities.len()))?;
+        for (id, comps) in entities {
+            map.serialize_entry(
+                &id,
+                &SerStoredEntity {
+                    world: self.world,
+                    id,
+                    comps,
+                },
+            )?;
+        }
+        map.end()
+    }
+}
+
+fn stored_components(world: &World, id: EntityId) -> Vec<ComponentDesc> {
+    world
+        .get_components(id)
+        .unwrap()
+        .into_iter()
+        .filter(|x| x.has_attribute::<Store>())
+        .collect_vec()
+}
+
+struct SerStoredEntity<'a> {
+    world: &'a World,
+    id: EntityId,
+    comps: Vec<ComponentDesc>,
+}
+impl<'a> Serialize for SerStoredEntity<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entity = serializer.serialize_map(Some(self.comps.len()))?;
+        for &comp in &self.comps {
+            if let Some(ser) = comp.attribute::<Serializable>() {
+                let value = self.world.get_entry(self.id, comp).unwrap();
+                entity.serialize_entry(&comp.path(), ser.serialize(&value))?;
+            }
+        }
+        entity.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    components!("test", {
+        @[Store]
+        snapshot_test_stored: String,
+        @[Serializable]
+        snapshot_test_serializable: String,
+    });
+
+    fn init() {
+        crate::init_components();
+        init_components();
+    }
+
+    #[test]
+    pub fn test_snapshot_only_contains_stored_components() {
+        init();
+        let mut world = World::new("test");
+        world.add_resource(snapshot_test_stored(), "resource".to_string());
+        let id = Entity::new()
+            .with(snapshot_test_stored(), "hi".to_string())
+            .with(snapshot_test_serializable(), "skipped".to_string())
+            .spawn(&mut world);
+        Entity::new()
+            .with(snapshot_test_serializable(), "skipped".to_string())
+            .spawn(&mut world);
+        Entity::new()
+            .with(snapshot_test_stored(), "skipped".to_string())
+            .with(dont_store(), ())
+            .spawn(&mut world);
+        Entity::new()
+            .with(snapshot_test_stored(), "excluded".to_string())
+            .with(snapshot_test_serializable(), "excluded".to_string())
+            .spawn(&mut world);
+
+        let snapshot = world
+            .to_snapshot(1, &[snapshot_test_serializable().desc()])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(snapshot).unwrap(),
+            format!(
+                "{{\"version\":1,\"entities\":{{\"{id}\":{{\"core::test::snapshot_test_stored\":\"hi\"}}}}}}"
+            )
+        );
+    }
+
+    #[test]
+    pub fn test_snapshot_roundtrip() {
+        init();
+        let mut world = World::new("test");
+        let id = Entity::new()
+            .with(snapshot_test_stored(), "hi".to_string())
+            .spawn(&mut world);
+
+        let snapshot = world.to_snapshot(1, &[]).unwrap();
+        let deser = World::from_snapshot(&snapshot, 1, &[]).unwrap();
+        assert!(deser.warnings.is_empty());
+        assert_eq!(
+            deser.world.get_ref(id, snapshot_test_stored()).unwrap(),
+            "hi"
+        );
+    }
+
+    #[test]
+    pub fn test_snapshot_migration() {
+        init();
+        let source = r#"{"version":1,"entities":{"L9wH6h4qgcNBfRv2Rv2FIQ":{"core::test::old_name":"hi","missing":5}}}"#;
+        let migrations = [WorldSnapshotMigration {
+            from_version: 1,
+            migrate: |entities| {
+                for entity in entities.as_object_mut().unwrap().values_mut() {
+                    let entity = entity.as_object_mut().unwrap();
+                    if let Some(value) = entity.remove("core::test::old_name") {
+                        entity.insert("core::test::snapshot_test_stored".to_string(), value);
+                    }
+                }
+                Ok(())
+            },
+        }];
+
+        let deser = World::from_snapshot(source.as_bytes(), 2, &migrations).unwrap();
+        assert_eq!(
+            deser
+                .world
+                .get_ref(
+                    "L9wH6h4qgcNBfRv2Rv2FIQ".parse().unwrap(),
+                    snapshot_test_stored()
+                )
+                .unwrap(),
+            "hi"
+        );
+        assert_eq!(deser.warnings.len(), 1);
+
+        assert!(World::from_snapshot(source.as_bytes(), 3, &migrations).is_err());
+        assert!(World::from_snapshot(source.as_bytes(), 0, &migrations).is_err());
+    }
+}
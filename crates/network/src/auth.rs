@@ -0,0 +1,129 @@
+//! Verification of the credentials presented by clients when they connect to the server.
+//!
+//! The server checks every connect request with the [connection_verifier] resource of the main
+//! instance. If there is none, every user id is accepted as-is.
+
+use std::time::Duration;
+
+use ambient_core::no_sync;
+use ambient_ecs::{dont_store, Entity};
+use ambient_sys::time::Instant;
+use futures::future::BoxFuture;
+
+use crate::server::{connection_verifier, SharedServerState, MAIN_INSTANCE_ID};
+
+pub use ambient_ecs::generated::components::core::network::{
+    connection_accepted, connection_rejected, connection_request_credential,
+    connection_request_user_id,
+};
+
+/// How often the [ModuleVerifier] checks whether a module has responded
+const MODULE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Decides whether a client may connect to the server as a given user
+pub trait ConnectionVerifier: Send + Sync {
+    /// Resolves to `Err` with a reason which is shown to the user if the connection is refused
+    fn verify(
+        &self,
+        state: SharedServerState,
+        user_id: String,
+        credential: Option<String>,
+    ) -> BoxFuture<'static, Result<(), String>>;
+}
+
+/// Verifies a connect request with the verifier of the server, if any
+pub(crate) async fn verify_connection(
+    state: &SharedServerState,
+    user_id: &str,
+    credential: Option<&str>,
+) -> Result<(), String> {
+    let verifier = state
+        .lock()
+        .instances
+        .get(MAIN_INSTANCE_ID)
+        .and_then(|instance| instance.world.resource_opt(connection_verifier()).cloned());
+
+    match verifier {
+        Some(verifier) => {
+            verifier
+                .verify(
+                    state.clone(),
+                    user_id.to_string(),
+                    credential.map(ToString::to_string),
+                )
+                .await
+        }
+        None => Ok(()),
+    }
+}
+
+/// Delegates the verification to the modules of the main instance.
+///
+/// An entity with [connection_request_user_id] and [connection_request_credential] is spawned for
+/// every connect request. A module then attaches [connection_accepted] or [connection_rejected]
+/// to it; if neither happens within the timeout, the connection is refused.
+#[derive(Debug, Clone)]
+pub struct ModuleVerifier {
+    pub timeout: Duration,
+}
+
+impl Default for ModuleVerifier {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ConnectionVerifier for ModuleVerifier {
+    fn verify(
+        &self,
+        state: SharedServerState,
+        user_id: String,
+        credential: Option<String>,
+    ) -> BoxFuture<'static, Result<(), String>> {
+        let timeout = self.timeout;
+        Box::pin(async move {
+            let id = {
+                let mut state = state.lock();
+                let instance = state.instances.get_mut(MAIN_INSTANCE_ID).unwrap();
+
+                let mut request = Entity::new()
+                    .with(connection_request_user_id(), user_id)
+                    .with(no_sync(), ())
+                    .with_default(dont_store());
+                if let Some(credential) = credential {
+                    request.set(connection_request_credential(), credential);
+                }
+                request.spawn(&mut instance.world)
+            };
+
+            let start = Instant::now();
+            loop {
+                {
+                    let mut state = state.lock();
+                    let world = &mut state.instances.get_mut(MAIN_INSTANCE_ID).unwrap().world;
+
+                    let result = if !world.exists(id) {
+                        Some(Err("The connection request was removed".to_string()))
+                    } else if let Ok(reason) = world.get_cloned(id, connection_rejected()) {
+                        Some(Err(reason))
+                    } else if world.has_component(id, connection_accepted()) {
+                        Some(Ok(()))
+                    } else if start.elapsed() > timeout {
+                        Some(Err("Timed out while verifying the connection".to_string()))
+                    } else {
+                        None
+                    };
+
+                    if let Some(result) = result {
+                        world.despawn(id);
+                        return result;
+                    }
+                }
+
+                ambient_sys::time::sleep(MODULE_POLL_INTERVAL).await;
+            }
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use bytes::Buf;
+use bytes::{Buf, BufMut};
 use thiserror::Error;
 
 #[derive(Error, Debug, Clone)]
@@ -33,6 +33,19 @@ pub trait BufExt: Buf {
         self.try_get()
     }
 
+    /// Reads an unsigned LEB128 varint
+    fn try_get_varint(&mut self) -> Result<u64, UnexpectedEnd> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.try_get::<u8>()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(value)
+    }
+
     fn try_get<T>(&mut self) -> Result<T, UnexpectedEnd>
     where
         T: Sized + Decode,
@@ -60,5 +73,41 @@ impl_decode_u!(u8, 1, get_u8);
 impl_decode_u!(u16, 2, get_u16);
 impl_decode_u!(u32, 4, get_u32);
 impl_decode_u!(u64, 8, get_u64);
+impl_decode_u!(u128, 16, get_u128);
 
 impl<B> BufExt for B where B: Buf {}
+
+pub trait BufMutExt: BufMut {
+    /// Writes an unsigned LEB128 varint, which takes less space the smaller the value is
+    fn put_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.put_u8(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.put_u8(value as u8);
+    }
+}
+
+impl<B> BufMutExt for B where B: BufMut {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn varint() {
+        let values = [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX];
+
+        let mut buf = Vec::new();
+        for value in values {
+            buf.put_varint(value);
+        }
+        assert_eq!(buf[..2], [0, 1]);
+
+        let mut buf = &buf[..];
+        for value in values {
+            assert_eq!(buf.try_get_varint().unwrap(), value);
+        }
+        assert!(buf.try_get_varint().is_err());
+    }
+}
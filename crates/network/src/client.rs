@@ -10,7 +10,7 @@ use bytes::Bytes;
 use futures::future::BoxFuture;
 use glam::UVec2;
 use parking_lot::Mutex;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     collections::HashMap,
     fmt::{Debug, Display},
@@ -32,6 +32,9 @@ components!("network::client", {
     uni_stream_handlers: UniStreamHandlers,
     @[Resource]
     datagram_handlers: DatagramHandlers,
+    /// Handles the unidirectional streams of a replayed session, whose content has already been read
+    @[Resource]
+    replayed_uni_stream_handlers: DatagramHandlers,
     /// The most recent server performance statistics
     @[Resource]
     client_network_stats: NetworkStats,
@@ -223,10 +226,14 @@ pub fn GameClientWorld(hooks: &mut Hooks) -> Element {
 }
 
 /// Set up and manage a connection to the server
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct NetworkStats {
     pub latency_ms: u64,
+    /// The fraction of the sent packets which were lost
+    pub packet_loss: f32,
+    /// Per second
     pub bytes_sent: u64,
+    /// Per second
     pub bytes_received: u64,
 }
 
@@ -234,8 +241,9 @@ impl Display for NetworkStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{:?} ms rtt, {}/s out, {}/s in",
+            "{:?} ms rtt, {:.1}% loss, {}/s out, {}/s in",
             self.latency_ms,
+            self.packet_loss * 100.0,
             to_byte_unit(self.bytes_sent),
             to_byte_unit(self.bytes_received)
         )
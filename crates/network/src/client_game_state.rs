@@ -69,6 +69,8 @@ impl ClientGameState {
             "game",
             vec![
                 Box::new(client_systems),
+                Box::new(crate::prediction::client_systems()),
+                Box::new(crate::interpolation::systems()),
                 Box::new(world_instance_systems(true)),
                 Box::new(spatial_audio_systems()),
             ],
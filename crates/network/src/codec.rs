@@ -1,13 +1,90 @@
-use std::marker::PhantomData;
+use std::{
+    io::{self, Read},
+    marker::PhantomData,
+};
 
 use bytes::{Buf, BufMut};
 use serde::Serialize;
 use tokio_util::codec::Encoder;
 
+use crate::MAX_FRAME_SIZE;
+
 pub enum ControlFrame {}
 
+const UNCOMPRESSED: u8 = 0;
+const ZSTD: u8 = 1;
+
+/// How a frame is compressed before it is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    /// Compress with zstd at the given level, from 1 to 22
+    Zstd(i32),
+}
+
+impl Compression {
+    /// Compresses `data`, prefixed with how it was compressed so that [decompress] can reverse it
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(not(target_os = "unknown"))]
+            Self::Zstd(level) => {
+                let mut output = vec![ZSTD];
+                zstd::stream::copy_encode(data, &mut output, level)?;
+                Ok(output)
+            }
+            // Only decompression is available on the web
+            #[cfg(target_os = "unknown")]
+            Self::Zstd(_) => Self::None.compress(data),
+            Self::None => {
+                let mut output = Vec::with_capacity(data.len() + 1);
+                output.push(UNCOMPRESSED);
+                output.extend_from_slice(data);
+                Ok(output)
+            }
+        }
+    }
+}
+
+/// Reverses [Compression::compress]. Fails if the data decompresses to more than
+/// `MAX_FRAME_SIZE` bytes.
+pub fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    match data.split_first() {
+        Some((&UNCOMPRESSED, data)) => Ok(data.to_vec()),
+        #[cfg(not(target_os = "unknown"))]
+        Some((&ZSTD, data)) => {
+            read_bounded(zstd::stream::read::Decoder::new(data)?, MAX_FRAME_SIZE)
+        }
+        #[cfg(target_os = "unknown")]
+        Some((&ZSTD, mut data)) => read_bounded(
+            ruzstd::StreamingDecoder::new(&mut data)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?,
+            MAX_FRAME_SIZE,
+        ),
+        Some((kind, _)) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown compression {kind}"),
+        )),
+        None => Err(io::ErrorKind::UnexpectedEof.into()),
+    }
+}
+
+/// Reads `reader` to the end, failing if it has more than `limit` bytes
+fn read_bounded(reader: impl Read, limit: usize) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    reader.take(limit as u64 + 1).read_to_end(&mut output)?;
+    if output.len() > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Frame decompresses to more than {limit} bytes"),
+        ));
+    }
+    Ok(output)
+}
+
 pub struct FramedCodec<T> {
     len: Option<usize>,
+    compression: Compression,
     _marker: PhantomData<T>,
 }
 
@@ -15,9 +92,16 @@ impl<T> FramedCodec<T> {
     pub fn new() -> Self {
         Self {
             len: None,
+            compression: Compression::None,
             _marker: PhantomData,
         }
     }
+
+    /// Compresses the frames which are encoded. Frames are decoded regardless of their compression
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
 }
 
 impl<T: serde::de::DeserializeOwned> tokio_util::codec::Decoder for FramedCodec<T> {
@@ -45,7 +129,7 @@ impl<T: serde::de::DeserializeOwned> tokio_util::codec::Decoder for FramedCodec<
 
         let bytes = src.split_to(len);
 
-        let value = bincode::deserialize(&bytes)?;
+        let value = bincode::deserialize(&decompress(&bytes)?)?;
 
         // Reset state
         self.len = None;
@@ -58,12 +142,11 @@ impl<T: Serialize> Encoder<T> for FramedCodec<T> {
     type Error = bincode::Error;
 
     fn encode(&mut self, item: T, dst: &mut bytes::BytesMut) -> Result<(), Self::Error> {
-        let len = bincode::serialized_size(&item)?;
-        dst.reserve(len as usize + 4);
-
-        dst.put_u32(len as u32);
+        let bytes = self.compression.compress(&bincode::serialize(&item)?)?;
+        dst.reserve(bytes.len() + 4);
 
-        bincode::serialize_into(dst.writer(), &item)?;
+        dst.put_u32(bytes.len() as u32);
+        dst.put_slice(&bytes);
 
         Ok(())
     }
@@ -104,4 +187,47 @@ mod test {
         assert_eq!(recv(), Frame::String("Another".into()));
         assert_eq!(recv(), Frame::Int(42));
     }
+
+    #[test]
+    fn compressed_framed_codec() {
+        let (send, recv) = tokio::io::duplex(1024 * 1024);
+
+        let mut send = FramedWrite::new(
+            send,
+            FramedCodec::<Vec<u32>>::new().with_compression(Compression::Zstd(3)),
+        );
+        let mut recv = FramedRead::new(recv, FramedCodec::<Vec<u32>>::new());
+
+        let frame = vec![7; 10000];
+        send.send(frame.clone()).now_or_never().unwrap().unwrap();
+        assert_eq!(recv.next().now_or_never().unwrap().unwrap().unwrap(), frame);
+    }
+
+    #[test]
+    fn compression() {
+        let data = "abc".repeat(1000).into_bytes();
+        for compression in [
+            Compression::None,
+            Compression::Zstd(1),
+            Compression::Zstd(19),
+        ] {
+            let compressed = compression.compress(&data).unwrap();
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+
+        assert!(Compression::Zstd(3).compress(&data).unwrap().len() < 100);
+        assert!(decompress(&[]).is_err());
+        assert!(decompress(&[42, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn decompression_is_bounded() {
+        let data = vec![0; 10000];
+        let compressed = Compression::Zstd(3).compress(&data).unwrap();
+        let decoder = || zstd::stream::read::Decoder::new(&compressed[1..]).unwrap();
+
+        assert_eq!(read_bounded(decoder(), 10000).unwrap(), data);
+        let err = read_bounded(decoder(), 9999).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
 }
@@ -0,0 +1,659 @@
+//! The wire format of the world diffs which the server sends to each client.
+//!
+//! Unlike the generic serialization of [WorldDiff], a frame depends on what has been sent on the
+//! connection before:
+//! - Components and entities are referred to by indices, which are assigned the first time they
+//!   are sent. Only then are the path of the component or the full id of the entity written.
+//! - Indices, counts and lengths are varints.
+//! - Components with the [Quantized] attribute are rounded to their precision and written as
+//!   varints instead of full-precision floats.
+//! - The whole frame can be compressed.
+//!
+//! The [DiffDecoder] of a client therefore has to see the frames of its [DiffEncoder] on the
+//! server in the same order.
+
+use std::collections::HashMap;
+
+use ambient_core::transform::{rotation, translation};
+use ambient_ecs::{
+    ComponentDesc, ComponentEntry, ComponentRegistry, Entity, EntityId, Quantized, Serializable,
+    WorldChange, WorldDiff,
+};
+use bincode::Options;
+use bytes::{Buf, BufMut, Bytes};
+use glam::{Quat, Vec2, Vec3, Vec4};
+use thiserror::Error;
+
+use crate::{
+    bytes_ext::{BufExt, BufMutExt},
+    codec::{decompress, Compression},
+    stats::DiffBandwidth,
+};
+
+/// The precision of `translation` over the network, in meters
+pub const TRANSLATION_PRECISION: f32 = 0.001;
+/// The precision of the components of `rotation` over the network
+pub const ROTATION_PRECISION: f32 = 0.0001;
+
+const SPAWN: u8 = 0;
+const SPAWN_WITHOUT_ID: u8 = 1;
+const DESPAWN: u8 = 2;
+const ADD_COMPONENTS: u8 = 3;
+const REMOVE_COMPONENTS: u8 = 4;
+const SET: u8 = 5;
+
+const FULL_PRECISION: u8 = 0;
+const QUANTIZED: u8 = 1;
+
+/// Largest quantized value which is still exact as a float
+const MAX_QUANTIZED: f64 = (1u64 << 53) as f64;
+
+/// Quantizes the transforms sent over the network by default
+pub(crate) fn init_quantization() {
+    translation().desc().attributes_mut().set(Quantized {
+        precision: TRANSLATION_PRECISION,
+    });
+    rotation().desc().attributes_mut().set(Quantized {
+        precision: ROTATION_PRECISION,
+    });
+}
+
+#[derive(Error, Debug)]
+pub enum DiffDecodeError {
+    #[error("Failed to decompress the diff")]
+    Decompress(#[source] std::io::Error),
+    #[error("Unexpected end of the diff")]
+    UnexpectedEnd,
+    #[error("Invalid change kind {0}")]
+    InvalidChange(u8),
+    #[error("No such component: {0}")]
+    UnknownComponent(String),
+    #[error("Component index {0} has not been sent")]
+    UnknownComponentIndex(u64),
+    #[error("Entity index {0} has not been sent")]
+    UnknownEntityIndex(u64),
+    #[error("Component {0} is not serializable")]
+    NotSerializable(String),
+    #[error("Invalid value of {0}")]
+    InvalidValue(String, #[source] Option<bincode::Error>),
+}
+
+impl From<crate::bytes_ext::UnexpectedEnd> for DiffDecodeError {
+    fn from(_: crate::bytes_ext::UnexpectedEnd) -> Self {
+        Self::UnexpectedEnd
+    }
+}
+
+/// Encodes the diffs sent over one connection
+#[derive(Debug, Default)]
+pub struct DiffEncoder {
+    /// The index on the connection and the precision of each component, by component index
+    components: HashMap<u32, (u64, Option<f32>)>,
+    entities: HashMap<EntityId, u64>,
+    next_entity: u64,
+    bandwidth: DiffBandwidth,
+}
+
+impl DiffEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the bytes encoded since the last call
+    pub fn take_bandwidth(&mut self) -> DiffBandwidth {
+        std::mem::take(&mut self.bandwidth)
+    }
+
+    pub fn encode(&mut self, diff: &WorldDiff, compression: Compression) -> Bytes {
+        let mut body = Vec::new();
+        let mut count = 0;
+        for change in &diff.changes {
+            if self.put_change(&mut body, change) {
+                count += 1;
+            }
+        }
+
+        let mut frame = Vec::with_capacity(body.len() + 4);
+        frame.put_varint(count);
+        frame.extend_from_slice(&body);
+        self.bandwidth.bytes += frame.len() as u64;
+
+        compression
+            .compress(&frame)
+            .expect("Compressing into memory can not fail")
+            .into()
+    }
+
+    /// Returns false if the change can not be sent
+    fn put_change(&mut self, buf: &mut Vec<u8>, change: &WorldChange) -> bool {
+        let start = buf.len();
+        match change {
+            WorldChange::Spawn(Some(id), data) => {
+                buf.put_u8(SPAWN);
+                self.put_entity(buf, *id);
+                self.put_entity_data(buf, data);
+            }
+            WorldChange::Spawn(None, data) => {
+                buf.put_u8(SPAWN_WITHOUT_ID);
+                self.put_entity_data(buf, data);
+            }
+            WorldChange::Despawn(id) => {
+                buf.put_u8(DESPAWN);
+                self.put_entity(buf, *id);
+                self.entities.remove(id);
+            }
+            WorldChange::AddComponents(id, data) => {
+                buf.put_u8(ADD_COMPONENTS);
+                self.put_entity(buf, *id);
+                self.put_entity_data(buf, data);
+            }
+            WorldChange::RemoveComponents(id, components) => {
+                buf.put_u8(REMOVE_COMPONENTS);
+                self.put_entity(buf, *id);
+                buf.put_varint(components.len() as u64);
+                for &desc in components {
+                    let start = buf.len();
+                    self.put_component(buf, desc);
+                    self.bandwidth.add_component(desc, buf.len() - start);
+                }
+            }
+            WorldChange::Set(id, entry) => {
+                if !entry.has_attribute::<Serializable>() {
+                    return false;
+                }
+
+                buf.put_u8(SET);
+                self.put_entity(buf, *id);
+                self.put_entry(buf, entry);
+            }
+        }
+        if let Some(id) = changed_entity(change) {
+            self.bandwidth.add_entity(id, buf.len() - start);
+        }
+        true
+    }
+
+    fn put_entity(&mut self, buf: &mut Vec<u8>, id: EntityId) {
+        match self.entities.get(&id) {
+            Some(&index) => buf.put_varint(index + 1),
+            None => {
+                self.entities.insert(id, self.next_entity);
+                self.next_entity += 1;
+                buf.put_varint(0);
+                buf.put_u128_le(id.0);
+            }
+        }
+    }
+
+    /// Returns the precision of the component
+    fn put_component(&mut self, buf: &mut Vec<u8>, desc: ComponentDesc) -> Option<f32> {
+        if let Some(&(index, precision)) = self.components.get(&desc.index()) {
+            buf.put_varint(index + 1);
+            return precision;
+        }
+
+        let precision = desc
+            .attribute::<Quantized>()
+            .map(|attr| attr.precision)
+            .filter(|&precision| precision > 0.0 && precision.is_finite())
+            .filter(|_| float_count(desc).is_some());
+        self.components
+            .insert(desc.index(), (self.components.len() as u64, precision));
+
+        let path = desc.path();
+        buf.put_varint(0);
+        buf.put_varint(path.len() as u64);
+        buf.put_slice(path.as_bytes());
+        buf.put_f32_le(precision.unwrap_or_default());
+        precision
+    }
+
+    fn put_entity_data(&mut self, buf: &mut Vec<u8>, data: &Entity) {
+        let entries = data
+            .iter()
+            .filter(|entry| entry.has_attribute::<Serializable>())
+            .collect::<Vec<_>>();
+
+        buf.put_varint(entries.len() as u64);
+        for entry in entries {
+            self.put_entry(buf, entry);
+        }
+    }
+
+    fn put_entry(&mut self, buf: &mut Vec<u8>, entry: &ComponentEntry) {
+        let start = buf.len();
+        self.put_value(buf, entry);
+        self.bandwidth
+            .add_component(entry.desc(), buf.len() - start);
+    }
+
+    fn put_value(&mut self, buf: &mut Vec<u8>, entry: &ComponentEntry) {
+        if let Some(precision) = self.put_component(buf, entry.desc()) {
+            match quantize(&to_floats(entry).unwrap_or_default(), precision) {
+                Some(values) => {
+                    buf.put_u8(QUANTIZED);
+                    for value in values {
+                        buf.put_varint(zigzag(value));
+                    }
+                    return;
+                }
+                // Values which are too large or not finite are sent as they are
+                None => buf.put_u8(FULL_PRECISION),
+            }
+        }
+
+        let ser = entry.attribute::<Serializable>().unwrap();
+        let bytes = bincode_options().serialize(ser.serialize(entry)).unwrap();
+        buf.put_varint(bytes.len() as u64);
+        buf.put_slice(&bytes);
+    }
+}
+
+/// Decodes the diffs received over one connection
+#[derive(Debug, Default)]
+pub struct DiffDecoder {
+    components: Vec<(ComponentDesc, Option<f32>)>,
+    entities: HashMap<u64, EntityId>,
+    next_entity: u64,
+    bandwidth: DiffBandwidth,
+}
+
+impl DiffDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the bytes decoded since the last call
+    pub fn take_bandwidth(&mut self) -> DiffBandwidth {
+        std::mem::take(&mut self.bandwidth)
+    }
+
+    pub fn decode(&mut self, frame: &[u8]) -> Result<WorldDiff, DiffDecodeError> {
+        let frame = decompress(frame).map_err(DiffDecodeError::Decompress)?;
+        self.bandwidth.bytes += frame.len() as u64;
+        let mut buf = &frame[..];
+
+        let count = buf.try_get_varint()?;
+        let changes = (0..count)
+            .map(|_| self.get_change(&mut buf))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(WorldDiff { changes })
+    }
+
+    fn get_change(&mut self, buf: &mut &[u8]) -> Result<WorldChange, DiffDecodeError> {
+        let start = buf.len();
+        let change = match buf.try_get::<u8>()? {
+            SPAWN => {
+                let (_, id) = self.get_entity(buf)?;
+                WorldChange::Spawn(Some(id), self.get_entity_data(buf)?)
+            }
+            SPAWN_WITHOUT_ID => WorldChange::Spawn(None, self.get_entity_data(buf)?),
+            DESPAWN => {
+                let (index, id) = self.get_entity(buf)?;
+                self.entities.remove(&index);
+                WorldChange::Despawn(id)
+            }
+            ADD_COMPONENTS => {
+                let (_, id) = self.get_entity(buf)?;
+                WorldChange::AddComponents(id, self.get_entity_data(buf)?)
+            }
+            REMOVE_COMPONENTS => {
+                let (_, id) = self.get_entity(buf)?;
+                let count = buf.try_get_varint()?;
+                let components = (0..count)
+                    .map(|_| {
+                        let start = buf.len();
+                        let (desc, _) = self.get_component(buf)?;
+                        self.bandwidth.add_component(desc, start - buf.len());
+                        Ok(desc)
+                    })
+                    .collect::<Result<Vec<_>, DiffDecodeError>>()?;
+                WorldChange::RemoveComponents(id, components)
+            }
+            SET => {
+                let (_, id) = self.get_entity(buf)?;
+                WorldChange::Set(id, self.get_entry(buf)?)
+            }
+            kind => return Err(DiffDecodeError::InvalidChange(kind)),
+        };
+        if let Some(id) = changed_entity(&change) {
+            self.bandwidth.add_entity(id, start - buf.len());
+        }
+        Ok(change)
+    }
+
+    fn get_entity(&mut self, buf: &mut &[u8]) -> Result<(u64, EntityId), DiffDecodeError> {
+        match buf.try_get_varint()? {
+            0 => {
+                let id = EntityId(u128::from_le_bytes(take(buf, 16)?.try_into().unwrap()));
+                let index = self.next_entity;
+                self.entities.insert(index, id);
+                self.next_entity += 1;
+                Ok((index, id))
+            }
+            index => {
+                let index = index - 1;
+                let id = self
+                    .entities
+                    .get(&index)
+                    .ok_or(DiffDecodeError::UnknownEntityIndex(index))?;
+                Ok((index, *id))
+            }
+        }
+    }
+
+    fn get_component(
+        &mut self,
+        buf: &mut &[u8],
+    ) -> Result<(ComponentDesc, Option<f32>), DiffDecodeError> {
+        match buf.try_get_varint()? {
+            0 => {
+                let len = buf.try_get_varint()? as usize;
+                let path = String::from_utf8_lossy(take(buf, len)?).into_owned();
+                let precision = f32::from_le_bytes(take(buf, 4)?.try_into().unwrap());
+
+                let desc = ComponentRegistry::get()
+                    .get_by_path(&path)
+                    .ok_or(DiffDecodeError::UnknownComponent(path))?;
+                let precision = Some(precision).filter(|&precision| precision > 0.0);
+                self.components.push((desc, precision));
+                Ok((desc, precision))
+            }
+            index => self
+                .components
+                .get(index as usize - 1)
+                .copied()
+                .ok_or(DiffDecodeError::UnknownComponentIndex(index - 1)),
+        }
+    }
+
+    fn get_entity_data(&mut self, buf: &mut &[u8]) -> Result<Entity, DiffDecodeError> {
+        let count = buf.try_get_varint()?;
+        let mut data = Entity::new();
+        for _ in 0..count {
+            data.set_entry(self.get_entry(buf)?);
+        }
+        Ok(data)
+    }
+
+    fn get_entry(&mut self, buf: &mut &[u8]) -> Result<ComponentEntry, DiffDecodeError> {
+        let start = buf.len();
+        let entry = self.get_value(buf)?;
+        self.bandwidth
+            .add_component(entry.desc(), start - buf.len());
+        Ok(entry)
+    }
+
+    fn get_value(&mut self, buf: &mut &[u8]) -> Result<ComponentEntry, DiffDecodeError> {
+        let (desc, precision) = self.get_component(buf)?;
+        let invalid = |err| DiffDecodeError::InvalidValue(desc.path(), err);
+
+        if let Some(precision) = precision {
+            if buf.try_get::<u8>()? == QUANTIZED {
+                let count = float_count(desc).ok_or_else(|| invalid(None))?;
+                let values = (0..count)
+                    .map(|_| Ok(unzigzag(buf.try_get_varint()?) as f64 * precision as f64))
+                    .collect::<Result<Vec<_>, DiffDecodeError>>()?;
+                return from_floats(desc, &values).ok_or_else(|| invalid(None));
+            }
+        }
+
+        let len = buf.try_get_varint()? as usize;
+        let bytes = take(buf, len)?;
+        let ser = desc
+            .attribute::<Serializable>()
+            .ok_or_else(|| DiffDecodeError::NotSerializable(desc.path()))?;
+        bincode_options()
+            .deserialize_seed(ser.deserializer(desc), bytes)
+            .map_err(|err| invalid(Some(err)))
+    }
+}
+
+/// The entity which a change is made to, if it is known
+fn changed_entity(change: &WorldChange) -> Option<EntityId> {
+    match change {
+        WorldChange::Spawn(id, _) => *id,
+        WorldChange::Despawn(id)
+        | WorldChange::AddComponents(id, _)
+        | WorldChange::RemoveComponents(id, _)
+        | WorldChange::Set(id, _) => Some(*id),
+    }
+}
+
+/// The options of `bincode::serialize`
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+}
+
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], DiffDecodeError> {
+    if buf.remaining() < len {
+        return Err(DiffDecodeError::UnexpectedEnd);
+    }
+
+    let (value, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(value)
+}
+
+fn float_count(desc: ComponentDesc) -> Option<usize> {
+    if desc.is::<f32>() {
+        Some(1)
+    } else if desc.is::<Vec2>() {
+        Some(2)
+    } else if desc.is::<Vec3>() {
+        Some(3)
+    } else if desc.is::<Vec4>() || desc.is::<Quat>() {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+fn to_floats(entry: &ComponentEntry) -> Option<Vec<f32>> {
+    if let Some(value) = entry.try_downcast_ref::<f32>() {
+        Some(vec![*value])
+    } else if let Some(value) = entry.try_downcast_ref::<Vec2>() {
+        Some(value.to_array().to_vec())
+    } else if let Some(value) = entry.try_downcast_ref::<Vec3>() {
+        Some(value.to_array().to_vec())
+    } else if let Some(value) = entry.try_downcast_ref::<Vec4>() {
+        Some(value.to_array().to_vec())
+    } else {
+        entry
+            .try_downcast_ref::<Quat>()
+            .map(|value| value.to_array().to_vec())
+    }
+}
+
+fn from_floats(desc: ComponentDesc, values: &[f64]) -> Option<ComponentEntry> {
+    let values = values.iter().map(|&value| value as f32).collect::<Vec<_>>();
+    let entry = if desc.is::<f32>() {
+        ComponentEntry::from_raw_parts(desc, values[0])
+    } else if desc.is::<Vec2>() {
+        ComponentEntry::from_raw_parts(desc, Vec2::from_slice(&values))
+    } else if desc.is::<Vec3>() {
+        ComponentEntry::from_raw_parts(desc, Vec3::from_slice(&values))
+    } else if desc.is::<Vec4>() {
+        ComponentEntry::from_raw_parts(desc, Vec4::from_slice(&values))
+    } else if desc.is::<Quat>() {
+        let value = Quat::from_slice(&values);
+        // Rounding the components denormalizes the rotation
+        let value = if value.length_squared() > 0.0 {
+            value.normalize()
+        } else {
+            value
+        };
+        ComponentEntry::from_raw_parts(desc, value)
+    } else {
+        return None;
+    };
+    Some(entry)
+}
+
+fn quantize(values: &[f32], precision: f32) -> Option<Vec<i64>> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values
+        .iter()
+        .map(|&value| {
+            let value = (value as f64 / precision as f64).round();
+            (value.abs() <= MAX_QUANTIZED).then_some(value as i64)
+        })
+        .collect()
+}
+
+/// Maps signed values to unsigned ones, so that small negative values are small varints
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_core::{name, transform::scale};
+    use ambient_ecs::World;
+
+    use super::*;
+
+    fn init() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        crate::init_all_components();
+    }
+
+    #[test]
+    fn zigzag_roundtrip() {
+        for value in [0, 1, -1, 63, -64, i64::MAX, i64::MIN] {
+            assert_eq!(unzigzag(zigzag(value)), value);
+        }
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+    }
+
+    #[test]
+    fn roundtrip() {
+        init();
+        let mut world = World::new_with_config("test", false);
+
+        let a = EntityId::new();
+        let b = EntityId::new();
+        let diff = WorldDiff {
+            changes: vec![
+                WorldChange::Spawn(
+                    Some(a),
+                    Entity::new()
+                        .with(name(), "a".to_string())
+                        .with(translation(), Vec3::new(1.23456, -100.0, 0.0))
+                        .with(rotation(), Quat::from_rotation_y(1.0)),
+                ),
+                WorldChange::Spawn(Some(b), Entity::new().with(scale(), Vec3::ONE * 0.123456)),
+            ],
+        }
+        .set(a, translation(), Vec3::new(f32::INFINITY, 0.0, 0.0))
+        .remove_components_raw(b, vec![scale().desc()]);
+
+        let mut encoder = DiffEncoder::new();
+        let mut decoder = DiffDecoder::new();
+        for compression in [Compression::None, Compression::Zstd(3)] {
+            let decoded = decoder.decode(&encoder.encode(&diff, compression)).unwrap();
+            decoded.apply(&mut world, Entity::new(), false);
+
+            assert_eq!(world.get_ref(a, name()).unwrap(), "a");
+            // Values which can not be quantized are sent as they are
+            assert_eq!(
+                world.get(a, translation()).unwrap(),
+                Vec3::new(f32::INFINITY, 0.0, 0.0)
+            );
+            assert!(world
+                .get(a, rotation())
+                .unwrap()
+                .abs_diff_eq(Quat::from_rotation_y(1.0), ROTATION_PRECISION));
+            assert!(!world.has_component(b, scale()));
+
+            world.despawn(a);
+            world.despawn(b);
+        }
+
+        // The paths of components and the ids of entities are only sent once
+        let update = WorldDiff::new().set(a, translation(), Vec3::new(1.23456, 2.0, 3.0));
+        let frame = encoder.encode(&update, Compression::None);
+        assert!(frame.len() < 16);
+
+        // Quantized components are rounded to their precision
+        let decoded = decoder.decode(&frame).unwrap();
+        let WorldChange::Set(id, entry) = &decoded.changes[0] else {
+            panic!("Expected a set");
+        };
+        assert_eq!(*id, a);
+        assert!(entry
+            .downcast_ref::<Vec3>()
+            .abs_diff_eq(Vec3::new(1.235, 2.0, 3.0), 1e-6));
+    }
+
+    #[test]
+    fn bandwidth_breakdown() {
+        init();
+
+        let a = EntityId::new();
+        let b = EntityId::new();
+        let diff = WorldDiff {
+            changes: vec![
+                WorldChange::Spawn(
+                    Some(a),
+                    Entity::new()
+                        .with(name(), "a".repeat(100))
+                        .with(translation(), Vec3::ONE),
+                ),
+                WorldChange::Spawn(Some(b), Entity::new().with(translation(), Vec3::ONE)),
+            ],
+        }
+        .set(b, translation(), Vec3::X);
+
+        let mut encoder = DiffEncoder::new();
+        let mut decoder = DiffDecoder::new();
+        let frame = encoder.encode(&diff, Compression::Zstd(3));
+        decoder.decode(&frame).unwrap();
+
+        // Both sides see the same bytes before compression
+        let bandwidth = encoder.take_bandwidth();
+        assert_eq!(bandwidth, decoder.take_bandwidth());
+        assert_eq!(encoder.take_bandwidth(), DiffBandwidth::default());
+
+        assert!(bandwidth.components[&name().desc()] > bandwidth.components[&translation().desc()]);
+        assert!(bandwidth.entities[&a] > bandwidth.entities[&b]);
+        let changes = bandwidth.entities.values().sum::<u64>();
+        assert!(changes < bandwidth.bytes);
+        assert!(bandwidth.components.values().sum::<u64>() < changes);
+    }
+
+    #[test]
+    fn despawned_entities_are_forgotten() {
+        init();
+
+        let id = EntityId::new();
+        let mut encoder = DiffEncoder::new();
+        let mut decoder = DiffDecoder::new();
+
+        let diff = WorldDiff::new().despawn(vec![id]);
+        decoder
+            .decode(&encoder.encode(&diff, Compression::None))
+            .unwrap();
+        assert!(encoder.entities.is_empty());
+        assert!(decoder.entities.is_empty());
+
+        // The decoder can not resolve an index the encoder has not sent
+        let frame = Compression::None.compress(&[1, DESPAWN, 5]).unwrap();
+        assert!(matches!(
+            decoder.decode(&frame),
+            Err(DiffDecodeError::UnknownEntityIndex(4))
+        ));
+    }
+}
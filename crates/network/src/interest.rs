@@ -0,0 +1,327 @@
+//! Per-player filtering of the entities and components which are replicated to each client.
+//!
+//! Which entities are relevant to a player is controlled with the components in
+//! `core::network`:
+//! - `interest_radius` and `interest_position` on the player limit replication to the entities
+//!   around them.
+//! - `visible_to` restricts an entity to an explicit set of players, and `always_relevant` makes
+//!   an entity ignore the interest radius.
+//! - `owner_only_components` hides components of an entity from everyone but its owner, which is
+//!   the player with the same `user_id`.
+
+use std::collections::HashSet;
+
+use ambient_core::{player::user_id, transform::translation};
+use ambient_ecs::{
+    ComponentDesc, Entity, EntityId, World, WorldChange, WorldDiff, WorldStreamFilter,
+};
+use glam::Vec3;
+
+use crate::server::player_interest;
+
+pub use ambient_ecs::generated::components::core::network::{
+    always_relevant, interest_position, interest_radius, owner_only_components, visible_to,
+};
+
+/// The full state of the world which is relevant to `player`, for a client which has just joined
+///
+/// This resets what the player is known to have been sent.
+pub fn initial_diff(world: &World, filter: &WorldStreamFilter, player: EntityId) -> WorldDiff {
+    match world.get_ref(player, player_interest()) {
+        Ok(interest) => {
+            let mut interest = interest.lock();
+            *interest = PlayerInterest::new();
+            interest.filter_diff(world, filter, player, &WorldDiff::new())
+        }
+        Err(_) => filter.initial_diff(world),
+    }
+}
+
+/// The entities which have been replicated to a player
+#[derive(Debug, Default)]
+pub struct PlayerInterest {
+    known: HashSet<EntityId>,
+}
+
+impl PlayerInterest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The entities which the player currently knows about
+    pub fn known_entities(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.known.iter().copied()
+    }
+
+    /// Filters a diff of the world down to what is relevant to `player`.
+    ///
+    /// Changes to entities the player does not know about are dropped. Entities which have become
+    /// relevant since the last diff are spawned with all of their components, and entities which
+    /// are no longer relevant are despawned.
+    pub fn filter_diff(
+        &mut self,
+        world: &World,
+        filter: &WorldStreamFilter,
+        player: EntityId,
+        diff: &WorldDiff,
+    ) -> WorldDiff {
+        let view = PlayerView::new(world, player);
+        let mut changes = Vec::new();
+
+        for change in &diff.changes {
+            match change {
+                // Spawns are handled below, with the entity's current state
+                WorldChange::Spawn(..) => {}
+                WorldChange::Despawn(id) => {
+                    if self.known.remove(id) {
+                        changes.push(change.clone());
+                    }
+                }
+                WorldChange::AddComponents(id, data) => {
+                    if self.known.contains(id) {
+                        let mut data = data.clone();
+                        let hidden = view.hidden_components(*id);
+                        data.filter(&|comp| !hidden.contains(&comp));
+                        if !data.is_empty() {
+                            changes.push(WorldChange::AddComponents(*id, data));
+                        }
+                    }
+                }
+                WorldChange::RemoveComponents(id, _) => {
+                    if self.known.contains(id) {
+                        changes.push(change.clone());
+                    }
+                }
+                WorldChange::Set(id, entry) => {
+                    if self.known.contains(id)
+                        && !view.hidden_components(*id).contains(&entry.desc())
+                    {
+                        changes.push(change.clone());
+                    }
+                }
+            }
+        }
+
+        for id in filter.all_entities(world) {
+            match (view.is_relevant(id), self.known.contains(&id)) {
+                (true, false) => {
+                    let hidden = view.hidden_components(id);
+                    let data: Entity = filter
+                        .get_entity_components(world, id)
+                        .into_iter()
+                        .filter(|comp| !hidden.contains(comp))
+                        .map(|comp| world.get_entry(id, comp).unwrap())
+                        .collect();
+                    changes.push(WorldChange::Spawn(Some(id), data));
+                    self.known.insert(id);
+                }
+                (false, true) => {
+                    changes.push(WorldChange::Despawn(id));
+                    self.known.remove(&id);
+                }
+                _ => {}
+            }
+        }
+
+        WorldDiff { changes }
+    }
+}
+
+/// The state of the player which decides what is relevant to them
+struct PlayerView<'a> {
+    world: &'a World,
+    id: EntityId,
+    user_id: Option<&'a String>,
+    /// The center and radius of the area of interest
+    area: Option<(Vec3, f32)>,
+}
+
+impl<'a> PlayerView<'a> {
+    fn new(world: &'a World, id: EntityId) -> Self {
+        let position = world
+            .get(id, interest_position())
+            .or_else(|_| world.get(id, translation()))
+            .ok();
+        let radius = world.get(id, interest_radius()).ok();
+
+        Self {
+            world,
+            id,
+            user_id: world.get_ref(id, user_id()).ok(),
+            area: position.zip(radius),
+        }
+    }
+
+    fn is_relevant(&self, id: EntityId) -> bool {
+        if id == self.id {
+            return true;
+        }
+
+        if let Ok(players) = self.world.get_ref(id, visible_to()) {
+            if !players.contains(&self.id) {
+                return false;
+            }
+        }
+
+        if self.world.has_component(id, always_relevant()) {
+            return true;
+        }
+
+        match (self.area, self.world.get(id, translation())) {
+            (Some((center, radius)), Ok(position)) => center.distance(position) <= radius,
+            _ => true,
+        }
+    }
+
+    /// The components of the entity which must not be replicated to this player
+    fn hidden_components(&self, id: EntityId) -> Vec<ComponentDesc> {
+        let Ok(paths) = self.world.get_ref(id, owner_only_components()) else {
+            return Vec::new();
+        };
+
+        let owner = self.world.get_ref(id, user_id()).ok();
+        if owner.is_some() && owner == self.user_id {
+            return Vec::new();
+        }
+
+        self.world
+            .get_components(id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|comp| paths.contains(&comp.path()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ambient_ecs::ArchetypeFilter;
+
+    use super::*;
+
+    fn init() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+    }
+
+    fn spawned(diff: &WorldDiff) -> HashSet<EntityId> {
+        diff.changes
+            .iter()
+            .filter_map(|change| match change {
+                WorldChange::Spawn(id, _) => *id,
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn despawned(diff: &WorldDiff) -> HashSet<EntityId> {
+        diff.changes
+            .iter()
+            .filter_map(|change| match change {
+                WorldChange::Despawn(id) => Some(*id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn area_of_interest() {
+        init();
+        let mut world = World::new_with_config("test", false);
+        let filter = WorldStreamFilter::new(ArchetypeFilter::new(), Arc::new(|_, _| true));
+
+        let player = Entity::new()
+            .with(interest_position(), Vec3::ZERO)
+            .with(interest_radius(), 10.0)
+            .spawn(&mut world);
+        let near = Entity::new().with(translation(), Vec3::X).spawn(&mut world);
+        let far = Entity::new()
+            .with(translation(), Vec3::X * 100.0)
+            .spawn(&mut world);
+        let global = Entity::new()
+            .with(translation(), Vec3::X * 100.0)
+            .with(always_relevant(), ())
+            .spawn(&mut world);
+        let hidden = Entity::new()
+            .with(translation(), Vec3::X)
+            .with(visible_to(), vec![EntityId::new()])
+            .spawn(&mut world);
+        let unpositioned = Entity::new()
+            .with(visible_to(), vec![player])
+            .spawn(&mut world);
+
+        let mut interest = PlayerInterest::new();
+        let diff = interest.filter_diff(&world, &filter, player, &WorldDiff::new());
+        assert_eq!(
+            spawned(&diff),
+            HashSet::from([player, near, global, unpositioned])
+        );
+
+        // Changes to irrelevant entities are dropped
+        let diff = WorldDiff::new().set(far, translation(), Vec3::X * 200.0);
+        assert!(interest
+            .filter_diff(&world, &filter, player, &diff)
+            .is_empty());
+
+        // Entities are spawned and despawned as they move in and out of range
+        world.set(far, translation(), Vec3::Y).unwrap();
+        world.set(near, translation(), Vec3::Y * 20.0).unwrap();
+        let diff = WorldDiff::new().set(far, translation(), Vec3::Y).set(
+            near,
+            translation(),
+            Vec3::Y * 20.0,
+        );
+        let diff = interest.filter_diff(&world, &filter, player, &diff);
+        assert_eq!(spawned(&diff), HashSet::from([far]));
+        assert_eq!(despawned(&diff), HashSet::from([near]));
+        assert!(!interest.known_entities().any(|id| id == hidden));
+    }
+
+    #[test]
+    fn owner_only() {
+        init();
+        let mut world = World::new_with_config("test", false);
+        let filter = WorldStreamFilter::new(ArchetypeFilter::new(), Arc::new(|_, _| true));
+
+        let owner = Entity::new()
+            .with(user_id(), "owner".to_string())
+            .spawn(&mut world);
+        let other = Entity::new()
+            .with(user_id(), "other".to_string())
+            .spawn(&mut world);
+        let inventory = Entity::new()
+            .with(user_id(), "owner".to_string())
+            .with(translation(), Vec3::ZERO)
+            .with(owner_only_components(), vec![translation().path()])
+            .spawn(&mut world);
+
+        let data = |diff: &WorldDiff| {
+            diff.changes
+                .iter()
+                .find_map(|change| match change {
+                    WorldChange::Spawn(Some(id), data) if *id == inventory => Some(data.clone()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        let mut owner_interest = PlayerInterest::new();
+        let diff = owner_interest.filter_diff(&world, &filter, owner, &WorldDiff::new());
+        assert!(data(&diff).contains(translation()));
+
+        let mut other_interest = PlayerInterest::new();
+        let diff = other_interest.filter_diff(&world, &filter, other, &WorldDiff::new());
+        assert!(!data(&diff).contains(translation()));
+        assert!(data(&diff).contains(user_id()));
+
+        let diff = WorldDiff::new().set(inventory, translation(), Vec3::ONE);
+        assert!(!owner_interest
+            .filter_diff(&world, &filter, owner, &diff)
+            .is_empty());
+        assert!(other_interest
+            .filter_diff(&world, &filter, other, &diff)
+            .is_empty());
+    }
+}
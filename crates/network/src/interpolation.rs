@@ -0,0 +1,252 @@
+//! Smooths the movement of remote entities on the client.
+//!
+//! The translation and rotation of entities with `interpolated` are not shown as soon as they
+//! arrive from the server. Instead, they are buffered and the entity is rendered
+//! `interpolation_delay` in the past, interpolating between the two snapshots around that time.
+
+use std::{collections::HashMap, collections::VecDeque, time::Duration};
+
+use ambient_core::{
+    abs_time,
+    transform::{rotation, translation},
+};
+use ambient_ecs::{components, query, EntityId, SystemGroup, World, WorldChange, WorldDiff};
+use glam::{Quat, Vec3};
+use itertools::Itertools;
+
+use crate::prediction::is_locally_predicted;
+
+pub use ambient_ecs::generated::components::core::network::{interpolated, interpolation_delay};
+
+components!("network::interpolation", {
+    /// The recent states of an interpolated entity received from the server
+    transform_snapshots: TransformSnapshots,
+});
+
+/// The interpolation delay used if the `interpolation_delay` resource is not set, in seconds
+pub const DEFAULT_INTERPOLATION_DELAY: f32 = 0.1;
+
+/// Snapshots older than this are discarded, as long as there are newer ones to interpolate between
+const MAX_SNAPSHOT_AGE: Duration = Duration::from_secs(1);
+
+/// The translation and rotation of an entity at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TransformChange {
+    pub translation: Option<Vec3>,
+    pub rotation: Option<Quat>,
+}
+
+impl TransformChange {
+    /// Overrides the values of this with the ones set in `other`
+    pub fn merge(&mut self, other: TransformChange) {
+        self.translation = other.translation.or(self.translation);
+        self.rotation = other.rotation.or(self.rotation);
+    }
+}
+
+/// Collects the translations and rotations set by a diff
+pub(crate) fn transform_changes(diff: &WorldDiff) -> HashMap<EntityId, TransformChange> {
+    let mut changes: HashMap<EntityId, TransformChange> = HashMap::new();
+    for change in &diff.changes {
+        let (id, value) = match change {
+            WorldChange::Spawn(Some(id), data) | WorldChange::AddComponents(id, data) => (
+                *id,
+                TransformChange {
+                    translation: data.get(translation()),
+                    rotation: data.get(rotation()),
+                },
+            ),
+            WorldChange::Set(id, entry) if entry.desc() == translation().desc() => (
+                *id,
+                TransformChange {
+                    translation: entry.try_downcast_ref().copied(),
+                    rotation: None,
+                },
+            ),
+            WorldChange::Set(id, entry) if entry.desc() == rotation().desc() => (
+                *id,
+                TransformChange {
+                    translation: None,
+                    rotation: entry.try_downcast_ref().copied(),
+                },
+            ),
+            _ => continue,
+        };
+
+        if value != TransformChange::default() {
+            changes.entry(id).or_default().merge(value);
+        }
+    }
+    changes
+}
+
+/// Buffers the transforms of interpolated entities changed by a diff from the server, which
+/// arrived at `time`
+pub(crate) fn record_snapshots(
+    world: &mut World,
+    changes: &HashMap<EntityId, TransformChange>,
+    time: Duration,
+) {
+    for (&id, &change) in changes {
+        if !world.has_component(id, interpolated()) || is_locally_predicted(world, id) {
+            continue;
+        }
+
+        if let Ok(snapshots) = world.get_mut(id, transform_snapshots()) {
+            snapshots.push(time, change);
+        } else {
+            let mut snapshots = TransformSnapshots::default();
+            snapshots.push(time, change);
+            world
+                .add_component(id, transform_snapshots(), snapshots)
+                .unwrap();
+        }
+    }
+}
+
+/// A buffer of the states of an entity received from the server
+#[derive(Debug, Clone, Default)]
+pub struct TransformSnapshots {
+    snapshots: VecDeque<(Duration, TransformChange)>,
+}
+
+impl TransformSnapshots {
+    /// Adds the state of the entity at `time`. Values which are not set carry over from the
+    /// previous snapshot.
+    pub fn push(&mut self, time: Duration, change: TransformChange) {
+        let mut state = self
+            .snapshots
+            .back()
+            .map(|(_, state)| *state)
+            .unwrap_or_default();
+        state.merge(change);
+
+        // Snapshots can only arrive in order
+        let time = self
+            .snapshots
+            .back()
+            .map(|(last, _)| time.max(*last))
+            .unwrap_or(time);
+        self.snapshots.push_back((time, state));
+
+        while self.snapshots.len() > 2
+            && time.saturating_sub(self.snapshots[1].0) > MAX_SNAPSHOT_AGE
+        {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// The state of the entity at `time`, interpolated between the surrounding snapshots
+    pub fn sample(&self, time: Duration) -> Option<TransformChange> {
+        let next = self.snapshots.iter().position(|(t, _)| *t >= time);
+        match next {
+            None => self.snapshots.back().map(|(_, state)| *state),
+            Some(0) => self.snapshots.front().map(|(_, state)| *state),
+            Some(i) => {
+                let (t0, a) = self.snapshots[i - 1];
+                let (t1, b) = self.snapshots[i];
+                let t = (time - t0).as_secs_f32() / (t1 - t0).as_secs_f32();
+
+                Some(TransformChange {
+                    translation: match (a.translation, b.translation) {
+                        (Some(a), Some(b)) => Some(a.lerp(b, t)),
+                        (a, b) => b.or(a),
+                    },
+                    rotation: match (a.rotation, b.rotation) {
+                        (Some(a), Some(b)) => Some(a.slerp(b, t)),
+                        (a, b) => b.or(a),
+                    },
+                })
+            }
+        }
+    }
+}
+
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "network/interpolation",
+        vec![query((transform_snapshots(),))
+            .incl(interpolated())
+            .to_system(|q, world, qs, _| {
+                let delay = world
+                    .resource_opt(interpolation_delay())
+                    .copied()
+                    .unwrap_or(DEFAULT_INTERPOLATION_DELAY);
+                let time = world
+                    .resource(abs_time())
+                    .saturating_sub(Duration::from_secs_f32(delay.max(0.)));
+
+                let states = q
+                    .iter(world, qs)
+                    .filter_map(|(id, (snapshots,))| Some((id, snapshots.sample(time)?)))
+                    .collect_vec();
+
+                for (id, state) in states {
+                    if let Some(value) = state.translation {
+                        world.add_component(id, translation(), value).unwrap();
+                    }
+                    if let Some(value) = state.rotation {
+                        world.add_component(id, rotation(), value).unwrap();
+                    }
+                }
+            })],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(translation: Vec3) -> TransformChange {
+        TransformChange {
+            translation: Some(translation),
+            rotation: None,
+        }
+    }
+
+    #[test]
+    fn sample() {
+        let ms = Duration::from_millis;
+        let mut snapshots = TransformSnapshots::default();
+        assert_eq!(snapshots.sample(ms(0)), None);
+
+        snapshots.push(ms(100), at(Vec3::ZERO));
+        snapshots.push(
+            ms(200),
+            TransformChange {
+                translation: Some(Vec3::X),
+                rotation: Some(Quat::from_rotation_z(1.0)),
+            },
+        );
+        snapshots.push(ms(300), at(Vec3::X * 3.0));
+
+        // Before the first and after the last snapshot, the closest one is used
+        assert_eq!(snapshots.sample(ms(0)), Some(at(Vec3::ZERO)));
+        assert_eq!(
+            snapshots.sample(ms(400)).unwrap().translation,
+            Some(Vec3::X * 3.0)
+        );
+
+        let state = snapshots.sample(ms(150)).unwrap();
+        assert!(state.translation.unwrap().abs_diff_eq(Vec3::X * 0.5, 1e-5));
+        // The rotation only becomes known with the second snapshot
+        assert_eq!(state.rotation, Some(Quat::from_rotation_z(1.0)));
+
+        let state = snapshots.sample(ms(250)).unwrap();
+        assert!(state.translation.unwrap().abs_diff_eq(Vec3::X * 2.0, 1e-5));
+        // Values which are not updated carry over
+        assert_eq!(state.rotation, Some(Quat::from_rotation_z(1.0)));
+    }
+
+    #[test]
+    fn old_snapshots_are_discarded() {
+        let mut snapshots = TransformSnapshots::default();
+        for i in 0..100 {
+            snapshots.push(Duration::from_millis(i * 100), at(Vec3::X * i as f32));
+        }
+
+        assert!(snapshots.snapshots.len() <= 12);
+        let state = snapshots.sample(Duration::from_millis(9850)).unwrap();
+        assert!(state.translation.unwrap().abs_diff_eq(Vec3::X * 98.5, 1e-3));
+    }
+}
@@ -15,15 +15,24 @@ pub use ambient_ecs::generated::components::core::network::{
 
 pub type AsyncMutex<T> = tokio::sync::Mutex<T>;
 
+pub mod auth;
 pub mod bytes_ext;
 pub mod client;
 pub mod client_game_state;
 pub mod codec;
+pub mod diff_codec;
 pub mod hooks;
+pub mod interest;
+pub mod interpolation;
+pub mod link_conditioner;
+pub mod prediction;
 pub mod proto;
+pub mod replay;
 pub mod rpc;
 pub mod server;
+pub mod stats;
 pub mod stream;
+pub mod tick;
 
 #[cfg(not(target_os = "unknown"))]
 pub mod native;
@@ -51,6 +60,9 @@ pub fn init_all_components() {
     client::init_components();
     server::init_components();
     client_game_state::init_components();
+    interpolation::init_components();
+    prediction::init_components();
+    diff_codec::init_quantization();
 }
 
 pub trait ServerWorldExt {
@@ -163,6 +175,8 @@ pub enum NetworkError {
     FrameError(#[from] FrameError),
     #[error("Frame or stream exceeds maximum allowed size")]
     FrameTooLarge,
+    #[error("Connection rejected by the server: {0}")]
+    ConnectionRejected(String),
 }
 
 #[cfg(not(target_os = "unknown"))]
@@ -222,6 +236,18 @@ pub fn log_network_error(err: &anyhow::Error) {
     }
 }
 
+/// Returns true if the error was caused by the connection timing out or being reset, rather than
+/// being closed by either side
+#[cfg(not(target_os = "unknown"))]
+pub fn is_connection_lost(err: &anyhow::Error) -> bool {
+    err.chain().any(|err| {
+        matches!(
+            err.downcast_ref::<quinn::ConnectionError>(),
+            Some(quinn::ConnectionError::TimedOut | quinn::ConnectionError::Reset)
+        )
+    })
+}
+
 #[cfg(target_os = "unknown")]
 pub fn log_network_error(err: &anyhow::Error) {
     log_error(err);
@@ -0,0 +1,315 @@
+//! Simulates a bad network link on localhost by delaying, reordering and dropping the traffic an
+//! endpoint receives.
+use std::{cmp::Ordering, collections::BinaryHeap, pin::Pin, task::Poll, time::Duration};
+
+use ambient_sys::time::Instant;
+use futures::{future::BoxFuture, FutureExt, Stream};
+use pin_project::pin_project;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// How much longer a datagram picked for reordering is held back, which lets the datagrams
+/// sent after it within a couple of server ticks overtake it
+pub const REORDER_DELAY: Duration = Duration::from_millis(30);
+
+/// The simulated conditions of a network link, in one direction
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LinkConditions {
+    /// Delay added to every datagram and stream frame
+    pub latency: Duration,
+    /// Maximum random delay added on top of `latency`
+    pub jitter: Duration,
+    /// Chance, from 0 to 1, of a datagram being dropped
+    pub loss: f32,
+    /// Chance, from 0 to 1, of a datagram being held back by [REORDER_DELAY]
+    pub reorder: f32,
+}
+
+impl LinkConditions {
+    /// Returns true if these conditions leave the traffic untouched
+    pub fn is_ideal(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Decides the fate of each datagram and frame passing through a link
+pub struct LinkConditioner {
+    conditions: LinkConditions,
+    rng: StdRng,
+    last_frame: Option<Instant>,
+}
+
+impl LinkConditioner {
+    pub fn new(conditions: LinkConditions) -> Self {
+        Self::from_rng(conditions, StdRng::from_entropy())
+    }
+
+    /// Creates a conditioner which makes the same decisions every time, for tests
+    pub fn with_seed(conditions: LinkConditions, seed: u64) -> Self {
+        Self::from_rng(conditions, StdRng::seed_from_u64(seed))
+    }
+
+    fn from_rng(conditions: LinkConditions, rng: StdRng) -> Self {
+        Self {
+            conditions,
+            rng,
+            last_frame: None,
+        }
+    }
+
+    pub fn conditions(&self) -> &LinkConditions {
+        &self.conditions
+    }
+
+    /// Returns when a datagram received at `now` should be delivered, or `None` if it is lost
+    pub fn datagram_deadline(&mut self, now: Instant) -> Option<Instant> {
+        if self.rng.gen::<f32>() < self.conditions.loss {
+            return None;
+        }
+
+        let mut delay = self.delay();
+        if self.rng.gen::<f32>() < self.conditions.reorder {
+            delay += REORDER_DELAY;
+        }
+
+        Some(now + delay)
+    }
+
+    /// Returns when a stream frame received at `now` should be delivered.
+    ///
+    /// Streams are reliable and ordered, so frames are never lost and never overtake each other.
+    pub fn frame_deadline(&mut self, now: Instant) -> Instant {
+        let deadline = now + self.delay();
+        let deadline = match self.last_frame {
+            Some(last) => deadline.max(last),
+            None => deadline,
+        };
+
+        self.last_frame = Some(deadline);
+        deadline
+    }
+
+    fn delay(&mut self) -> Duration {
+        self.conditions.latency + self.conditions.jitter.mul_f32(self.rng.gen::<f32>())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Traffic {
+    Datagrams,
+    Frames,
+}
+
+struct Pending<T> {
+    deliver_at: Instant,
+    seq: u64,
+    item: T,
+}
+
+impl<T> PartialEq for Pending<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Pending<T> {}
+
+impl<T> PartialOrd for Pending<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Pending<T> {
+    // Reversed, so that the heap yields the earliest deadline first
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.deliver_at, other.seq).cmp(&(self.deliver_at, self.seq))
+    }
+}
+
+/// Holds back the items of a stream of received traffic according to a [LinkConditioner].
+///
+/// Without conditions, the items are passed through as they arrive.
+#[pin_project]
+pub struct Conditioned<S: Stream> {
+    #[pin]
+    inner: S,
+    conditioner: Option<LinkConditioner>,
+    traffic: Traffic,
+    pending: BinaryHeap<Pending<S::Item>>,
+    next_seq: u64,
+    ended: bool,
+    sleep: Option<(Instant, BoxFuture<'static, ()>)>,
+}
+
+impl<S: Stream> Conditioned<S> {
+    /// Conditions a stream of datagrams, which may be dropped and reordered
+    pub fn datagrams(inner: S, conditions: Option<LinkConditions>) -> Self {
+        Self::new(inner, conditions, Traffic::Datagrams)
+    }
+
+    /// Conditions a stream of frames read from a reliable stream, which are only delayed
+    pub fn frames(inner: S, conditions: Option<LinkConditions>) -> Self {
+        Self::new(inner, conditions, Traffic::Frames)
+    }
+
+    fn new(inner: S, conditions: Option<LinkConditions>, traffic: Traffic) -> Self {
+        Self {
+            inner,
+            conditioner: conditions
+                .filter(|v| !v.is_ideal())
+                .map(LinkConditioner::new),
+            traffic,
+            pending: BinaryHeap::new(),
+            next_seq: 0,
+            ended: false,
+            sleep: None,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_conditioner(inner: S, conditioner: LinkConditioner, traffic: Traffic) -> Self {
+        Self {
+            conditioner: Some(conditioner),
+            ..Self::new(inner, None, traffic)
+        }
+    }
+}
+
+impl<S: Stream> Stream for Conditioned<S> {
+    type Item = S::Item;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut p = self.project();
+
+        let Some(conditioner) = p.conditioner.as_mut() else {
+            return p.inner.poll_next(cx);
+        };
+
+        // Take in everything which has arrived so far
+        while !*p.ended {
+            match p.inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    let now = Instant::now();
+                    let deliver_at = match p.traffic {
+                        Traffic::Datagrams => conditioner.datagram_deadline(now),
+                        Traffic::Frames => Some(conditioner.frame_deadline(now)),
+                    };
+
+                    if let Some(deliver_at) = deliver_at {
+                        p.pending.push(Pending {
+                            deliver_at,
+                            seq: *p.next_seq,
+                            item,
+                        });
+                        *p.next_seq += 1;
+                    }
+                }
+                Poll::Ready(None) => *p.ended = true,
+                Poll::Pending => break,
+            }
+        }
+
+        loop {
+            let Some(next) = p.pending.peek() else {
+                return if *p.ended {
+                    Poll::Ready(None)
+                } else {
+                    Poll::Pending
+                };
+            };
+
+            let deliver_at = next.deliver_at;
+            if deliver_at <= Instant::now() {
+                *p.sleep = None;
+                return Poll::Ready(p.pending.pop().map(|v| v.item));
+            }
+
+            match p.sleep {
+                Some((deadline, _)) if *deadline == deliver_at => {}
+                _ => {
+                    *p.sleep = Some((
+                        deliver_at,
+                        ambient_sys::time::sleep_until(deliver_at).boxed(),
+                    ))
+                }
+            }
+
+            let (_, sleep) = p.sleep.as_mut().unwrap();
+            if sleep.poll_unpin(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::*;
+
+    #[test]
+    fn frames_keep_their_order() {
+        let mut conditioner = LinkConditioner::with_seed(
+            LinkConditions {
+                latency: Duration::from_millis(50),
+                jitter: Duration::from_millis(100),
+                ..Default::default()
+            },
+            1,
+        );
+
+        let start = Instant::now();
+        let mut last = start;
+        for i in 0..100 {
+            let deadline = conditioner.frame_deadline(start + Duration::from_millis(i));
+            assert!(deadline >= last);
+            assert!(deadline >= start + Duration::from_millis(i + 50));
+            last = deadline;
+        }
+    }
+
+    #[test]
+    fn datagrams_are_dropped_and_reordered() {
+        let mut conditioner = LinkConditioner::with_seed(
+            LinkConditions {
+                loss: 0.5,
+                reorder: 0.5,
+                ..Default::default()
+            },
+            1,
+        );
+
+        let now = Instant::now();
+        let deadlines = (0..1000)
+            .map(|_| conditioner.datagram_deadline(now))
+            .collect::<Vec<_>>();
+
+        let delivered = deadlines.iter().flatten().count();
+        assert!((400..600).contains(&delivered), "{delivered}");
+        assert!(deadlines.contains(&Some(now)));
+        assert!(deadlines.contains(&Some(now + REORDER_DELAY)));
+    }
+
+    #[test]
+    fn conditioned_stream() {
+        let ideal = Conditioned::datagrams(stream::iter(0..10), Some(LinkConditions::default()));
+        assert_eq!(
+            block_on(ideal.collect::<Vec<_>>()),
+            (0..10).collect::<Vec<_>>()
+        );
+
+        let lossy = LinkConditioner::with_seed(
+            LinkConditions {
+                loss: 1.0,
+                ..Default::default()
+            },
+            1,
+        );
+        let lossy = Conditioned::with_conditioner(stream::iter(0..10), lossy, Traffic::Datagrams);
+        assert_eq!(block_on(lossy.collect::<Vec<_>>()), Vec::<i32>::new());
+    }
+}
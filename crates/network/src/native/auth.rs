@@ -0,0 +1,466 @@
+//! Connection verifiers which check signed tokens issued by a trusted service, such as a login
+//! server or matchmaker.
+
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use data_encoding::{BASE64, BASE64URL_NOPAD};
+use futures::future::BoxFuture;
+use ring::{hmac, signature};
+use serde::Deserialize;
+
+use crate::{auth::ConnectionVerifier, server::SharedServerState};
+
+const MISSING_CREDENTIAL: &str = "A credential is required to join this server";
+
+/// Accepts tokens signed with a secret which is shared between the server and the service which
+/// issues them.
+///
+/// Tokens have the form `<expires>.<signature>`, where `expires` is the Unix time in seconds after
+/// which the token is no longer valid, and `signature` is the unpadded base64url encoded
+/// HMAC-SHA256 of `<user_id>.<expires>`.
+pub struct SharedSecretVerifier {
+    key: hmac::Key,
+}
+
+impl SharedSecretVerifier {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret),
+        }
+    }
+
+    /// Issues a token which lets `user_id` connect until `expires`
+    pub fn issue(&self, user_id: &str, expires: SystemTime) -> String {
+        let expires = unix_time(expires);
+        let tag = hmac::sign(&self.key, format!("{user_id}.{expires}").as_bytes());
+        format!("{expires}.{}", BASE64URL_NOPAD.encode(tag.as_ref()))
+    }
+
+    pub fn verify_token(&self, user_id: &str, token: &str) -> Result<(), String> {
+        const INVALID: &str = "Invalid credential";
+
+        let (expires, signature) = token.split_once('.').ok_or(INVALID)?;
+        let signature = BASE64URL_NOPAD
+            .decode(signature.as_bytes())
+            .map_err(|_| INVALID)?;
+        hmac::verify(
+            &self.key,
+            format!("{user_id}.{expires}").as_bytes(),
+            &signature,
+        )
+        .map_err(|_| INVALID)?;
+
+        let expires: u64 = expires.parse().map_err(|_| INVALID)?;
+        if unix_time(SystemTime::now()) > expires {
+            return Err("The credential has expired".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl ConnectionVerifier for SharedSecretVerifier {
+    fn verify(
+        &self,
+        _state: SharedServerState,
+        user_id: String,
+        credential: Option<String>,
+    ) -> BoxFuture<'static, Result<(), String>> {
+        let result = match credential {
+            Some(token) => self.verify_token(&user_id, &token),
+            None => Err(MISSING_CREDENTIAL.to_string()),
+        };
+        Box::pin(futures::future::ready(result))
+    }
+}
+
+/// Accepts JSON Web Tokens signed by the holder of a private key.
+///
+/// The `sub` claim of the token must be the user id the client connects with, and the token must
+/// have an `exp` claim. `EdDSA` (Ed25519), `ES256` and `RS256` signatures are supported, depending
+/// on the type of the public key.
+pub struct JwtVerifier {
+    algorithm: &'static str,
+    key: signature::UnparsedPublicKey<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+}
+
+#[derive(Deserialize)]
+struct JwtClaims {
+    sub: String,
+    exp: u64,
+    nbf: Option<u64>,
+}
+
+impl JwtVerifier {
+    /// Creates a verifier from a public key in the PEM or DER `SubjectPublicKeyInfo` format, as
+    /// written by `openssl pkey -pubout`
+    pub fn from_public_key(key: &[u8]) -> anyhow::Result<Self> {
+        let der = match std::str::from_utf8(key) {
+            Ok(pem) if pem.trim_start().starts_with("-----BEGIN") => {
+                let body: String = pem
+                    .lines()
+                    .filter(|line| !line.starts_with("-----"))
+                    .flat_map(|line| line.trim().chars())
+                    .collect();
+                BASE64
+                    .decode(body.as_bytes())
+                    .context("Invalid PEM encoded public key")?
+            }
+            _ => key.to_vec(),
+        };
+
+        let (algorithm, key) =
+            parse_public_key(&der).context("Unsupported or malformed public key")?;
+        let verification: &'static dyn signature::VerificationAlgorithm = match algorithm {
+            "EdDSA" => &signature::ED25519,
+            "ES256" => &signature::ECDSA_P256_SHA256_FIXED,
+            "RS256" => &signature::RSA_PKCS1_2048_8192_SHA256,
+            _ => unreachable!(),
+        };
+
+        Ok(Self {
+            algorithm,
+            key: signature::UnparsedPublicKey::new(verification, key.to_vec()),
+        })
+    }
+
+    pub fn verify_token(&self, user_id: &str, token: &str) -> Result<(), String> {
+        const INVALID: &str = "Invalid credential";
+
+        let mut parts = token.split('.');
+        let (Some(header), Some(claims), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(INVALID.to_string());
+        };
+
+        let decode = |part: &str| BASE64URL_NOPAD.decode(part.as_bytes()).map_err(|_| INVALID);
+
+        let header: JwtHeader = serde_json::from_slice(&decode(header)?).map_err(|_| INVALID)?;
+        if header.alg != self.algorithm {
+            return Err(INVALID.to_string());
+        }
+
+        let signed = &token[..token.len() - signature.len() - 1];
+        self.key
+            .verify(signed.as_bytes(), &decode(signature)?)
+            .map_err(|_| INVALID)?;
+
+        let claims: JwtClaims = serde_json::from_slice(&decode(claims)?).map_err(|_| INVALID)?;
+        let now = unix_time(SystemTime::now());
+        if claims.sub != user_id {
+            return Err(format!("The credential is not valid for {user_id:?}"));
+        }
+        if now > claims.exp {
+            return Err("The credential has expired".to_string());
+        }
+        if claims.nbf.map(|nbf| now < nbf).unwrap_or(false) {
+            return Err("The credential is not valid yet".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl ConnectionVerifier for JwtVerifier {
+    fn verify(
+        &self,
+        _state: SharedServerState,
+        user_id: String,
+        credential: Option<String>,
+    ) -> BoxFuture<'static, Result<(), String>> {
+        let result = match credential {
+            Some(token) => self.verify_token(&user_id, &token),
+            None => Err(MISSING_CREDENTIAL.to_string()),
+        };
+        Box::pin(futures::future::ready(result))
+    }
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
+
+const ED25519_OID: &[u8] = &[0x2b, 0x65, 0x70];
+const EC_PUBLIC_KEY_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const P256_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const RSA_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+/// Extracts the JWT algorithm and the key from a DER encoded `SubjectPublicKeyInfo`
+fn parse_public_key(der: &[u8]) -> Option<(&'static str, &[u8])> {
+    const SEQUENCE: u8 = 0x30;
+    const OID: u8 = 0x06;
+    const BIT_STRING: u8 = 0x03;
+
+    let (info, _) = der_element(der, SEQUENCE)?;
+    let (algorithm, rest) = der_element(info, SEQUENCE)?;
+    let (oid, parameters) = der_element(algorithm, OID)?;
+    let (bits, _) = der_element(rest, BIT_STRING)?;
+    // The number of unused bits in the last byte, which is always 0 for keys
+    let (&0, key) = bits.split_first()? else {
+        return None;
+    };
+
+    let algorithm = match oid {
+        ED25519_OID => "EdDSA",
+        EC_PUBLIC_KEY_OID if der_element(parameters, OID)?.0 == P256_OID => "ES256",
+        RSA_OID => "RS256",
+        _ => return None,
+    };
+    Some((algorithm, key))
+}
+
+/// Splits a DER element with the given tag into its contents and the input which follows it
+fn der_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&actual, input) = input.split_first()?;
+    if actual != tag {
+        return None;
+    }
+
+    let (&len, mut input) = input.split_first()?;
+    let len = if len & 0x80 == 0 {
+        len as usize
+    } else {
+        let count = (len & 0x7f) as usize;
+        if count == 0 || count > 4 || input.len() < count {
+            return None;
+        }
+        let (bytes, rest) = input.split_at(count);
+        input = rest;
+        bytes
+            .iter()
+            .fold(0usize, |len, &byte| (len << 8) | byte as usize)
+    };
+
+    (input.len() >= len).then(|| input.split_at(len))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ambient_std::asset_cache::AssetCache;
+    use parking_lot::Mutex;
+    use ring::{
+        rand::SystemRandom,
+        signature::{EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair},
+    };
+
+    use super::*;
+    use crate::{
+        auth::verify_connection,
+        server::{connection_verifier, ServerState, MAIN_INSTANCE_ID},
+    };
+
+    const ED25519_SPKI_PREFIX: &[u8] = &[
+        0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+    ];
+    const P256_SPKI_PREFIX: &[u8] = &[
+        0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08,
+        0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+    ];
+    /// ring cannot generate RSA keys, so the test uses a pair generated with openssl
+    const RSA_PRIVATE_KEY: &[u8] = include_bytes!("test_data/rsa_2048.pk8");
+    const RSA_PUBLIC_KEY: &[u8] = include_bytes!("test_data/rsa_2048.pub.pem");
+
+    fn in_an_hour() -> u64 {
+        unix_time(SystemTime::now()) + 3600
+    }
+
+    fn jwt(alg: &str, claims: &str, sign: impl Fn(&[u8]) -> Vec<u8>) -> String {
+        let header = BASE64URL_NOPAD.encode(format!(r#"{{"alg":"{alg}","typ":"JWT"}}"#).as_bytes());
+        let signed = format!("{header}.{}", BASE64URL_NOPAD.encode(claims.as_bytes()));
+        let signature = BASE64URL_NOPAD.encode(&sign(signed.as_bytes()));
+        format!("{signed}.{signature}")
+    }
+
+    #[test]
+    fn shared_secret() {
+        let verifier = SharedSecretVerifier::new(b"secret");
+        let expires = SystemTime::now() + Duration::from_secs(60);
+        let token = verifier.issue("alice", expires);
+
+        assert_eq!(verifier.verify_token("alice", &token), Ok(()));
+        assert!(verifier.verify_token("bob", &token).is_err());
+        assert!(SharedSecretVerifier::new(b"other")
+            .verify_token("alice", &token)
+            .is_err());
+
+        // The expiry is covered by the signature
+        let (_, signature) = token.split_once('.').unwrap();
+        let extended = format!("{}.{signature}", in_an_hour());
+        assert!(verifier.verify_token("alice", &extended).is_err());
+
+        let expired = verifier.issue("alice", SystemTime::now() - Duration::from_secs(60));
+        assert_eq!(
+            verifier.verify_token("alice", &expired),
+            Err("The credential has expired".to_string())
+        );
+    }
+
+    #[test]
+    fn jwt_ed25519() {
+        let pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let mut spki = ED25519_SPKI_PREFIX.to_vec();
+        spki.extend_from_slice(pair.public_key().as_ref());
+
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            BASE64.encode(&spki)
+        );
+        let verifier = JwtVerifier::from_public_key(pem.as_bytes()).unwrap();
+        let sign = |data: &[u8]| pair.sign(data).as_ref().to_vec();
+
+        let token = jwt(
+            "EdDSA",
+            &format!(r#"{{"sub":"alice","exp":{}}}"#, in_an_hour()),
+            sign,
+        );
+        assert_eq!(verifier.verify_token("alice", &token), Ok(()));
+        assert!(verifier.verify_token("bob", &token).is_err());
+
+        // Tampering with the claims invalidates the signature
+        let mut parts = token.split('.').collect::<Vec<_>>();
+        let forged =
+            BASE64URL_NOPAD.encode(format!(r#"{{"sub":"bob","exp":{}}}"#, in_an_hour()).as_bytes());
+        parts[1] = &forged;
+        assert!(verifier.verify_token("bob", &parts.join(".")).is_err());
+
+        let expired = jwt("EdDSA", r#"{"sub":"alice","exp":1000}"#, sign);
+        assert_eq!(
+            verifier.verify_token("alice", &expired),
+            Err("The credential has expired".to_string())
+        );
+
+        let no_expiry = jwt("EdDSA", r#"{"sub":"alice"}"#, sign);
+        assert!(verifier.verify_token("alice", &no_expiry).is_err());
+
+        // The algorithm is decided by the key, not by the token
+        let wrong_alg = jwt(
+            "ES256",
+            &format!(r#"{{"sub":"alice","exp":{}}}"#, in_an_hour()),
+            sign,
+        );
+        assert!(verifier.verify_token("alice", &wrong_alg).is_err());
+    }
+
+    #[test]
+    fn jwt_es256() {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&signature::ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            .unwrap();
+        let pair =
+            EcdsaKeyPair::from_pkcs8(&signature::ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref())
+                .unwrap();
+        let mut spki = P256_SPKI_PREFIX.to_vec();
+        spki.extend_from_slice(pair.public_key().as_ref());
+
+        let verifier = JwtVerifier::from_public_key(&spki).unwrap();
+        let token = jwt(
+            "ES256",
+            &format!(r#"{{"sub":"alice","exp":{}}}"#, in_an_hour()),
+            |data| pair.sign(&rng, data).unwrap().as_ref().to_vec(),
+        );
+        assert_eq!(verifier.verify_token("alice", &token), Ok(()));
+    }
+
+    #[test]
+    fn jwt_rs256() {
+        let rng = SystemRandom::new();
+        let pair = RsaKeyPair::from_pkcs8(RSA_PRIVATE_KEY).unwrap();
+        let verifier = JwtVerifier::from_public_key(RSA_PUBLIC_KEY).unwrap();
+        let sign = |data: &[u8]| {
+            let mut output = vec![0; pair.public_modulus_len()];
+            pair.sign(&signature::RSA_PKCS1_SHA256, &rng, data, &mut output)
+                .unwrap();
+            output
+        };
+
+        let token = jwt(
+            "RS256",
+            &format!(r#"{{"sub":"alice","exp":{}}}"#, in_an_hour()),
+            sign,
+        );
+        assert_eq!(verifier.verify_token("alice", &token), Ok(()));
+        assert!(verifier.verify_token("bob", &token).is_err());
+
+        let expired = jwt("RS256", r#"{"sub":"alice","exp":1000}"#, sign);
+        assert_eq!(
+            verifier.verify_token("alice", &expired),
+            Err("The credential has expired".to_string())
+        );
+    }
+
+    /// Verifies a connect request the way the server does when a client connects
+    fn connect(
+        verifier: impl ConnectionVerifier + 'static,
+        user_id: &str,
+        credential: Option<&str>,
+    ) -> Result<(), String> {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        crate::init_all_components();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut state = ServerState::new_local(AssetCache::new(runtime.handle().clone()));
+        let verifier: Arc<dyn ConnectionVerifier> = Arc::new(verifier);
+        state
+            .instances
+            .get_mut(MAIN_INSTANCE_ID)
+            .unwrap()
+            .world
+            .add_resource(connection_verifier(), verifier);
+
+        let state = Arc::new(Mutex::new(state));
+        runtime.block_on(verify_connection(&state, user_id, credential))
+    }
+
+    #[test]
+    fn connections_are_rejected_with_a_reason() {
+        let verifier = || SharedSecretVerifier::new(b"secret");
+        let token = verifier().issue("alice", SystemTime::now() + Duration::from_secs(60));
+        let expired = verifier().issue("alice", SystemTime::now() - Duration::from_secs(60));
+
+        assert_eq!(connect(verifier(), "alice", Some(&token)), Ok(()));
+        assert_eq!(
+            connect(verifier(), "alice", None),
+            Err(MISSING_CREDENTIAL.to_string())
+        );
+        assert_eq!(
+            connect(verifier(), "alice", Some("1234.bad-token")),
+            Err("Invalid credential".to_string())
+        );
+        assert_eq!(
+            connect(verifier(), "bob", Some(&token)),
+            Err("Invalid credential".to_string())
+        );
+        assert_eq!(
+            connect(verifier(), "alice", Some(&expired)),
+            Err("The credential has expired".to_string())
+        );
+
+        let rsa = || JwtVerifier::from_public_key(RSA_PUBLIC_KEY).unwrap();
+        assert_eq!(
+            connect(rsa(), "alice", Some("not.a.jwt")),
+            Err("Invalid credential".to_string())
+        );
+    }
+
+    #[test]
+    fn malformed_keys() {
+        assert!(JwtVerifier::from_public_key(b"").is_err());
+        assert!(JwtVerifier::from_public_key(&ED25519_SPKI_PREFIX[..8]).is_err());
+        assert!(JwtVerifier::from_public_key(b"-----BEGIN PUBLIC KEY-----\n!!\n").is_err());
+    }
+}
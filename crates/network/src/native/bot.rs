@@ -0,0 +1,438 @@
+//! Headless clients, which connect to a server without a window or a renderer.
+//!
+//! A [Bot] replicates the world of the server like a regular client, and can send input and
+//! messages to it. They are cheap enough to run hundreds of them from one process, which makes
+//! them useful for load testing and for asserting on the replicated state in integration tests.
+
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use ambient_core::{abs_time, player::local_user_id};
+use ambient_ecs::{query, EntityId, World};
+use ambient_input::PlayerRawInput;
+use ambient_std::asset_cache::AssetCache;
+use anyhow::Context;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use parking_lot::Mutex;
+use quinn::Connection;
+use rustls::Certificate;
+use tokio::task::JoinHandle;
+
+use crate::{
+    client::{
+        bi_stream_handlers, client_network_stats, datagram_handlers, uni_stream_handlers,
+        ClientConnection, Control, NetworkStats, PlatformRecvStream,
+    },
+    is_remote_entity,
+    link_conditioner::LinkConditions,
+    native::client::{handle_connection, open_connection, ResolvedAddr},
+    prediction::{InputFrame, INPUT_REDUNDANCY, MAX_INPUT_DT},
+    proto::client::ClientWorldState,
+    tick::current_tick,
+    PLAYER_INPUT_DATAGRAM_ID, WASM_DATAGRAM_ID, WASM_UNISTREAM_ID,
+};
+
+/// The most bytes read from a message stream
+const MAX_MESSAGE_LENGTH: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct BotConfig {
+    pub server_addr: ResolvedAddr,
+    pub cert: Option<Vec<u8>>,
+    pub user_id: String,
+    /// Presented to the server's connection verifier, if any
+    pub credential: Option<String>,
+    /// Simulated conditions of the link from the server
+    pub link_conditions: Option<LinkConditions>,
+}
+
+/// A message sent by a module on the server to the bot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotMessage {
+    /// The module on the server which sent the message
+    pub module: EntityId,
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BotStats {
+    /// Updated by the connection every few seconds
+    pub network: Option<NetworkStats>,
+    /// The number of entities replicated from the server
+    pub remote_entities: usize,
+    pub messages_received: usize,
+    pub inputs_sent: u32,
+}
+
+#[derive(Debug)]
+struct BotState {
+    world: World,
+    assets: AssetCache,
+}
+
+impl ClientWorldState for BotState {
+    fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    fn assets(&self) -> &AssetCache {
+        &self.assets
+    }
+
+    fn received_diff_world_mut(&mut self) -> &mut World {
+        // Bots do not run frames, so interpolation snapshots are timed by when they are received
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        self.world.add_resource(abs_time(), now);
+        &mut self.world
+    }
+}
+
+/// A headless client connected to a server
+pub struct Bot {
+    user_id: String,
+    connection: Connection,
+    state: Arc<Mutex<BotState>>,
+    control_tx: flume::Sender<Control>,
+    task: JoinHandle<anyhow::Result<()>>,
+    messages: flume::Receiver<BotMessage>,
+    messages_received: usize,
+    input: BotInput,
+}
+
+impl Bot {
+    /// Connects to the server, and returns once the server has accepted the bot as a player
+    pub async fn connect(assets: AssetCache, config: BotConfig) -> anyhow::Result<Self> {
+        let BotConfig {
+            server_addr,
+            cert,
+            user_id,
+            credential,
+            link_conditions,
+        } = config;
+
+        let connection = open_connection(server_addr.clone(), cert.map(Certificate))
+            .await
+            .with_context(|| format!("Failed to connect to endpoint: {server_addr:?}"))?;
+
+        let (message_tx, messages) = flume::unbounded();
+        let state = Arc::new(Mutex::new(BotState {
+            world: create_world(&user_id, message_tx),
+            assets,
+        }));
+
+        let (control_tx, control_rx) = flume::unbounded();
+        let (connected_tx, connected_rx) = tokio::sync::oneshot::channel();
+        let task = tokio::spawn({
+            let connection = connection.clone();
+            let user_id = user_id.clone();
+            let state = state.clone();
+            async move {
+                handle_connection(
+                    connection,
+                    user_id,
+                    credential,
+                    link_conditions,
+                    move || {
+                        connected_tx.send(()).ok();
+                        Ok(())
+                    },
+                    state,
+                    control_rx,
+                )
+                .await
+            }
+        });
+
+        if connected_rx.await.is_err() {
+            // The connection ended before the server accepted the bot
+            let err = match task.await {
+                Ok(Ok(())) => anyhow::anyhow!("Disconnected before being accepted"),
+                Ok(Err(err)) => err,
+                Err(err) => err.into(),
+            };
+            return Err(err.context(format!("Bot {user_id:?} failed to connect")));
+        }
+
+        Ok(Self {
+            user_id,
+            connection,
+            state,
+            control_tx,
+            task,
+            messages,
+            messages_received: 0,
+            input: BotInput::default(),
+        })
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Whether the connection to the server is still running
+    pub fn is_connected(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Runs `f` with the world replicated from the server
+    pub fn with_world<R>(&self, f: impl FnOnce(&World) -> R) -> R {
+        f(&self.state.lock().world)
+    }
+
+    /// Waits until `condition` holds for the replicated world.
+    ///
+    /// Returns `false` if it did not hold within `timeout`, or the bot was disconnected.
+    pub async fn wait_for(&self, condition: impl Fn(&World) -> bool, timeout: Duration) -> bool {
+        let wait = async {
+            loop {
+                if self.with_world(&condition) {
+                    return true;
+                }
+                if !self.is_connected() {
+                    return false;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+
+        tokio::time::timeout(timeout, wait).await.unwrap_or(false)
+    }
+
+    /// Sends one frame of input, which lasted `dt` seconds, to the server.
+    ///
+    /// The server applies it to the predicted entities of the bot.
+    pub async fn send_input(&mut self, input: PlayerRawInput, dt: f32) -> anyhow::Result<()> {
+        let tick = self.with_world(current_tick);
+        let frames = self.input.push(input, dt, tick);
+        let data = Bytes::from(bincode::serialize(&frames)?);
+        ClientConnection::send_datagram(&self.connection, PLAYER_INPUT_DATAGRAM_ID, data).await?;
+        Ok(())
+    }
+
+    /// Sends a message to the server, as if it was sent by the client-side `module`
+    pub async fn send_message(
+        &self,
+        module: EntityId,
+        name: &str,
+        data: &[u8],
+        reliable: bool,
+    ) -> anyhow::Result<()> {
+        let payload = encode_message(module, name, data)?;
+        if reliable {
+            ClientConnection::request_uni(&self.connection, WASM_UNISTREAM_ID, payload).await?;
+        } else {
+            ClientConnection::send_datagram(&self.connection, WASM_DATAGRAM_ID, payload).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the messages received since the last call
+    pub fn messages(&mut self) -> Vec<BotMessage> {
+        let messages: Vec<_> = self.messages.try_iter().collect();
+        self.messages_received += messages.len();
+        messages
+    }
+
+    /// Waits for the next message from the server
+    pub async fn next_message(&mut self, timeout: Duration) -> Option<BotMessage> {
+        let message = tokio::time::timeout(timeout, self.messages.recv_async())
+            .await
+            .ok()?
+            .ok()?;
+        self.messages_received += 1;
+        Some(message)
+    }
+
+    pub fn stats(&self) -> BotStats {
+        let state = self.state.lock();
+        let world = &state.world;
+        BotStats {
+            network: world.resource_opt(client_network_stats()).cloned(),
+            remote_entities: query(()).incl(is_remote_entity()).iter(world, None).count(),
+            messages_received: self.messages_received + self.messages.len(),
+            inputs_sent: self.input.next_sequence,
+        }
+    }
+
+    /// Gracefully leaves the server, and waits for the connection to close
+    pub async fn disconnect(self) -> anyhow::Result<()> {
+        self.control_tx.send(Control::Disconnect).ok();
+        match tokio::time::timeout(Duration::from_secs(5), self.task).await {
+            Ok(result) => result?,
+            Err(_) => {
+                self.connection.close(0u32.into(), b"disconnect");
+                Ok(())
+            }
+        }
+    }
+}
+
+fn create_world(user_id: &str, message_tx: flume::Sender<BotMessage>) -> World {
+    let mut world = World::new("bot_world");
+    world.add_resource(local_user_id(), user_id.to_string());
+    world.add_resource(bi_stream_handlers(), Default::default());
+    world.add_resource(uni_stream_handlers(), Default::default());
+    world.add_resource(datagram_handlers(), Default::default());
+
+    world
+        .resource_mut(datagram_handlers())
+        .insert(WASM_DATAGRAM_ID, {
+            let message_tx = message_tx.clone();
+            (
+                "bot_datagram",
+                Arc::new(move |_: &mut World, _: AssetCache, bytes: Bytes| {
+                    match decode_message(bytes) {
+                        Ok(message) => {
+                            message_tx.send(message).ok();
+                        }
+                        Err(err) => tracing::warn!("Received invalid message: {err:?}"),
+                    }
+                }),
+            )
+        });
+    world.resource_mut(uni_stream_handlers()).insert(
+        WASM_UNISTREAM_ID,
+        (
+            "bot_uni_stream",
+            Arc::new(
+                move |_: &mut World, _: AssetCache, recv: PlatformRecvStream| {
+                    let message_tx = message_tx.clone();
+                    // The returned future is not polled to completion, so the stream is read in a
+                    // task of its own
+                    tokio::spawn(async move {
+                        match read_message(recv).await {
+                            Ok(message) => {
+                                message_tx.send(message).ok();
+                            }
+                            Err(err) => tracing::warn!("Failed to read message stream: {err:?}"),
+                        }
+                    });
+                    ambient_sys::task::PlatformBoxFuture::new(async {})
+                },
+            ),
+        ),
+    );
+
+    world
+}
+
+/// Encodes a message the same way as the modules of a client
+fn encode_message(module: EntityId, name: &str, data: &[u8]) -> anyhow::Result<Bytes> {
+    let mut payload = BytesMut::new();
+    payload.put_u128(module.0);
+    payload.put_u32(name.len().try_into()?);
+    payload.put(name.as_bytes());
+    payload.put(data);
+    Ok(payload.freeze())
+}
+
+fn decode_message(mut bytes: Bytes) -> anyhow::Result<BotMessage> {
+    anyhow::ensure!(bytes.len() >= 20, "Message is too short");
+    let module = EntityId(bytes.get_u128());
+    let name_len = bytes.get_u32() as usize;
+    anyhow::ensure!(bytes.len() >= name_len, "Message name is too long");
+    let name = String::from_utf8(bytes.split_to(name_len).to_vec())?;
+
+    Ok(BotMessage {
+        module,
+        name,
+        data: bytes.to_vec(),
+    })
+}
+
+async fn read_message(mut recv: PlatformRecvStream) -> anyhow::Result<BotMessage> {
+    let bytes = recv.read_to_end(MAX_MESSAGE_LENGTH).await?;
+    decode_message(bytes.into())
+}
+
+/// The inputs of a bot, of which the latest are sent with every frame, like a regular client
+#[derive(Debug, Default)]
+struct BotInput {
+    next_sequence: u32,
+    frames: VecDeque<InputFrame>,
+}
+
+impl BotInput {
+    fn push(&mut self, input: PlayerRawInput, dt: f32, tick: u64) -> Vec<InputFrame> {
+        self.next_sequence += 1;
+        self.frames.push_back(InputFrame {
+            sequence: self.next_sequence,
+            dt: dt.clamp(0.0, MAX_INPUT_DT),
+            tick,
+            input,
+        });
+        while self.frames.len() > INPUT_REDUNDANCY {
+            self.frames.pop_front();
+        }
+
+        self.frames.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn message_round_trip() {
+        let module = EntityId(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        let bytes = encode_message(module, "Hello", b"world").unwrap();
+
+        assert_eq!(
+            decode_message(bytes).unwrap(),
+            BotMessage {
+                module,
+                name: "Hello".into(),
+                data: b"world".to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_messages() {
+        assert!(decode_message(Bytes::from_static(&[0; 8])).is_err());
+
+        let mut bytes = encode_message(EntityId(1), "Hello", &[]).unwrap().to_vec();
+        bytes.truncate(bytes.len() - 1);
+        assert!(decode_message(bytes.into()).is_err());
+    }
+
+    #[test]
+    fn snapshots_are_timed_by_when_they_are_received() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut state = BotState {
+            world: World::new("bot_world"),
+            assets: AssetCache::new(runtime.handle().clone()),
+        };
+        state.world.add_resource(abs_time(), Duration::ZERO);
+
+        // Only receiving a diff moves the time of the world forward
+        state.world_mut();
+        assert_eq!(*state.world.resource(abs_time()), Duration::ZERO);
+        state.received_diff_world_mut();
+        assert!(*state.world.resource(abs_time()) > Duration::ZERO);
+    }
+
+    #[test]
+    fn input_redundancy() {
+        let mut input = BotInput::default();
+
+        for i in 1..=INPUT_REDUNDANCY as u32 + 3 {
+            let frames = input.push(PlayerRawInput::default(), 1.0, 0);
+            assert_eq!(frames.last().unwrap().sequence, i);
+            assert_eq!(frames.len(), (i as usize).min(INPUT_REDUNDANCY));
+            assert!(frames.iter().all(|frame| frame.dt == MAX_INPUT_DT));
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use crate::{
-    client::{Control, GameClient, GameClientRenderTarget, LoadedFunc, NetworkStats},
+    client::{CleanupFunc, Control, GameClient, GameClientRenderTarget, LoadedFunc},
     client_game_state::ClientGameState,
-    native::load_root_certs,
+    is_connection_lost,
+    link_conditioner::{Conditioned, LinkConditions},
+    native::{common::ConnectionStatsSampler, load_root_certs},
     proto::{
-        client::{ClientState, SharedClientState},
+        client::{ClientState, ClientWorldState},
         ClientRequest,
     },
     server::RpcArgs,
-    stream::{FramedRecvStream, FramedSendStream},
+    stats::STATS_INTERVAL,
+    stream::{FramedRecvStream, FramedSendStream, RawFramedRecvStream},
     NetworkError,
 };
 use ambient_app::window_title;
@@ -32,6 +35,12 @@ use std::{
 };
 use tokio::net::ToSocketAddrs;
 
+/// How many times in a row the client tries to reconnect after losing its connection
+const RECONNECT_ATTEMPTS: u32 = 8;
+/// How long the client waits before the first reconnection attempt, doubled after each failure
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Clone)]
 pub struct ResolvedAddr {
     pub host_name: String,
@@ -66,6 +75,10 @@ pub struct GameClientView {
     pub server_addr: ResolvedAddr,
     pub cert: Option<Vec<u8>>,
     pub user_id: String,
+    /// Presented to the server's connection verifier, if any
+    pub credential: Option<String>,
+    /// Simulated conditions of the link from the server, for testing
+    pub link_conditions: Option<LinkConditions>,
     pub systems_and_resources: Cb<dyn Fn() -> (SystemGroup, Entity) + Sync + Send>,
     pub on_loaded: LoadedFunc,
     pub create_rpc_registry: Cb<dyn Fn() -> RpcRegistry<RpcArgs> + Sync + Send>,
@@ -77,6 +90,8 @@ impl ElementComponent for GameClientView {
         let Self {
             server_addr,
             user_id,
+            credential,
+            link_conditions,
             systems_and_resources,
             create_rpc_registry,
             on_loaded,
@@ -157,45 +172,80 @@ impl ElementComponent for GameClientView {
 
         hooks.use_task(move |_| {
             let task = async move {
-                let conn = open_connection(server_addr.clone(), cert.map(Certificate))
-                    .await
-                    .with_context(|| format!("Failed to connect to endpoint: {server_addr:?}"))?;
-
-                tracing::debug!("Connected to the server");
-
-                // Create a handle for the game client
-                let game_client = GameClient::new(
-                    Arc::new(conn.clone()),
-                    Arc::new(create_rpc_registry()),
-                    game_state.clone(),
-                    user_id.clone(),
-                );
-
-                handle_connection(
-                    game_client,
-                    conn,
-                    user_id,
-                    cb(move |game_client| {
-                        let game_state = &game_client.game_state;
+                // Set once the client has loaded, and run once it has left the server for good
+                let mut cleanup = scopeguard::guard(None::<CleanupFunc>, |cleanup| {
+                    if let Some(cleanup) = cleanup {
+                        tracing::info!("Running connection cleanup");
+                        cleanup()
+                    }
+                });
+                let mut attempt = 0;
+
+                loop {
+                    let result = async {
+                        let conn =
+                            open_connection(server_addr.clone(), cert.clone().map(Certificate))
+                                .await
+                                .with_context(|| {
+                                    format!("Failed to connect to endpoint: {server_addr:?}")
+                                })?;
+
+                        tracing::debug!("Connected to the server");
+
+                        // Create a handle for the game client
+                        let game_client = GameClient::new(
+                            Arc::new(conn.clone()),
+                            Arc::new(create_rpc_registry()),
+                            game_state.clone(),
+                            user_id.clone(),
+                        );
+
+                        handle_connection(
+                            conn,
+                            user_id.clone(),
+                            credential.clone(),
+                            link_conditions,
+                            || {
+                                attempt = 0;
+                                // Updates the game client context in the Ui tree
+                                set_game_client(Some(game_client.clone()));
+                                // Update the resources on the client side world to reflect the new
+                                // connection state
+                                game_state.lock().world.add_resource(
+                                    crate::client::game_client(),
+                                    Some(game_client.clone()),
+                                );
+                                // A reconnected client resumes where it left off
+                                if cleanup.is_none() {
+                                    *cleanup = Some((on_loaded)(game_client)?);
+                                }
+                                Ok(())
+                            },
+                            game_state.clone(),
+                            control_rx.clone(),
+                        )
+                        .await
+                    }
+                    .await;
+
+                    match result {
+                        Err(err)
+                            if cleanup.is_some()
+                                && attempt < RECONNECT_ATTEMPTS
+                                && is_connection_lost(&err) =>
                         {
-                            // Updates the game client context in the Ui tree
-                            set_game_client(Some(game_client.clone()));
-                            // Update the resources on the client side world to reflect the new connection
-                            // state
-                            let world = &mut game_state.lock().world;
-                            world.add_resource(
-                                crate::client::game_client(),
-                                Some(game_client.clone()),
+                            let backoff =
+                                (RECONNECT_BACKOFF * 2u32.pow(attempt)).min(MAX_RECONNECT_BACKOFF);
+                            attempt += 1;
+                            tracing::warn!(
+                                "Lost connection to the server, reconnecting in {backoff:?} \
+                                 ({attempt}/{RECONNECT_ATTEMPTS}): {err:#}"
                             );
+                            tokio::time::sleep(backoff).await;
                         }
-                        (on_loaded)(game_client)
-                    }),
-                    game_state,
-                    control_rx,
-                )
-                .await?;
-
-                Ok(()) as anyhow::Result<()>
+                        result => return result,
+                    }
+                }
             };
 
             async move {
@@ -241,12 +291,14 @@ impl ElementComponent for GameClientView {
     }
 }
 
-async fn handle_connection(
-    game_client: GameClient,
+/// Runs the client side of a connection, replicating the world of the server into `state`
+pub(crate) async fn handle_connection<S: ClientWorldState>(
     conn: quinn::Connection,
     user_id: String,
-    on_loaded: LoadedFunc,
-    state: SharedClientState,
+    credential: Option<String>,
+    link_conditions: Option<LinkConditions>,
+    on_connected: impl FnOnce() -> anyhow::Result<()>,
+    state: Arc<Mutex<S>>,
     control_rx: flume::Receiver<Control>,
 ) -> anyhow::Result<()> {
     tracing::info!("Handling client connection");
@@ -263,13 +315,19 @@ async fn handle_connection(
     tracing::info!("Attempting to connect using {user_id:?}");
 
     request_send
-        .send(ClientRequest::Connect(user_id.clone()))
+        .send(ClientRequest::Connect {
+            user_id: user_id.clone(),
+            credential,
+        })
         .await?;
 
     let mut client = ClientState::Connecting(user_id);
 
     tracing::info!("Accepting control stream from server");
-    let mut push_recv = FramedRecvStream::new(conn.accept_uni().await?);
+    let mut push_recv = Conditioned::frames(
+        FramedRecvStream::new(conn.accept_uni().await?),
+        link_conditions,
+    );
 
     tracing::info!("Entering client loop");
     while client.is_connecting() {
@@ -280,19 +338,23 @@ async fn handle_connection(
     }
 
     tracing::info!("Accepting diff stream");
-    let mut diff_stream = FramedRecvStream::new(conn.accept_uni().await?);
-
-    let cleanup = on_loaded(game_client)?;
-    let on_disconnect = move || {
-        tracing::info!("Running connection cleanup");
-        cleanup()
-    };
-
-    scopeguard::defer!(on_disconnect());
-
-    let stats_interval = 5;
-    let mut stats_timer = tokio::time::interval(Duration::from_secs_f32(stats_interval as f32));
-    let mut prev_stats = conn.stats();
+    let mut diff_stream = Conditioned::frames(
+        RawFramedRecvStream::new(conn.accept_uni().await?),
+        link_conditions,
+    );
+    let mut datagrams = Conditioned::datagrams(
+        Box::pin(futures::stream::unfold(conn.clone(), |conn| async move {
+            let datagram = conn.read_datagram().await.ok()?;
+            Some((datagram, conn))
+        })),
+        link_conditions,
+    );
+
+    on_connected()?;
+
+    let mut stats_timer =
+        tokio::time::interval_at(tokio::time::Instant::now() + STATS_INTERVAL, STATS_INTERVAL);
+    let mut stats_sampler = ConnectionStatsSampler::new(&conn);
 
     let mut control_rx = control_rx.into_stream();
 
@@ -304,15 +366,8 @@ async fn handle_connection(
                 client.process_push(&state, frame?)?;
             }
             _ = stats_timer.tick() => {
-                let stats = conn.stats();
-
-                client.process_client_stats(&state, NetworkStats {
-                    latency_ms: conn.rtt().as_millis() as u64,
-                    bytes_sent: (stats.udp_tx.bytes - prev_stats.udp_tx.bytes) / stats_interval,
-                    bytes_received: (stats.udp_rx.bytes - prev_stats.udp_rx.bytes) / stats_interval,
-                });
-
-                prev_stats = stats;
+                let (stats, elapsed) = stats_sampler.sample(&conn);
+                client.process_client_stats(&state, stats, elapsed);
             }
 
            Some(control) = control_rx.next() => {
@@ -325,7 +380,7 @@ async fn handle_connection(
                 }
             }
 
-            Ok(datagram) = conn.read_datagram() => {
+            Some(datagram) = datagrams.next() => {
                 connected.process_datagram(&state, datagram)?;
             }
             Ok((send, recv)) = conn.accept_bi() => {
@@ -346,7 +401,7 @@ async fn handle_connection(
 
 /// Connnect to the server endpoint.
 #[tracing::instrument(level = "debug")]
-async fn open_connection(
+pub(crate) async fn open_connection(
     server_addr: ResolvedAddr,
     cert: Option<Certificate>,
 ) -> anyhow::Result<Connection> {
@@ -24,6 +24,14 @@ impl From<ProxiedConnection> for ConnectionKind {
 }
 
 impl ConnectionKind {
+    /// The underlying connection, unless it is relayed by the proxy
+    pub fn direct(&self) -> Option<&Connection> {
+        match self {
+            ConnectionKind::Direct(conn) => Some(conn),
+            ConnectionKind::Proxied(_) => None,
+        }
+    }
+
     #[inline]
     pub async fn open_uni(&self) -> Result<SendStream, NetworkError> {
         match self {
@@ -1,10 +1,16 @@
+use std::time::{Duration, Instant};
+
 use bytes::{BufMut, Bytes, BytesMut};
 use futures::future::BoxFuture;
 use h3::quic::BidiStream;
 use h3_webtransport::server::WebTransportSession;
+use quinn::ConnectionStats;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-use crate::{client::ClientConnection, NetworkError, MAX_FRAME_SIZE};
+use crate::{
+    client::{ClientConnection, NetworkStats},
+    NetworkError, MAX_FRAME_SIZE,
+};
 
 impl ClientConnection for WebTransportSession<h3_quinn::Connection, Bytes> {
     fn request_bi(&self, id: u32, data: Bytes) -> BoxFuture<Result<Bytes, NetworkError>> {
@@ -128,3 +134,96 @@ impl ClientConnection for crate::native::client_connection::ConnectionKind {
         Box::pin(futures::future::ready(res))
     }
 }
+
+/// Measures the statistics of a connection between samples
+#[derive(Debug)]
+pub(crate) struct ConnectionStatsSampler {
+    prev: ConnectionStats,
+    sampled_at: Instant,
+}
+
+impl ConnectionStatsSampler {
+    pub fn new(conn: &quinn::Connection) -> Self {
+        Self {
+            prev: conn.stats(),
+            sampled_at: Instant::now(),
+        }
+    }
+
+    /// Returns the statistics since the previous sample, and how long ago that was
+    pub fn sample(&mut self, conn: &quinn::Connection) -> (NetworkStats, Duration) {
+        let stats = conn.stats();
+        let elapsed = self.sampled_at.elapsed();
+        let result = stats_between(&self.prev, &stats, conn.rtt(), elapsed);
+
+        self.prev = stats;
+        self.sampled_at = Instant::now();
+        (result, elapsed)
+    }
+}
+
+fn stats_between(
+    prev: &ConnectionStats,
+    current: &ConnectionStats,
+    rtt: Duration,
+    elapsed: Duration,
+) -> NetworkStats {
+    let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+    let per_second = |bytes: u64| (bytes as f64 / seconds).round() as u64;
+
+    let sent = current
+        .path
+        .sent_packets
+        .saturating_sub(prev.path.sent_packets);
+    let lost = current
+        .path
+        .lost_packets
+        .saturating_sub(prev.path.lost_packets);
+
+    NetworkStats {
+        latency_ms: rtt.as_millis() as u64,
+        packet_loss: if sent > 0 {
+            (lost as f32 / sent as f32).min(1.0)
+        } else {
+            0.0
+        },
+        bytes_sent: per_second(current.udp_tx.bytes.saturating_sub(prev.udp_tx.bytes)),
+        bytes_received: per_second(current.udp_rx.bytes.saturating_sub(prev.udp_rx.bytes)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stats_rates() {
+        let prev = ConnectionStats::default();
+        let mut current = ConnectionStats::default();
+        current.udp_tx.bytes = 4000;
+        current.udp_rx.bytes = 1000;
+        current.path.sent_packets = 40;
+        current.path.lost_packets = 2;
+
+        let stats = stats_between(
+            &prev,
+            &current,
+            Duration::from_millis(25),
+            Duration::from_secs(2),
+        );
+        assert_eq!(
+            stats,
+            NetworkStats {
+                latency_ms: 25,
+                packet_loss: 0.05,
+                bytes_sent: 2000,
+                bytes_received: 500,
+            }
+        );
+
+        // Nothing was sent since
+        let stats = stats_between(&current, &current, Duration::ZERO, Duration::from_secs(1));
+        assert_eq!(stats.packet_loss, 0.0);
+        assert_eq!(stats.bytes_sent, 0);
+    }
+}
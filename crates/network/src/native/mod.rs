@@ -1,6 +1,8 @@
 //! Contains native implementations of the network interface.
 //!
 //! This included quinn server+client and webtransport server using `h3`
+pub mod auth;
+pub mod bot;
 pub mod client;
 pub mod client_connection;
 pub mod common;
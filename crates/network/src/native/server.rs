@@ -1,4 +1,5 @@
 use std::{
+    future::Future,
     net::{IpAddr, SocketAddr},
     ops::Range,
     sync::Arc,
@@ -20,27 +21,32 @@ use ambient_std::{
 use ambient_sys::time::Instant;
 use anyhow::Context;
 use colored::Colorize;
-use futures::{SinkExt, StreamExt};
+use futures::{future::BoxFuture, FutureExt, SinkExt, StreamExt};
 use parking_lot::{Mutex, RwLock};
 use quinn::{ClientConfig, Connecting, Endpoint, ServerConfig, TransportConfig};
 use rustls::{Certificate, PrivateKey};
-use tokio::time::{interval, MissedTickBehavior};
+use tokio::time::interval;
 use uuid::Uuid;
 
 use crate::{
+    client::NetworkStats,
+    link_conditioner::Conditioned,
     native::{
-        client_connection::ConnectionKind, load_root_certs, webtransport::handle_h3_connection,
+        client_connection::ConnectionKind, common::ConnectionStatsSampler, load_root_certs,
+        webtransport::handle_h3_connection,
     },
     proto::{
         self,
-        server::{handle_diffs, ConnectionData},
+        server::{handle_diffs, ConnectionData, REJECTION_GRACE_PERIOD},
         ServerInfo, ServerPush, VERSION,
     },
     server::{
-        server_stats, ForkingEvent, ProxySettings, ServerState, SharedServerState, ShutdownEvent,
-        WorldInstance, MAIN_INSTANCE_ID,
+        link_conditions, server_stats, ForkingEvent, ProxySettings, ServerState, SharedServerState,
+        ShutdownEvent, WorldInstance, MAIN_INSTANCE_ID,
     },
+    stats::STATS_INTERVAL,
     stream::{FramedRecvStream, FramedSendStream},
+    tick::{tick_duration, TickClock},
     ServerWorldExt,
 };
 
@@ -56,6 +62,7 @@ pub struct GameServer {
     /// Shuts down the server if there are no players
     pub use_inactivity_shutdown: bool,
     proxy_settings: Option<ProxySettings>,
+    shutdown_signal: Option<BoxFuture<'static, ()>>,
 }
 
 impl GameServer {
@@ -72,9 +79,19 @@ impl GameServer {
             endpoint,
             use_inactivity_shutdown,
             proxy_settings,
+            shutdown_signal: None,
         })
     }
 
+    /// Shuts down the server once `signal` completes
+    pub fn with_shutdown_signal(
+        mut self,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> Self {
+        self.shutdown_signal = Some(signal.boxed());
+        self
+    }
+
     pub async fn new_with_port_in_range(
         bind_addr: IpAddr,
         port_range: Range<u16>,
@@ -106,7 +123,7 @@ impl GameServer {
     pub async fn run(
         self,
         mut world: World,
-        create_server_systems: Arc<dyn Fn(&mut World) -> SystemGroup + Sync + Send>,
+        create_server_systems: Arc<dyn Fn(&str, &mut World) -> SystemGroup + Sync + Send>,
         create_on_forking_systems: Arc<dyn Fn() -> SystemGroup<ForkingEvent> + Sync + Send>,
         create_shutdown_systems: Arc<dyn Fn() -> SystemGroup<ShutdownEvent> + Sync + Send>,
         is_sync_component: Arc<dyn Fn(ComponentDesc, WorldStreamCompEvent) -> bool + Sync + Send>,
@@ -114,10 +131,14 @@ impl GameServer {
         let Self {
             endpoint,
             proxy_settings,
+            shutdown_signal,
             ..
         } = self;
+        let mut shutdown_signal =
+            shutdown_signal.unwrap_or_else(|| futures::future::pending().boxed());
 
         let assets = world.resource(asset_cache()).clone();
+        let mut clock = TickClock::new(tick_duration(&world), Instant::now());
         let world_stream_filter =
             WorldStreamFilter::new(ArchetypeFilter::new().excl(no_sync()), is_sync_component);
         let state = Arc::new(Mutex::new(ServerState::new(
@@ -125,7 +146,7 @@ impl GameServer {
             [(
                 MAIN_INSTANCE_ID.to_string(),
                 WorldInstance {
-                    systems: create_server_systems(&mut world),
+                    systems: create_server_systems(MAIN_INSTANCE_ID, &mut world),
                     world,
                     world_stream: WorldStream::new(world_stream_filter.clone()),
                 },
@@ -138,8 +159,6 @@ impl GameServer {
         )));
 
         let mut fps_counter = FpsCounter::new();
-        let mut sim_interval = interval(Duration::from_secs_f32(1. / 60.));
-        sim_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         let mut inactivity_interval = interval(Duration::from_secs_f32(5.));
         let mut last_active = ambient_sys::time::Instant::now();
@@ -170,13 +189,19 @@ impl GameServer {
                     let fut = resolve_connection(conn, state.clone(), world_stream_filter.clone(), ServerBaseUrlKey.get(&assets));
                     tokio::spawn(async move {  log_result!(fut.await) });
                 }
-                _ = sim_interval.tick() => {
+                _ = ambient_sys::time::sleep_until(clock.next_at()) => {
+                    let due = clock.advance(Instant::now());
                     fps_counter.frame_start();
                     let mut state = state.lock();
                     tokio::task::block_in_place(|| {
                         ambient_profiling::finish_frame!();
                         ambient_profiling::scope!("sim_tick");
-                        state.step();
+                        for _ in 0..due.run {
+                            state.step();
+                        }
+                        if due.skipped > 0 {
+                            state.skip(due.skipped);
+                        }
                         state.broadcast_diffs();
                         if let Some(sample) = fps_counter.frame_end() {
                             for instance in state.instances.values_mut() {
@@ -186,6 +211,10 @@ impl GameServer {
                         }
                     });
                 }
+                _ = &mut shutdown_signal => {
+                    tracing::info!("Shutting down");
+                    break;
+                }
                 _ = inactivity_interval.tick(), if self.use_inactivity_shutdown => {
                     if state.lock().player_count() == 0 {
                         if Instant::now().duration_since(last_active).as_secs_f32() > 2. * 60. {
@@ -292,7 +321,7 @@ async fn handle_quinn_connection(
     tracing::debug!("Handling server connection");
     let (diffs_tx, diffs_rx) = flume::unbounded();
 
-    let server_info = {
+    let (server_info, conditions) = {
         let state = state.lock();
         let instance = state.instances.get(MAIN_INSTANCE_ID).unwrap();
         let world = &instance.world;
@@ -301,24 +330,23 @@ async fn handle_quinn_connection(
             .map(|x| x.0)
             .collect();
 
-        ServerInfo {
+        let server_info = ServerInfo {
             project_name: world.resource(project_name()).clone(),
             content_base_url,
             version: VERSION.into(),
             external_components,
-        }
+        };
+        (server_info, world.resource_opt(link_conditions()).copied())
     };
 
     let mut server = proto::server::ServerState::default();
 
-    let mut request_recv = FramedRecvStream::new(conn.accept_uni().await?);
+    let mut request_recv =
+        Conditioned::frames(FramedRecvStream::new(conn.accept_uni().await?), conditions);
     let mut push_send = FramedSendStream::new(conn.open_uni().await?);
 
     let diffs_rx = diffs_rx.into_stream();
 
-    // Send who we are
-    push_send.send(ServerPush::ServerInfo(server_info)).await?;
-
     // Feed the channel senders to the connection data
     //
     // Once connected they will be added to the player entity
@@ -333,10 +361,24 @@ async fn handle_quinn_connection(
     while server.is_pending_connection() {
         tracing::info!("Waiting for connect request");
         if let Some(frame) = request_recv.next().await {
-            server.process_control(&data, frame?)?;
+            server.process_control(&data, frame?).await?;
         }
     }
 
+    if let Some(reason) = server.rejection() {
+        push_send
+            .send(ServerPush::Rejected(reason.to_string()))
+            .await?;
+        // Give the client a chance to receive the reason before the connection is dropped
+        tokio::time::timeout(REJECTION_GRACE_PERIOD, request_recv.next())
+            .await
+            .ok();
+        return Ok(());
+    }
+
+    // Send who we are
+    push_send.send(ServerPush::ServerInfo(server_info)).await?;
+
     tracing::debug!("Performing additional on connect tracingic after the fact");
 
     tokio::spawn(handle_diffs(
@@ -344,31 +386,65 @@ async fn handle_quinn_connection(
         diffs_rx,
     ));
 
+    let mut datagrams = Conditioned::datagrams(
+        Box::pin(futures::stream::unfold(
+            Some(conn.clone()),
+            |conn| async move {
+                let conn = conn?;
+                let datagram = conn.read_datagram().await;
+                // Stop reading once the connection fails
+                let conn = datagram.is_ok().then_some(conn);
+                Some((datagram, conn))
+            },
+        )),
+        conditions,
+    );
+
+    let mut stats_timer =
+        tokio::time::interval_at(tokio::time::Instant::now() + STATS_INTERVAL, STATS_INTERVAL);
+    let mut stats_sampler = conn.direct().map(ConnectionStatsSampler::new);
+
     // Before a connection has been established, only process the control stream
-    while let proto::server::ServerState::Connected(connected) = &mut server {
-        tokio::select! {
-            Some(frame) = request_recv.next() => {
-                server.process_control(&data, frame?)?;
-            }
-            stream = conn.accept_uni() => {
-                connected.process_uni(&data, stream?).await?;
-            }
-            stream = conn.accept_bi() => {
-                let (send, recv) = stream?;
-                connected.process_bi(&data, send, recv).await?;
-            }
-            datagram = conn.read_datagram() => {
-                connected.process_datagram(&data, datagram?).await?;
-            }
-            Some(msg) = connected.control_rx.next() => {
-                push_send.send(&msg).await?;
+    let result = async {
+        while let proto::server::ServerState::Connected(connected) = &mut server {
+            tokio::select! {
+                Some(frame) = request_recv.next() => {
+                    server.process_control(&data, frame?).await?;
+                }
+                _ = stats_timer.tick() => {
+                    // The connection statistics of proxied clients are not known
+                    let (stats, elapsed) = match (&mut stats_sampler, conn.direct()) {
+                        (Some(sampler), Some(conn)) => sampler.sample(conn),
+                        _ => (NetworkStats::default(), STATS_INTERVAL),
+                    };
+                    connected.process_stats(&data, stats, elapsed);
+                }
+                stream = conn.accept_uni() => {
+                    connected.process_uni(&data, stream?).await?;
+                }
+                stream = conn.accept_bi() => {
+                    let (send, recv) = stream?;
+                    connected.process_bi(&data, send, recv).await?;
+                }
+                Some(datagram) = datagrams.next() => {
+                    connected.process_datagram(&data, datagram?).await?;
+                }
+                Some(msg) = connected.control_rx.next() => {
+                    push_send.send(&msg).await?;
+                }
             }
         }
+
+        Ok::<_, anyhow::Error>(())
     }
+    .await;
+
+    // Unless the client disconnected, keep the player around so that it can reconnect
+    server.process_connection_lost(&data);
 
     tracing::info!("Client disconnected");
 
-    Ok(())
+    result
 }
 
 async fn start_proxy_connection(
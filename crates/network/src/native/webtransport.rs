@@ -12,12 +12,15 @@ use http::Method;
 use uuid::Uuid;
 
 use crate::{
+    link_conditioner::Conditioned,
+    native::common::ConnectionStatsSampler,
     proto::{
         self,
-        server::{handle_diffs, ConnectionData},
+        server::{handle_diffs, ConnectionData, REJECTION_GRACE_PERIOD},
         ServerInfo, ServerPush, VERSION,
     },
-    server::{SharedServerState, MAIN_INSTANCE_ID},
+    server::{link_conditions, SharedServerState, MAIN_INSTANCE_ID},
+    stats::STATS_INTERVAL,
     stream::{FramedRecvStream, FramedSendStream},
     NetworkError,
 };
@@ -29,6 +32,8 @@ pub async fn handle_h3_connection(
     world_stream_filter: WorldStreamFilter,
     content_base_url: AbsAssetUrl,
 ) -> anyhow::Result<()> {
+    let quic = conn.clone();
+
     // Establish an HTTP/3 connection
     //
     // The webtransport client will soon send a `CONNECT` request
@@ -54,6 +59,7 @@ pub async fn handle_h3_connection(
 
                     return handle_webtransport_session(
                         session,
+                        quic,
                         state,
                         world_stream_filter,
                         content_base_url,
@@ -71,6 +77,7 @@ pub async fn handle_h3_connection(
 #[tracing::instrument(level = "info", skip_all)]
 async fn handle_webtransport_session(
     conn: WebTransportSession<h3_quinn::Connection, Bytes>,
+    quic: quinn::Connection,
     state: SharedServerState,
     world_stream_filter: WorldStreamFilter,
     content_base_url: AbsAssetUrl,
@@ -81,7 +88,7 @@ async fn handle_webtransport_session(
     tracing::info!("Handling webtransport connection");
     let (diffs_tx, diffs_rx) = flume::unbounded();
 
-    let server_info = {
+    let (server_info, conditions) = {
         let state = state.lock();
         let instance = state.instances.get(MAIN_INSTANCE_ID).unwrap();
         let world = &instance.world;
@@ -90,31 +97,32 @@ async fn handle_webtransport_session(
             .map(|x| x.0)
             .collect();
 
-        ServerInfo {
+        let server_info = ServerInfo {
             project_name: world.resource(project_name()).clone(),
             content_base_url,
             version: VERSION.into(),
             external_components,
-        }
+        };
+        (server_info, world.resource_opt(link_conditions()).copied())
     };
 
     let mut server = proto::server::ServerState::default();
 
     tracing::info!("Accepting request stream from client");
-    let mut request_recv = FramedRecvStream::new(
-        conn.accept_uni()
-            .await?
-            .ok_or(NetworkError::ConnectionClosed)?
-            .1,
+    let mut request_recv = Conditioned::frames(
+        FramedRecvStream::new(
+            conn.accept_uni()
+                .await?
+                .ok_or(NetworkError::ConnectionClosed)?
+                .1,
+        ),
+        conditions,
     );
     tracing::info!("Opening control stream");
     let mut push_send = FramedSendStream::new(conn.open_uni(sid).await?);
 
     let diffs_rx = diffs_rx.into_stream();
 
-    // Send who we are
-    push_send.send(ServerPush::ServerInfo(server_info)).await?;
-
     // Feed the channel senders to the connection data
     //
     // Once connected they will be added to the player entity
@@ -129,10 +137,24 @@ async fn handle_webtransport_session(
     while server.is_pending_connection() {
         tracing::info!("Waiting for connect request");
         if let Some(frame) = request_recv.next().await {
-            server.process_control(&data, frame?)?;
+            server.process_control(&data, frame?).await?;
         }
     }
 
+    if let Some(reason) = server.rejection() {
+        push_send
+            .send(ServerPush::Rejected(reason.to_string()))
+            .await?;
+        // Give the client a chance to receive the reason before the connection is dropped
+        tokio::time::timeout(REJECTION_GRACE_PERIOD, request_recv.next())
+            .await
+            .ok();
+        return Ok(());
+    }
+
+    // Send who we are
+    push_send.send(ServerPush::ServerInfo(server_info)).await?;
+
     tracing::debug!("Performing additional on connect tracingic after the fact");
 
     tokio::spawn(handle_diffs(
@@ -140,32 +162,62 @@ async fn handle_webtransport_session(
         diffs_rx,
     ));
 
+    let mut datagrams = Conditioned::datagrams(
+        Box::pin(futures::stream::unfold(
+            Some(conn.clone()),
+            |conn| async move {
+                let conn = conn?;
+                let datagram = conn.accept_datagram().await;
+                // Stop reading once the session ends
+                let conn = matches!(datagram, Ok(Some(_))).then_some(conn);
+                Some((datagram, conn))
+            },
+        )),
+        conditions,
+    );
+
+    let mut stats_timer =
+        tokio::time::interval_at(tokio::time::Instant::now() + STATS_INTERVAL, STATS_INTERVAL);
+    let mut stats_sampler = ConnectionStatsSampler::new(&quic);
+
     // Before a connection has been established, only process the control stream
-    while let proto::server::ServerState::Connected(connected) = &mut server {
-        tokio::select! {
-            Some(frame) = request_recv.next() => {
-                server.process_control(&data, frame?)?;
-            }
-            stream = conn.accept_uni() => {
-                connected.process_uni(&data, stream?.ok_or(NetworkError::ConnectionClosed)?.1).await?;
-            }
-            stream = conn.accept_bi() => {
-                if let AcceptedBi::BidiStream(_, stream) = stream?.ok_or(NetworkError::ConnectionClosed)? {
-                    let (send, recv) = stream.split();
+    let result = async {
+        while let proto::server::ServerState::Connected(connected) = &mut server {
+            tokio::select! {
+                Some(frame) = request_recv.next() => {
+                    server.process_control(&data, frame?).await?;
+                }
+                _ = stats_timer.tick() => {
+                    let (stats, elapsed) = stats_sampler.sample(&quic);
+                    connected.process_stats(&data, stats, elapsed);
+                }
+                stream = conn.accept_uni() => {
+                    connected.process_uni(&data, stream?.ok_or(NetworkError::ConnectionClosed)?.1).await?;
+                }
+                stream = conn.accept_bi() => {
+                    if let AcceptedBi::BidiStream(_, stream) = stream?.ok_or(NetworkError::ConnectionClosed)? {
+                        let (send, recv) = stream.split();
 
-                    connected.process_bi(&data, send, recv).await?;
+                        connected.process_bi(&data, send, recv).await?;
+                    }
+                }
+                Some(datagram) = datagrams.next() => {
+                    connected.process_datagram(&data, datagram?.ok_or(NetworkError::ConnectionClosed)?.1).await?;
+                }
+                Some(msg) = connected.control_rx.next() => {
+                    push_send.send(&msg).await?;
                 }
-            }
-            datagram = conn.accept_datagram() => {
-                connected.process_datagram(&data, datagram?.ok_or(NetworkError::ConnectionClosed)?.1).await?;
-            }
-            Some(msg) = connected.control_rx.next() => {
-                push_send.send(&msg).await?;
             }
         }
+
+        Ok::<_, anyhow::Error>(())
     }
+    .await;
+
+    // Unless the client disconnected, keep the player around so that it can reconnect
+    server.process_connection_lost(&data);
 
     tracing::info!("Client disconnected");
 
-    Ok(())
+    result
 }
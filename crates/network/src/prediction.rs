@@ -0,0 +1,590 @@
+//! Client-side prediction of the entities controlled by the local player.
+//!
+//! The owner of an entity with `predicted` applies their input to it immediately with the
+//! [prediction_model], instead of waiting for the server. The inputs are also sent to the server,
+//! which applies them with the same model and acknowledges them through `last_processed_input`.
+//! The server buffers the inputs of each player for a few ticks, and applies them at the same
+//! pace as they were sampled, so that a jittery connection does not bunch them up. A player can
+//! not apply more time than passes on the server: the inputs which go over are shortened.
+//! When the server's state of the entity arrives, the client resets the entity to it and
+//! re-applies the inputs which the server has not processed yet.
+//!
+//! Only the translation and rotation of the entity are reconciled, so the model should only
+//! depend on those and the input.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use ambient_core::{
+    dtime,
+    player::{local_user_id, user_id},
+    runtime,
+    transform::{rotation, translation},
+};
+use ambient_ecs::{components, query, EntityId, FnSystem, SystemGroup, World};
+use ambient_input::{player_raw_input, PlayerRawInput};
+use ambient_std::asset_cache::AssetCache;
+use bytes::Bytes;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::game_client,
+    interpolation::TransformChange,
+    log_network_result,
+    server::{DatagramHandlers, SharedServerState},
+    tick::{current_tick, input_tick, tick_duration},
+    PLAYER_INPUT_DATAGRAM_ID,
+};
+
+pub use ambient_ecs::generated::components::core::network::{last_processed_input, predicted};
+
+components!("network::prediction", {
+    /// Moves a predicted entity according to the input of its owner. Must be the same on the
+    /// server and the clients
+    @[Resource]
+    prediction_model: PredictionModel,
+    /// The inputs of the local player which the server has not acknowledged yet
+    @[Resource]
+    local_prediction: LocalPrediction,
+    /// The latest state of a predicted entity received from the server
+    predicted_state: PredictedState,
+
+    /// The inputs received from a player which have not been applied yet, by the tick they will
+    /// be applied in. Server side only
+    player_input_queue: InputQueue,
+});
+
+/// Applies one frame of input, which lasted for the given number of seconds, to an entity
+pub type PredictionModel = Arc<dyn Fn(&mut World, EntityId, &PlayerRawInput, f32) + Sync + Send>;
+
+/// The longest frame which is applied in one step, in seconds
+pub(crate) const MAX_INPUT_DT: f32 = 0.1;
+/// How many of the latest inputs are sent with every frame, so that a lost datagram does not
+/// lose the input
+pub(crate) const INPUT_REDUNDANCY: usize = 8;
+/// The most inputs which are kept waiting for an acknowledgement
+const MAX_PENDING_INPUTS: usize = 256;
+/// The most inputs which are buffered for a player on the server
+const MAX_QUEUED_INPUTS: usize = 64;
+/// How many ticks the inputs of a player are delayed by on the server, to absorb the jitter of
+/// their connection
+const INPUT_BUFFER_TICKS: u64 = 2;
+/// The furthest ahead an input is scheduled. If the player gets further ahead, their inputs are
+/// scheduled again from [INPUT_BUFFER_TICKS] on, instead of being delayed further
+const MAX_INPUT_BUFFER_TICKS: u64 = 8;
+/// How many ticks worth of time a player can save up, for inputs which arrive in bursts
+const MAX_INPUT_BUDGET_TICKS: u32 = 8;
+
+/// The input of the player for one frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub sequence: u32,
+    pub dt: f32,
+    /// The tick of the latest state the client had received from the server when the input was
+    /// sampled
+    pub tick: u64,
+    pub input: PlayerRawInput,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LocalPrediction {
+    next_sequence: u32,
+    pending: VecDeque<InputFrame>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PredictedState {
+    transform: TransformChange,
+    acknowledged: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InputQueue {
+    frames: BTreeMap<u64, Vec<InputFrame>>,
+    last_received: u32,
+    last_applied: u32,
+    /// The tick the inputs are scheduled from, and the time of the player in that tick
+    anchor: Option<(u64, f64)>,
+    /// The total duration of the inputs received, in seconds
+    player_time: f64,
+    /// How many seconds of input can still be applied
+    budget: f32,
+}
+
+impl InputQueue {
+    /// Schedules the inputs which have not been received before, after `tick`, which is the last
+    /// tick the server has run.
+    ///
+    /// Consecutive inputs are scheduled as far apart as they were sampled by the player.
+    pub fn receive(
+        &mut self,
+        frames: impl IntoIterator<Item = InputFrame>,
+        tick: u64,
+        tick_duration: Duration,
+    ) {
+        for mut frame in frames {
+            if frame.sequence <= self.last_received {
+                continue;
+            }
+
+            self.last_received = frame.sequence;
+            frame.dt = if frame.dt.is_finite() {
+                frame.dt.clamp(0.0, MAX_INPUT_DT)
+            } else {
+                0.0
+            };
+            self.player_time += frame.dt as f64;
+
+            let scheduled = self.anchor.map(|(anchor, time)| {
+                anchor + ((self.player_time - time) / tick_duration.as_secs_f64()) as u64
+            });
+            let scheduled = match scheduled {
+                Some(scheduled)
+                    if scheduled > tick && scheduled <= tick + MAX_INPUT_BUFFER_TICKS =>
+                {
+                    scheduled
+                }
+                // The input arrived too late or too early for the schedule, so the schedule is
+                // started over
+                _ => {
+                    let scheduled = tick + INPUT_BUFFER_TICKS;
+                    self.anchor = Some((scheduled, self.player_time));
+                    scheduled
+                }
+            };
+            self.frames.entry(scheduled).or_default().push(frame);
+        }
+
+        while self.frames.values().map(Vec::len).sum::<usize>() > MAX_QUEUED_INPUTS {
+            self.frames.pop_first();
+        }
+    }
+
+    /// Removes the inputs which are scheduled up to and including `tick`, which is called once
+    /// per tick.
+    ///
+    /// The inputs are shortened so that they do not add up to more than the time which has
+    /// passed on the server, so that a player cannot move faster by claiming longer frames.
+    pub fn take(&mut self, tick: u64, tick_duration: Duration) -> Vec<InputFrame> {
+        let max_budget = (tick_duration * MAX_INPUT_BUDGET_TICKS).as_secs_f32();
+        self.budget = (self.budget + tick_duration.as_secs_f32()).min(max_budget);
+
+        let later = self.frames.split_off(&(tick + 1));
+        let mut frames = std::mem::replace(&mut self.frames, later)
+            .into_values()
+            .flatten()
+            .collect_vec();
+        for frame in &mut frames {
+            frame.dt = frame.dt.min(self.budget);
+            self.budget -= frame.dt;
+        }
+        frames
+    }
+}
+
+/// Whether the entity is predicted by the player of this client
+pub fn is_locally_predicted(world: &World, id: EntityId) -> bool {
+    if !world.has_component(id, predicted()) {
+        return false;
+    }
+
+    match (
+        world.get_ref(id, user_id()),
+        world.resource_opt(local_user_id()),
+    ) {
+        (Ok(owner), Some(local)) => owner == local,
+        _ => false,
+    }
+}
+
+fn locally_predicted_entities(world: &World) -> Vec<EntityId> {
+    let Some(local) = world.resource_opt(local_user_id()) else {
+        return Vec::new();
+    };
+
+    query(user_id())
+        .incl(predicted())
+        .iter(world, None)
+        .filter(|(_, owner)| *owner == local)
+        .map(|(id, _)| id)
+        .collect_vec()
+}
+
+/// Applies the input of the local player for this frame to the entities they predict.
+///
+/// Returns the latest inputs to send to the server, or `None` if nothing is predicted.
+pub(crate) fn predict(
+    world: &mut World,
+    input: PlayerRawInput,
+    dt: f32,
+) -> Option<Vec<InputFrame>> {
+    let model = world.resource_opt(prediction_model()).cloned()?;
+    let entities = locally_predicted_entities(world);
+    if entities.is_empty() {
+        return None;
+    }
+
+    if world.resource_opt(local_prediction()).is_none() {
+        world.add_resource(local_prediction(), LocalPrediction::default());
+    }
+    let tick = current_tick(world);
+    let state = world.resource_mut(local_prediction());
+    state.next_sequence += 1;
+    let frame = InputFrame {
+        sequence: state.next_sequence,
+        dt: dt.clamp(0.0, MAX_INPUT_DT),
+        tick,
+        input,
+    };
+    state.pending.push_back(frame.clone());
+    while state.pending.len() > MAX_PENDING_INPUTS {
+        state.pending.pop_front();
+    }
+    let unacknowledged = state
+        .pending
+        .iter()
+        .skip(state.pending.len().saturating_sub(INPUT_REDUNDANCY))
+        .cloned()
+        .collect_vec();
+
+    for id in entities {
+        model(world, id, &frame.input, frame.dt);
+    }
+
+    Some(unacknowledged)
+}
+
+/// Resets the locally predicted entities to the state received from the server, and re-applies
+/// the inputs which the server has not processed yet
+pub(crate) fn reconcile(world: &mut World, changes: &HashMap<EntityId, TransformChange>) {
+    let entities = locally_predicted_entities(world);
+    if entities.is_empty() {
+        return;
+    }
+
+    let model = world.resource_opt(prediction_model()).cloned();
+    let pending = world
+        .resource_opt(local_prediction())
+        .map(|state| state.pending.clone())
+        .unwrap_or_default();
+
+    let mut oldest_acknowledged = u32::MAX;
+    for id in entities {
+        let acknowledged = world.get(id, last_processed_input()).unwrap_or_default();
+        oldest_acknowledged = oldest_acknowledged.min(acknowledged);
+
+        let mut state = world.get(id, predicted_state()).unwrap_or_default();
+        let change = changes.get(&id).copied();
+        if let Some(change) = change {
+            state.transform.merge(change);
+        }
+        if change.is_none() && state.acknowledged == acknowledged {
+            continue;
+        }
+        state.acknowledged = acknowledged;
+        world.add_component(id, predicted_state(), state).unwrap();
+        // Nothing to reset to until the server has sent the transform
+        if state.transform == TransformChange::default() {
+            continue;
+        }
+
+        if let Some(value) = state.transform.translation {
+            world.add_component(id, translation(), value).unwrap();
+        }
+        if let Some(value) = state.transform.rotation {
+            world.add_component(id, rotation(), value).unwrap();
+        }
+
+        if let Some(model) = &model {
+            for frame in pending.iter().filter(|frame| frame.sequence > acknowledged) {
+                model(world, id, &frame.input, frame.dt);
+            }
+        }
+    }
+
+    if let Some(state) = world.resource_mut_opt(local_prediction()) {
+        state
+            .pending
+            .retain(|frame| frame.sequence > oldest_acknowledged);
+    }
+}
+
+/// Predicts the entities of the local player and sends their input to the server
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "network/prediction/client",
+        vec![Box::new(FnSystem::new(|world, _| {
+            let Some(Some(client)) = world.resource_opt(game_client()).cloned() else {
+                return;
+            };
+            let Some(input) = world.resource_opt(player_raw_input()).cloned() else {
+                return;
+            };
+            let dt = *world.resource(dtime());
+
+            let Some(frames) = predict(world, input, dt) else {
+                return;
+            };
+
+            let data = Bytes::from(bincode::serialize(&frames).unwrap());
+            world.resource(runtime()).spawn(async move {
+                log_network_result!(
+                    client
+                        .connection
+                        .send_datagram(PLAYER_INPUT_DATAGRAM_ID, data)
+                        .await
+                );
+            });
+        }))],
+    )
+}
+
+/// Receives the inputs of the players which predict entities
+pub fn register_datagram_handler(handlers: &mut DatagramHandlers) {
+    handlers.insert(
+        PLAYER_INPUT_DATAGRAM_ID,
+        ("player_input", Arc::new(on_input_datagram)),
+    );
+}
+
+#[allow(clippy::ptr_arg)]
+fn on_input_datagram(state: SharedServerState, _assets: AssetCache, user_id: &str, bytes: Bytes) {
+    let frames: Vec<InputFrame> = match bincode::deserialize(&bytes) {
+        Ok(frames) => frames,
+        Err(err) => {
+            log::warn!("Received invalid input from {user_id}: {err:?}");
+            return;
+        }
+    };
+
+    let mut state = state.lock();
+    let Some(world) = state.get_player_world_mut(user_id) else {
+        return;
+    };
+    let Some(player) = ambient_core::player::get_by_user_id(world, user_id) else {
+        return;
+    };
+    let (tick, duration) = (current_tick(world), tick_duration(world));
+    if let Ok(queue) = world.get_mut(player, player_input_queue()) {
+        queue.receive(frames, tick, duration);
+    }
+}
+
+/// Applies the inputs of the players scheduled for this tick to the entities they predict
+pub fn server_systems() -> SystemGroup {
+    SystemGroup::new(
+        "network/prediction/server",
+        vec![Box::new(FnSystem::new(|world, _| {
+            apply_player_inputs(world)
+        }))],
+    )
+}
+
+fn apply_player_inputs(world: &mut World) {
+    let Some(model) = world.resource_opt(prediction_model()).cloned() else {
+        return;
+    };
+    let (tick, duration) = (current_tick(world), tick_duration(world));
+
+    let predicted = query(user_id())
+        .incl(predicted())
+        .iter(world, None)
+        .map(|(id, owner)| (owner.clone(), id))
+        .into_group_map();
+    let players = query(user_id())
+        .incl(player_input_queue())
+        .iter(world, None)
+        .map(|(id, owner)| (id, owner.clone()))
+        .collect_vec();
+
+    for (player, owner) in players {
+        let queue = world.get_mut(player, player_input_queue()).unwrap();
+        let frames = queue.take(tick, duration);
+        if let Some(frame) = frames.last() {
+            queue.last_applied = frame.sequence;
+        }
+        let acknowledged = queue.last_applied;
+        if let Some(frame) = frames.last() {
+            world
+                .add_component(player, input_tick(), frame.tick)
+                .unwrap();
+        }
+
+        for &id in predicted.get(&owner).into_iter().flatten() {
+            for frame in &frames {
+                model(world, id, &frame.input, frame.dt);
+            }
+            if world.get(id, last_processed_input()).ok() != Some(acknowledged) {
+                world
+                    .add_component(id, last_processed_input(), acknowledged)
+                    .unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_ecs::Entity;
+    use glam::Vec3;
+
+    use super::*;
+
+    fn init() -> World {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        crate::init_all_components();
+
+        let mut world = World::new_with_config("test", false);
+        world.add_resource(local_user_id(), "local".to_string());
+        world.add_resource(
+            prediction_model(),
+            Arc::new(
+                |world: &mut World, id: EntityId, _: &PlayerRawInput, dt: f32| {
+                    let position = world.get(id, translation()).unwrap();
+                    world
+                        .set(id, translation(), position + Vec3::X * dt)
+                        .unwrap();
+                },
+            ),
+        );
+        world
+    }
+
+    #[test]
+    fn reconcile_replays_unacknowledged_inputs() {
+        let mut world = init();
+        let id = Entity::new()
+            .with(user_id(), "local".to_string())
+            .with(predicted(), ())
+            .with(translation(), Vec3::ZERO)
+            .spawn(&mut world);
+
+        for _ in 0..3 {
+            predict(&mut world, PlayerRawInput::default(), 0.1).unwrap();
+        }
+        assert!(world
+            .get(id, translation())
+            .unwrap()
+            .abs_diff_eq(Vec3::X * 0.3, 1e-5));
+
+        // The server has processed the first input, and was pushed slightly further by something
+        let server = Vec3::X * 0.1 + Vec3::Y;
+        world.set(id, translation(), server).unwrap();
+        world.add_component(id, last_processed_input(), 1).unwrap();
+        let changes = HashMap::from([(
+            id,
+            TransformChange {
+                translation: Some(server),
+                rotation: None,
+            },
+        )]);
+        reconcile(&mut world, &changes);
+
+        assert!(world
+            .get(id, translation())
+            .unwrap()
+            .abs_diff_eq(server + Vec3::X * 0.2, 1e-5));
+        assert_eq!(world.resource(local_prediction()).pending.len(), 2);
+    }
+
+    #[test]
+    fn input_queue() {
+        let frame = |sequence, dt| InputFrame {
+            sequence,
+            dt,
+            tick: 0,
+            input: PlayerRawInput::default(),
+        };
+        let duration = Duration::from_millis(50);
+
+        let mut queue = InputQueue::default();
+        queue.receive([frame(1, 0.016), frame(2, 10.0)], 0, duration);
+        // Inputs are resent until acknowledged
+        queue.receive(
+            [frame(1, 0.016), frame(2, 10.0), frame(3, f32::NAN)],
+            0,
+            duration,
+        );
+
+        assert_eq!(
+            queue
+                .frames
+                .values()
+                .flatten()
+                .map(|f| (f.sequence, f.dt))
+                .collect_vec(),
+            vec![(1, 0.016), (2, MAX_INPUT_DT), (3, 0.0)]
+        );
+    }
+
+    #[test]
+    fn input_budget() {
+        let frame = |sequence, dt| InputFrame {
+            sequence,
+            dt,
+            tick: 0,
+            input: PlayerRawInput::default(),
+        };
+        let total_dt = |frames: Vec<InputFrame>| frames.iter().map(|f| f.dt).sum::<f32>();
+        let duration = Duration::from_millis(50);
+
+        // A player who claims that more time passes than on the server
+        let mut queue = InputQueue::default();
+        let mut applied = 0.0;
+        for tick in 0..20 {
+            let sequence = tick as u32 * 5;
+            queue.receive(
+                (1..=5).map(|i| frame(sequence + i, MAX_INPUT_DT)),
+                tick,
+                duration,
+            );
+            applied += total_dt(queue.take(tick, duration));
+        }
+        assert!(applied <= 20. * duration.as_secs_f32() + 1e-4);
+
+        // Time which was not used can be used by inputs which arrive in a burst
+        let mut queue = InputQueue::default();
+        for tick in 0..4 {
+            queue.take(tick, duration);
+        }
+        queue.receive([frame(1, 0.1), frame(2, 0.1)], 4, duration);
+        queue.take(5, duration);
+        let frames = queue.take(6, duration);
+        assert_eq!(frames.iter().map(|f| f.dt).collect_vec(), vec![0.1, 0.1]);
+    }
+
+    #[test]
+    fn input_schedule() {
+        let frame = |sequence, dt| InputFrame {
+            sequence,
+            dt,
+            tick: 0,
+            input: PlayerRawInput::default(),
+        };
+        let sequences = |frames: Vec<InputFrame>| frames.iter().map(|f| f.sequence).collect_vec();
+        let duration = Duration::from_secs_f64(1. / 16.);
+
+        let mut queue = InputQueue::default();
+        queue.receive(
+            [frame(1, 1. / 32.), frame(2, 1. / 32.), frame(3, 1. / 16.)],
+            10,
+            duration,
+        );
+        let mut take = |tick| sequences(queue.take(tick, duration));
+        assert_eq!(take(10 + INPUT_BUFFER_TICKS - 1), vec![]);
+        assert_eq!(take(10 + INPUT_BUFFER_TICKS), vec![1, 2]);
+        assert_eq!(take(11 + INPUT_BUFFER_TICKS), vec![3]);
+
+        // The server is past the schedule by the time the input arrives
+        queue.receive([frame(4, 1. / 16.)], 20, duration);
+        assert_eq!(
+            queue.frames.keys().copied().collect_vec(),
+            vec![20 + INPUT_BUFFER_TICKS]
+        );
+    }
+}
@@ -1,9 +1,14 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
+use ambient_core::abs_time;
 use ambient_ecs::{
-    generated::components::core::network::is_remote_entity, ComponentRegistry, Entity, WorldDiff,
+    generated::components::core::network::is_remote_entity, query, ComponentRegistry, Entity,
+    World, WorldDiff,
+};
+use ambient_std::{
+    asset_cache::{AssetCache, SyncAssetKeyExt},
+    asset_url::ContentBaseUrlKey,
 };
-use ambient_std::{asset_cache::SyncAssetKeyExt, asset_url::ContentBaseUrlKey};
 use anyhow::{bail, Context};
 use bytes::{Buf, Bytes};
 use parking_lot::Mutex;
@@ -16,14 +21,20 @@ use crate::{
         NetworkStats, PlatformRecvStream, PlatformSendStream,
     },
     client_game_state::ClientGameState,
+    diff_codec::DiffDecoder,
+    interpolation, prediction,
     proto::*,
+    stats::NetworkReport,
+    NetworkError,
 };
 
 /// The client logic handler in a connected state
 ///
 /// Entered after the client has sent a connect request and received a `ServerInfo` message from the server, in no particular order.
 #[derive(Debug)]
-pub(crate) struct ConnectedClient {}
+pub(crate) struct ConnectedClient {
+    diff_decoder: DiffDecoder,
+}
 
 #[derive(Debug)]
 pub(crate) enum ClientState {
@@ -35,6 +46,29 @@ pub(crate) enum ClientState {
 /// Holds the material world of the client.
 pub type SharedClientState = Arc<Mutex<ClientGameState>>;
 
+/// The state of a client which the world of the server is replicated into
+pub trait ClientWorldState: std::fmt::Debug + Send + 'static {
+    fn world_mut(&mut self) -> &mut World;
+    fn assets(&self) -> &AssetCache;
+
+    /// The world to apply a diff which was just received from the server to.
+    ///
+    /// Interpolation snapshots in the diff are timed by the `abs_time` of this world.
+    fn received_diff_world_mut(&mut self) -> &mut World {
+        self.world_mut()
+    }
+}
+
+impl ClientWorldState for ClientGameState {
+    fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    fn assets(&self) -> &AssetCache {
+        &self.assets
+    }
+}
+
 impl ClientState {
     pub fn process_disconnect(&mut self) {
         tracing::info!("Disconnecting client: {self:#?}");
@@ -44,19 +78,33 @@ impl ClientState {
 
     /// Processes an incoming control frame from the server.
     #[tracing::instrument(level = "debug")]
-    pub fn process_push(
+    pub fn process_push<S: ClientWorldState>(
         &mut self,
-        state: &SharedClientState,
+        state: &Arc<Mutex<S>>,
         frame: ServerPush,
     ) -> anyhow::Result<()> {
         match (frame, &self) {
             (ServerPush::ServerInfo(server_info), Self::Connecting(_user_id)) => {
-                let state = state.lock();
-                ContentBaseUrlKey.insert(&state.assets, server_info.content_base_url.clone());
+                let mut state = state.lock();
+                ContentBaseUrlKey.insert(state.assets(), server_info.content_base_url.clone());
                 tracing::debug!(?server_info.external_components, "Adding external components");
                 ComponentRegistry::get_mut().add_external(server_info.external_components);
 
-                *self = Self::Connected(ConnectedClient {});
+                // When reconnecting, what was replicated over the previous connection is sent
+                // again by the server in its initial diff
+                let world = state.world_mut();
+                let remote_entities = query(())
+                    .incl(is_remote_entity())
+                    .iter(world, None)
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                for id in remote_entities {
+                    world.despawn(id);
+                }
+
+                *self = Self::Connected(ConnectedClient {
+                    diff_decoder: DiffDecoder::new(),
+                });
 
                 Ok(())
             }
@@ -64,6 +112,15 @@ impl ClientState {
                 tracing::warn!("Received server info while already connected");
                 Ok(())
             }
+            (ServerPush::ExternalComponents(components), _) => {
+                tracing::debug!(?components, "Adding external components");
+                ComponentRegistry::get_mut().add_external(components);
+                Ok(())
+            }
+            (ServerPush::Rejected(reason), _) => {
+                self.process_disconnect();
+                Err(NetworkError::ConnectionRejected(reason).into())
+            }
             (ServerPush::Disconnect, _) => {
                 self.process_disconnect();
                 Ok(())
@@ -71,11 +128,25 @@ impl ClientState {
         }
     }
 
+    /// Writes the statistics of the connection, measured over `elapsed`, to the resources
     #[cfg(not(target_os = "unknown"))]
-    pub fn process_client_stats(&mut self, state: &SharedClientState, stats: NetworkStats) {
+    pub fn process_client_stats<S: ClientWorldState>(
+        &mut self,
+        state: &Arc<Mutex<S>>,
+        stats: NetworkStats,
+        elapsed: Duration,
+    ) {
+        let bandwidth = match self {
+            Self::Connected(connected) => connected.diff_decoder.take_bandwidth(),
+            _ => Default::default(),
+        };
+
         let mut gs = state.lock();
         tracing::debug!(?stats, "Client network stats");
-        gs.world.add_resource(client_network_stats(), stats);
+        let world = gs.world_mut();
+        let report = NetworkReport::new(stats.clone(), &bandwidth, elapsed);
+        report.write(world, world.resource_entity());
+        world.add_resource(client_network_stats(), stats);
     }
 
     /// Returns `true` if the client state is [`Connecting`].
@@ -87,37 +158,43 @@ impl ClientState {
     }
 }
 
+/// Applies a diff of the server world to the client world, marking the spawned entities as remote
+pub(crate) fn apply_remote_diff(world: &mut World, diff: WorldDiff) {
+    let changes = interpolation::transform_changes(&diff);
+    diff.apply(world, Entity::new().with(is_remote_entity(), ()), false);
+
+    let time = *world.resource(abs_time());
+    interpolation::record_snapshots(world, &changes, time);
+    prediction::reconcile(world, &changes);
+}
+
 impl ConnectedClient {
-    #[tracing::instrument(level = "debug")]
-    pub fn process_diff(
+    #[tracing::instrument(level = "debug", skip(frame))]
+    pub fn process_diff<S: ClientWorldState>(
         &mut self,
-        state: &SharedClientState,
-        diff: WorldDiff,
+        state: &Arc<Mutex<S>>,
+        frame: Bytes,
     ) -> anyhow::Result<()> {
+        let diff = self.diff_decoder.decode(&frame)?;
         let mut gs = state.lock();
         tracing::debug!(?diff, "Applying diff");
-        diff.apply(
-            &mut gs.world,
-            Entity::new().with(is_remote_entity(), ()),
-            false,
-        );
+        apply_remote_diff(gs.received_diff_world_mut(), diff);
         Ok(())
     }
 
     /// Processes a server initiated bidirectional stream
     #[tracing::instrument(level = "debug", skip(send, recv))]
-    pub async fn process_bi(
+    pub async fn process_bi<S: ClientWorldState>(
         &mut self,
-        state: &SharedClientState,
+        state: &Arc<Mutex<S>>,
         send: PlatformSendStream,
         mut recv: PlatformRecvStream,
     ) -> anyhow::Result<()> {
         let id = recv.read_u32().await?;
 
         let mut gs = state.lock();
-        let gs = &mut *gs;
-        let world = &mut gs.world;
-        let assets = gs.assets.clone();
+        let assets = gs.assets().clone();
+        let world = gs.world_mut();
 
         let (name, handler) = world
             .resource(bi_stream_handlers())
@@ -133,17 +210,16 @@ impl ConnectedClient {
 
     /// Processes a server initiated unidirectional stream
     #[tracing::instrument(level = "debug", skip(recv))]
-    pub async fn process_uni(
+    pub async fn process_uni<S: ClientWorldState>(
         &mut self,
-        state: &SharedClientState,
+        state: &Arc<Mutex<S>>,
         mut recv: PlatformRecvStream,
     ) -> anyhow::Result<()> {
         let id = recv.read_u32().await?;
 
         let mut gs = state.lock();
-        let gs = &mut *gs;
-        let world = &mut gs.world;
-        let assets = gs.assets.clone();
+        let assets = gs.assets().clone();
+        let world = gs.world_mut();
 
         let (name, handler) = world
             .resource(uni_stream_handlers())
@@ -159,9 +235,9 @@ impl ConnectedClient {
 
     /// Processes an incoming datagram
     #[tracing::instrument(level = "debug")]
-    pub fn process_datagram(
+    pub fn process_datagram<S: ClientWorldState>(
         &mut self,
-        state: &SharedClientState,
+        state: &Arc<Mutex<S>>,
         mut data: Bytes,
     ) -> anyhow::Result<()> {
         if data.len() < 4 {
@@ -171,9 +247,8 @@ impl ConnectedClient {
         let id = data.get_u32();
 
         let mut gs = state.lock();
-        let gs = &mut *gs;
-        let world = &mut gs.world;
-        let assets = gs.assets.clone();
+        let assets = gs.assets().clone();
+        let world = gs.world_mut();
 
         let (name, handler) = world
             .resource(datagram_handlers())
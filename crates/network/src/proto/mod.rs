@@ -8,7 +8,12 @@ pub mod server;
 /// Request sent by the client to the server
 pub enum ClientRequest {
     /// Connect to the server with the specified user id
-    Connect(String),
+    ///
+    /// The credential is checked by the server's [ConnectionVerifier](crate::auth::ConnectionVerifier), if any.
+    Connect {
+        user_id: String,
+        credential: Option<String>,
+    },
     /// Client wants to disconnect
     Disconnect,
 }
@@ -17,6 +22,10 @@ pub enum ClientRequest {
 /// Frame used by the server to send information to the client
 pub enum ServerPush {
     ServerInfo(ServerInfo),
+    /// Components which were registered on the server after the client connected, such as those of a mod
+    ExternalComponents(Vec<ExternalComponentDesc>),
+    /// The connect request was refused, with a reason which can be shown to the user
+    Rejected(String),
     /// Graceful disconnect
     Disconnect,
 }
@@ -1,8 +1,9 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use ambient_core::player::get_by_user_id;
-use ambient_ecs::{WorldDiff, WorldStreamFilter};
+use ambient_ecs::{ExternalComponentDesc, WorldDiff, WorldStreamFilter};
 use ambient_std::{fps_counter::FpsSample, log_result};
+use ambient_sys::time::Instant;
 use anyhow::{bail, Context};
 use bytes::{Buf, Bytes};
 use futures::{Stream, StreamExt};
@@ -11,18 +12,25 @@ use tracing::{debug_span, Instrument};
 use uuid::Uuid;
 
 use crate::{
-    client::ClientConnection,
-    log_network_result,
+    auth,
+    client::{ClientConnection, NetworkStats},
+    interest, log_network_result,
     proto::ServerPush,
+    replay::RecordingConnection,
     server::{
-        bi_stream_handlers, create_player_entity_data, datagram_handlers, uni_stream_handlers,
+        bi_stream_handlers, create_player_entity_data, datagram_handlers, encode_player_diff,
+        player_diff_encoder, player_entity_stream, session_recorder, uni_stream_handlers,
     },
     server::{SharedServerState, MAIN_INSTANCE_ID},
+    stats::NetworkReport,
     stream,
 };
 
 use super::ClientRequest;
 
+/// How long the server waits for a rejected client to disconnect before closing the connection
+pub(crate) const REJECTION_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
 /// The server can be in multiple states depending on what has been received from the client.
 ///
 /// The server starts in the `PendingConnection` state, until
@@ -32,6 +40,8 @@ pub enum ServerState {
     #[default]
     PendingConnection,
     Connected(ConnectedClient),
+    /// The connect request was refused by the verifier, for the contained reason
+    Rejected(String),
     Disconnected,
 }
 
@@ -81,6 +91,10 @@ pub struct Player {
     pub instance: String,
     control_tx: flume::Sender<ServerPush>,
     connection_id: Uuid,
+    /// When the connection to the player was lost, if it has been.
+    ///
+    /// The player is kept in the world until the reconnection grace period has passed.
+    pub(crate) lost_at: Option<Instant>,
 }
 
 impl Player {
@@ -91,6 +105,7 @@ impl Player {
             instance: instance.into(),
             control_tx,
             connection_id: Uuid::new_v4(),
+            lost_at: None,
         }
     }
 
@@ -98,27 +113,48 @@ impl Player {
     pub fn abort(&self) {
         self.control_tx.send(ServerPush::Disconnect).ok();
     }
+
+    /// Sends the components which were registered after the player connected
+    pub(crate) fn push_external_components(&self, components: Vec<ExternalComponentDesc>) {
+        self.control_tx
+            .send(ServerPush::ExternalComponents(components))
+            .ok();
+    }
 }
 
 impl ServerState {
     /// Processes a client request
-    pub fn process_control(
+    pub async fn process_control(
         &mut self,
         data: &ConnectionData,
         frame: ClientRequest,
     ) -> anyhow::Result<()> {
         match (frame, &self) {
-            (_, Self::Disconnected) => {
+            (_, Self::Disconnected | Self::Rejected(_)) => {
                 tracing::info!("Client is disconnected, ignoring control frame");
                 Ok(())
             }
-            (ClientRequest::Connect(user_id), Self::PendingConnection) => {
-                // Connect the user
-                tracing::info!("User connected");
-                self.process_connect(data, user_id);
+            (
+                ClientRequest::Connect {
+                    user_id,
+                    credential,
+                },
+                Self::PendingConnection,
+            ) => {
+                match auth::verify_connection(&data.state, &user_id, credential.as_deref()).await {
+                    Ok(()) => {
+                        // Connect the user
+                        tracing::info!("User connected");
+                        self.process_connect(data, user_id);
+                    }
+                    Err(reason) => {
+                        tracing::info!(user_id, reason, "Connection rejected");
+                        *self = Self::Rejected(reason);
+                    }
+                }
                 Ok(())
             }
-            (ClientRequest::Connect(_), Self::Connected(_)) => {
+            (ClientRequest::Connect { .. }, Self::Connected(_)) => {
                 tracing::warn!("Client already connected");
                 Ok(())
             }
@@ -136,47 +172,77 @@ impl ServerState {
 
         let (control_tx, control_rx) = flume::unbounded();
 
+        // A reconnecting player resumes in the instance they were in
+        let instance_id = state
+            .players
+            .get(&user_id)
+            .map(|player| player.instance.clone())
+            .filter(|id| state.instances.contains_key(id))
+            .unwrap_or_else(|| MAIN_INSTANCE_ID.to_string());
+
         let old_player = state.players.insert(
             user_id.clone(),
             Player {
-                instance: MAIN_INSTANCE_ID.to_string(),
+                instance: instance_id.clone(),
                 control_tx,
                 connection_id: data.connection_id,
+                lost_at: None,
             },
         );
 
-        let instance = state.instances.get_mut(MAIN_INSTANCE_ID).unwrap();
+        // The messages sent to the player are part of the recording, if the session is recorded
+        let conn: Arc<dyn ClientConnection> = match state
+            .instances
+            .get(MAIN_INSTANCE_ID)
+            .and_then(|instance| instance.world.resource_opt(session_recorder()))
+        {
+            Some(recorder) => Arc::new(RecordingConnection::new(
+                data.conn.clone(),
+                user_id.clone(),
+                recorder.clone(),
+            )),
+            None => data.conn.clone(),
+        };
+
+        let instance = state.instances.get_mut(&instance_id).unwrap();
 
         // Bring world stream up to the current time
         tracing::debug!("[{}] Broadcasting diffs", user_id);
         instance.broadcast_diffs();
-        tracing::debug!("[{}] Creating init diff", user_id);
-
-        let diff = data.world_stream_filter.initial_diff(&instance.world);
-        let diff = bincode::serialize(&diff).unwrap().into();
-
-        log_result!(data.diff_tx.send(diff));
-        tracing::debug!("[{}] Init diff sent", user_id);
 
         let entity_data = create_player_entity_data(
-            data.conn.clone(),
+            conn,
             user_id.clone(),
             data.diff_tx.clone(),
             data.connection_id,
         );
 
-        if let Some(old_player) = old_player {
+        if let Some(old_player) = &old_player {
             old_player.control_tx.send(ServerPush::Disconnect).ok();
+        }
 
-            let id = get_by_user_id(&instance.world, &user_id).unwrap();
+        let id = match old_player.and(get_by_user_id(&instance.world, &user_id)) {
+            Some(id) => {
+                instance.world.add_components(id, entity_data).unwrap();
 
-            instance.world.add_components(id, entity_data).unwrap();
+                tracing::info!(user_id, ?id, "Player reconnected");
+                id
+            }
+            None => {
+                let id = instance.spawn_player(entity_data);
+                tracing::info!(user_id, ?id, "Player connected");
+                id
+            }
+        };
 
-            tracing::info!(user_id, ?id, "Player reconnected");
-        } else {
-            let id = instance.spawn_player(entity_data);
-            tracing::info!(user_id, ?id, "Player connected");
-        }
+        // The player entity has to exist before the init diff is created, as what is sent
+        // depends on which entities are relevant to the player
+        tracing::debug!("[{}] Creating init diff", user_id);
+        let diff = interest::initial_diff(&instance.world, &data.world_stream_filter, id);
+        let diff = encode_player_diff(&instance.world, id, &diff);
+
+        log_result!(data.diff_tx.send(diff));
+        tracing::debug!("[{}] Init diff sent", user_id);
 
         *self = Self::Connected(ConnectedClient {
             user_id,
@@ -214,6 +280,34 @@ impl ServerState {
         *self = Self::Disconnected;
     }
 
+    /// Called when the connection ended without the client disconnecting.
+    ///
+    /// The player entity is kept, without anything being sent to it, so that the client can
+    /// resume its session if it reconnects within the grace period.
+    pub fn process_connection_lost(&mut self, data: &ConnectionData) {
+        if let Self::Connected(ConnectedClient { user_id, .. }) = self {
+            tracing::info!(?user_id, "Lost connection to user");
+            let mut state = data.state.lock();
+
+            if let Some(player) = state
+                .players
+                .get_mut(user_id)
+                .filter(|player| player.connection_id == data.connection_id)
+            {
+                player.lost_at = Some(Instant::now());
+
+                let instance = player.instance.clone();
+                if let Some(world) = state.instances.get_mut(&instance).map(|i| &mut i.world) {
+                    if let Some(id) = get_by_user_id(world, user_id) {
+                        world.remove_component(id, player_entity_stream()).ok();
+                    }
+                }
+            }
+        }
+
+        *self = Self::Disconnected;
+    }
+
     /// Returns `true` if the server state is [`Connected`].
     ///
     /// [`Connected`]: ServerState::Connected
@@ -229,9 +323,35 @@ impl ServerState {
     pub fn is_pending_connection(&self) -> bool {
         matches!(self, Self::PendingConnection)
     }
+
+    /// Returns the reason the connection was refused, if it was.
+    #[must_use]
+    pub fn rejection(&self) -> Option<&str> {
+        match self {
+            Self::Rejected(reason) => Some(reason),
+            _ => None,
+        }
+    }
 }
 
 impl ConnectedClient {
+    /// Writes the statistics of the connection, measured over `elapsed`, to the player entity
+    pub fn process_stats(&self, data: &ConnectionData, stats: NetworkStats, elapsed: Duration) {
+        let mut state = data.state.lock();
+        let Some(world) = state.get_player_world_mut(&self.user_id) else {
+            return;
+        };
+        let Some(player) = get_by_user_id(world, &self.user_id) else {
+            return;
+        };
+        let Ok(encoder) = world.get_ref(player, player_diff_encoder()) else {
+            return;
+        };
+
+        let bandwidth = encoder.lock().take_bandwidth();
+        NetworkReport::new(stats, &bandwidth, elapsed).write(world, player);
+    }
+
     /// Processes an incoming datagram
     #[tracing::instrument(level = "debug", skip(data))]
     pub async fn process_datagram(
@@ -0,0 +1,560 @@
+//! Recording of server sessions to a file, and their playback into a client world.
+//!
+//! A recording starts with a header holding the [ServerInfo] of the server, followed by records
+//! which are each prefixed by their length:
+//! - The first record is the whole world at the time the recording started.
+//! - Every diff broadcast by the main instance afterwards.
+//! - Every message sent by the server to a player, over a datagram or a unidirectional stream.
+//!
+//! Each record holds the time since the start of the recording at which it was made.
+
+use std::{
+    io::{self, BufWriter, Read, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+use ambient_core::{asset_cache, project_name};
+use ambient_ecs::{query, ComponentRegistry, World, WorldDiff, WorldStreamFilter};
+use ambient_std::asset_url::AbsAssetUrl;
+use ambient_sys::time::Instant;
+use anyhow::{bail, Context};
+use bytes::Bytes;
+use futures::{future::BoxFuture, FutureExt};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::{datagram_handlers, replayed_uni_stream_handlers, ClientConnection},
+    is_remote_entity,
+    proto::{client::apply_remote_diff, ServerInfo},
+    NetworkError, MAX_FRAME_SIZE,
+};
+
+const MAGIC: &[u8; 8] = b"AMBREPLY";
+const FORMAT_VERSION: u32 = 1;
+/// How often the recording is flushed to the writer, so that recording does not slow down ticks
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How a message was sent to the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageKind {
+    Datagram,
+    UniStream,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ReplayEvent {
+    Diff(WorldDiff),
+    Message {
+        user_id: String,
+        kind: MessageKind,
+        id: u32,
+        payload: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayRecord {
+    /// Time since the start of the recording
+    pub time: Duration,
+    pub event: ReplayEvent,
+}
+
+/// Writes the diffs and messages of the main instance of a server to a file.
+///
+/// The records are buffered and flushed every [FLUSH_INTERVAL], and when the recorder is dropped.
+pub struct SessionRecorder {
+    writer: Option<BufWriter<Box<dyn Write + Send + Sync>>>,
+    content_base_url: AbsAssetUrl,
+    start: Option<Instant>,
+    last_flush: Instant,
+}
+
+impl std::fmt::Debug for SessionRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionRecorder")
+            .field("content_base_url", &self.content_base_url)
+            .field("start", &self.start)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionRecorder {
+    /// Creates a recorder which writes to `writer`.
+    ///
+    /// Nothing is written until the first diff is recorded.
+    pub fn new(writer: impl Write + Send + Sync + 'static, content_base_url: AbsAssetUrl) -> Self {
+        Self {
+            writer: Some(BufWriter::new(Box::new(writer))),
+            content_base_url,
+            start: None,
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Records the changes to the world since the last call.
+    ///
+    /// The first call records the whole world instead, along with the header of the recording.
+    pub fn record_diff(&mut self, world: &World, filter: &WorldStreamFilter, diff: &WorldDiff) {
+        let (time, diff) = match self.start {
+            Some(_) if diff.is_empty() => return,
+            Some(start) => (start.elapsed(), diff.clone()),
+            None => {
+                let server_info = ServerInfo {
+                    project_name: world
+                        .resource_opt(project_name())
+                        .cloned()
+                        .unwrap_or_else(|| "Ambient".into()),
+                    content_base_url: self.content_base_url.clone(),
+                    version: env!("CARGO_PKG_VERSION").into(),
+                    external_components: ComponentRegistry::get()
+                        .all_external()
+                        .map(|x| x.0)
+                        .collect(),
+                };
+                self.write(|writer| {
+                    writer.write_all(MAGIC)?;
+                    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+                    write_frame(writer, &server_info)
+                });
+
+                self.start = Some(Instant::now());
+                (Duration::ZERO, filter.initial_diff(world))
+            }
+        };
+
+        self.write(|writer| {
+            write_frame(
+                writer,
+                &ReplayRecord {
+                    time,
+                    event: ReplayEvent::Diff(diff),
+                },
+            )
+        });
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.last_flush = Instant::now();
+            self.write(|writer| writer.flush());
+        }
+    }
+
+    /// Records a message sent to the player `user_id`
+    pub fn record_message(&mut self, user_id: &str, kind: MessageKind, id: u32, payload: &[u8]) {
+        // Without the world to go with it, the message is of no use
+        let Some(start) = self.start else {
+            return;
+        };
+
+        let record = ReplayRecord {
+            time: start.elapsed(),
+            event: ReplayEvent::Message {
+                user_id: user_id.into(),
+                kind,
+                id,
+                payload: payload.to_vec(),
+            },
+        };
+        self.write(|writer| write_frame(writer, &record));
+    }
+
+    /// Stops recording after the first error, as the file can not be read past it anyway
+    fn write(&mut self, f: impl FnOnce(&mut dyn Write) -> io::Result<()>) {
+        if let Some(writer) = &mut self.writer {
+            if let Err(err) = f(writer) {
+                log::error!("Failed to write session recording, stopping the recording: {err}");
+                self.writer = None;
+            }
+        }
+    }
+}
+
+fn write_frame(writer: &mut dyn Write, value: &impl Serialize) -> io::Result<()> {
+    let bytes =
+        bincode::serialize(value).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    writer.write_all(&u32::try_from(bytes.len()).unwrap().to_le_bytes())?;
+    writer.write_all(&bytes)
+}
+
+/// Reads a frame, or returns `None` at the end of the file
+fn read_frame(reader: &mut impl Read) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        bail!("The record is {len} bytes long, more than the maximum of {MAX_FRAME_SIZE}");
+    }
+
+    let mut bytes = vec![0u8; len];
+    reader
+        .read_exact(&mut bytes)
+        .context("The recording ends with a partial record")?;
+    Ok(Some(bytes))
+}
+
+/// Records the messages sent to a player before passing them on to their connection
+pub struct RecordingConnection {
+    inner: Arc<dyn ClientConnection>,
+    user_id: String,
+    recorder: Arc<Mutex<SessionRecorder>>,
+}
+
+impl RecordingConnection {
+    pub fn new(
+        inner: Arc<dyn ClientConnection>,
+        user_id: String,
+        recorder: Arc<Mutex<SessionRecorder>>,
+    ) -> Self {
+        Self {
+            inner,
+            user_id,
+            recorder,
+        }
+    }
+}
+
+impl ClientConnection for RecordingConnection {
+    fn request_bi(&self, id: u32, data: Bytes) -> BoxFuture<Result<Bytes, NetworkError>> {
+        self.inner.request_bi(id, data)
+    }
+
+    fn request_uni(&self, id: u32, data: Bytes) -> BoxFuture<Result<(), NetworkError>> {
+        self.recorder
+            .lock()
+            .record_message(&self.user_id, MessageKind::UniStream, id, &data);
+        self.inner.request_uni(id, data)
+    }
+
+    fn send_datagram(&self, id: u32, data: Bytes) -> BoxFuture<Result<(), NetworkError>> {
+        self.recorder
+            .lock()
+            .record_message(&self.user_id, MessageKind::Datagram, id, &data);
+        self.inner.send_datagram(id, data)
+    }
+}
+
+/// The connection of a client playing back a recording, which has no server to talk to
+pub struct ReplayConnection;
+
+impl ClientConnection for ReplayConnection {
+    fn request_bi(&self, _id: u32, _data: Bytes) -> BoxFuture<Result<Bytes, NetworkError>> {
+        async { Err(NetworkError::ConnectionClosed) }.boxed()
+    }
+
+    fn request_uni(&self, _id: u32, _data: Bytes) -> BoxFuture<Result<(), NetworkError>> {
+        async { Err(NetworkError::ConnectionClosed) }.boxed()
+    }
+
+    fn send_datagram(&self, _id: u32, _data: Bytes) -> BoxFuture<Result<(), NetworkError>> {
+        async { Err(NetworkError::ConnectionClosed) }.boxed()
+    }
+}
+
+/// Plays a recorded session back into a client world
+#[derive(Debug)]
+pub struct SessionReplay {
+    server_info: ServerInfo,
+    records: Vec<ReplayRecord>,
+    /// The index of the next record to play
+    next: usize,
+    time: Duration,
+    /// How fast the recording is played back, 1 being real time
+    pub speed: f32,
+    pub paused: bool,
+    /// The player whose messages are delivered to the client world
+    pub user_id: Option<String>,
+}
+
+impl SessionReplay {
+    /// Reads a whole recording.
+    ///
+    /// This registers the external components of the recorded server, which are needed to read
+    /// the diffs.
+    pub fn read(mut reader: impl Read) -> anyhow::Result<Self> {
+        let mut magic = [0u8; 8];
+        reader
+            .read_exact(&mut magic)
+            .context("Failed to read the recording header")?;
+        if &magic != MAGIC {
+            bail!("Not a session recording");
+        }
+
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            bail!("Unsupported recording format version {version}, expected {FORMAT_VERSION}");
+        }
+
+        let server_info: ServerInfo = match read_frame(&mut reader)? {
+            Some(bytes) => bincode::deserialize(&bytes)?,
+            None => bail!("The recording has no header"),
+        };
+        ComponentRegistry::get_mut().add_external(server_info.external_components.clone());
+
+        let mut records = Vec::new();
+        loop {
+            match read_frame(&mut reader) {
+                Ok(Some(bytes)) => {
+                    let record = bincode::deserialize(&bytes)
+                        .with_context(|| format!("Failed to read record {}", records.len()))?;
+                    records.push(record);
+                }
+                Ok(None) => break,
+                // A server which did not shut down cleanly may have left a partial record
+                Err(err) if !records.is_empty() => {
+                    tracing::warn!("Ignoring the end of the recording: {err:#}");
+                    break;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        let user_id = records.iter().find_map(|record| match &record.event {
+            ReplayEvent::Message { user_id, .. } => Some(user_id.clone()),
+            ReplayEvent::Diff(_) => None,
+        });
+
+        Ok(Self {
+            server_info,
+            records,
+            next: 0,
+            time: Duration::ZERO,
+            speed: 1.0,
+            paused: false,
+            user_id,
+        })
+    }
+
+    pub fn server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
+
+    /// The ids of the players which were sent messages during the recording
+    pub fn user_ids(&self) -> Vec<&str> {
+        let mut user_ids = Vec::new();
+        for record in &self.records {
+            if let ReplayEvent::Message { user_id, .. } = &record.event {
+                if !user_ids.contains(&user_id.as_str()) {
+                    user_ids.push(user_id.as_str());
+                }
+            }
+        }
+        user_ids
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.records
+            .last()
+            .map(|record| record.time)
+            .unwrap_or_default()
+    }
+
+    /// The current playback position
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.records.len()
+    }
+
+    /// Advances the playback by `dt` of real time, applying the diffs and delivering the messages
+    /// recorded in between
+    pub fn update(&mut self, world: &mut World, dt: Duration) {
+        if self.paused {
+            return;
+        }
+
+        let time = (self.time + dt.mul_f32(self.speed)).min(self.duration());
+        self.play_until(world, time, true);
+    }
+
+    /// Jumps to `time`.
+    ///
+    /// Only the world is brought up to date; the messages recorded in between are skipped.
+    /// Seeking backwards replays the recording from the start.
+    pub fn seek(&mut self, world: &mut World, time: Duration) {
+        let time = time.min(self.duration());
+        if time < self.time {
+            let remote_entities = query(())
+                .incl(is_remote_entity())
+                .iter(world, None)
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>();
+            for id in remote_entities {
+                world.despawn(id);
+            }
+            self.next = 0;
+        }
+
+        self.play_until(world, time, false);
+    }
+
+    fn play_until(&mut self, world: &mut World, time: Duration, deliver_messages: bool) {
+        while let Some(record) = self.records.get(self.next).filter(|r| r.time <= time) {
+            match &record.event {
+                ReplayEvent::Diff(diff) => apply_remote_diff(world, diff.clone()),
+                ReplayEvent::Message {
+                    user_id,
+                    kind,
+                    id,
+                    payload,
+                } if deliver_messages && self.user_id.as_ref() == Some(user_id) => {
+                    deliver_message(world, *kind, *id, payload)
+                }
+                ReplayEvent::Message { .. } => {}
+            }
+            self.next += 1;
+        }
+
+        self.time = time;
+    }
+}
+
+fn deliver_message(world: &mut World, kind: MessageKind, id: u32, payload: &[u8]) {
+    let handlers = match kind {
+        MessageKind::Datagram => datagram_handlers(),
+        MessageKind::UniStream => replayed_uni_stream_handlers(),
+    };
+    let Some((name, handler)) = world
+        .resource_opt(handlers)
+        .and_then(|handlers| handlers.get(&id))
+        .cloned()
+    else {
+        tracing::warn!(?kind, id, "No handler for replayed message");
+        return;
+    };
+
+    let _span = tracing::debug_span!("handle_replayed", name, id).entered();
+    let assets = world.resource(asset_cache()).clone();
+    handler(world, assets, Bytes::copy_from_slice(payload));
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_core::{abs_time, name, transform::translation};
+    use ambient_ecs::{ArchetypeFilter, Entity, WorldStream};
+    use glam::Vec3;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn init() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        crate::init_all_components();
+    }
+
+    #[test]
+    fn record_and_replay() {
+        init();
+        let filter = WorldStreamFilter::new(ArchetypeFilter::new(), Arc::new(|_, _| true));
+        let mut server = World::new_with_config("test", false);
+        let mut stream = WorldStream::new(filter.clone());
+        let buffer = SharedBuffer::default();
+        let mut recorder = SessionRecorder::new(
+            buffer.clone(),
+            AbsAssetUrl::parse("http://localhost/content/").unwrap(),
+        );
+
+        let id = Entity::new()
+            .with(name(), "Box".to_string())
+            .with(translation(), Vec3::ZERO)
+            .spawn(&mut server);
+        recorder.record_diff(&server, &filter, &stream.next_diff(&server));
+        // Messages are kept for whichever player they were sent to
+        recorder.record_message("player", MessageKind::Datagram, 1, &[1, 2, 3]);
+
+        server.set(id, translation(), Vec3::X).unwrap();
+        recorder.record_diff(&server, &filter, &stream.next_diff(&server));
+        // Flushes the rest of the recording
+        drop(recorder);
+
+        let mut replay = SessionReplay::read(&buffer.0.lock()[..]).unwrap();
+        assert_eq!(replay.server_info().project_name, "Ambient");
+        assert_eq!(replay.user_ids(), vec!["player"]);
+        assert_eq!(replay.records.len(), 3);
+
+        let mut client = World::new_with_config("test", true);
+        client.add_resource(abs_time(), Duration::ZERO);
+
+        replay.seek(&mut client, Duration::ZERO);
+        assert_eq!(client.get_ref(id, name()).unwrap(), "Box");
+        assert!(client.has_component(id, is_remote_entity()));
+
+        replay.seek(&mut client, replay.duration());
+        assert!(replay.is_finished());
+        assert_eq!(client.get(id, translation()).unwrap(), Vec3::X);
+
+        // Seeking backwards plays the recording again from the start
+        replay.seek(&mut client, Duration::ZERO);
+        assert_eq!(client.get(id, translation()).unwrap(), Vec3::ZERO);
+    }
+
+    #[test]
+    fn oversized_records_are_rejected() {
+        let len = (MAX_FRAME_SIZE as u32 + 1).to_le_bytes();
+        assert!(read_frame(&mut &len[..]).is_err());
+    }
+
+    #[test]
+    fn playback_speed() {
+        init();
+        let mut replay = SessionReplay {
+            server_info: ServerInfo {
+                project_name: "Ambient".into(),
+                content_base_url: AbsAssetUrl::parse("http://localhost/content/").unwrap(),
+                version: String::new(),
+                external_components: Vec::new(),
+            },
+            records: [0, 100, 200, 300]
+                .into_iter()
+                .map(|ms| ReplayRecord {
+                    time: Duration::from_millis(ms),
+                    event: ReplayEvent::Diff(WorldDiff::new()),
+                })
+                .collect(),
+            next: 0,
+            time: Duration::ZERO,
+            speed: 2.0,
+            paused: false,
+            user_id: None,
+        };
+
+        let mut client = World::new_with_config("test", true);
+        client.add_resource(abs_time(), Duration::ZERO);
+
+        replay.update(&mut client, Duration::from_millis(60));
+        assert_eq!(replay.time(), Duration::from_millis(120));
+        assert_eq!(replay.next, 2);
+
+        replay.paused = true;
+        replay.update(&mut client, Duration::from_millis(60));
+        assert_eq!(replay.next, 2);
+
+        // Playback stops at the end of the recording
+        replay.paused = false;
+        replay.update(&mut client, Duration::from_secs(10));
+        assert_eq!(replay.time(), replay.duration());
+        assert!(replay.is_finished());
+    }
+}
@@ -1,14 +1,16 @@
 use std::collections::HashMap;
 
-use ambient_ecs::{query, Entity, System, WorldDiff};
+use ambient_ecs::{query, Entity, EntityId, System, WorldDiff};
 use ambient_rpc::RpcRegistry;
 use ambient_std::friendly_id;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    interest,
     server::{
-        create_player_entity_data, player_connection, player_connection_id, player_entity_stream,
-        ForkingEvent, RpcArgs as ServerRpcArgs, WorldInstance, MAIN_INSTANCE_ID,
+        create_player_entity_data, encode_player_diff, player_connection, player_connection_id,
+        player_diff_encoder, player_entity_stream, player_interest, session_recorder, ForkingEvent,
+        RpcArgs as ServerRpcArgs, WorldInstance, MAIN_INSTANCE_ID,
     },
     ServerWorldExt,
 };
@@ -60,6 +62,10 @@ pub async fn rpc_fork_instance(
             for (id, _) in query(user_id()).collect_cloned(&world, None) {
                 world.despawn(id);
             }
+            // Only the main instance is recorded
+            world
+                .remove_component(world.resource_entity(), session_recorder())
+                .ok();
             world
                 .add_components(
                     world.resource_entity(),
@@ -76,7 +82,7 @@ pub async fn rpc_fork_instance(
             world.reset_events();
 
             WorldInstance {
-                systems: (state.create_server_systems)(&mut world),
+                systems: (state.create_server_systems)(&id, &mut world),
                 world,
                 world_stream: instance.world_stream.clone(),
             }
@@ -100,27 +106,14 @@ pub async fn rpc_join_instance(args: ServerRpcArgs, new_instance_id: String) {
         .unwrap()
         .broadcast_diffs();
 
-    // Borrow both worlds immutably to extract the old world's player count and the diff between the two, and
-    // to broadcast the latest diffs for the new instance.
-    let (old_player_count, diff) = {
-        let (old_instance, new_instance) = instances
-            .get(&old_instance_id)
-            .zip(instances.get(&new_instance_id))
-            .unwrap();
-        (
-            old_instance.player_count(),
-            WorldDiff::from_a_to_b(
-                old_instance.world_stream.filter().clone(),
-                &old_instance.world,
-                &new_instance.world,
-            ),
-        )
-    };
+    let old_player_count = instances.get(&old_instance_id).unwrap().player_count();
 
     // Borrow the old world mutably to remove the player and their streams.
     let entities_tx;
     let connection_id;
     let conn;
+    let diff_encoder;
+    let known_entities: Vec<EntityId>;
 
     {
         let mut ed = instances
@@ -132,21 +125,36 @@ pub async fn rpc_join_instance(args: ServerRpcArgs, new_instance_id: String) {
 
         connection_id = ed.remove_self(player_connection_id()).unwrap();
         conn = ed.remove_self(player_connection()).unwrap();
+        // The client keeps decoding the diffs of the new instance on the same stream
+        diff_encoder = ed.remove_self(player_diff_encoder()).unwrap();
+        known_entities = ed
+            .get_ref(player_interest())
+            .map(|interest| interest.lock().known_entities().collect())
+            .unwrap_or_default();
     };
 
     // Borrow the new world mutably to spawn the player in with their old streams.
-    instances
-        .get_mut(&new_instance_id)
-        .unwrap()
-        .spawn_player(create_player_entity_data(
+    let new_instance = instances.get_mut(&new_instance_id).unwrap();
+    let id = new_instance.spawn_player(
+        create_player_entity_data(
             conn,
             args.user_id.clone(),
             entities_tx.clone(),
             connection_id,
-        ));
+        )
+        .with(player_diff_encoder(), diff_encoder),
+    );
+
+    // Replace everything the player knew about in the old instance with what is relevant to them
+    // in the new one
+    let mut diff = WorldDiff::new().despawn(known_entities);
+    diff.changes.extend(
+        interest::initial_diff(&new_instance.world, new_instance.world_stream.filter(), id).changes,
+    );
+
     state.players.get_mut(&args.user_id).unwrap().instance = new_instance_id.to_string();
 
-    let msg = bincode::serialize(&diff).unwrap().into();
+    let msg = encode_player_diff(&new_instance.world, id, &diff);
     entities_tx.send(msg).ok();
 
     // Remove old instance
@@ -1,7 +1,17 @@
-use std::{collections::HashMap, fmt::Debug, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use crate::{
-    client::ClientConnection, proto::server::Player, DynRecv, DynSend, NetworkError,
+    auth::ConnectionVerifier, client::ClientConnection, codec::Compression,
+    diff_codec::DiffEncoder, interest::PlayerInterest, link_conditioner::LinkConditions,
+    proto::server::Player, replay::SessionRecorder, tick, DynRecv, DynSend, NetworkError,
     RPC_BISTREAM_ID,
 };
 use ambient_core::{
@@ -9,14 +19,14 @@ use ambient_core::{
     player::{get_by_user_id, player},
 };
 use ambient_ecs::{
-    components, dont_store, query, ArchetypeFilter, Entity, EntityId, FrameEvent, Networked,
-    Resource, System, SystemGroup, World, WorldStream, WorldStreamFilter,
+    components, dont_store, query, ArchetypeFilter, ComponentRegistry, Entity, EntityId,
+    FrameEvent, Networked, Resource, System, SystemGroup, World, WorldDiff, WorldStream,
+    WorldStreamFilter,
 };
 use ambient_rpc::RpcRegistry;
 use ambient_std::{
     asset_cache::AssetCache, asset_url::AbsAssetUrl, fps_counter::FpsSample, log_result,
 };
-use ambient_sys::time::SystemTime;
 use bytes::Bytes;
 use flume::Sender;
 use parking_lot::Mutex;
@@ -30,10 +40,29 @@ components!("network::server", {
     uni_stream_handlers: UniStreamHandlers,
     @[Resource]
     datagram_handlers: DatagramHandlers,
+    /// Checks the credentials of connecting clients. Only used on the main instance
+    @[Resource]
+    connection_verifier: Arc<dyn ConnectionVerifier>,
+    /// How the diffs sent to the players are compressed
+    @[Resource]
+    diff_compression: Compression,
+    /// Simulated conditions of the links from the clients to this server. Only used on the main instance
+    @[Resource]
+    link_conditions: LinkConditions,
+    /// How long a player whose connection was lost is kept, waiting for them to reconnect. Only used on the main instance
+    @[Resource]
+    reconnect_grace_period: Duration,
+    /// Records the session to a file for later replay. Only used on the main instance
+    @[Resource]
+    session_recorder: Arc<Mutex<SessionRecorder>>,
 
     player_entity_stream: Sender<Bytes>,
     player_connection_id: Uuid,
     player_connection: Arc<dyn ClientConnection>,
+    /// The entities which have been replicated to the player
+    player_interest: Arc<Mutex<PlayerInterest>>,
+    /// Encodes the diffs sent to the player, which depend on what they have been sent before
+    player_diff_encoder: Arc<Mutex<DiffEncoder>>,
     // synced resource
     @[Networked]
     server_stats: FpsSample,
@@ -87,9 +116,25 @@ pub fn create_player_entity_data(
         .with(player_connection(), conn)
         .with(player_entity_stream(), entities_tx)
         .with(player_connection_id(), connection_id)
+        .with(player_interest(), Default::default())
+        .with(player_diff_encoder(), Default::default())
+        .with(crate::prediction::player_input_queue(), Default::default())
         .with_default(dont_store())
 }
 
+/// Encodes a diff in the format the client of `player` expects
+pub fn encode_player_diff(world: &World, player: EntityId, diff: &WorldDiff) -> Bytes {
+    let compression = world
+        .resource_opt(diff_compression())
+        .copied()
+        .unwrap_or_default();
+    world
+        .get_ref(player, player_diff_encoder())
+        .expect("Player has no diff encoder")
+        .lock()
+        .encode(diff, compression)
+}
+
 pub fn register_rpc_bi_stream_handler(
     handlers: &mut BiStreamHandlers,
     rpc_registry: RpcRegistry<RpcArgs>,
@@ -130,16 +175,33 @@ impl WorldInstance {
     pub fn despawn_player(&mut self, user_id: &str) -> Option<Entity> {
         self.world.despawn(get_by_user_id(&self.world, user_id)?)
     }
+    /// Sends the changes to the world since the last broadcast to every player, filtered down to
+    /// the entities and components which are relevant to each of them
     pub fn broadcast_diffs(&mut self) {
         let diff = self.world_stream.next_diff(&self.world);
+        if let Some(recorder) = self.world.resource_opt(session_recorder()) {
+            recorder
+                .lock()
+                .record_diff(&self.world, self.world_stream.filter(), &diff);
+        }
         if diff.is_empty() {
             return;
         }
-        let msg: Bytes = bincode::serialize(&diff).unwrap().into();
 
         ambient_profiling::scope!("Send MsgEntities");
-        for (_, (entity_stream,)) in query((player_entity_stream(),)).iter(&self.world, None) {
-            if let Err(_err) = entity_stream.send(msg.clone()) {
+        for (id, (entity_stream, interest)) in
+            query((player_entity_stream(), player_interest())).iter(&self.world, None)
+        {
+            let diff =
+                interest
+                    .lock()
+                    .filter_diff(&self.world, self.world_stream.filter(), id, &diff);
+            if diff.is_empty() {
+                continue;
+            }
+
+            let msg = encode_player_diff(&self.world, id, &diff);
+            if let Err(_err) = entity_stream.send(msg) {
                 log::warn!("Failed to broadcast diff to player");
             }
         }
@@ -147,26 +209,40 @@ impl WorldInstance {
     pub fn player_count(&self) -> usize {
         query((player(),)).iter(&self.world, None).count()
     }
-    pub fn step(&mut self, time: Duration) {
-        self.world
-            .set(self.world.resource_entity(), ambient_core::abs_time(), time)
-            .unwrap();
+    /// Runs one tick of the world
+    pub fn step(&mut self) {
+        tick::advance(&mut self.world);
         self.systems.run(&mut self.world, &FrameEvent);
         self.world.next_frame();
     }
+    /// Advances the time of the world by `ticks` which are skipped, without running them
+    pub fn skip(&mut self, ticks: u32) {
+        tick::skip(&mut self.world, ticks);
+    }
 }
 
 pub const MAIN_INSTANCE_ID: &str = "main";
 
+/// How long a player whose connection was lost is kept if [reconnect_grace_period] is not set
+pub const DEFAULT_RECONNECT_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
 pub type SharedServerState = Arc<Mutex<ServerState>>;
 
 pub struct ServerState {
     pub assets: AssetCache,
     pub instances: HashMap<String, WorldInstance>,
     pub players: HashMap<String, Player>,
-    pub create_server_systems: Arc<dyn Fn(&mut World) -> SystemGroup + Sync + Send>,
+    /// Creates the systems of the instance with the given ID
+    pub create_server_systems: Arc<dyn Fn(&str, &mut World) -> SystemGroup + Sync + Send>,
     pub create_on_forking_systems: Arc<dyn Fn() -> SystemGroup<ForkingEvent> + Sync + Send>,
     pub create_shutdown_systems: Arc<dyn Fn() -> SystemGroup<ShutdownEvent> + Sync + Send>,
+    /// Set when components are registered while the server runs, such as when a mod is loaded
+    external_components_changed: Arc<AtomicBool>,
+    /// Sets [Self::external_components_changed]; registered with the [ComponentRegistry] until
+    /// this is dropped
+    on_external_components_change: Option<Arc<dyn Fn() + Sync + Send>>,
+    /// The paths of the components which were already sent to the players
+    pushed_external_components: HashSet<String>,
 }
 
 impl ServerState {
@@ -185,18 +261,30 @@ impl ServerState {
             )]
             .into(),
             players: Default::default(),
-            create_server_systems: Arc::new(|_| SystemGroup::new("", vec![])),
+            create_server_systems: Arc::new(|_, _| SystemGroup::new("", vec![])),
             create_on_forking_systems: Arc::new(|| SystemGroup::new("", vec![])),
             create_shutdown_systems: Arc::new(|| SystemGroup::new("", vec![])),
+            external_components_changed: Default::default(),
+            on_external_components_change: None,
+            pushed_external_components: Default::default(),
         }
     }
     pub fn new(
         assets: AssetCache,
         instances: HashMap<String, WorldInstance>,
-        create_server_systems: Arc<dyn Fn(&mut World) -> SystemGroup + Sync + Send>,
+        create_server_systems: Arc<dyn Fn(&str, &mut World) -> SystemGroup + Sync + Send>,
         create_on_forking_systems: Arc<dyn Fn() -> SystemGroup<ForkingEvent> + Sync + Send>,
         create_shutdown_systems: Arc<dyn Fn() -> SystemGroup<ShutdownEvent> + Sync + Send>,
     ) -> Self {
+        let external_components_changed = Arc::new(AtomicBool::new(false));
+        let on_external_components_change: Arc<dyn Fn() + Sync + Send> = Arc::new({
+            let changed = external_components_changed.clone();
+            move || changed.store(true, Ordering::Relaxed)
+        });
+        ComponentRegistry::get_mut()
+            .on_external_components_change
+            .add(on_external_components_change.clone());
+
         Self {
             assets,
             instances,
@@ -204,15 +292,77 @@ impl ServerState {
             create_server_systems,
             create_on_forking_systems,
             create_shutdown_systems,
+            external_components_changed,
+            on_external_components_change: Some(on_external_components_change),
+            pushed_external_components: Default::default(),
         }
     }
 
+    /// Runs one tick of every instance
     pub fn step(&mut self) {
-        let time = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap();
+        self.push_external_components();
         for instance in self.instances.values_mut() {
-            instance.step(time);
+            instance.step();
+        }
+        self.despawn_lost_players();
+    }
+    /// Skips `ticks` of every instance, which the server is too far behind to run
+    pub fn skip(&mut self, ticks: u32) {
+        for instance in self.instances.values_mut() {
+            instance.skip(ticks);
+        }
+    }
+    /// Sends the components registered since the last step to the connected players.
+    ///
+    /// This happens before the instances step, so that the players know of the components before
+    /// they receive any entities which use them.
+    fn push_external_components(&mut self) {
+        if !self
+            .external_components_changed
+            .swap(false, Ordering::Relaxed)
+        {
+            return;
+        }
+        // A client in the same process registers the components again when it receives them,
+        // so only the components which were not sent yet are sent
+        let components = ComponentRegistry::get()
+            .all_external()
+            .map(|(desc, _)| desc)
+            .filter(|desc| self.pushed_external_components.insert(desc.path.clone()))
+            .collect::<Vec<_>>();
+        if components.is_empty() {
+            return;
+        }
+        for player in self.players.values() {
+            player.push_external_components(components.clone());
+        }
+    }
+    /// Despawns the players whose connection was lost longer ago than the reconnection grace period
+    fn despawn_lost_players(&mut self) {
+        let grace_period = self
+            .instances
+            .get(MAIN_INSTANCE_ID)
+            .and_then(|instance| instance.world.resource_opt(reconnect_grace_period()))
+            .copied()
+            .unwrap_or(DEFAULT_RECONNECT_GRACE_PERIOD);
+
+        let expired = self
+            .players
+            .iter()
+            .filter(|(_, player)| {
+                player
+                    .lost_at
+                    .map_or(false, |lost_at| lost_at.elapsed() >= grace_period)
+            })
+            .map(|(user_id, _)| user_id.clone())
+            .collect::<Vec<_>>();
+
+        for user_id in expired {
+            log::info!("Player {user_id} did not reconnect in time");
+            let player = self.players.remove(&user_id).unwrap();
+            if let Some(instance) = self.instances.get_mut(&player.instance) {
+                instance.despawn_player(&user_id);
+            }
         }
     }
     pub fn broadcast_diffs(&mut self) {
@@ -248,6 +398,15 @@ impl ServerState {
         self.instances.remove(instance_id);
     }
 }
+impl Drop for ServerState {
+    fn drop(&mut self) {
+        if let Some(handler) = self.on_external_components_change.take() {
+            ComponentRegistry::get_mut()
+                .on_external_components_change
+                .remove(handler);
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ProxySettings {
@@ -256,3 +415,107 @@ pub struct ProxySettings {
     pub pre_cache_assets: bool,
     pub project_id: String,
 }
+
+#[cfg(test)]
+mod test {
+    use ambient_core::player::user_id;
+    use ambient_sys::time::Instant;
+
+    use super::*;
+
+    fn state(grace_period: Duration) -> (tokio::runtime::Runtime, ServerState) {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        crate::init_all_components();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut state = ServerState::new_local(AssetCache::new(runtime.handle().clone()));
+        state
+            .instances
+            .get_mut(MAIN_INSTANCE_ID)
+            .unwrap()
+            .world
+            .add_resource(reconnect_grace_period(), grace_period);
+        (runtime, state)
+    }
+
+    /// Spawns the player of `user`, whose connection was lost `lost_for` ago
+    fn spawn_lost_player(state: &mut ServerState, user: &str, lost_for: Duration) -> EntityId {
+        let id = state
+            .instances
+            .get_mut(MAIN_INSTANCE_ID)
+            .unwrap()
+            .spawn_player(
+                Entity::new()
+                    .with(player(), ())
+                    .with(user_id(), user.to_string()),
+            );
+        let mut lost = Player::new_local(MAIN_INSTANCE_ID);
+        lost.lost_at = Some(Instant::now() - lost_for);
+        state.players.insert(user.to_string(), lost);
+        id
+    }
+
+    #[test]
+    fn lost_players_are_despawned_after_the_grace_period() {
+        let (_runtime, mut state) = state(Duration::from_secs(10));
+        let waiting = spawn_lost_player(&mut state, "alice", Duration::from_secs(5));
+        let expired = spawn_lost_player(&mut state, "bob", Duration::from_secs(15));
+
+        state.despawn_lost_players();
+
+        let world = &state.instances[MAIN_INSTANCE_ID].world;
+        assert!(world.exists(waiting));
+        assert!(state.players.contains_key("alice"));
+        assert!(!world.exists(expired));
+        assert!(!state.players.contains_key("bob"));
+    }
+
+    #[test]
+    fn players_which_reconnect_in_time_are_kept() {
+        let (_runtime, mut state) = state(Duration::from_secs(10));
+        let id = spawn_lost_player(&mut state, "alice", Duration::from_secs(5));
+        state.despawn_lost_players();
+        assert!(state.players.contains_key("alice"));
+
+        // Reconnecting replaces the player with one whose connection is not lost, so the player
+        // is kept even once the grace period is over
+        state
+            .players
+            .insert("alice".to_string(), Player::new_local(MAIN_INSTANCE_ID));
+        let world = &mut state.instances.get_mut(MAIN_INSTANCE_ID).unwrap().world;
+        world.add_resource(reconnect_grace_period(), Duration::ZERO);
+        state.despawn_lost_players();
+
+        let world = &state.instances[MAIN_INSTANCE_ID].world;
+        assert_eq!(get_by_user_id(world, "alice"), Some(id));
+        assert!(state.players.contains_key("alice"));
+    }
+
+    #[test]
+    fn dropped_states_stop_listening_for_new_components() {
+        let (runtime, _) = state(Duration::from_secs(10));
+        let handlers = || {
+            ComponentRegistry::get()
+                .on_external_components_change
+                .iter()
+                .count()
+        };
+        let before = handlers();
+
+        let state = ServerState::new(
+            AssetCache::new(runtime.handle().clone()),
+            HashMap::new(),
+            Arc::new(|_, _| SystemGroup::new("", vec![])),
+            Arc::new(|| SystemGroup::new("", vec![])),
+            Arc::new(|| SystemGroup::new("", vec![])),
+        );
+        assert_eq!(handlers(), before + 1);
+
+        drop(state);
+        assert_eq!(handlers(), before);
+    }
+}
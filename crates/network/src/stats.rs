@@ -0,0 +1,201 @@
+//! Statistics about the connections, and the bandwidth spent on the world diffs.
+//!
+//! Every [STATS_INTERVAL], the server writes the statistics of the connection of each player to
+//! their player entity, and each client writes its own to its resource entity, using the same
+//! components on both sides. The bytes of the diffs are broken down by the components and the
+//! entities they were spent on, before compression, to find what uses the bandwidth.
+
+use std::{collections::HashMap, time::Duration};
+
+use ambient_ecs::{ComponentDesc, EntityId, World};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::client::NetworkStats;
+
+pub use ambient_ecs::generated::components::core::network::{
+    bytes_received_per_second, bytes_sent_per_second, diff_bytes_per_second, diff_component_bytes,
+    diff_component_paths, diff_entities, diff_entity_bytes, packet_loss, rtt,
+};
+
+/// How often the statistics of a connection are updated
+pub const STATS_INTERVAL: Duration = Duration::from_secs(1);
+/// The most components and entities which are listed in a breakdown
+const MAX_BREAKDOWN: usize = 32;
+
+/// The bytes of the diffs sent or received on a connection, before compression
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffBandwidth {
+    pub bytes: u64,
+    /// The bytes of the values of each component, including the reference to the component
+    pub components: HashMap<ComponentDesc, u64>,
+    /// The bytes of the changes to each entity, including the values of its components
+    pub entities: HashMap<EntityId, u64>,
+}
+
+impl DiffBandwidth {
+    pub fn add_component(&mut self, desc: ComponentDesc, bytes: usize) {
+        *self.components.entry(desc).or_default() += bytes as u64;
+    }
+
+    pub fn add_entity(&mut self, id: EntityId, bytes: usize) {
+        *self.entities.entry(id).or_default() += bytes as u64;
+    }
+}
+
+/// The statistics of a connection over the last [STATS_INTERVAL], in a form which can be sent
+/// over the network
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkReport {
+    pub stats: NetworkStats,
+    pub diff_bytes_per_second: u64,
+    /// The component paths with the most bytes per second, in descending order
+    pub components: Vec<(String, u64)>,
+    /// The entities with the most bytes per second, in descending order
+    pub entities: Vec<(EntityId, u64)>,
+}
+
+impl NetworkReport {
+    /// Converts the bandwidth measured over `interval` to rates
+    pub fn new(stats: NetworkStats, bandwidth: &DiffBandwidth, interval: Duration) -> Self {
+        let seconds = interval.as_secs_f64().max(f64::EPSILON);
+        let per_second = |bytes: u64| (bytes as f64 / seconds).round() as u64;
+
+        Self {
+            stats,
+            diff_bytes_per_second: per_second(bandwidth.bytes),
+            components: top(&bandwidth.components)
+                .map(|(desc, bytes)| (desc.path(), per_second(bytes)))
+                .collect(),
+            entities: top(&bandwidth.entities)
+                .map(|(id, bytes)| (id, per_second(bytes)))
+                .collect(),
+        }
+    }
+
+    /// Reads the report written to `id`, if any
+    pub fn read(world: &World, id: EntityId) -> Option<Self> {
+        let stats = NetworkStats {
+            latency_ms: world.get(id, rtt()).ok()?.round() as u64,
+            packet_loss: world.get(id, packet_loss()).unwrap_or_default(),
+            bytes_sent: world.get(id, bytes_sent_per_second()).unwrap_or_default(),
+            bytes_received: world
+                .get(id, bytes_received_per_second())
+                .unwrap_or_default(),
+        };
+        let zip = |a: Vec<_>, b: Vec<u64>| a.into_iter().zip(b).collect_vec();
+
+        Some(Self {
+            stats,
+            diff_bytes_per_second: world.get(id, diff_bytes_per_second()).unwrap_or_default(),
+            components: zip(
+                world
+                    .get_cloned(id, diff_component_paths())
+                    .unwrap_or_default(),
+                world
+                    .get_cloned(id, diff_component_bytes())
+                    .unwrap_or_default(),
+            ),
+            entities: zip(
+                world.get_cloned(id, diff_entities()).unwrap_or_default(),
+                world
+                    .get_cloned(id, diff_entity_bytes())
+                    .unwrap_or_default(),
+            ),
+        })
+    }
+
+    /// Writes the report to the components of `id`
+    pub fn write(&self, world: &mut World, id: EntityId) {
+        let (paths, component_bytes) = self.components.iter().cloned().unzip();
+        let (entities, entity_bytes) = self.entities.iter().copied().unzip();
+
+        world
+            .add_components(
+                id,
+                ambient_ecs::Entity::new()
+                    .with(rtt(), self.stats.latency_ms as f32)
+                    .with(packet_loss(), self.stats.packet_loss)
+                    .with(bytes_sent_per_second(), self.stats.bytes_sent)
+                    .with(bytes_received_per_second(), self.stats.bytes_received)
+                    .with(diff_bytes_per_second(), self.diff_bytes_per_second)
+                    .with(diff_component_paths(), paths)
+                    .with(diff_component_bytes(), component_bytes)
+                    .with(diff_entities(), entities)
+                    .with(diff_entity_bytes(), entity_bytes),
+            )
+            .ok();
+    }
+}
+
+fn top<K: Copy>(bytes: &HashMap<K, u64>) -> impl Iterator<Item = (K, u64)> + '_ {
+    bytes
+        .iter()
+        .map(|(&key, &bytes)| (key, bytes))
+        .sorted_by(|a, b| b.1.cmp(&a.1))
+        .take(MAX_BREAKDOWN)
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_core::{name, transform::translation};
+
+    use super::*;
+
+    fn init() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        crate::init_all_components();
+    }
+
+    #[test]
+    fn report_rates() {
+        init();
+
+        let mut bandwidth = DiffBandwidth {
+            bytes: 1000,
+            ..Default::default()
+        };
+        bandwidth.add_component(translation().desc(), 300);
+        bandwidth.add_component(name().desc(), 100);
+        bandwidth.add_component(translation().desc(), 300);
+        bandwidth.add_entity(EntityId(1), 200);
+        bandwidth.add_entity(EntityId(2), 800);
+
+        let report =
+            NetworkReport::new(NetworkStats::default(), &bandwidth, Duration::from_secs(2));
+        assert_eq!(report.diff_bytes_per_second, 500);
+        assert_eq!(
+            report.components,
+            vec![(translation().path(), 300), (name().path(), 50),]
+        );
+        assert_eq!(
+            report.entities,
+            vec![(EntityId(2), 400), (EntityId(1), 100)]
+        );
+    }
+
+    #[test]
+    fn write_and_read() {
+        init();
+
+        let report = NetworkReport {
+            stats: NetworkStats {
+                latency_ms: 42,
+                packet_loss: 0.25,
+                bytes_sent: 100,
+                bytes_received: 2000,
+            },
+            diff_bytes_per_second: 1500,
+            components: vec![(translation().path(), 1000), (name().path(), 500)],
+            entities: vec![(EntityId(7), 1500)],
+        };
+
+        let mut world = World::new("test");
+        let id = world.resource_entity();
+        assert_eq!(NetworkReport::read(&world, id), None);
+
+        report.write(&mut world, id);
+        assert_eq!(NetworkReport::read(&world, id), Some(report));
+    }
+}
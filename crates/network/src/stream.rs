@@ -57,6 +57,35 @@ where
     }
 }
 
+/// Transport agnostic framed reader, which returns the frames without deserializing them
+#[pin_project]
+pub struct RawFramedRecvStream<S> {
+    #[pin]
+    read: FramedRead<S, LengthDelimitedCodec>,
+}
+
+impl<S: AsyncRead> RawFramedRecvStream<S> {
+    pub fn new(stream: S) -> Self {
+        let mut codec = LengthDelimitedCodec::new();
+        codec.set_max_frame_length(MAX_FRAME_SIZE);
+        Self {
+            read: FramedRead::new(stream, codec),
+        }
+    }
+}
+
+impl<S: AsyncRead> Stream for RawFramedRecvStream<S> {
+    type Item = Result<Bytes, FrameError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let bytes = ready!(self.project().read.poll_next(cx)?);
+        Poll::Ready(bytes.map(|v| Ok(v.freeze())))
+    }
+}
+
 /// Transport agnostic framed writer
 #[pin_project]
 pub struct FramedSendStream<T, S> {
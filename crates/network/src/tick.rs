@@ -0,0 +1,183 @@
+//! The fixed timestep of the server.
+//!
+//! The server steps its worlds in ticks of exactly `1 / tick_rate` seconds, numbered by [tick].
+//! `abs_time` and `dtime` advance by the same amount every tick, regardless of when the tick
+//! actually runs, so that the simulation only depends on the number of ticks and the inputs
+//! applied in each of them. When the server falls behind, it runs several ticks in a row to catch
+//! up, up to [MAX_CATCH_UP_TICKS]. Any further ticks are skipped: `abs_time` still advances by
+//! them, so that it keeps up with the wall clock, but no systems are run for them.
+
+use std::time::Duration;
+
+use ambient_core::{abs_time, dtime};
+use ambient_ecs::World;
+use ambient_sys::time::Instant;
+
+use crate::ServerWorldExt;
+
+pub use ambient_ecs::generated::components::core::network::{input_tick, tick, tick_rate};
+
+/// The tick rate of the server if [tick_rate] is not set, in ticks per second
+pub const DEFAULT_TICK_RATE: f32 = 60.;
+/// The most ticks which are run at once to catch up; any further ticks are skipped
+pub const MAX_CATCH_UP_TICKS: u32 = 10;
+
+/// The tick of the latest state of the server. On the server, this is the tick which was last run
+pub fn current_tick(world: &World) -> u64 {
+    world.synced_resource(tick()).copied().unwrap_or_default()
+}
+
+/// How long one tick of the server lasts
+pub fn tick_duration(world: &World) -> Duration {
+    let rate = world
+        .synced_resource(tick_rate())
+        .copied()
+        .filter(|rate| rate.is_finite() && *rate > 0.)
+        .unwrap_or(DEFAULT_TICK_RATE);
+    Duration::from_secs_f32(1. / rate)
+}
+
+/// Advances the tick and the time of the world by one tick, before its systems are run
+pub(crate) fn advance(world: &mut World) {
+    let duration = tick_duration(world);
+    let id = world.resource_entity();
+    let time = *world.resource(abs_time()) + duration;
+    world.set(id, abs_time(), time).unwrap();
+    world.set(id, dtime(), duration.as_secs_f32()).unwrap();
+
+    if let Some(id) = world.synced_resource_entity() {
+        let next = current_tick(world) + 1;
+        world.add_component(id, tick(), next).unwrap();
+    }
+}
+
+/// Advances the time of the world by `count` ticks which were skipped, without running them
+pub(crate) fn skip(world: &mut World, count: u32) {
+    let id = world.resource_entity();
+    let time = *world.resource(abs_time()) + tick_duration(world) * count;
+    world.set(id, abs_time(), time).unwrap();
+}
+
+/// The ticks which are due, as decided by [TickClock::advance]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DueTicks {
+    /// The ticks to run
+    pub run: u32,
+    /// The ticks which the server is too far behind to run
+    pub skipped: u32,
+}
+
+/// Decides when the ticks of the server are due
+#[derive(Debug, Clone)]
+pub struct TickClock {
+    duration: Duration,
+    next_at: Instant,
+}
+
+impl TickClock {
+    pub fn new(duration: Duration, now: Instant) -> Self {
+        Self {
+            duration,
+            next_at: now + duration,
+        }
+    }
+
+    /// When the next tick is due
+    pub fn next_at(&self) -> Instant {
+        self.next_at
+    }
+
+    /// Returns how many ticks are due at `now`, and schedules the next one.
+    ///
+    /// If more than [MAX_CATCH_UP_TICKS] are due, the rest are skipped, and the clock is reset
+    /// to tick from `now` on.
+    pub fn advance(&mut self, now: Instant) -> DueTicks {
+        if now < self.next_at {
+            return DueTicks::default();
+        }
+
+        let due = ((now - self.next_at).as_secs_f64() / self.duration.as_secs_f64()) as u64 + 1;
+        if due > MAX_CATCH_UP_TICKS as u64 {
+            tracing::warn!(
+                "The server is {due} ticks behind; skipping {} ticks",
+                due - MAX_CATCH_UP_TICKS as u64
+            );
+            self.next_at = now + self.duration;
+            return DueTicks {
+                run: MAX_CATCH_UP_TICKS,
+                skipped: (due - MAX_CATCH_UP_TICKS as u64).min(u32::MAX as u64) as u32,
+            };
+        }
+
+        self.next_at = self.next_at + self.duration * due as u32;
+        DueTicks {
+            run: due as u32,
+            skipped: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_ecs::Entity;
+
+    use super::*;
+    use crate::synced_resources;
+
+    fn init() -> World {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        crate::init_all_components();
+
+        let mut world = World::new_with_config("test", false);
+        world.add_resource(abs_time(), Duration::from_secs(100));
+        world.add_resource(dtime(), 0.);
+        Entity::new()
+            .with(synced_resources(), ())
+            .with(tick_rate(), 20.)
+            .spawn(&mut world);
+        world
+    }
+
+    #[test]
+    fn advance_is_fixed() {
+        let mut world = init();
+        for _ in 0..4 {
+            advance(&mut world);
+        }
+
+        assert_eq!(current_tick(&world), 4);
+        assert!((*world.resource(dtime()) - 0.05).abs() < 1e-6);
+        let time = *world.resource(abs_time()) - Duration::from_secs(100);
+        assert!((time.as_secs_f32() - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn clock_catches_up() {
+        let duration = Duration::from_millis(10);
+        let start = Instant::now();
+        let mut clock = TickClock::new(duration, start);
+
+        let due = |run, skipped| DueTicks { run, skipped };
+        assert_eq!(clock.advance(start + Duration::from_millis(5)), due(0, 0));
+        assert_eq!(clock.advance(start + Duration::from_millis(10)), due(1, 0));
+        assert_eq!(clock.advance(start + Duration::from_millis(45)), due(3, 0));
+        assert_eq!(clock.next_at(), start + Duration::from_millis(50));
+
+        // Too far behind to catch up
+        let late = start + Duration::from_secs(1);
+        assert_eq!(clock.advance(late), due(MAX_CATCH_UP_TICKS, 86));
+        assert_eq!(clock.next_at(), late + duration);
+    }
+
+    #[test]
+    fn skipped_ticks_advance_time() {
+        let mut world = init();
+        advance(&mut world);
+        skip(&mut world, 3);
+
+        assert_eq!(current_tick(&world), 1);
+        let time = *world.resource(abs_time()) - Duration::from_secs(100);
+        assert!((time.as_secs_f32() - 0.2).abs() < 1e-6);
+    }
+}
@@ -24,7 +24,7 @@ use crate::{
         ClientRequest,
     },
     server::RpcArgs,
-    stream::{FramedRecvStream, FramedSendStream},
+    stream::{FramedRecvStream, FramedSendStream, RawFramedRecvStream},
     web::WebTransportProxy,
     webtransport::Connection,
     NetworkError,
@@ -37,6 +37,8 @@ pub struct GameClientView {
     /// The url to connect to
     pub url: Url,
     pub user_id: String,
+    /// Presented to the server's connection verifier, if any
+    pub credential: Option<String>,
     pub systems_and_resources: Cb<dyn Fn() -> (SystemGroup, Entity) + Sync + Send>,
     /// Invoked when the game client is loaded
     ///
@@ -52,6 +54,7 @@ impl ElementComponent for GameClientView {
         let Self {
             url,
             user_id,
+            credential,
             systems_and_resources,
             on_loaded,
             create_rpc_registry,
@@ -118,6 +121,7 @@ impl ElementComponent for GameClientView {
                     game_client,
                     conn,
                     user_id,
+                    credential,
                     cb(move |game_client| {
                         let game_state = &game_client.game_state;
                         {
@@ -218,6 +222,7 @@ async fn handle_connection(
     game_client: GameClient,
     mut conn: Connection,
     user_id: String,
+    credential: Option<String>,
     on_loaded: LoadedFunc,
     state: SharedClientState,
     control_rx: flume::Receiver<Control>,
@@ -239,7 +244,10 @@ async fn handle_connection(
     tracing::info!("Attempting to connect using {user_id:?}");
 
     request_send
-        .send(ClientRequest::Connect(user_id.clone()))
+        .send(ClientRequest::Connect {
+            user_id: user_id.clone(),
+            credential,
+        })
         .await?;
 
     let mut client = ClientState::Connecting(user_id);
@@ -260,7 +268,7 @@ async fn handle_connection(
     }
 
     tracing::info!("Accepting diff stream");
-    let mut diff_stream = FramedRecvStream::new(
+    let mut diff_stream = RawFramedRecvStream::new(
         conn.accept_uni()
             .await
             .ok_or(NetworkError::ConnectionClosed)??,
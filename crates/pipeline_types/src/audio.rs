@@ -6,7 +6,19 @@ use crate::is_false;
 #[serde(deny_unknown_fields)]
 pub struct AudioPipeline {
     /// Whether or not the audio should be converted to Ogg Vorbis.
+    ///
+    /// FLAC and MP3 files are always converted.
     #[serde(default)]
     #[serde(skip_serializing_if = "is_false")]
     pub convert: bool,
+    /// The quality of the Ogg Vorbis encoding, from -0.1 (smallest files) to 1.0 (best quality).
+    /// Defaults to 0.9.
+    pub quality: Option<f32>,
+    /// The sample rate to resample converted audio to, in Hz.
+    /// By default, the sample rate of the source file is kept.
+    pub sample_rate: Option<u32>,
+    /// The integrated loudness to normalize converted audio to, in LUFS (e.g. -16).
+    /// The gain is reduced if the peaks of the audio would otherwise exceed -1 dBFS.
+    /// By default, the loudness is left unchanged.
+    pub normalize_loudness: Option<f32>,
 }
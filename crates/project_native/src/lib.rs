@@ -1,24 +1,33 @@
 use ambient_ecs::{
     ExternalComponentAttributes, ExternalComponentDesc, ExternalComponentFlagAttributes,
-    PrimitiveComponentType,
+    PrimitiveComponentType, Quantized,
 };
 
-use ambient_project::{ComponentType, IdentifierPathBuf, Manifest};
+use std::collections::BTreeMap;
+
+use ambient_project::{
+    CamelCaseIdentifier, ComponentType, Enum, IdentifierPathBuf, Manifest, Struct,
+};
 
 pub fn all_defined_components(
     manifest: &Manifest,
     global_namespace: bool,
+) -> Result<Vec<ExternalComponentDesc>, &'static str> {
+    let mut components = defined_components(manifest, global_namespace)?;
+    for dependency in manifest.all_dependencies() {
+        components.append(&mut defined_components(dependency, false)?);
+    }
+    Ok(components)
+}
+
+pub fn defined_components(
+    manifest: &Manifest,
+    global_namespace: bool,
 ) -> Result<Vec<ExternalComponentDesc>, &'static str> {
     let project_path: Vec<_> = if global_namespace {
         vec![]
     } else {
-        manifest
-            .ember
-            .organization
-            .iter()
-            .chain(std::iter::once(&manifest.ember.id))
-            .cloned()
-            .collect()
+        manifest.project_path().to_vec()
     };
 
     manifest
@@ -31,12 +40,26 @@ pub fn all_defined_components(
             );
             Ok(ExternalComponentDesc {
                 path: full_path.to_string(),
-                ty: component_type_to_primitive(&component.type_)?,
+                ty: component_type_to_primitive(
+                    &component.type_,
+                    &manifest.enums,
+                    &manifest.structs,
+                )?,
                 attributes: ExternalComponentAttributes {
                     name: component.name.clone(),
                     description: component.description.clone(),
+                    quantized: component
+                        .attributes
+                        .iter()
+                        .find_map(|s| Quantized::parse(s))
+                        .transpose()?
+                        .map(|q| q.precision),
                     flags: ExternalComponentFlagAttributes::from_iter(
-                        component.attributes.iter().map(|s| s.as_str()),
+                        component
+                            .attributes
+                            .iter()
+                            .map(|s| s.as_str())
+                            .filter(|s| Quantized::parse(s).is_none()),
                     ),
                 },
             })
@@ -44,16 +67,29 @@ pub fn all_defined_components(
         .collect::<Result<Vec<_>, _>>()
 }
 
-fn component_type_to_primitive(ty: &ComponentType) -> Result<PrimitiveComponentType, &'static str> {
+fn component_type_to_primitive(
+    ty: &ComponentType,
+    enums: &BTreeMap<CamelCaseIdentifier, Enum>,
+    structs: &BTreeMap<CamelCaseIdentifier, Struct>,
+) -> Result<PrimitiveComponentType, &'static str> {
     match ty {
-        ComponentType::String(ty) => PrimitiveComponentType::try_from(ty.as_str()),
+        ComponentType::String(ty) => type_name_to_primitive(ty, enums, structs),
         ComponentType::ContainerType {
             type_,
             element_type,
         } => {
+            // Containers of enums with fields are serialized as a whole, like the enums themselves
+            if let Some(enum_) = element_type.as_deref().and_then(|ty| find_enum(ty, enums)) {
+                if enum_.has_payloads() {
+                    return match type_.as_str() {
+                        "Vec" | "Option" => Ok(PrimitiveComponentType::VecU8),
+                        _ => Err("invalid container type"),
+                    };
+                }
+            }
             let element_ty = element_type
                 .as_deref()
-                .map(PrimitiveComponentType::try_from)
+                .map(|ty| type_name_to_primitive(ty, enums, structs))
                 .transpose()?;
             match element_ty {
                 Some(element_ty) => match type_.as_str() {
@@ -65,16 +101,40 @@ fn component_type_to_primitive(ty: &ComponentType) -> Result<PrimitiveComponentT
                         .ok_or("invalid element type for Option"),
                     _ => Err("invalid container type"),
                 },
-                None => PrimitiveComponentType::try_from(type_.as_str()),
+                None => type_name_to_primitive(type_, enums, structs),
             }
         }
     }
 }
 
+/// Enums without fields are stored as their discriminant; enums with fields, and containers
+/// of them, are stored as their serialized bytes. Structs are stored as their serialized fields.
+fn type_name_to_primitive(
+    ty: &str,
+    enums: &BTreeMap<CamelCaseIdentifier, Enum>,
+    structs: &BTreeMap<CamelCaseIdentifier, Struct>,
+) -> Result<PrimitiveComponentType, &'static str> {
+    if CamelCaseIdentifier::new(ty).map_or(false, |ty| structs.contains_key(&ty)) {
+        return Ok(PrimitiveComponentType::Struct);
+    }
+
+    match find_enum(ty, enums) {
+        Some(enum_) if enum_.has_payloads() => Ok(PrimitiveComponentType::VecU8),
+        Some(_) => Ok(PrimitiveComponentType::U32),
+        None => PrimitiveComponentType::try_from(ty),
+    }
+}
+
+fn find_enum<'a>(ty: &str, enums: &'a BTreeMap<CamelCaseIdentifier, Enum>) -> Option<&'a Enum> {
+    enums.get(&CamelCaseIdentifier::new(ty).ok()?)
+}
+
 #[cfg(test)]
 mod tests {
     use ambient_shared_types::primitive_component_definitions;
 
+    use std::collections::BTreeMap;
+
     use crate::{component_type_to_primitive, ComponentType};
 
     #[test]
@@ -101,11 +161,22 @@ mod tests {
                 }
             }
 
-            assert_eq!(component_type_to_primitive(&str_ty(ty)), Ok(pct_raw));
-            assert_eq!(component_type_to_primitive(&ct_str_ty(ty)), Ok(pct_raw));
-            assert_eq!(component_type_to_primitive(&ct_ty("Vec", ty)), Ok(pct_vec));
+            let enums = BTreeMap::new();
+            let structs = BTreeMap::new();
+            assert_eq!(
+                component_type_to_primitive(&str_ty(ty), &enums, &structs),
+                Ok(pct_raw)
+            );
+            assert_eq!(
+                component_type_to_primitive(&ct_str_ty(ty), &enums, &structs),
+                Ok(pct_raw)
+            );
+            assert_eq!(
+                component_type_to_primitive(&ct_ty("Vec", ty), &enums, &structs),
+                Ok(pct_vec)
+            );
             assert_eq!(
-                component_type_to_primitive(&ct_ty("Option", ty)),
+                component_type_to_primitive(&ct_ty("Option", ty), &enums, &structs),
                 Ok(pct_option)
             );
         }
@@ -125,4 +196,120 @@ mod tests {
 
         primitive_component_definitions!(make_test_cases);
     }
+    #[test]
+    fn can_convert_defined_types() {
+        use ambient_ecs::PrimitiveComponentType as PCT;
+        use ambient_project::{CamelCaseIdentifier, Enum, EnumVariant, Identifier, Struct};
+        use ComponentType as CT;
+
+        fn variant(name: &str, fields: &[(&str, &str)]) -> EnumVariant {
+            EnumVariant {
+                name: CamelCaseIdentifier::new(name).unwrap(),
+                description: None,
+                fields: fields
+                    .iter()
+                    .map(|(k, v)| (Identifier::new(*k).unwrap(), CT::String(v.to_string())))
+                    .collect(),
+            }
+        }
+
+        let enums = BTreeMap::from_iter([
+            (
+                CamelCaseIdentifier::new("Stance").unwrap(),
+                Enum {
+                    description: None,
+                    variants: vec![variant("Idle", &[]), variant("Running", &[])],
+                },
+            ),
+            (
+                CamelCaseIdentifier::new("Shape").unwrap(),
+                Enum {
+                    description: None,
+                    variants: vec![variant("Sphere", &[("radius", "F32")])],
+                },
+            ),
+        ]);
+
+        let structs = BTreeMap::from_iter([(
+            CamelCaseIdentifier::new("Damage").unwrap(),
+            Struct {
+                description: None,
+                fields: BTreeMap::from_iter([(
+                    Identifier::new("amount").unwrap(),
+                    CT::String("F32".to_string()),
+                )]),
+            },
+        )]);
+
+        let vec_of = |ty: &str| CT::ContainerType {
+            type_: "Vec".to_string(),
+            element_type: Some(ty.to_string()),
+        };
+
+        assert_eq!(
+            component_type_to_primitive(&CT::String("Stance".to_string()), &enums, &structs),
+            Ok(PCT::U32)
+        );
+        assert_eq!(
+            component_type_to_primitive(&vec_of("Stance"), &enums, &structs),
+            Ok(PCT::VecU32)
+        );
+        assert_eq!(
+            component_type_to_primitive(&CT::String("Shape".to_string()), &enums, &structs),
+            Ok(PCT::VecU8)
+        );
+        assert_eq!(
+            component_type_to_primitive(&vec_of("Shape"), &enums, &structs),
+            Ok(PCT::VecU8)
+        );
+        assert_eq!(
+            component_type_to_primitive(&CT::String("Damage".to_string()), &enums, &structs),
+            Ok(PCT::Struct)
+        );
+        assert_eq!(
+            component_type_to_primitive(&vec_of("Damage"), &enums, &structs),
+            Ok(PCT::VecStruct)
+        );
+        assert!(
+            component_type_to_primitive(&CT::String("Struct".to_string()), &enums, &structs)
+                .is_err()
+        );
+    }
+    #[test]
+    fn quantized_attribute_is_parsed() {
+        use ambient_project::Manifest;
+
+        use crate::defined_components;
+
+        let manifest = |attributes: &str| {
+            Manifest::parse(&format!(
+                r#"
+                [ember]
+                id = "quantized_test"
+                name = "Quantized"
+                version = "0.0.1"
+
+                [components]
+                position = {{ type = "Vec3", attributes = [{attributes}] }}
+                "#
+            ))
+            .unwrap()
+        };
+
+        let components =
+            defined_components(&manifest(r#""Networked", "Quantized[0.01]""#), false).unwrap();
+        assert_eq!(components[0].attributes.quantized, Some(0.01));
+        assert!(components[0].attributes.flags.networked);
+
+        let components = defined_components(&manifest(r#""Networked""#), false).unwrap();
+        assert_eq!(components[0].attributes.quantized, None);
+
+        for invalid in [
+            r#""Quantized""#,
+            r#""Quantized[-1]""#,
+            r#""Quantized[abc]""#,
+        ] {
+            assert!(defined_components(&manifest(invalid), false).is_err());
+        }
+    }
 }
@@ -2,9 +2,9 @@
 //!
 //! If implementing a trait that is also available on the server, it should go in [super].
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use ambient_audio::AudioFromUrl;
+use ambient_audio::{AudioFromUrl, BusSettings, Ducking, MAX_PLAYBACK_RATE};
 use ambient_core::{
     asset_cache,
     async_ecs::async_run,
@@ -13,6 +13,7 @@ use ambient_core::{
     runtime,
     window::{window_ctl, WindowCtl},
 };
+use ambient_ecs::World;
 use ambient_gpu::texture::Texture;
 use ambient_input::{player_prev_raw_input, player_raw_input};
 use ambient_network::client::game_client;
@@ -22,7 +23,10 @@ use ambient_procedurals::{
 };
 use ambient_renderer::pbr_material::{PbrMaterialConfig, PbrMaterialParams};
 use ambient_std::{asset_cache::AsyncAssetKeyExt, asset_url::AbsAssetUrl, mesh::MeshBuilder};
-use ambient_world_audio::{audio_sender, AudioMessage};
+use ambient_world_audio::{
+    audio_buses, audio_emitter, audio_sender, get_audio_listener, hrtf_lib, sound_controls,
+    AudioMessage, SoundControls, SpatialSound,
+};
 use anyhow::Context;
 use glam::Vec4;
 use wgpu::TextureViewDescriptor;
@@ -30,6 +34,7 @@ use winit::window::CursorGrabMode;
 
 use super::Bindings;
 use crate::shared::{
+    self,
     conversion::{FromBindgen, IntoBindgen},
     implementation::message,
     message::Target,
@@ -205,7 +210,68 @@ impl wit::client_audio::Host for Bindings {
     }
 
     fn play(&mut self, url: String, looping: bool, volume: f32, uid: u32) -> anyhow::Result<()> {
+        self.play_with_options(
+            url,
+            wit::client_audio::PlayOptions {
+                looping,
+                volume,
+                playback_rate: 1.0,
+                filter: wit::client_audio::Filter {
+                    kind: wit::client_audio::FilterKind::None,
+                    freq: 0.0,
+                    bandwidth: 0.0,
+                },
+                start_position: 0.0,
+                emitter: None,
+                bus: None,
+            },
+            uid,
+        )
+    }
+
+    fn play_with_options(
+        &mut self,
+        url: String,
+        options: wit::client_audio::PlayOptions,
+        uid: u32,
+    ) -> anyhow::Result<()> {
+        let wit::client_audio::PlayOptions {
+            looping,
+            volume,
+            playback_rate,
+            filter,
+            start_position,
+            emitter,
+            bus,
+        } = options;
+
+        let spatial = match emitter {
+            Some(emitter) => {
+                let world = self.world_mut();
+                if !world.has_component(emitter.from_bindgen(), audio_emitter()) {
+                    shared::implementation::world_audio::set_emitter(world, emitter)?;
+                }
+                Some(SpatialSound {
+                    hrtf_lib: world.resource(hrtf_lib()).clone(),
+                    listener: get_audio_listener(world)?.clone(),
+                    emitter: world.get_cloned(emitter.from_bindgen(), audio_emitter())?,
+                })
+            }
+            None => None,
+        };
+
+        let playback_rate = validate_playback_rate(playback_rate)?;
+        let start = position_to_duration(start_position)?;
+
         let world = self.world();
+        let controls = SoundControls::new(volume, playback_rate, filter.from_bindgen());
+        {
+            let mut sound_controls = world.resource(sound_controls()).lock();
+            // Forget about sounds that have finished playing, so that the map does not keep growing
+            sound_controls.retain(|_, controls| !controls.progress.is_finished());
+            sound_controls.insert(uid, controls.clone());
+        }
+
         let assets = world.resource(asset_cache()).clone();
         let runtime = world.resource(runtime()).clone();
         let async_run = world.resource(async_run()).clone();
@@ -217,10 +283,22 @@ impl wit::client_audio::Host for Bindings {
                     Ok(track) => {
                         let sender = world.resource(audio_sender());
                         sender
-                            .send(AudioMessage::Track(track, looping, volume, url, uid))
+                            .send(AudioMessage::Track {
+                                track,
+                                url,
+                                uid,
+                                looping,
+                                start,
+                                controls,
+                                spatial,
+                                bus,
+                            })
                             .unwrap();
                     }
-                    Err(e) => log::error!("{e:?}"),
+                    Err(e) => {
+                        controls.progress.set_finished();
+                        log::error!("{e:?}")
+                    }
                 };
             });
         });
@@ -271,7 +349,113 @@ impl wit::client_audio::Host for Bindings {
         });
         Ok(())
     }
+
+    fn set_volume_by_id(&mut self, uid: u32, volume: f32) -> anyhow::Result<()> {
+        let controls = get_sound_controls(self.world(), uid)?;
+        *controls.gain.lock() = volume;
+        Ok(())
+    }
+
+    fn set_playback_rate(&mut self, uid: u32, rate: f32) -> anyhow::Result<()> {
+        let rate = validate_playback_rate(rate)?;
+        let controls = get_sound_controls(self.world(), uid)?;
+        *controls.playback_rate.lock() = rate;
+        Ok(())
+    }
+
+    fn set_filter(&mut self, uid: u32, filter: wit::client_audio::Filter) -> anyhow::Result<()> {
+        let controls = get_sound_controls(self.world(), uid)?;
+        *controls.filter.lock() = filter.from_bindgen();
+        Ok(())
+    }
+
+    fn seek(&mut self, uid: u32, position: f32) -> anyhow::Result<()> {
+        let position = position_to_duration(position)?;
+        let world = self.world();
+        get_sound_controls(world, uid)?;
+        world
+            .resource(audio_sender())
+            .send(AudioMessage::Seek(uid, position))?;
+        Ok(())
+    }
+
+    fn get_position(&mut self, uid: u32) -> anyhow::Result<Option<f32>> {
+        Ok(self
+            .world()
+            .resource(sound_controls())
+            .lock()
+            .get(&uid)
+            .map(|controls| controls.progress.position().as_secs_f32()))
+    }
+
+    fn is_finished(&mut self, uid: u32) -> anyhow::Result<bool> {
+        Ok(self
+            .world()
+            .resource(sound_controls())
+            .lock()
+            .get(&uid)
+            .map_or(true, |controls| controls.progress.is_finished()))
+    }
+
+    fn set_bus_volume(&mut self, bus: String, volume: f32) -> anyhow::Result<()> {
+        bus_settings_mut(self.world_mut(), bus).volume = volume.max(0.0);
+        Ok(())
+    }
+
+    fn set_bus_muted(&mut self, bus: String, muted: bool) -> anyhow::Result<()> {
+        bus_settings_mut(self.world_mut(), bus).muted = muted;
+        Ok(())
+    }
+
+    fn set_bus_effects(
+        &mut self,
+        bus: String,
+        effects: Vec<wit::client_audio::BusEffect>,
+    ) -> anyhow::Result<()> {
+        let effects = effects
+            .into_iter()
+            .map(shared::conversion::bus_effect_from_bindgen)
+            .collect::<anyhow::Result<_>>()?;
+        bus_settings_mut(self.world_mut(), bus).effects = effects;
+        Ok(())
+    }
+
+    fn set_bus_ducking(
+        &mut self,
+        bus: String,
+        trigger: Option<String>,
+        gain: f32,
+    ) -> anyhow::Result<()> {
+        bus_settings_mut(self.world_mut(), bus).ducking = trigger.map(|trigger| Ducking {
+            trigger,
+            gain: gain.max(0.0),
+        });
+        Ok(())
+    }
+}
+fn bus_settings_mut(world: &mut World, bus: String) -> &mut BusSettings {
+    world.resource_mut(audio_buses()).entry(bus).or_default()
+}
+fn get_sound_controls(world: &World, uid: u32) -> anyhow::Result<SoundControls> {
+    world
+        .resource(sound_controls())
+        .lock()
+        .get(&uid)
+        .cloned()
+        .with_context(|| format!("No sound with id {uid}"))
 }
+/// Rates above [MAX_PLAYBACK_RATE] are clamped, as each output sample reads up to that many samples.
+fn validate_playback_rate(rate: f32) -> anyhow::Result<f32> {
+    anyhow::ensure!(rate.is_finite(), "Invalid playback rate {rate}");
+    Ok(rate.clamp(0.0, MAX_PLAYBACK_RATE))
+}
+/// Negative positions are treated as the start of the sound.
+fn position_to_duration(position: f32) -> anyhow::Result<Duration> {
+    anyhow::ensure!(!position.is_nan(), "Invalid position {position}");
+    Duration::try_from_secs_f32(position.max(0.0))
+        .with_context(|| format!("Invalid position {position}"))
+}
+
 impl wit::client_window::Host for Bindings {
     fn set_fullscreen(&mut self, fullscreen: bool) -> anyhow::Result<()> {
         self.world_mut()
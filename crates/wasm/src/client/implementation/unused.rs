@@ -138,3 +138,56 @@ impl wit::server_message::Host for Bindings {
         unimplemented!()
     }
 }
+impl wit::server_storage::Host for Bindings {
+    fn get(
+        &mut self,
+        _: String,
+        _: String,
+    ) -> anyhow::Result<Result<Option<Vec<u8>>, wit::server_storage::StorageError>> {
+        unsupported()
+    }
+
+    fn set(
+        &mut self,
+        _: String,
+        _: String,
+        _: Vec<u8>,
+    ) -> anyhow::Result<Result<(), wit::server_storage::StorageError>> {
+        unsupported()
+    }
+
+    fn delete(
+        &mut self,
+        _: String,
+        _: String,
+    ) -> anyhow::Result<Result<bool, wit::server_storage::StorageError>> {
+        unsupported()
+    }
+
+    fn list(
+        &mut self,
+        _: String,
+    ) -> anyhow::Result<Result<Vec<String>, wit::server_storage::StorageError>> {
+        unsupported()
+    }
+}
+impl wit::server_http::Host for Bindings {
+    fn send(
+        &mut self,
+        _: wit::server_http::Method,
+        _: String,
+        _: Vec<(String, String)>,
+        _: Vec<u8>,
+    ) -> anyhow::Result<Result<u64, wit::server_http::HttpError>> {
+        unsupported()
+    }
+}
+impl wit::server_mod::Host for Bindings {
+    fn load(&mut self, _: String) -> anyhow::Result<Result<u64, String>> {
+        unsupported()
+    }
+
+    fn unload(&mut self, _: String) -> anyhow::Result<Result<(), String>> {
+        unsupported()
+    }
+}
@@ -2,8 +2,8 @@ use ambient_core::async_ecs::async_run;
 use ambient_ecs::World;
 use ambient_network::{
     client::{
-        bi_stream_handlers, datagram_handlers, uni_stream_handlers, PlatformRecvStream,
-        PlatformSendStream,
+        bi_stream_handlers, datagram_handlers, replayed_uni_stream_handlers, uni_stream_handlers,
+        PlatformRecvStream, PlatformSendStream,
     },
     log_network_result, unwrap_log_network_err, WASM_BISTREAM_ID, WASM_DATAGRAM_ID,
     WASM_UNISTREAM_ID,
@@ -33,6 +33,12 @@ pub fn initialize(world: &mut World) {
         WASM_UNISTREAM_ID,
         ("client_wasm_uni_stream", Arc::new(on_unistream)),
     );
+
+    // A replayed uni stream holds the same data as a datagram
+    world.resource_mut(replayed_uni_stream_handlers()).insert(
+        WASM_UNISTREAM_ID,
+        ("client_wasm_replayed_uni_stream", Arc::new(on_datagram)),
+    );
 }
 
 fn on_datagram(world: &mut World, _asset_cache: AssetCache, bytes: Bytes) {
@@ -4,11 +4,27 @@ pub mod client;
 pub mod server;
 pub mod shared;
 
+/// How often the epoch of the engine advances, which is the precision of the execution time
+/// limits of the modules
+#[cfg(not(target_os = "unknown"))]
+pub(crate) const EPOCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
 #[cfg(not(target_os = "unknown"))]
 pub(crate) static WASMTIME_ENGINE: once_cell::sync::Lazy<wasmtime::Engine> =
     once_cell::sync::Lazy::new(|| {
         let mut config = wasmtime::Config::new();
         config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
         config.wasm_component_model(true);
-        wasmtime::Engine::new(&config).unwrap()
+        config.epoch_interruption(true);
+        let engine = wasmtime::Engine::new(&config).unwrap();
+
+        std::thread::spawn({
+            let engine = engine.clone();
+            move || loop {
+                std::thread::sleep(EPOCH_INTERVAL);
+                engine.increment_epoch();
+            }
+        });
+
+        engine
     });
@@ -0,0 +1,354 @@
+//! Outbound HTTP requests from the server modules.
+//!
+//! A module can only send requests to the URLs in the `[http]` allowlist of its ember's manifest,
+//! which is attached to the module as [http_allowlist]. Redirects are not followed, as they could
+//! lead outside of the allowlist; the module receives the redirect and can follow it with another
+//! request. Requests are sent on the runtime, and their results are delivered to the module that
+//! sent them as an `HttpResponse` message.
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use ambient_core::{async_ecs::async_run, runtime};
+use ambient_ecs::{components, generated::messages, Debuggable, Description, EntityId, World};
+use once_cell::sync::Lazy;
+use reqwest::{Method, Url};
+
+use crate::shared::{message::RuntimeMessageExt, module_state};
+
+components!("wasm::http", {
+    @[Debuggable, Description["The URLs that this server module can send HTTP requests to, from the `[http]` section of its ember's manifest."]]
+    http_allowlist: Vec<String>,
+});
+
+/// How long a request can take before it fails
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The largest response body a module can receive, in bytes
+pub const MAX_RESPONSE_SIZE: usize = 16 * 1024 * 1024;
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+});
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpError {
+    /// The URL is not in the allowlist of the module
+    NotAllowed(String),
+    InvalidRequest(String),
+}
+
+/// Whether `url` is covered by an entry of `allowlist`: it must have the same scheme, host and
+/// port as the entry, and a path within the entry's path. Entries which are not valid URLs are
+/// ignored.
+pub fn is_allowed(url: &Url, allowlist: &[String]) -> bool {
+    allowlist
+        .iter()
+        .filter_map(|entry| Url::parse(entry).ok())
+        .any(|entry| {
+            let entry_path = entry.path().trim_end_matches('/');
+            entry.scheme() == url.scheme()
+                && entry.host_str() == url.host_str()
+                && entry.port_or_known_default() == url.port_or_known_default()
+                && url
+                    .path()
+                    .strip_prefix(entry_path)
+                    .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+        })
+}
+
+/// The entries of `allowlist` which are covered by `limit`, such as the entries of a mod's
+/// manifest which the server operator also allows
+pub fn restrict_allowlist(allowlist: &[String], limit: &[String]) -> Vec<String> {
+    allowlist
+        .iter()
+        .filter(|entry| Url::parse(entry).map_or(false, |url| is_allowed(&url, limit)))
+        .cloned()
+        .collect()
+}
+
+/// Sends a request on behalf of the module `module_id`, returning the ID of the request.
+///
+/// The result is sent to the module as an `HttpResponse` message with the same ID.
+pub fn send(
+    world: &World,
+    module_id: EntityId,
+    method: Method,
+    url: &str,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+) -> Result<u64, HttpError> {
+    let url = Url::parse(url).map_err(|err| HttpError::InvalidRequest(err.to_string()))?;
+    let allowlist = world
+        .get_ref(module_id, http_allowlist())
+        .map(|allowlist| allowlist.as_slice())
+        .unwrap_or_default();
+    if !is_allowed(&url, allowlist) {
+        return Err(HttpError::NotAllowed(format!(
+            "{url} is not in the `[http]` allowlist of the ember"
+        )));
+    }
+
+    let mut request = CLIENT
+        .request(method, url)
+        .timeout(REQUEST_TIMEOUT)
+        .body(body);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let request = request
+        .build()
+        .map_err(|err| HttpError::InvalidRequest(err.to_string()))?;
+
+    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    let async_run = world.resource(async_run()).clone();
+    world.resource(runtime()).spawn(async move {
+        let response = receive(CLIENT.execute(request).await).await;
+        async_run.run(move |world| {
+            // The module may have been unloaded while the request was in flight
+            if !world.has_component(module_id, module_state()) {
+                return;
+            }
+
+            let message = match response {
+                Ok((status, headers, body)) => {
+                    let (header_names, header_values) = headers.into_iter().unzip();
+                    messages::HttpResponse::new(
+                        body,
+                        None,
+                        header_names,
+                        header_values,
+                        request_id,
+                        status,
+                    )
+                }
+                Err(err) => messages::HttpResponse::new(
+                    vec![],
+                    Some(err.to_string()),
+                    vec![],
+                    vec![],
+                    request_id,
+                    0,
+                ),
+            };
+            if let Err(err) = message.run(world, Some(module_id)) {
+                log::error!("Failed to deliver HTTP response to module: {err:?}");
+            }
+        });
+    });
+
+    Ok(request_id)
+}
+
+type Response = (u32, Vec<(String, String)>, Vec<u8>);
+
+async fn receive(response: reqwest::Result<reqwest::Response>) -> anyhow::Result<Response> {
+    let mut response = response?;
+    let too_large = || anyhow::anyhow!("the response is larger than {MAX_RESPONSE_SIZE} bytes");
+    if response.content_length().unwrap_or_default() > MAX_RESPONSE_SIZE as u64 {
+        return Err(too_large());
+    }
+
+    let status = response.status().as_u16() as u32;
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect();
+    let mut body = vec![];
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > MAX_RESPONSE_SIZE {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((status, headers, body))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread::JoinHandle,
+    };
+
+    use ambient_core::async_ecs::{async_ecs_resources, async_ecs_systems};
+    use ambient_ecs::{FrameEvent, Message, System};
+    use ambient_sys::task::RuntimeHandle;
+
+    use super::*;
+    use crate::shared::testing;
+
+    fn allowlist() -> Vec<String> {
+        vec![
+            "https://matchmaking.example.com".to_string(),
+            "https://example.com/analytics/".to_string(),
+            "http://localhost:8080".to_string(),
+        ]
+    }
+
+    /// A stub server which answers a single request with `response`, returning its URL and the
+    /// request it received
+    fn stub(response: &'static [u8]) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let len = stream.read(&mut request).unwrap();
+            stream.write_all(response).unwrap();
+            String::from_utf8_lossy(&request[..len]).into_owned()
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn allowlist_matches_origin_and_path() {
+        let allowed = |url: &str| is_allowed(&Url::parse(url).unwrap(), &allowlist());
+
+        assert!(allowed("https://matchmaking.example.com/"));
+        assert!(allowed(
+            "https://matchmaking.example.com:443/queue?mode=duel"
+        ));
+        assert!(allowed("https://example.com/analytics"));
+        assert!(allowed("https://example.com/analytics/events"));
+        assert!(allowed("http://localhost:8080/"));
+
+        assert!(!allowed("http://matchmaking.example.com/"));
+        assert!(!allowed("https://matchmaking.example.com.evil.com/"));
+        assert!(!allowed("https://example.com/"));
+        assert!(!allowed("https://example.com/analytics-private"));
+        assert!(!allowed("http://localhost:8081/"));
+    }
+
+    #[test]
+    fn restricted_allowlist_keeps_covered_entries() {
+        let requested = vec![
+            "https://example.com/analytics/events".to_string(),
+            "https://example.com/".to_string(),
+            "http://169.254.169.254".to_string(),
+            "not a url".to_string(),
+        ];
+
+        assert_eq!(
+            restrict_allowlist(&requested, &allowlist()),
+            vec!["https://example.com/analytics/events".to_string()]
+        );
+        assert!(restrict_allowlist(&requested, &[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn receives_response_from_stub() {
+        let (url, server) = stub(
+            b"HTTP/1.1 201 Created\r\nContent-Length: 5\r\nX-Test: yes\r\nConnection: close\r\n\r\nhello",
+        );
+
+        let request = CLIENT
+            .request(Method::POST, format!("{url}/echo"))
+            .header("X-Ember", "test")
+            .body("ping")
+            .build()
+            .unwrap();
+        let (status, headers, body) = receive(CLIENT.execute(request).await).await.unwrap();
+
+        assert_eq!(status, 201);
+        assert!(headers.contains(&("x-test".to_string(), "yes".to_string())));
+        assert_eq!(body, b"hello");
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /echo HTTP/1.1"));
+        assert!(request.to_lowercase().contains("x-ember: test"));
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_response() {
+        let (url, server) =
+            stub(b"HTTP/1.1 200 OK\r\nContent-Length: 1000000000\r\nConnection: close\r\n\r\n");
+
+        let request = CLIENT.get(url).build().unwrap();
+        let err = receive(CLIENT.execute(request).await).await.unwrap_err();
+
+        assert!(err.to_string().contains("larger than"));
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn send_delivers_redirect_without_following_it() {
+        let (url, server) = stub(
+            b"HTTP/1.1 302 Found\r\nLocation: http://169.254.169.254/\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+
+        let mut world = testing::world();
+        world
+            .add_components(world.resource_entity(), async_ecs_resources())
+            .unwrap();
+        world.add_resource(runtime(), RuntimeHandle::current());
+        let (module_id, module) = testing::spawn_module(&mut world);
+        world
+            .add_component(module_id, http_allowlist(), vec![url.clone()])
+            .unwrap();
+
+        let not_allowed = send(
+            &world,
+            module_id,
+            Method::GET,
+            "http://169.254.169.254/",
+            vec![],
+            vec![],
+        );
+        assert!(matches!(not_allowed, Err(HttpError::NotAllowed(_))));
+
+        let request_id = send(
+            &world,
+            module_id,
+            Method::GET,
+            &format!("{url}/redirect"),
+            vec![],
+            vec![],
+        )
+        .unwrap();
+
+        // Run the world until the response is delivered to the module
+        let mut systems = async_ecs_systems();
+        let mut response = None;
+        for _ in 0..500 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            systems.run(&mut world, &FrameEvent);
+            response = module
+                .received
+                .lock()
+                .iter()
+                .find(|(name, _)| name == messages::HttpResponse::id())
+                .map(|(_, data)| messages::HttpResponse::deserialize_message(data).unwrap());
+            if response.is_some() {
+                break;
+            }
+        }
+        let response = response.expect("the response was not delivered");
+
+        assert_eq!(response.request_id, request_id);
+        assert_eq!(response.status, 302);
+        assert_eq!(response.error, None);
+        let location = response
+            .header_names
+            .iter()
+            .position(|name| name == "location")
+            .unwrap();
+        assert_eq!(response.header_values[location], "http://169.254.169.254/");
+
+        // Only the allowed request reached the stub, and the redirect was not followed
+        assert!(server.join().unwrap().starts_with("GET /redirect HTTP/1.1"));
+    }
+}
@@ -1,7 +1,7 @@
 //! Used to implement all the *shared* host functions on the server.
 //!
 //! If implementing a trait that is only available on the server, it should go in [specific].
-use crate::shared::{self, wit};
+use crate::shared::{self, conversion::IntoBindgen, wit};
 
 use super::Bindings;
 
@@ -168,7 +168,7 @@ impl wit::player::Host for Bindings {
 }
 impl wit::asset::Host for Bindings {
     fn url(&mut self, path: String) -> anyhow::Result<Result<String, wit::asset::UrlError>> {
-        shared::implementation::asset::url(self.world(), path, false)
+        shared::implementation::asset::url(self.world(), self.id, path, false)
     }
 }
 
@@ -188,3 +188,15 @@ impl wit::world_audio::Host for Bindings {
         shared::implementation::world_audio::play_sound_on_entity(self.world_mut(), sound, emitter)
     }
 }
+
+impl wit::prediction::Host for Bindings {
+    fn set_model(&mut self) -> anyhow::Result<()> {
+        let id = self.id;
+        shared::implementation::prediction::set_model(self.world_mut(), id)
+    }
+
+    fn get_input(&mut self) -> anyhow::Result<Option<wit::client_input::Input>> {
+        Ok(shared::implementation::prediction::get_input(self.world())
+            .map(|input| input.into_bindgen()))
+    }
+}
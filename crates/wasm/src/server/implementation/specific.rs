@@ -10,8 +10,14 @@ use super::super::Bindings;
 
 use crate::shared::{self, conversion::FromBindgen, implementation::message, message::Target};
 
+#[cfg(feature = "wit")]
+mod http;
+#[cfg(feature = "wit")]
+mod mods;
 #[cfg(all(feature = "wit", feature = "physics"))]
 mod physics;
+#[cfg(feature = "wit")]
+mod storage;
 
 #[cfg(feature = "wit")]
 impl shared::wit::server_message::Host for Bindings {
@@ -0,0 +1,31 @@
+use super::super::super::{
+    http::{self, HttpError},
+    Bindings,
+};
+use crate::shared::wit::server_http::{self, Host};
+
+impl Host for Bindings {
+    fn send(
+        &mut self,
+        method: server_http::Method,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    ) -> anyhow::Result<Result<u64, server_http::HttpError>> {
+        let method = match method {
+            server_http::Method::Get => reqwest::Method::GET,
+            server_http::Method::Post => reqwest::Method::POST,
+        };
+
+        Ok(
+            http::send(self.world(), self.id, method, &url, headers, body).map_err(
+                |err| match err {
+                    HttpError::NotAllowed(reason) => server_http::HttpError::NotAllowed(reason),
+                    HttpError::InvalidRequest(reason) => {
+                        server_http::HttpError::InvalidRequest(reason)
+                    }
+                },
+            ),
+        )
+    }
+}
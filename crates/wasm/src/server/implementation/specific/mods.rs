@@ -0,0 +1,13 @@
+use super::super::super::{mods, Bindings};
+use crate::shared::wit::server_mod::Host;
+
+impl Host for Bindings {
+    fn load(&mut self, url: String) -> anyhow::Result<Result<u64, String>> {
+        let id = self.id;
+        Ok(mods::load(self.world_mut(), id, &url).map_err(|err| err.to_string()))
+    }
+
+    fn unload(&mut self, ember_id: String) -> anyhow::Result<Result<(), String>> {
+        Ok(mods::unload_later(self.world(), self.id, ember_id).map_err(|err| err.to_string()))
+    }
+}
@@ -0,0 +1,59 @@
+use super::super::super::{
+    storage::{Storage, StorageError},
+    Bindings,
+};
+use crate::shared::wit::server_storage::{self, Host};
+
+impl Host for Bindings {
+    fn get(
+        &mut self,
+        namespace: String,
+        key: String,
+    ) -> anyhow::Result<Result<Option<Vec<u8>>, server_storage::StorageError>> {
+        Ok(self.storage(|storage| storage.get(&namespace, &key)))
+    }
+
+    fn set(
+        &mut self,
+        namespace: String,
+        key: String,
+        value: Vec<u8>,
+    ) -> anyhow::Result<Result<(), server_storage::StorageError>> {
+        Ok(self.storage(|storage| storage.set(&namespace, &key, &value)))
+    }
+
+    fn delete(
+        &mut self,
+        namespace: String,
+        key: String,
+    ) -> anyhow::Result<Result<bool, server_storage::StorageError>> {
+        Ok(self.storage(|storage| storage.delete(&namespace, &key)))
+    }
+
+    fn list(
+        &mut self,
+        namespace: String,
+    ) -> anyhow::Result<Result<Vec<String>, server_storage::StorageError>> {
+        Ok(self.storage(|storage| storage.list(&namespace)))
+    }
+}
+
+impl Bindings {
+    fn storage<R>(
+        &self,
+        f: impl FnOnce(&Storage) -> Result<R, StorageError>,
+    ) -> Result<R, server_storage::StorageError> {
+        Storage::for_module(self.world(), self.id)
+            .and_then(|storage| f(&storage))
+            .map_err(|err| match err {
+                StorageError::Unavailable => server_storage::StorageError::Unavailable,
+                StorageError::InvalidName(reason) => {
+                    server_storage::StorageError::InvalidName(reason)
+                }
+                StorageError::LimitExceeded(limit) => {
+                    server_storage::StorageError::LimitExceeded(limit)
+                }
+                StorageError::Io(err) => server_storage::StorageError::Io(err.to_string()),
+            })
+    }
+}
@@ -79,6 +79,14 @@ impl wit::client_audio::Host for Bindings {
     ) -> anyhow::Result<()> {
         unsupported()
     }
+    fn play_with_options(
+        &mut self,
+        _name: String,
+        _options: wit::client_audio::PlayOptions,
+        _uid: u32,
+    ) -> anyhow::Result<()> {
+        unsupported()
+    }
     fn stop(&mut self, _name: String) -> anyhow::Result<()> {
         unsupported()
     }
@@ -88,6 +96,45 @@ impl wit::client_audio::Host for Bindings {
     fn stop_by_id(&mut self, _id: u32) -> anyhow::Result<()> {
         unsupported()
     }
+    fn set_volume_by_id(&mut self, _uid: u32, _volume: f32) -> anyhow::Result<()> {
+        unsupported()
+    }
+    fn set_playback_rate(&mut self, _uid: u32, _rate: f32) -> anyhow::Result<()> {
+        unsupported()
+    }
+    fn set_filter(&mut self, _uid: u32, _filter: wit::client_audio::Filter) -> anyhow::Result<()> {
+        unsupported()
+    }
+    fn seek(&mut self, _uid: u32, _position: f32) -> anyhow::Result<()> {
+        unsupported()
+    }
+    fn get_position(&mut self, _uid: u32) -> anyhow::Result<Option<f32>> {
+        unsupported()
+    }
+    fn is_finished(&mut self, _uid: u32) -> anyhow::Result<bool> {
+        unsupported()
+    }
+    fn set_bus_volume(&mut self, _bus: String, _volume: f32) -> anyhow::Result<()> {
+        unsupported()
+    }
+    fn set_bus_muted(&mut self, _bus: String, _muted: bool) -> anyhow::Result<()> {
+        unsupported()
+    }
+    fn set_bus_effects(
+        &mut self,
+        _bus: String,
+        _effects: Vec<wit::client_audio::BusEffect>,
+    ) -> anyhow::Result<()> {
+        unsupported()
+    }
+    fn set_bus_ducking(
+        &mut self,
+        _bus: String,
+        _trigger: Option<String>,
+        _gain: f32,
+    ) -> anyhow::Result<()> {
+        unsupported()
+    }
 }
 impl wit::client_window::Host for Bindings {
     fn set_fullscreen(&mut self, _fullscreen: bool) -> anyhow::Result<()> {
@@ -3,8 +3,11 @@ use ambient_ecs::{query, EntityId, FnSystem, SystemGroup, World};
 use ambient_network::server::{ForkingEvent, ShutdownEvent};
 use std::sync::Arc;
 
+pub mod http;
 mod implementation;
+pub mod mods;
 mod network;
+pub mod storage;
 
 pub fn initialize(
     world: &mut World,
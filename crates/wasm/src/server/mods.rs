@@ -0,0 +1,212 @@
+//! Built embers which are loaded into a running server as mods.
+//!
+//! Mods are loaded by the [mod_loader] that the server provides, which spawns the modules of the
+//! mod with [is_mod_module]. Unloading a mod unloads and despawns its modules, which despawns what
+//! they spawned; the clients despawn what their client modules spawned when the module entities
+//! are despawned.
+//!
+//! Only the modules of the project can load and unload mods, so that a mod cannot unload the
+//! others, nor make the server load and serve arbitrary files.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use ambient_core::async_ecs::async_run;
+use ambient_ecs::{
+    components, generated::messages, query, Debuggable, Description, EntityId, Networked, Resource,
+    World,
+};
+use ambient_std::asset_url::AbsAssetUrl;
+use anyhow::Context;
+use itertools::Itertools;
+
+use crate::shared::{self, message::RuntimeMessageExt, module_ember_id, module_state};
+
+/// Called once a mod has been loaded, with its ember ID, or with why it could not be loaded
+pub type ModLoaded = Box<dyn FnOnce(&mut World, anyhow::Result<String>) + Sync + Send>;
+
+/// Downloads the built ember at the URL and spawns its modules. Loading happens in the background.
+pub type ModLoader = Arc<dyn Fn(&mut World, AbsAssetUrl, ModLoaded) + Sync + Send>;
+
+components!("wasm::mods", {
+    @[Networked, Debuggable, Description["This module belongs to a mod which was loaded while the server was running, and can be unloaded."]]
+    is_mod_module: (),
+    @[Resource, Description["Loads mods into the world. If not set, this server cannot load mods."]]
+    mod_loader: ModLoader,
+});
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Starts loading the built ember at `url`, which is either a URL or a path, as a mod on behalf
+/// of the module `caller`, returning the ID of the request.
+///
+/// The result is sent to the module as a `ModLoad` message with the same ID.
+pub fn load(world: &mut World, caller: EntityId, url: &str) -> anyhow::Result<u64> {
+    check_caller(world, caller)?;
+    let url = AbsAssetUrl::parse(url)?;
+    let loader = world
+        .resource_opt(mod_loader())
+        .context("This server cannot load mods")?
+        .clone();
+
+    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    loader(
+        world,
+        url,
+        Box::new(move |world, result| {
+            // The module may have been unloaded while the mod was loading
+            if !world.has_component(caller, module_state()) {
+                return;
+            }
+
+            let message = match result {
+                Ok(ember_id) => messages::ModLoad::new(ember_id, None, request_id),
+                Err(err) => {
+                    messages::ModLoad::new(String::new(), Some(format!("{err:#}")), request_id)
+                }
+            };
+            if let Err(err) = message.run(world, Some(caller)) {
+                log::error!("Failed to deliver the result of loading a mod to module: {err:?}");
+            }
+        }),
+    );
+    Ok(request_id)
+}
+
+/// Unloads the mod `ember_id` and despawns its modules, returning whether it was loaded
+pub fn unload(world: &mut World, ember_id: &str) -> bool {
+    let modules = query(module_ember_id())
+        .incl(is_mod_module())
+        .iter(world, None)
+        .filter(|(_, id)| id.as_str() == ember_id)
+        .map(|(id, _)| id)
+        .collect_vec();
+
+    for &id in &modules {
+        shared::unload(world, id, "the mod was unloaded");
+        world.despawn(id);
+    }
+    if !modules.is_empty() {
+        log::info!("Unloaded mod {ember_id}");
+    }
+    !modules.is_empty()
+}
+
+/// Unloads the mod `ember_id` on the next frame on behalf of the module `caller`, as a module
+/// cannot be unloaded while it is running.
+pub fn unload_later(world: &World, caller: EntityId, ember_id: String) -> anyhow::Result<()> {
+    check_caller(world, caller)?;
+    world.resource(async_run()).clone().run(move |world| {
+        unload(world, &ember_id);
+    });
+    Ok(())
+}
+
+fn check_caller(world: &World, caller: EntityId) -> anyhow::Result<()> {
+    if world.has_component(caller, is_mod_module()) {
+        anyhow::bail!("Mods cannot load or unload mods");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_core::async_ecs::{async_ecs_resources, async_ecs_systems};
+    use ambient_ecs::{dont_despawn_on_unload, Entity, FrameEvent, Message, System};
+
+    use super::*;
+    use crate::shared::testing;
+
+    fn world(loader: ModLoader) -> World {
+        let mut world = testing::world();
+        world
+            .add_components(world.resource_entity(), async_ecs_resources())
+            .unwrap();
+        world.add_resource(mod_loader(), loader);
+        world
+    }
+
+    fn spawn_mod_module(world: &mut World, ember_id: &str) -> (EntityId, testing::FakeModuleState) {
+        let (id, module) = testing::spawn_module(world);
+        world.add_component(id, is_mod_module(), ()).unwrap();
+        world
+            .add_component(id, module_ember_id(), ember_id.to_string())
+            .unwrap();
+        (id, module)
+    }
+
+    fn received_loads(module: &testing::FakeModuleState) -> Vec<messages::ModLoad> {
+        module
+            .received
+            .lock()
+            .iter()
+            .filter(|(name, _)| name == messages::ModLoad::id())
+            .map(|(_, data)| messages::ModLoad::deserialize_message(data).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn load_result_is_sent_to_caller() {
+        let mut world = world(Arc::new(|world, url, loaded| {
+            let result = if url.to_string().ends_with("/my_mod") {
+                Ok("my_mod".to_string())
+            } else {
+                Err(anyhow::anyhow!("not found"))
+            };
+            loaded(world, result);
+        }));
+        let (caller, module) = testing::spawn_module(&mut world);
+
+        let loaded = load(&mut world, caller, "https://example.com/my_mod").unwrap();
+        let failed = load(&mut world, caller, "https://example.com/missing").unwrap();
+
+        let results = received_loads(&module);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].request_id, loaded);
+        assert_eq!(results[0].ember_id, "my_mod");
+        assert_eq!(results[0].error, None);
+        assert_eq!(results[1].request_id, failed);
+        assert_eq!(results[1].error.as_deref(), Some("not found"));
+    }
+
+    #[test]
+    fn mods_cannot_load_or_unload_mods() {
+        let mut world = world(Arc::new(|_, _, _| panic!("a mod loaded a mod")));
+        let (caller, _) = spawn_mod_module(&mut world, "evil_mod");
+        let (victim, _) = spawn_mod_module(&mut world, "other_mod");
+
+        assert!(load(&mut world, caller, "https://example.com/my_mod").is_err());
+        assert!(unload_later(&world, caller, "other_mod".to_string()).is_err());
+
+        async_ecs_systems().run(&mut world, &FrameEvent);
+        assert!(world.has_component(victim, module_state()));
+    }
+
+    #[test]
+    fn unloading_despawns_what_the_mod_spawned() {
+        let mut world = world(Arc::new(|_, _, _| {}));
+        let (id, module) = spawn_mod_module(&mut world, "my_mod");
+        let (other_id, _) = spawn_mod_module(&mut world, "other_mod");
+        let spawned = Entity::new().spawn(&mut world);
+        let kept = Entity::new()
+            .with(dont_despawn_on_unload(), ())
+            .spawn(&mut world);
+        module.spawned.lock().extend([spawned, kept]);
+
+        // Modules of the project can unload mods; the mod is unloaded on the next frame
+        let (caller, _) = testing::spawn_module(&mut world);
+        unload_later(&world, caller, "my_mod".to_string()).unwrap();
+        assert!(world.exists(id));
+        async_ecs_systems().run(&mut world, &FrameEvent);
+
+        assert_eq!(module.received_names(), vec![messages::ModuleUnload::id()]);
+        assert!(!world.exists(id));
+        assert!(!world.exists(spawned));
+        assert!(world.exists(kept));
+        assert!(world.has_component(other_id, module_state()));
+
+        assert!(!unload(&mut world, "my_mod"));
+    }
+}
@@ -0,0 +1,304 @@
+//! Persistent key-value storage for the server modules.
+//!
+//! Every ember gets its own directory under [storage_dir], with a directory for each namespace and
+//! a file for each key in it. Namespaces and keys are hex-encoded in the file names, so that they
+//! can contain any character. Values are written to a temporary file which is then renamed over
+//! the old one, so that a crash never leaves a value half-written. The values of an ember can
+//! add up to at most [storage_limit] bytes.
+
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use ambient_ecs::{components, Description, EntityId, Resource, World};
+use ambient_project::Identifier;
+use data_encoding::HEXLOWER;
+
+use crate::shared::module_ember_id;
+
+components!("wasm::storage", {
+    @[Resource, Description["The directory the server modules store their data in, with one subdirectory per ember. If not set, storage is unavailable."]]
+    storage_dir: PathBuf,
+    @[Resource, Description["The most bytes the values stored by each ember can add up to. If not set, the storage of an ember is only limited by the disk."]]
+    storage_limit: u64,
+});
+
+/// The longest namespace or key, in bytes
+pub const MAX_NAME_LEN: usize = 100;
+
+const TEMP_EXTENSION: &str = "tmp";
+
+#[derive(Debug)]
+pub enum StorageError {
+    /// The server has no [storage_dir], or the module does not belong to an ember
+    Unavailable,
+    InvalidName(String),
+    /// Storing the value would take the ember over its [storage_limit], in bytes
+    LimitExceeded(u64),
+    Io(io::Error),
+}
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable => write!(f, "storage is not available on this server"),
+            Self::InvalidName(reason) => write!(f, "invalid name: {reason}"),
+            Self::LimitExceeded(limit) => {
+                write!(f, "the storage is limited to {limit} bytes per ember")
+            }
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+impl std::error::Error for StorageError {}
+impl From<io::Error> for StorageError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// The stored data of one ember
+#[derive(Debug, Clone)]
+pub struct Storage {
+    dir: PathBuf,
+    limit: Option<u64>,
+}
+impl Storage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            limit: None,
+        }
+    }
+
+    /// Limits the values to `limit` bytes in total
+    pub fn with_limit(mut self, limit: Option<u64>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// The storage of the ember that the module `id` belongs to
+    pub fn for_module(world: &World, id: EntityId) -> Result<Self, StorageError> {
+        let root = world
+            .resource_opt(storage_dir())
+            .ok_or(StorageError::Unavailable)?;
+        let ember_id = world
+            .get_ref(id, module_ember_id())
+            .map_err(|_| StorageError::Unavailable)?;
+        Identifier::validate(ember_id).map_err(|err| StorageError::InvalidName(err.to_string()))?;
+
+        Ok(Self::new(root.join(ember_id)).with_limit(world.resource_opt(storage_limit()).copied()))
+    }
+
+    pub fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match std::fs::read(self.key_path(namespace, key)?) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn set(&self, namespace: &str, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        let path = self.key_path(namespace, key)?;
+        if let Some(limit) = self.limit {
+            let replaced = file_size(&path)?;
+            if self.size()? - replaced + value.len() as u64 > limit {
+                return Err(StorageError::LimitExceeded(limit));
+            }
+        }
+        std::fs::create_dir_all(path.parent().unwrap())?;
+
+        let temp_path = path.with_extension(TEMP_EXTENSION);
+        std::fs::write(&temp_path, value)?;
+        std::fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    /// Returns whether there was a value to delete
+    pub fn delete(&self, namespace: &str, key: &str) -> Result<bool, StorageError> {
+        match std::fs::remove_file(self.key_path(namespace, key)?) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// The keys in `namespace`, sorted
+    pub fn list(&self, namespace: &str) -> Result<Vec<String>, StorageError> {
+        let entries = match std::fs::read_dir(self.namespace_path(namespace)?) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut keys = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some() {
+                // A value that is being written, or was left behind by a crash
+                continue;
+            }
+            if let Some(key) = path.file_name().and_then(|name| decode(name.to_str()?)) {
+                keys.push(key);
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// The total size of the values, in bytes
+    fn size(&self) -> Result<u64, StorageError> {
+        let namespaces = match std::fs::read_dir(&self.dir) {
+            Ok(namespaces) => namespaces,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut size = 0;
+        for namespace in namespaces {
+            for entry in std::fs::read_dir(namespace?.path())? {
+                let entry = entry?;
+                if entry.path().extension().is_none() {
+                    size += entry.metadata()?.len();
+                }
+            }
+        }
+        Ok(size)
+    }
+
+    fn namespace_path(&self, namespace: &str) -> Result<PathBuf, StorageError> {
+        Ok(self.dir.join(encode("namespace", namespace)?))
+    }
+
+    fn key_path(&self, namespace: &str, key: &str) -> Result<PathBuf, StorageError> {
+        Ok(self.namespace_path(namespace)?.join(encode("key", key)?))
+    }
+}
+
+fn file_size(path: &Path) -> Result<u64, StorageError> {
+    match std::fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn encode(kind: &str, name: &str) -> Result<String, StorageError> {
+    if name.is_empty() {
+        return Err(StorageError::InvalidName(format!("the {kind} is empty")));
+    }
+    if name.len() > MAX_NAME_LEN {
+        return Err(StorageError::InvalidName(format!(
+            "the {kind} is longer than {MAX_NAME_LEN} bytes"
+        )));
+    }
+    Ok(HEXLOWER.encode(name.as_bytes()))
+}
+
+fn decode(file_name: &str) -> Option<String> {
+    String::from_utf8(HEXLOWER.decode(file_name.as_bytes()).ok()?).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn storage(name: &str) -> Storage {
+        let dir =
+            std::env::temp_dir().join(format!("ambient_storage_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Storage::new(dir)
+    }
+
+    #[test]
+    fn values_round_trip() {
+        let storage = storage("round_trip");
+        assert_eq!(storage.get("scores", "alice").unwrap(), None);
+        assert_eq!(storage.list("scores").unwrap(), Vec::<String>::new());
+
+        storage.set("scores", "bob", b"2").unwrap();
+        storage.set("scores", "alice", b"1").unwrap();
+        storage.set("scores", "alice", b"3").unwrap();
+        storage.set("settings", "volume", b"11").unwrap();
+        assert_eq!(storage.get("scores", "alice").unwrap(), Some(b"3".to_vec()));
+        assert_eq!(storage.list("scores").unwrap(), vec!["alice", "bob"]);
+
+        assert!(storage.delete("scores", "alice").unwrap());
+        assert!(!storage.delete("scores", "alice").unwrap());
+        assert_eq!(storage.get("scores", "alice").unwrap(), None);
+        assert_eq!(storage.list("scores").unwrap(), vec!["bob"]);
+        assert_eq!(storage.list("settings").unwrap(), vec!["volume"]);
+
+        std::fs::remove_dir_all(&storage.dir).unwrap();
+    }
+
+    #[test]
+    fn names_are_encoded() {
+        let storage = storage("names");
+        for name in ["..", "/", "../escape", "a/b", "\\", "ünïcødé"] {
+            storage.set(name, name, name.as_bytes()).unwrap();
+            assert_eq!(
+                storage.get(name, name).unwrap(),
+                Some(name.as_bytes().to_vec())
+            );
+            assert_eq!(storage.list(name).unwrap(), vec![name]);
+        }
+        // Everything stays in the directory of the storage
+        assert!(!storage.dir.parent().unwrap().join("escape").exists());
+
+        let longest = "a".repeat(MAX_NAME_LEN);
+        storage.set("names", &longest, b"").unwrap();
+        for (namespace, key) in [("names", ""), ("", "key")] {
+            assert!(matches!(
+                storage.set(namespace, key, b""),
+                Err(StorageError::InvalidName(_))
+            ));
+        }
+        let too_long = "a".repeat(MAX_NAME_LEN + 1);
+        assert!(matches!(
+            storage.set("names", &too_long, b""),
+            Err(StorageError::InvalidName(_))
+        ));
+        assert!(matches!(
+            storage.get(&too_long, "key"),
+            Err(StorageError::InvalidName(_))
+        ));
+
+        std::fs::remove_dir_all(&storage.dir).unwrap();
+    }
+
+    #[test]
+    fn temporary_files_are_not_listed() {
+        let storage = storage("temporary");
+        storage.set("scores", "alice", b"1").unwrap();
+        let temp_path = storage
+            .key_path("scores", "bob")
+            .unwrap()
+            .with_extension(TEMP_EXTENSION);
+        std::fs::write(temp_path, b"2").unwrap();
+
+        assert_eq!(storage.list("scores").unwrap(), vec!["alice"]);
+        assert_eq!(storage.get("scores", "bob").unwrap(), None);
+
+        std::fs::remove_dir_all(&storage.dir).unwrap();
+    }
+
+    #[test]
+    fn values_are_limited_in_size() {
+        let storage = storage("limit").with_limit(Some(10));
+        storage.set("a", "key", &[0; 6]).unwrap();
+        storage.set("b", "key", &[0; 4]).unwrap();
+        assert!(matches!(
+            storage.set("b", "other", &[0; 1]),
+            Err(StorageError::LimitExceeded(10))
+        ));
+        // Replacing a value only counts the difference
+        storage.set("a", "key", &[0; 5]).unwrap();
+        storage.set("b", "other", &[0; 1]).unwrap();
+
+        storage.delete("a", "key").unwrap();
+        storage.set("b", "key", &[0; 9]).unwrap();
+
+        std::fs::remove_dir_all(&storage.dir).unwrap();
+    }
+}
@@ -22,6 +22,7 @@ pub trait BindingsBound:
     + super::wit::message::Host
     + super::wit::player::Host
     + super::wit::world_audio::Host
+    + super::wit::prediction::Host
     // Client
     + super::wit::client_audio::Host
     + super::wit::client_message::Host
@@ -36,6 +37,9 @@ pub trait BindingsBound:
     // Server
     + super::wit::server_message::Host
     + super::wit::server_physics::Host
+    + super::wit::server_storage::Host
+    + super::wit::server_http::Host
+    + super::wit::server_mod::Host
     + Clone
     + Sync
     + Send
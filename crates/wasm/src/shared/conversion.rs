@@ -3,7 +3,7 @@ use std::time::Duration;
 use ambient_ecs::EntityId;
 use ambient_shared_types::{
     procedural_storage_handle_definitions, ProceduralMaterialHandle, ProceduralMeshHandle,
-    ProceduralSamplerHandle, ProceduralTextureHandle,
+    ProceduralSamplerHandle, ProceduralTextureHandle, StructValue,
 };
 use ambient_std::shapes::Ray;
 use glam::{Mat4, Quat, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
@@ -223,6 +223,19 @@ impl FromBindgen for wit::types::Duration {
     }
 }
 
+impl IntoBindgen for StructValue {
+    type Item = wit::types::StructValue;
+    fn into_bindgen(self) -> Self::Item {
+        wit::types::StructValue { data: self.0 }
+    }
+}
+impl FromBindgen for wit::types::StructValue {
+    type Item = StructValue;
+    fn from_bindgen(self) -> Self::Item {
+        StructValue(self.data)
+    }
+}
+
 macro_rules! bindgen_passthrough {
     ($type:ty) => {
         impl IntoBindgen for $type {
@@ -417,3 +430,61 @@ impl FromBindgen for wit::client_sampler::AddressMode {
         }
     }
 }
+
+impl FromBindgen for wit::client_audio::Filter {
+    type Item = ambient_audio::blt::Filter;
+
+    fn from_bindgen(self) -> Self::Item {
+        use ambient_audio::blt::{Bpf, Filter, Hpf, Lpf};
+        use wit::client_audio::FilterKind;
+
+        let Self {
+            kind,
+            freq,
+            bandwidth,
+        } = self;
+        match kind {
+            FilterKind::None => Filter::None,
+            FilterKind::LowPass => Filter::LowPass(Lpf { freq, bandwidth }),
+            FilterKind::HighPass => Filter::HighPass(Hpf { freq, bandwidth }),
+            FilterKind::BandPass => Filter::BandPass(Bpf { freq, bandwidth }),
+        }
+    }
+}
+
+/// Fails if a duration is not a finite number of seconds. Negative durations are treated as zero.
+pub(crate) fn bus_effect_from_bindgen(
+    effect: wit::client_audio::BusEffect,
+) -> anyhow::Result<ambient_audio::Effect> {
+    use ambient_audio::{CompressorParams, DelayParams, Effect, ReverbParams};
+    use wit::client_audio::BusEffect;
+
+    fn duration(seconds: f32) -> anyhow::Result<Duration> {
+        anyhow::ensure!(!seconds.is_nan(), "Invalid duration {seconds}");
+        Duration::try_from_secs_f32(seconds.max(0.0))
+            .map_err(|_| anyhow::anyhow!("Invalid duration {seconds}"))
+    }
+
+    Ok(match effect {
+        BusEffect::Filter(filter) => Effect::Filter(filter.from_bindgen()),
+        BusEffect::Delay(v) => Effect::Delay(DelayParams {
+            delay: duration(v.delay)?,
+            feedback: v.feedback,
+            wet: v.wet,
+        }),
+        BusEffect::Reverb(v) => Effect::Reverb(ReverbParams {
+            room_size: v.room_size,
+            damping: v.damping,
+            wet: v.wet,
+            dry: v.dry,
+            width: v.width,
+        }),
+        BusEffect::Compressor(v) => Effect::Compressor(CompressorParams {
+            threshold: v.threshold,
+            ratio: v.ratio,
+            attack: duration(v.attack)?,
+            release: duration(v.release)?,
+            makeup_gain: v.makeup_gain,
+        }),
+    })
+}
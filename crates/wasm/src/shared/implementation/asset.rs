@@ -1,17 +1,25 @@
 use ambient_core::asset_cache;
-use ambient_ecs::World;
+use ambient_ecs::{EntityId, World};
 use ambient_std::asset_url::{AbsAssetUrl, ParseError};
 
-use crate::shared::wit;
+use crate::shared::{module_asset_base_url, wit};
 
 pub(crate) fn url(
     world: &World,
+    module_id: EntityId,
     path: String,
     resolve: bool,
 ) -> anyhow::Result<Result<String, wit::asset::UrlError>> {
     ok_wrap(move || {
         let assets = world.resource(asset_cache()).clone();
-        let asset_url = AbsAssetUrl::from_asset_key(&path).map_err(parse_error_to_url_error)?;
+        // The assets of a mod are relative to the mod, rather than to the project
+        let asset_url = match world.get_ref(module_id, module_asset_base_url()) {
+            Ok(base_url) => AbsAssetUrl::parse(base_url)
+                .map_err(|err| wit::asset::UrlError::InvalidUrl(err.to_string()))?
+                .push(path.trim_start_matches('/')),
+            Err(_) => AbsAssetUrl::from_asset_key(&path),
+        }
+        .map_err(parse_error_to_url_error)?;
         let asset_url = if resolve {
             asset_url
                 .to_download_url(&assets)
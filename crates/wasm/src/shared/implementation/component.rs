@@ -2,10 +2,10 @@ use ambient_ecs::{
     with_component_registry, Component, ComponentDesc, ComponentEntry, ComponentSet,
     ComponentValue, Entity, EntityId, PrimitiveComponentType as PCT, QueryEvent, QueryState, World,
 };
-use ambient_shared_types::primitive_component_definitions;
+use ambient_shared_types::stored_component_definitions;
 use ambient_shared_types::{
     ProceduralMaterialHandle, ProceduralMeshHandle, ProceduralSamplerHandle,
-    ProceduralTextureHandle,
+    ProceduralTextureHandle, StructValue,
 };
 use anyhow::Context;
 use glam::{Mat4, Quat, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
@@ -34,6 +34,11 @@ pub fn get_component_type<T: ComponentValue>(component_index: u32) -> Option<Com
 }
 
 macro_rules! define_component_types {
+    ($(($primitive_value:ident, $primitive_type:ty)),*; $(($internal_value:ident, $internal_type:ty)),*) => {
+        define_component_types!(
+            $(($primitive_value, $primitive_type)),*, $(($internal_value, $internal_type)),*
+        );
+    };
     ($(($value:ident, $type:ty)),*) => { paste! {
         fn read_primitive_component_from_world(
             world: &World,
@@ -220,7 +225,7 @@ macro_rules! define_component_types {
     }};
 }
 
-primitive_component_definitions!(define_component_types);
+stored_component_definitions!(define_component_types);
 
 pub(crate) fn add_components(
     world: &mut World,
@@ -8,6 +8,8 @@ pub mod message;
 #[cfg(feature = "wit")]
 pub mod player;
 #[cfg(feature = "wit")]
+pub mod prediction;
+#[cfg(feature = "wit")]
 pub mod world_audio;
 
 pub fn unsupported<T>() -> anyhow::Result<T> {
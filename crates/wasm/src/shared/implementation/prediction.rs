@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use ambient_ecs::{generated::messages, EntityId, World};
+use ambient_input::PlayerRawInput;
+use ambient_network::prediction::prediction_model;
+
+use crate::shared::{message::RuntimeMessageExt, module_state, prediction_input};
+
+/// Makes the module the prediction model, which is sent a `PredictionStep` message for every
+/// frame of input it should apply
+pub(crate) fn set_model(world: &mut World, module_id: EntityId) -> anyhow::Result<()> {
+    world.add_resource(
+        prediction_model(),
+        Arc::new(
+            move |world: &mut World, id: EntityId, input: &PlayerRawInput, dt: f32| {
+                // Nothing is predicted once the module is unloaded
+                if !world.has_component(module_id, module_state()) {
+                    return;
+                }
+
+                world.add_resource(prediction_input(), Some(input.clone()));
+                let result = messages::PredictionStep::new(dt, id).run(world, Some(module_id));
+                world.add_resource(prediction_input(), None);
+                if let Err(err) = result {
+                    log::error!("Failed to run the prediction model: {err:?}");
+                }
+            },
+        ),
+    );
+    Ok(())
+}
+
+pub(crate) fn get_input(world: &World) -> Option<PlayerRawInput> {
+    world.resource_opt(prediction_input()).cloned().flatten()
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_ecs::Message;
+
+    use super::*;
+    use crate::shared::testing;
+
+    #[test]
+    fn model_steps_are_sent_to_the_module() {
+        ambient_network::init_all_components();
+        let mut world = testing::world();
+        let (id, module) = testing::spawn_module(&mut world);
+        set_model(&mut world, id).unwrap();
+        let model = world.resource(prediction_model()).clone();
+
+        let entity = EntityId::new();
+        model(&mut world, entity, &PlayerRawInput::default(), 0.1);
+        let steps = module
+            .received
+            .lock()
+            .iter()
+            .map(|(_, data)| messages::PredictionStep::deserialize_message(data).unwrap())
+            .map(|step| (step.entity, step.dt))
+            .collect::<Vec<_>>();
+        assert_eq!(steps, vec![(entity, 0.1)]);
+        // The input is only available while the module handles the step
+        assert!(get_input(&world).is_none());
+
+        // Nothing is predicted once the module is unloaded
+        world.remove_component(id, module_state()).unwrap();
+        model(&mut world, entity, &PlayerRawInput::default(), 0.1);
+        assert_eq!(module.received.lock().len(), 1);
+    }
+}
@@ -74,15 +74,13 @@ pub(crate) fn play_sound_on_entity(
                     )
                 })
                 .unwrap();
-            match track {
-                Ok(track) => {
+            match track.map(|track| track.decode()) {
+                Ok(Ok(decoded)) => {
                     let sender = world.resource(audio_sender());
-                    let source =
-                        track
-                            .decode()
-                            .spatial(hrtf_lib, listener.clone(), emitter.clone());
+                    let source = decoded.spatial(hrtf_lib, listener.clone(), emitter.clone());
                     sender.send(AudioMessage::Spatial(source)).unwrap();
                 }
+                Ok(Err(e)) => log::error!("{e:?}"),
                 Err(e) => log::error!("{e:?}"),
             };
         });
@@ -8,6 +8,9 @@ pub mod build;
 pub mod conversion;
 pub mod host_guest_state;
 pub mod message;
+pub mod profile;
+#[cfg(test)]
+pub(crate) mod testing;
 
 #[cfg(feature = "wit")]
 pub mod wit;
@@ -16,8 +19,8 @@ use std::sync::Arc;
 
 use ambient_core::async_ecs::async_run;
 use ambient_ecs::{
-    dont_despawn_on_unload, generated::messages, query, world_events, Entity, EntityId, FnSystem,
-    Message, SystemGroup, World, WorldEventReader,
+    dont_despawn_on_unload, generated::messages, query, world_events, DynSystem, Entity, EntityId,
+    FnSystem, Message, SystemGroup, World, WorldEventReader,
 };
 
 use ambient_project::Identifier;
@@ -30,8 +33,11 @@ mod internal {
     use ambient_ecs::{
         components, Debuggable, Description, EntityId, Networked, Resource, Store, World,
     };
+    use ambient_input::PlayerRawInput;
 
-    use super::{MessageType, ModuleBytecode, ModuleErrors, ModuleState, ModuleStateArgs};
+    use super::{
+        MessageType, ModuleBytecode, ModuleErrors, ModuleLimits, ModuleState, ModuleStateArgs,
+    };
 
     components!("wasm::shared", {
         @[Networked, Store, Debuggable]
@@ -45,21 +51,30 @@ mod internal {
         module_enabled: bool,
         @[Networked, Store, Debuggable]
         module_name: String,
+        @[Networked, Store, Debuggable, Description["The ID of the ember this module belongs to."]]
+        module_ember_id: String,
+        @[Networked, Store, Debuggable, Description["The URL that the asset paths of this module are relative to, if not the assets of the project. Used by mods."]]
+        module_asset_base_url: String,
         @[Networked, Store, Debuggable]
         module_errors: ModuleErrors,
         @[Networked, Debuggable, Description["The ID of the module on the \"other side\" of this module, if available. (e.g. serverside module to clientside module)."]]
         remote_paired_id: EntityId,
+        @[Resource, Debuggable, Description["The limits of the modules which do not have their own. If attached to a module, the limits of that module. Applied when the module is loaded; modules without either are not limited."]]
+        module_limits: ModuleLimits,
 
         @[Resource, Description["Used to signal messages from the WASM host/runtime."]]
         messenger: Arc<dyn Fn(&World, EntityId, MessageType, &str) + Send + Sync>,
         @[Resource]
         module_state_maker: Arc<dyn Fn(ModuleStateArgs<'_>) -> anyhow::Result<ModuleState> + Sync + Send>,
+        @[Resource, Description["The input which the prediction model is applying while it handles `PredictionStep`."]]
+        prediction_input: Option<PlayerRawInput>,
     });
 }
 
 pub use internal::{
-    client_bytecode_from_url, messenger, module, module_bytecode, module_enabled, module_errors,
-    module_state, module_state_maker, remote_paired_id,
+    client_bytecode_from_url, messenger, module, module_asset_base_url, module_bytecode,
+    module_ember_id, module_enabled, module_errors, module_limits, module_state,
+    module_state_maker, prediction_input, remote_paired_id,
 };
 
 use self::{internal::module_name, message::Source};
@@ -68,6 +83,12 @@ use crate::shared::message::{RuntimeMessageExt, Target};
 pub fn init_all_components() {
     internal::init_components();
     message::init_components();
+    #[cfg(feature = "native")]
+    {
+        crate::server::http::init_components();
+        crate::server::mods::init_components();
+        crate::server::storage::init_components();
+    }
 }
 
 pub const MAXIMUM_ERROR_COUNT: usize = 5;
@@ -104,6 +125,7 @@ pub fn systems() -> SystemGroup {
                     }
                 },
             ),
+            despawned_modules_system(),
             Box::new(FnSystem::new(move |world, _| {
                 ambient_profiling::scope!("WASM module app events");
                 let events = app_events_reader
@@ -140,6 +162,7 @@ pub fn systems() -> SystemGroup {
                     message::run(world, message);
                 }
             })),
+            Box::new(profile::system()),
         ],
     )
 }
@@ -191,6 +214,11 @@ fn load(world: &mut World, module_id: EntityId, component_bytecode: &[u8]) {
         .get_ref(module_id, module_name())
         .map(|x| x.clone())
         .unwrap_or_else(|_| "Unknown".to_string());
+    // Only the hosts which set the resource (i.e. the server) limit their modules
+    let limits = world
+        .get(module_id, module_limits())
+        .ok()
+        .or_else(|| world.resource_opt(module_limits()).copied());
 
     // Spawn the module on another thread to ensure that it does not block the main thread during compilation.
     std::thread::spawn(move || {
@@ -208,6 +236,7 @@ fn load(world: &mut World, module_id: EntityId, component_bytecode: &[u8]) {
                     messenger(world, module_id, MessageType::Stderr, msg);
                 }),
                 id: module_id,
+                limits,
             });
             log::info!("Done loading module: {}", name);
             res
@@ -275,14 +304,45 @@ fn run(
         return;
     }
 
-    let result =
-        run_and_catch_panics(|| state.run(world, message_source, message_name, message_data));
+    let mut exceeded = None;
+    let result = run_and_catch_panics(|| {
+        state
+            .run(world, message_source, message_name, message_data)
+            .map_err(|err| {
+                exceeded = LimitExceeded::find(&err);
+                err
+            })
+    });
 
-    if let Err(message) = result {
+    // The module may be stuck in a bad state after being stopped, so it is not run again, not
+    // even to handle `ModuleUnload`
+    if let Some(exceeded) = exceeded {
+        stop(world, id, &exceeded.to_string());
+        // Unloading clears the errors, but this one explains why the module is not running
+        if let Ok(module_errors) = world.get_mut(id, module_errors()) {
+            module_errors.0.push(exceeded.to_string());
+        }
+    } else if let Err(message) = result {
         update_errors(world, &[(id, message)]);
     }
 }
 
+/// A module entity can be despawned without being unloaded, such as the client module of a mod
+/// when the server unloads the mod, so what it spawned is despawned with it
+fn despawned_modules_system() -> DynSystem {
+    query(module_state())
+        .despawned()
+        .to_system(|q, world, qs, _| {
+            for (_, mut state) in q.collect_cloned(world, qs) {
+                for id in state.drain_spawned_entities() {
+                    if !world.has_component(id, dont_despawn_on_unload()) {
+                        world.despawn(id);
+                    }
+                }
+            }
+        })
+}
+
 pub(crate) fn unload(world: &mut World, module_id: EntityId, reason: &str) {
     if !world.has_component(module_id, module_state()) {
         return;
@@ -292,6 +352,15 @@ pub(crate) fn unload(world: &mut World, module_id: EntityId, reason: &str) {
         .run(world, Some(module_id))
         .unwrap();
 
+    stop(world, module_id, reason);
+}
+
+/// Unloads the module without letting it run again, despawning what it spawned
+fn stop(world: &mut World, module_id: EntityId, reason: &str) {
+    if !world.has_component(module_id, module_state()) {
+        return;
+    }
+
     let spawned_entities = world
         .get_mut(module_id, module_state())
         .map(|sms| sms.drain_spawned_entities())
@@ -302,6 +371,7 @@ pub(crate) fn unload(world: &mut World, module_id: EntityId, reason: &str) {
     }
 
     world.remove_component(module_id, module_state()).unwrap();
+    profile::clear(world, module_id);
 
     for id in spawned_entities {
         if !world.has_component(id, dont_despawn_on_unload()) {
@@ -362,3 +432,61 @@ fn run_and_catch_panics<R>(f: impl FnOnce() -> anyhow::Result<R>) -> Result<R, S
         }),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use ambient_ecs::{FrameEvent, System};
+
+    use super::*;
+
+    #[test]
+    fn despawning_a_module_despawns_what_it_spawned() {
+        let mut world = testing::world();
+        let mut system = despawned_modules_system();
+        system.run(&mut world, &FrameEvent);
+
+        let (id, module) = testing::spawn_module(&mut world);
+        let spawned = Entity::new().spawn(&mut world);
+        let kept = Entity::new()
+            .with(dont_despawn_on_unload(), ())
+            .spawn(&mut world);
+        module.spawned.lock().extend([spawned, kept]);
+        system.run(&mut world, &FrameEvent);
+        assert!(world.exists(spawned));
+
+        world.despawn(id);
+        system.run(&mut world, &FrameEvent);
+        assert!(!world.exists(spawned));
+        assert!(world.exists(kept));
+        // The module was not unloaded, so it was not sent anything
+        assert!(module.received_names().is_empty());
+    }
+
+    #[test]
+    fn modules_which_exceed_a_limit_are_unloaded_without_running() {
+        let mut world = testing::world();
+        let (id, module) = testing::spawn_failing_module(&mut world, || {
+            anyhow::Error::new(LimitExceeded::Memory(1024 * 1024)).context("while running")
+        });
+        let spawned = Entity::new().spawn(&mut world);
+        module.spawned.lock().insert(spawned);
+
+        let state = world.get_cloned(id, module_state()).unwrap();
+        run(
+            &mut world,
+            id,
+            state,
+            &message::Source::Runtime,
+            "test",
+            &[],
+        );
+
+        assert_eq!(module.received_names(), vec!["test"]);
+        assert!(!world.has_component(id, module_state()));
+        assert!(!world.exists(spawned));
+        assert_eq!(
+            world.get_ref(id, module_errors()).unwrap().0,
+            vec![LimitExceeded::Memory(1024 * 1024).to_string()]
+        );
+    }
+}
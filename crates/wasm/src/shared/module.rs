@@ -1,13 +1,21 @@
 use crate::shared;
 
-use super::Source;
 #[cfg(feature = "wit")]
-use super::{bindings::BindingsBound, conversion::IntoBindgen};
+use super::{
+    bindings::BindingsBound,
+    conversion::IntoBindgen,
+    profile::{HostCalls, MessageProfile},
+};
+use super::{profile::ModuleProfile, Source};
 use ambient_ecs::{EntityId, World};
+#[cfg(feature = "wit")]
+use ambient_sys::time::Instant;
 use data_encoding::BASE64;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::{any::Any, collections::HashSet, sync::Arc};
+#[cfg(feature = "wit")]
+use std::collections::HashMap;
+use std::{any::Any, collections::HashSet, sync::Arc, time::Duration};
 
 #[derive(Clone)]
 pub struct ModuleBytecode(pub Vec<u8>);
@@ -66,10 +74,93 @@ impl<'de> Deserialize<'de> for ModuleBytecode {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct ModuleErrors(pub Vec<String>);
 
+/// The resources a module can use before it is unloaded
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleLimits {
+    /// How long a single call into the module, such as handling a message, can run for
+    pub max_exec_time: Duration,
+    /// The most bytes each linear memory of the module can grow to
+    pub max_memory: usize,
+}
+impl Default for ModuleLimits {
+    fn default() -> Self {
+        Self {
+            max_exec_time: Duration::from_secs(1),
+            max_memory: 512 * 1024 * 1024,
+        }
+    }
+}
+
+/// A module went over one of its [ModuleLimits]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitExceeded {
+    ExecTime(Duration),
+    Memory(usize),
+}
+impl LimitExceeded {
+    /// Finds the limit which caused the error, if any
+    pub fn find(err: &anyhow::Error) -> Option<Self> {
+        err.chain()
+            .find_map(|err| err.downcast_ref::<Self>())
+            .copied()
+    }
+}
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ExecTime(limit) => write!(
+                f,
+                "the module ran for longer than its limit of {} ms",
+                limit.as_millis()
+            ),
+            Self::Memory(limit) => write!(
+                f,
+                "the module tried to use more memory than its limit of {} MiB",
+                limit / (1024 * 1024)
+            ),
+        }
+    }
+}
+impl std::error::Error for LimitExceeded {}
+
 #[cfg(feature = "wit")]
 struct WasmtimeContext<Bindings: BindingsBound> {
     wasi: wasmtime_wasi::WasiCtx,
     bindings: Bindings,
+    limiter: MemoryLimiter,
+    host_calls: HostCalls,
+}
+
+#[cfg(feature = "wit")]
+struct MemoryLimiter {
+    max_memory: usize,
+    /// The bytes of all the linear memories of the module
+    memory: usize,
+}
+
+#[cfg(feature = "wit")]
+impl wasmtime::ResourceLimiter for MemoryLimiter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        if desired > self.max_memory {
+            return Err(LimitExceeded::Memory(self.max_memory).into());
+        }
+        self.memory += desired.saturating_sub(current);
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: u32,
+        _desired: u32,
+        _maximum: Option<u32>,
+    ) -> anyhow::Result<bool> {
+        Ok(true)
+    }
 }
 
 pub trait ModuleStateBehavior: Sync + Send {
@@ -83,6 +174,8 @@ pub trait ModuleStateBehavior: Sync + Send {
     fn drain_spawned_entities(&mut self) -> HashSet<EntityId>;
     fn listen_to_message(&mut self, event_name: String);
     fn supports_message(&self, event_name: &str) -> bool;
+    /// Takes what the module has done since this was last called
+    fn take_profile(&mut self) -> ModuleProfile;
 }
 
 pub type Messenger = Box<dyn Fn(&World, &str) + Sync + Send>;
@@ -92,6 +185,8 @@ pub struct ModuleStateArgs<'a> {
     pub stdout_output: Messenger,
     pub stderr_output: Messenger,
     pub id: EntityId,
+    /// `None` if the module can use as much time and memory as it likes
+    pub limits: Option<ModuleLimits>,
 }
 
 #[derive(Clone)]
@@ -111,6 +206,7 @@ impl ModuleState {
             stdout_output,
             stderr_output,
             id,
+            limits,
         } = args;
 
         Ok(Self {
@@ -119,10 +215,19 @@ impl ModuleState {
                 stdout_output,
                 stderr_output,
                 bindings(id),
+                limits,
             )?)),
         })
     }
 
+    /// Wraps a module which does not run any WASM, for testing what the host does with modules
+    #[cfg(test)]
+    pub(crate) fn from_behavior(behavior: impl ModuleStateBehavior + 'static) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(behavior)),
+        }
+    }
+
     #[cfg(feature = "wit")]
     pub fn create_state_maker<Bindings: BindingsBound + 'static>(
         bindings: fn(EntityId) -> Bindings,
@@ -154,6 +259,10 @@ impl ModuleStateBehavior for ModuleState {
     fn supports_message(&self, message_name: &str) -> bool {
         self.inner.read().supports_message(message_name)
     }
+
+    fn take_profile(&mut self) -> ModuleProfile {
+        self.inner.write().take_profile()
+    }
 }
 
 #[cfg(feature = "wit")]
@@ -165,6 +274,9 @@ struct ModuleStateInnerImpl<Bindings: BindingsBound> {
 
     stdout_consumer: WasiOutputStreamConsumer,
     stderr_consumer: WasiOutputStreamConsumer,
+
+    limits: Option<ModuleLimits>,
+    messages: HashMap<String, MessageProfile>,
 }
 
 #[cfg(feature = "wit")]
@@ -181,6 +293,7 @@ impl<Bindings: BindingsBound> ModuleStateInnerImpl<Bindings> {
         stdout_output: Box<dyn Fn(&World, &str) + Sync + Send>,
         stderr_output: Box<dyn Fn(&World, &str) + Sync + Send>,
         bindings: Bindings,
+        limits: Option<ModuleLimits>,
     ) -> anyhow::Result<Self> {
         let engine = &*crate::WASMTIME_ENGINE;
 
@@ -194,8 +307,22 @@ impl<Bindings: BindingsBound> ModuleStateInnerImpl<Bindings> {
                     .stderr(stderr_output)
                     .build(),
                 bindings,
+                limiter: MemoryLimiter {
+                    max_memory: limits.map_or(usize::MAX, |limits| limits.max_memory),
+                    memory: 0,
+                },
+                host_calls: HostCalls::default(),
             },
         );
+        store.limiter(|x| &mut x.limiter);
+        store.call_hook(|x, hook| {
+            match hook {
+                wasmtime::CallHook::CallingHost => x.host_calls.enter(),
+                wasmtime::CallHook::ReturningFromHost => x.host_calls.exit(),
+                _ => {}
+            }
+            Ok(())
+        });
 
         let mut linker = wasmtime::component::Linker::<WasmtimeContext<Bindings>>::new(engine);
         wasmtime_wasi::command::add_to_linker(&mut linker, |x| &mut x.wasi)?;
@@ -207,7 +334,11 @@ impl<Bindings: BindingsBound> ModuleStateInnerImpl<Bindings> {
             shared::wit::Bindings::instantiate(&mut store, &component, &linker)?;
 
         // Initialise the runtime.
-        guest_bindings.guest().call_init(&mut store)?;
+        set_exec_deadline(&mut store, limits);
+        guest_bindings
+            .guest()
+            .call_init(&mut store)
+            .map_err(|err| check_exec_time(err, limits))?;
 
         Ok(Self {
             store,
@@ -216,10 +347,38 @@ impl<Bindings: BindingsBound> ModuleStateInnerImpl<Bindings> {
 
             stdout_consumer,
             stderr_consumer,
+
+            limits,
+            messages: HashMap::new(),
         })
     }
 }
 
+/// Interrupts the next call into the module once it has run for longer than its limit
+#[cfg(feature = "wit")]
+fn set_exec_deadline<T>(store: &mut wasmtime::Store<T>, limits: Option<ModuleLimits>) {
+    let epochs = match limits {
+        // The current epoch has already partially elapsed, so one more is allowed
+        Some(limits) => {
+            (limits.max_exec_time.as_nanos() / crate::EPOCH_INTERVAL.as_nanos() + 1) as u64
+        }
+        // Never reached, but small enough that adding the current epoch does not overflow
+        None => u64::MAX / 2,
+    };
+    store.set_epoch_deadline(epochs);
+}
+
+/// Replaces the interruption of a module which reached its deadline with a clearer error
+#[cfg(feature = "wit")]
+fn check_exec_time(err: anyhow::Error, limits: Option<ModuleLimits>) -> anyhow::Error {
+    match (err.downcast_ref::<wasmtime::Trap>(), limits) {
+        (Some(wasmtime::Trap::Interrupt), Some(limits)) => {
+            LimitExceeded::ExecTime(limits.max_exec_time).into()
+        }
+        _ => err,
+    }
+}
+
 #[cfg(feature = "wit")]
 impl<Bindings: BindingsBound> ModuleStateBehavior for ModuleStateInnerImpl<Bindings> {
     fn run(
@@ -230,6 +389,9 @@ impl<Bindings: BindingsBound> ModuleStateBehavior for ModuleStateInnerImpl<Bindi
         message_data: &[u8],
     ) -> anyhow::Result<()> {
         self.store.data_mut().bindings.set_world(world);
+        set_exec_deadline(&mut self.store, self.limits);
+        self.store.data_mut().host_calls = HostCalls::default();
+        let start = Instant::now();
 
         let result = self.guest_bindings.guest().call_exec(
             &mut self.store,
@@ -244,6 +406,11 @@ impl<Bindings: BindingsBound> ModuleStateBehavior for ModuleStateInnerImpl<Bindi
             message_name,
             message_data,
         );
+        let result = result.map_err(|err| check_exec_time(err, self.limits));
+        self.messages
+            .entry(message_name.to_string())
+            .or_default()
+            .record(start.elapsed(), self.store.data().host_calls);
 
         self.store.data_mut().bindings.clear_world();
 
@@ -274,6 +441,13 @@ impl<Bindings: BindingsBound> ModuleStateBehavior for ModuleStateInnerImpl<Bindi
             .subscribed_messages
             .contains(event_name)
     }
+
+    fn take_profile(&mut self) -> ModuleProfile {
+        ModuleProfile {
+            messages: std::mem::take(&mut self.messages),
+            memory: self.store.data().limiter.memory,
+        }
+    }
 }
 
 struct WasiOutputStream(flume::Sender<String>);
@@ -334,3 +508,78 @@ impl WasiOutputStreamConsumer {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn limit_exceeded_is_found_through_context() {
+        let err = anyhow::Error::new(LimitExceeded::ExecTime(Duration::from_millis(250)))
+            .context("while handling a message");
+        assert_eq!(
+            LimitExceeded::find(&err),
+            Some(LimitExceeded::ExecTime(Duration::from_millis(250)))
+        );
+        assert_eq!(LimitExceeded::find(&anyhow::anyhow!("other error")), None);
+
+        assert_eq!(
+            LimitExceeded::ExecTime(Duration::from_millis(250)).to_string(),
+            "the module ran for longer than its limit of 250 ms"
+        );
+        assert_eq!(
+            LimitExceeded::Memory(64 * 1024 * 1024).to_string(),
+            "the module tried to use more memory than its limit of 64 MiB"
+        );
+    }
+
+    #[cfg(feature = "wit")]
+    #[test]
+    fn calls_are_interrupted_once_they_exceed_their_limit() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let mut config = wasmtime::Config::new();
+        config.epoch_interruption(true);
+        let engine = wasmtime::Engine::new(&config).unwrap();
+        let module =
+            wasmtime::Module::new(&engine, r#"(module (func (export "run") (loop (br 0))))"#)
+                .unwrap();
+        let mut store = wasmtime::Store::new(&engine, ());
+        let instance = wasmtime::Instance::new(&mut store, &module, &[]).unwrap();
+        let run = instance
+            .get_typed_func::<(), ()>(&mut store, "run")
+            .unwrap();
+
+        let limits = Some(ModuleLimits {
+            max_exec_time: crate::EPOCH_INTERVAL * 3,
+            ..Default::default()
+        });
+        set_exec_deadline(&mut store, limits);
+        // The limit and the epoch which has already partially elapsed
+        for _ in 0..3 {
+            engine.increment_epoch();
+        }
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let ticker = std::thread::spawn({
+            let engine = engine.clone();
+            let interrupted = interrupted.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(50));
+                interrupted.store(true, Ordering::SeqCst);
+                engine.increment_epoch();
+            }
+        });
+
+        let err = run.call(&mut store, ()).unwrap_err();
+        ticker.join().unwrap();
+        assert!(interrupted.load(Ordering::SeqCst));
+        assert_eq!(
+            LimitExceeded::find(&check_exec_time(err, limits)),
+            Some(LimitExceeded::ExecTime(crate::EPOCH_INTERVAL * 3))
+        );
+
+        // Other errors are left as they are
+        let err = check_exec_time(anyhow::anyhow!("other error"), limits);
+        assert_eq!(err.to_string(), "other error");
+    }
+}
@@ -0,0 +1,264 @@
+//! How long the modules take to handle their messages, and how much memory they use.
+//!
+//! The modules accumulate a [ModuleProfile] as they run, which is taken every [PROFILE_INTERVAL]
+//! and written to the `profile_` components of the module entity as rates per second.
+
+use std::{collections::HashMap, time::Duration};
+
+use ambient_ecs::{query, Entity, EntityId, FnSystem, World};
+use ambient_sys::time::Instant;
+use itertools::Itertools;
+
+pub use ambient_ecs::generated::components::core::wasm::{
+    module_memory, profile_host_calls, profile_message_calls, profile_message_host_calls,
+    profile_message_host_time, profile_message_max_time, profile_message_time, profile_messages,
+    profile_time,
+};
+
+use super::{module_state, ModuleStateBehavior};
+
+/// How often the profiles of the modules are updated
+pub const PROFILE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a module spent handling one message, over all the times it handled it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MessageProfile {
+    pub calls: u64,
+    pub time: Duration,
+    pub max_time: Duration,
+    pub host_calls: u64,
+    pub host_time: Duration,
+}
+impl MessageProfile {
+    pub fn record(&mut self, time: Duration, host: HostCalls) {
+        self.calls += 1;
+        self.time += time;
+        self.max_time = self.max_time.max(time);
+        self.host_calls += host.calls;
+        self.host_time += host.time;
+    }
+}
+
+/// What a module did since its profile was last taken
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModuleProfile {
+    pub messages: HashMap<String, MessageProfile>,
+    /// The bytes of linear memory the module is using
+    pub memory: usize,
+}
+
+/// The calls a module made to the host during one call into the module
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HostCalls {
+    pub calls: u64,
+    pub time: Duration,
+    entered_at: Option<Instant>,
+}
+impl HostCalls {
+    pub fn enter(&mut self) {
+        self.calls += 1;
+        self.entered_at = Some(Instant::now());
+    }
+
+    pub fn exit(&mut self) {
+        if let Some(entered_at) = self.entered_at.take() {
+            self.time += entered_at.elapsed();
+        }
+    }
+}
+
+/// Writes the profile which a module accumulated over `interval` to its entity
+pub fn write(world: &mut World, id: EntityId, profile: &ModuleProfile, interval: Duration) {
+    let seconds = interval.as_secs_f32().max(f32::EPSILON);
+    let per_second = |count: u64| (count as f32 / seconds).round() as u64;
+    let ms_per_second = |time: Duration| time.as_secs_f32() * 1000. / seconds;
+
+    let (names, messages): (Vec<_>, Vec<_>) = profile
+        .messages
+        .iter()
+        .sorted_by(|a, b| b.1.time.cmp(&a.1.time))
+        .map(|(name, message)| (name.clone(), *message))
+        .unzip();
+    fn map<T>(messages: &[MessageProfile], f: impl Fn(&MessageProfile) -> T) -> Vec<T> {
+        messages.iter().map(f).collect()
+    }
+
+    world
+        .add_components(
+            id,
+            Entity::new()
+                .with(module_memory(), profile.memory as u64)
+                .with(
+                    profile_time(),
+                    ms_per_second(messages.iter().map(|m| m.time).sum()),
+                )
+                .with(
+                    profile_host_calls(),
+                    per_second(messages.iter().map(|m| m.host_calls).sum()),
+                )
+                .with(profile_messages(), names)
+                .with(
+                    profile_message_calls(),
+                    map(&messages, |m| per_second(m.calls)),
+                )
+                .with(
+                    profile_message_time(),
+                    map(&messages, |m| ms_per_second(m.time)),
+                )
+                .with(
+                    profile_message_max_time(),
+                    map(&messages, |m| m.max_time.as_secs_f32() * 1000.),
+                )
+                .with(
+                    profile_message_host_calls(),
+                    map(&messages, |m| per_second(m.host_calls)),
+                )
+                .with(
+                    profile_message_host_time(),
+                    map(&messages, |m| ms_per_second(m.host_time)),
+                ),
+        )
+        .ok();
+}
+
+/// Removes the profile of a module which is no longer running
+pub(crate) fn clear(world: &mut World, id: EntityId) {
+    world
+        .remove_components(
+            id,
+            vec![
+                module_memory().desc(),
+                profile_time().desc(),
+                profile_host_calls().desc(),
+                profile_messages().desc(),
+                profile_message_calls().desc(),
+                profile_message_time().desc(),
+                profile_message_max_time().desc(),
+                profile_message_host_calls().desc(),
+                profile_message_host_time().desc(),
+            ],
+        )
+        .ok();
+}
+
+/// Takes the profiles of the running modules every [PROFILE_INTERVAL]
+pub(crate) fn system() -> FnSystem {
+    let mut last_update = Instant::now();
+    FnSystem::new(move |world, _| {
+        let interval = last_update.elapsed();
+        if interval < PROFILE_INTERVAL {
+            return;
+        }
+        last_update = Instant::now();
+
+        ambient_profiling::scope!("WASM module profiles");
+        let modules = query(module_state())
+            .iter(world, None)
+            .map(|(id, state)| (id, state.clone()))
+            .collect_vec();
+        for (id, mut state) in modules {
+            let profile = state.take_profile();
+            write(world, id, &profile, interval);
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shared::testing;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn message(
+        calls: u64,
+        time: u64,
+        max_time: u64,
+        host_calls: u64,
+        host_time: u64,
+    ) -> MessageProfile {
+        MessageProfile {
+            calls,
+            time: ms(time),
+            max_time: ms(max_time),
+            host_calls,
+            host_time: ms(host_time),
+        }
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?} != {expected:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() < 1e-3,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn record() {
+        let mut profile = MessageProfile::default();
+        for (time, calls, host_time) in [(2, 3, 1), (5, 1, 2)] {
+            let host = HostCalls {
+                calls,
+                time: ms(host_time),
+                ..Default::default()
+            };
+            profile.record(ms(time), host);
+        }
+
+        assert_eq!(profile, message(2, 7, 5, 4, 3));
+    }
+
+    #[test]
+    fn write_rates_per_second_by_time() {
+        let mut world = testing::world();
+        let id = Entity::new().spawn(&mut world);
+        let profile = ModuleProfile {
+            messages: HashMap::from([
+                ("Frame".to_string(), message(120, 40, 3, 600, 10)),
+                ("Collision".to_string(), message(4, 80, 30, 2, 2)),
+            ]),
+            memory: 1024,
+        };
+        write(&mut world, id, &profile, Duration::from_secs(2));
+
+        assert_eq!(world.get(id, module_memory()).unwrap(), 1024);
+        assert_close(&[world.get(id, profile_time()).unwrap()], &[60.]);
+        assert_eq!(world.get(id, profile_host_calls()).unwrap(), 301);
+
+        // The message which took the longest comes first
+        assert_eq!(
+            world.get_cloned(id, profile_messages()).unwrap(),
+            vec!["Collision".to_string(), "Frame".to_string()]
+        );
+        assert_eq!(
+            world.get_cloned(id, profile_message_calls()).unwrap(),
+            vec![2, 60]
+        );
+        assert_close(
+            &world.get_cloned(id, profile_message_time()).unwrap(),
+            &[40., 20.],
+        );
+        // The longest time is not a rate
+        assert_close(
+            &world.get_cloned(id, profile_message_max_time()).unwrap(),
+            &[30., 3.],
+        );
+        assert_eq!(
+            world.get_cloned(id, profile_message_host_calls()).unwrap(),
+            vec![1, 300]
+        );
+        assert_close(
+            &world.get_cloned(id, profile_message_host_time()).unwrap(),
+            &[1., 5.],
+        );
+
+        clear(&mut world, id);
+        assert!(!world.has_component(id, profile_time()));
+        assert!(!world.has_component(id, profile_messages()));
+    }
+}
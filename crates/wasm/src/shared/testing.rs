@@ -0,0 +1,112 @@
+//! Modules which do not run any WASM, for testing what the host does with modules.
+
+use std::{collections::HashSet, sync::Arc};
+
+use ambient_ecs::{Entity, EntityId, World};
+use parking_lot::Mutex;
+
+use super::{
+    internal::module_name, message::pending_messages, messenger, module, module_errors,
+    module_state, profile::ModuleProfile, MessageType, ModuleState, ModuleStateBehavior, Source,
+};
+
+/// What a [FakeModule] received and spawned, which the test keeps a handle to
+#[derive(Clone, Default)]
+pub(crate) struct FakeModuleState {
+    /// The name and data of every message the module received
+    pub received: Arc<Mutex<Vec<(String, Vec<u8>)>>>,
+    /// The entities the module spawned, as the host would track them
+    pub spawned: Arc<Mutex<HashSet<EntityId>>>,
+}
+impl FakeModuleState {
+    pub fn received_names(&self) -> Vec<String> {
+        self.received
+            .lock()
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+pub(crate) struct FakeModule {
+    state: FakeModuleState,
+    /// If set, every message fails with the error this returns
+    fail_with: Option<fn() -> anyhow::Error>,
+}
+impl ModuleStateBehavior for FakeModule {
+    fn run(
+        &mut self,
+        _world: &mut World,
+        _message_source: &Source,
+        message_name: &str,
+        message_data: &[u8],
+    ) -> anyhow::Result<()> {
+        self.state
+            .received
+            .lock()
+            .push((message_name.to_string(), message_data.to_vec()));
+        match self.fail_with {
+            Some(error) => Err(error()),
+            None => Ok(()),
+        }
+    }
+
+    fn drain_spawned_entities(&mut self) -> HashSet<EntityId> {
+        std::mem::take(&mut *self.state.spawned.lock())
+    }
+
+    fn listen_to_message(&mut self, _event_name: String) {}
+
+    fn supports_message(&self, _event_name: &str) -> bool {
+        true
+    }
+
+    fn take_profile(&mut self) -> ModuleProfile {
+        ModuleProfile::default()
+    }
+}
+
+pub(crate) fn world() -> World {
+    ambient_ecs::init_components();
+    ambient_core::init_all_components();
+    super::init_all_components();
+
+    let mut world = World::new_with_config("test", false);
+    world.add_resource(
+        messenger(),
+        Arc::new(|_: &World, _: EntityId, _: MessageType, _: &str| {}),
+    );
+    world.add_resource(pending_messages(), vec![]);
+    world
+}
+
+/// Spawns a module which records what it receives
+pub(crate) fn spawn_module(world: &mut World) -> (EntityId, FakeModuleState) {
+    spawn_module_with(world, None)
+}
+
+/// Spawns a module which fails every message with the error `fail_with` returns
+pub(crate) fn spawn_failing_module(
+    world: &mut World,
+    fail_with: fn() -> anyhow::Error,
+) -> (EntityId, FakeModuleState) {
+    spawn_module_with(world, Some(fail_with))
+}
+
+fn spawn_module_with(
+    world: &mut World,
+    fail_with: Option<fn() -> anyhow::Error>,
+) -> (EntityId, FakeModuleState) {
+    let state = FakeModuleState::default();
+    let behavior = FakeModule {
+        state: state.clone(),
+        fail_with,
+    };
+    let id = Entity::new()
+        .with(module(), ())
+        .with(module_name(), "test".to_string())
+        .with_default(module_errors())
+        .with(module_state(), ModuleState::from_behavior(behavior))
+        .spawn(world);
+    (id, state)
+}
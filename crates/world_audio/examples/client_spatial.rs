@@ -57,7 +57,8 @@ fn spawn_emitters(world: &mut World) {
             .with(audio_emitter(), emitter)
             .spawn_static(world);
 
-        play_sound_on_entity(world, id, track.decode().repeat()).expect("Failed to play sound");
+        play_sound_on_entity(world, id, track.decode().unwrap().repeat())
+            .expect("Failed to play sound");
     }
 }
 
@@ -10,6 +10,10 @@ pub enum Error {
     MissingEffect(String),
     #[error("There are no more available sinks")]
     NoAvailableSink,
+    #[error("Spatial audio requires an HRTF library and an audio listener")]
+    MissingSpatialContext,
+    #[error("Invalid duration {0}, expected a finite number of seconds which is not negative")]
+    InvalidDuration(f32),
     #[error(transparent)]
     AudioError(#[from] Arc<ambient_audio::Error>),
 }
@@ -1,7 +1,6 @@
-use ambient_core::asset_cache;
 use ambient_ecs::{EntityId, World};
 
-use crate::{play_sound_on_entity, AudioNode, AudioSeed};
+use crate::{play_sound_on_entity, AudioGraphContext, AudioNode, AudioSeed};
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 /// Plays a sound on an entity. Requires that the entity has an AudioEmitterDef on the server.
 /// Otherwise, the audio is played on a temporary emitter
@@ -14,8 +13,8 @@ pub struct PlayLocalSound {
 }
 
 pub fn play_local_sound(world: &mut World, event: PlayLocalSound) -> anyhow::Result<()> {
-    let assets = world.resource(asset_cache());
-    let source = match event.source.try_build(assets, event.seed).transpose() {
+    let ctx = AudioGraphContext::from_world(world);
+    let source = match event.source.try_build(&ctx, event.seed).transpose() {
         Some(source) => source?,
         None => {
             tracing::warn!("Sound {} is not yet loaded", event.label);
@@ -1,15 +1,27 @@
-use std::fmt::Debug;
+use std::{sync::Arc, time::Duration};
 
-use ambient_audio::{Source, VorbisFromUrl};
+use ambient_audio::{
+    hrtf::HrtfLib, value::Constant, AudioEmitter, AudioListener, BufferedSource, DynamicMix, Frame,
+    SampleRate, Source, Uniform, VorbisFromUrl,
+};
+use ambient_core::asset_cache;
+use ambient_ecs::World;
 use ambient_std::{
     self,
     asset_cache::{AssetCache, AsyncAssetKeyExt},
     asset_url::AbsAssetUrl,
 };
+use parking_lot::Mutex;
 use rand::{thread_rng, Rng, SeedableRng};
 use rand_chacha::ChaCha12Rng;
 
-use crate::error::Result;
+use crate::{
+    error::{Error, Result},
+    get_audio_listener, hrtf_lib,
+};
+
+/// The sample rate used for nodes which do not produce any samples
+const EMPTY_SAMPLE_RATE: SampleRate = 44100;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 /// Textual representation of a node in the audio graph which specifies how to construct a Sound.
@@ -21,6 +33,41 @@ pub enum AudioNode {
         /// Url asset
         url: String,
     },
+    /// Scales the amplitude of the input
+    Gain { input: Box<AudioNode>, gain: f32 },
+    /// Removes the frequencies above `freq` from the input
+    LowPass {
+        input: Box<AudioNode>,
+        freq: f32,
+        bandwidth: f32,
+    },
+    /// Removes the frequencies below `freq` from the input
+    HighPass {
+        input: Box<AudioNode>,
+        freq: f32,
+        bandwidth: f32,
+    },
+    /// Plays all inputs at the same time, until the longest of them has finished
+    Mix { inputs: Vec<AudioNode> },
+    /// Plays the inputs one after another
+    Chain { inputs: Vec<AudioNode> },
+    /// Fades from the end of `from` into `to` over `duration` seconds, which must not be negative
+    Crossfade {
+        from: Box<AudioNode>,
+        to: Box<AudioNode>,
+        duration: f32,
+    },
+    /// Plays the input forever.
+    ///
+    /// The input is rebuilt for every repetition, so any random choices are made again.
+    Repeat { input: Box<AudioNode> },
+    /// Plays one of the choices, picked using the [AudioSeed]
+    RandomChoice { choices: Vec<AudioNode> },
+    /// Plays the input from a fixed point in the world, as heard by the audio listener
+    Spatial {
+        input: Box<AudioNode>,
+        emitter: AudioEmitter,
+    },
 }
 
 impl Default for AudioNode {
@@ -34,22 +81,215 @@ impl AudioNode {
     /// If the graph can not immediately be built, it returns None
     pub fn try_build(
         self,
-        assets: &AssetCache,
-        _seed: AudioSeed,
+        ctx: &AudioGraphContext,
+        seed: AudioSeed,
     ) -> Result<Option<Box<dyn Source>>> {
-        match self {
+        let mut rng = ChaCha12Rng::from_seed(seed.rng_seed);
+        self.build(ctx, &mut rng)
+    }
+
+    fn build(
+        &self,
+        ctx: &AudioGraphContext,
+        rng: &mut ChaCha12Rng,
+    ) -> Result<Option<Box<dyn Source>>> {
+        let source: Box<dyn Source> = match self {
+            AudioNode::Identity => empty_source(),
             AudioNode::Vorbis { url } => {
                 let track = VorbisFromUrl {
                     url: AbsAssetUrl::parse(url).unwrap(),
                 }
-                .peek(assets)
+                .peek(&ctx.assets)
                 .transpose()?;
                 match track {
-                    Some(track) => Ok(Some(Box::new(track.decode()))),
-                    None => Ok(None),
+                    Some(track) => Box::new(track.decode().map_err(Arc::new)?),
+                    None => return Ok(None),
+                }
+            }
+            AudioNode::Gain { input, gain } => {
+                let Some(input) = input.build(ctx, rng)? else {
+                    return Ok(None);
+                };
+                Box::new(input.gain(*gain))
+            }
+            AudioNode::LowPass {
+                input,
+                freq,
+                bandwidth,
+            } => {
+                let Some(input) = input.build(ctx, rng)? else {
+                    return Ok(None);
+                };
+                Box::new(input.low_pass(*freq, *bandwidth))
+            }
+            AudioNode::HighPass {
+                input,
+                freq,
+                bandwidth,
+            } => {
+                let Some(input) = input.build(ctx, rng)? else {
+                    return Ok(None);
+                };
+                Box::new(input.high_pass(*freq, *bandwidth))
+            }
+            AudioNode::Mix { inputs } => {
+                let Some(sources) = build_all(inputs, ctx, rng)? else {
+                    return Ok(None);
+                };
+                if sources.is_empty() {
+                    return Ok(Some(empty_source()));
                 }
+
+                // Mixing stops as soon as any input has finished, so pad them to the longest one
+                let longest = sources
+                    .iter()
+                    .map(|source| source.duration())
+                    .collect::<Option<Vec<_>>>()
+                    .and_then(|durations| durations.into_iter().max());
+                let sources = match longest {
+                    Some(longest) => sources
+                        .into_iter()
+                        .map(|source| Box::new(source.pad_to(longest)) as Box<dyn Source>)
+                        .collect(),
+                    None => sources,
+                };
+
+                let weights = Arc::new(Mutex::new(vec![1.0; sources.len()].into_boxed_slice()));
+                Box::new(DynamicMix::new(sources, weights))
+            }
+            AudioNode::Chain { inputs } => {
+                let Some(sources) = build_all(inputs, ctx, rng)? else {
+                    return Ok(None);
+                };
+                sources
+                    .into_iter()
+                    .reduce(|acc, source| Box::new(acc.chain(source)) as Box<dyn Source>)
+                    .unwrap_or_else(empty_source)
+            }
+            AudioNode::Crossfade { from, to, duration } => {
+                let duration = Duration::try_from_secs_f32(*duration)
+                    .map_err(|_| Error::InvalidDuration(*duration))?;
+                let Some(from) = from.build(ctx, rng)? else {
+                    return Ok(None);
+                };
+                let Some(to) = to.build(ctx, rng)? else {
+                    return Ok(None);
+                };
+                Box::new(from.crossfade(to, duration))
+            }
+            AudioNode::Repeat { input } => {
+                // The repetitions get their own random state, so that the rest of the graph is
+                // built the same way regardless of how many times the input repeats
+                let mut rng = ChaCha12Rng::seed_from_u64(rng.gen());
+                let Some(source) = input.build(ctx, &mut rng)? else {
+                    return Ok(None);
+                };
+                let sample_rate = source.sample_rate();
+                Box::new(RepeatNode {
+                    node: (**input).clone(),
+                    ctx: ctx.clone(),
+                    rng,
+                    sample_rate,
+                    source: Uniform::new(source, sample_rate),
+                })
             }
-            _ => unimplemented!(),
+            AudioNode::RandomChoice { choices } => {
+                if choices.is_empty() {
+                    return Ok(Some(empty_source()));
+                }
+                let choice = &choices[rng.gen_range(0..choices.len())];
+                return choice.build(ctx, rng);
+            }
+            AudioNode::Spatial { input, emitter } => {
+                let (Some(hrtf_lib), Some(listener)) = (&ctx.hrtf_lib, &ctx.listener) else {
+                    return Err(Error::MissingSpatialContext);
+                };
+                let Some(input) = input.build(ctx, rng)? else {
+                    return Ok(None);
+                };
+                Box::new(input.spatial(hrtf_lib, listener.clone(), Constant(*emitter)))
+            }
+        };
+
+        Ok(Some(source))
+    }
+}
+
+/// Builds every node, returning None if any of them can not be built yet
+fn build_all(
+    nodes: &[AudioNode],
+    ctx: &AudioGraphContext,
+    rng: &mut ChaCha12Rng,
+) -> Result<Option<Vec<Box<dyn Source>>>> {
+    let mut sources = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let Some(source) = node.build(ctx, rng)? else {
+            return Ok(None);
+        };
+        sources.push(source);
+    }
+    Ok(Some(sources))
+}
+
+fn empty_source() -> Box<dyn Source> {
+    Box::new(BufferedSource::new(Vec::<f32>::new(), 2, EMPTY_SAMPLE_RATE))
+}
+
+/// Plays a node over and over again, building a new source for each repetition
+struct RepeatNode {
+    node: AudioNode,
+    ctx: AudioGraphContext,
+    rng: ChaCha12Rng,
+    sample_rate: SampleRate,
+    source: Uniform<Box<dyn Source>>,
+}
+
+impl Source for RepeatNode {
+    fn next_sample(&mut self) -> Option<Frame> {
+        if let Some(sample) = self.source.next_sample() {
+            return Some(sample);
+        }
+
+        // Stop rather than spinning forever if the node does not produce any samples
+        let source = self.node.build(&self.ctx, &mut self.rng).ok().flatten()?;
+        self.source = Uniform::new(source, self.sample_rate);
+        self.source.next_sample()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn sample_count(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// The resources required to build an [AudioNode] graph
+#[derive(Clone)]
+pub struct AudioGraphContext {
+    pub assets: AssetCache,
+    /// Required by [AudioNode::Spatial]
+    pub hrtf_lib: Option<Arc<HrtfLib>>,
+    /// Required by [AudioNode::Spatial]
+    pub listener: Option<Arc<Mutex<AudioListener>>>,
+}
+
+impl AudioGraphContext {
+    pub fn new(assets: AssetCache) -> Self {
+        Self {
+            assets,
+            hrtf_lib: None,
+            listener: None,
+        }
+    }
+
+    /// Uses the assets, HRTF library and audio listener of the world
+    pub fn from_world(world: &World) -> Self {
+        Self {
+            assets: world.resource(asset_cache()).clone(),
+            hrtf_lib: world.resource_opt(hrtf_lib()).cloned(),
+            listener: get_audio_listener(world).ok().cloned(),
         }
     }
 }
@@ -73,3 +313,267 @@ impl Default for AudioSeed {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use ambient_std::asset_cache::AsyncAssetKey;
+    use itertools::Itertools;
+    use tokio::runtime::Runtime;
+
+    use super::*;
+
+    const PING_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/test_data/ping.ogg");
+
+    fn runtime() -> Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    fn ping_url() -> AbsAssetUrl {
+        AbsAssetUrl::from_file_path(PING_PATH)
+    }
+
+    fn ping() -> AudioNode {
+        AudioNode::Vorbis {
+            url: ping_url().to_string(),
+        }
+    }
+
+    /// A context in which the ping track has already been loaded
+    fn context(runtime: &Runtime) -> AudioGraphContext {
+        let assets = AssetCache::new(runtime.handle().clone());
+        let key = VorbisFromUrl { url: ping_url() }.in_background();
+        runtime.block_on(key.get(&assets)).unwrap();
+        AudioGraphContext::new(assets)
+    }
+
+    fn seed(value: u8) -> AudioSeed {
+        AudioSeed {
+            rng_seed: [value; 32],
+        }
+    }
+
+    fn build(ctx: &AudioGraphContext, node: AudioNode) -> Box<dyn Source> {
+        node.try_build(ctx, seed(0)).unwrap().unwrap()
+    }
+
+    fn samples(ctx: &AudioGraphContext, node: AudioNode) -> Vec<Frame> {
+        build(ctx, node).samples_iter().collect_vec()
+    }
+
+    #[test]
+    fn identity() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        assert!(samples(&ctx, AudioNode::Identity).is_empty());
+    }
+
+    #[test]
+    fn vorbis() {
+        let runtime = runtime();
+
+        // The graph can not be built until the track has been loaded
+        let unloaded = AudioGraphContext::new(AssetCache::new(runtime.handle().clone()));
+        assert!(ping().try_build(&unloaded, seed(0)).unwrap().is_none());
+
+        let ctx = context(&runtime);
+        let source = build(&ctx, ping());
+        let len = source.sample_count().unwrap() as usize;
+        assert!(len > 0);
+        assert_eq!(source.samples_iter().count(), len);
+    }
+
+    #[test]
+    fn gain() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        let input = samples(&ctx, ping());
+        let output = samples(
+            &ctx,
+            AudioNode::Gain {
+                input: Box::new(ping()),
+                gain: 0.5,
+            },
+        );
+        assert_eq!(output.len(), input.len());
+        for (input, output) in input.iter().zip(&output) {
+            assert!((*input * 0.5 - *output).abs().max_element() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn filters() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        let len = samples(&ctx, ping()).len();
+        let low_pass = samples(
+            &ctx,
+            AudioNode::LowPass {
+                input: Box::new(ping()),
+                freq: 500.0,
+                bandwidth: 1.0,
+            },
+        );
+        let high_pass = samples(
+            &ctx,
+            AudioNode::HighPass {
+                input: Box::new(ping()),
+                freq: 500.0,
+                bandwidth: 1.0,
+            },
+        );
+        assert_eq!(low_pass.len(), len);
+        assert_eq!(high_pass.len(), len);
+        assert_ne!(low_pass, high_pass);
+    }
+
+    #[test]
+    fn mix_plays_until_the_longest_input_has_finished() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        let len = samples(&ctx, ping()).len();
+        let mixed = samples(
+            &ctx,
+            AudioNode::Mix {
+                inputs: vec![
+                    ping(),
+                    AudioNode::Chain {
+                        inputs: vec![ping(), ping()],
+                    },
+                ],
+            },
+        );
+        assert_eq!(mixed.len(), 2 * len);
+        assert!(samples(&ctx, AudioNode::Mix { inputs: vec![] }).is_empty());
+    }
+
+    #[test]
+    fn chain() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        let input = samples(&ctx, ping());
+        let chained = samples(
+            &ctx,
+            AudioNode::Chain {
+                inputs: vec![ping(), ping()],
+            },
+        );
+        assert_eq!(chained, [input.clone(), input].concat());
+        assert!(samples(&ctx, AudioNode::Chain { inputs: vec![] }).is_empty());
+    }
+
+    #[test]
+    fn crossfade() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        let len = samples(&ctx, ping()).len();
+        let crossfade = |duration| AudioNode::Crossfade {
+            from: Box::new(ping()),
+            to: Box::new(ping()),
+            duration,
+        };
+
+        let faded = samples(&ctx, crossfade(0.01)).len();
+        assert!(faded > len && faded < 2 * len);
+        // Crossfades which are longer than `from` fade over all of it
+        assert_eq!(samples(&ctx, crossfade(1000.0)).len(), len);
+
+        for duration in [-1.0, f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                crossfade(duration).try_build(&ctx, seed(0)),
+                Err(Error::InvalidDuration(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn repeat() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        let input = samples(&ctx, ping());
+        let repeated = build(
+            &ctx,
+            AudioNode::Repeat {
+                input: Box::new(ping()),
+            },
+        )
+        .samples_iter()
+        .take(3 * input.len())
+        .collect_vec();
+        assert_eq!(repeated, input.repeat(3));
+
+        // A repeated node which does not produce any samples ends instead of spinning forever
+        let empty = AudioNode::Repeat {
+            input: Box::new(AudioNode::Identity),
+        };
+        assert!(samples(&ctx, empty).is_empty());
+    }
+
+    #[test]
+    fn random_choice() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        let len = samples(&ctx, ping()).len();
+        // Every choice has a different length, so the picked choice can be told from the output
+        let choice = AudioNode::RandomChoice {
+            choices: (1..=4)
+                .map(|count| AudioNode::Chain {
+                    inputs: vec![ping(); count],
+                })
+                .collect(),
+        };
+
+        let picked = (0..16)
+            .map(|value| {
+                let source = choice.clone().try_build(&ctx, seed(value));
+                source.unwrap().unwrap().samples_iter().count() / len
+            })
+            .collect_vec();
+        assert!(picked.iter().all(|count| (1..=4).contains(count)));
+        assert!(picked.iter().any(|&count| count != picked[0]));
+        assert!(samples(&ctx, AudioNode::RandomChoice { choices: vec![] }).is_empty());
+    }
+
+    #[test]
+    fn the_same_seed_builds_the_same_output() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        let len = samples(&ctx, ping()).len();
+        let node = AudioNode::Repeat {
+            input: Box::new(AudioNode::RandomChoice {
+                choices: vec![
+                    ping(),
+                    AudioNode::Gain {
+                        input: Box::new(ping()),
+                        gain: 0.5,
+                    },
+                    AudioNode::Identity,
+                ],
+            }),
+        };
+        let render = |seed: AudioSeed| {
+            let source = node.clone().try_build(&ctx, seed).unwrap().unwrap();
+            source.samples_iter().take(8 * len).collect_vec()
+        };
+
+        for value in 0..4 {
+            assert_eq!(render(seed(value)), render(seed(value)));
+        }
+    }
+
+    #[test]
+    fn spatial_requires_a_listener() {
+        let runtime = runtime();
+        let ctx = context(&runtime);
+        let node = AudioNode::Spatial {
+            input: Box::new(ping()),
+            emitter: AudioEmitter::default(),
+        };
+        assert!(matches!(
+            node.try_build(&ctx, seed(0)),
+            Err(Error::MissingSpatialContext)
+        ));
+    }
+}
@@ -1,10 +1,14 @@
 mod error;
 mod events;
 mod graph;
+mod offline;
+mod player;
 mod sounds;
 pub mod systems;
 pub use ambient_audio as core;
 pub use error::*;
 pub use events::*;
 pub use graph::*;
+pub use offline::*;
+pub use player::*;
 pub use sounds::*;
@@ -0,0 +1,245 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use ambient_audio::{Frame, OfflineStream, SampleRate};
+use ambient_ecs::World;
+use anyhow::Context;
+use flume::{Receiver, Sender};
+
+use crate::{audio_buses, audio_mixer, audio_sender, sound_controls, AudioMessage, AudioPlayer};
+
+/// Renders the audio of a world into memory instead of playing it on an output device.
+///
+/// The world is driven by the same audio systems and [AudioMessage]s as on the client, which
+/// makes it possible to test mixing, spatialisation and audio graphs on machines without a sound
+/// card. Rendering only advances when [OfflineAudio::render] is called, so the output is
+/// deterministic.
+pub struct OfflineAudio {
+    stream: OfflineStream,
+    player: AudioPlayer,
+    sender: Arc<Sender<AudioMessage>>,
+    receiver: Receiver<AudioMessage>,
+}
+
+impl OfflineAudio {
+    pub fn new(sample_rate: SampleRate) -> Self {
+        let stream = OfflineStream::new(sample_rate);
+        let player = AudioPlayer::new(stream.mixer().clone());
+        let (sender, receiver) = flume::unbounded();
+
+        Self {
+            stream,
+            player,
+            sender: Arc::new(sender),
+            receiver,
+        }
+    }
+
+    /// Adds the audio resources to the world, so that its sounds are rendered by this
+    pub fn install(&self, world: &mut World) {
+        world.add_resource(audio_sender(), self.sender.clone());
+        world.add_resource(audio_mixer(), self.stream.mixer().clone());
+        world.add_resource(sound_controls(), Default::default());
+        world.add_resource(audio_buses(), Default::default());
+    }
+
+    pub fn sender(&self) -> &Arc<Sender<AudioMessage>> {
+        &self.sender
+    }
+
+    pub fn stream(&self) -> &OfflineStream {
+        &self.stream
+    }
+
+    /// Handles the messages sent since the last render, then renders the next `duration` of
+    /// audio
+    pub fn render(&mut self, duration: Duration) -> Vec<Frame> {
+        for message in self.receiver.try_iter() {
+            self.player.handle(message);
+        }
+
+        self.stream.render(duration)
+    }
+
+    /// Renders the next `duration` of audio into a WAV file
+    pub fn render_wav(&mut self, duration: Duration, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let frames = self.render(duration);
+
+        let file =
+            std::fs::File::create(path).with_context(|| format!("Failed to create {path:?}"))?;
+        ambient_audio::write_wav(
+            std::io::BufWriter::new(file),
+            &frames,
+            self.stream.sample_rate(),
+        )
+        .with_context(|| format!("Failed to write {path:?}"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, io::Cursor};
+
+    use ambient_audio::{track::Track, BusSettings, Source};
+    use ambient_ecs::{FrameEvent, System};
+    use ambient_std::asset_url::AbsAssetUrl;
+
+    use super::*;
+    use crate::{systems::audio_bus_systems, SoundControls};
+
+    /// The output of [render_world], which can be rewritten by running the test with
+    /// `AMBIENT_BLESS_GOLDEN=1` after changing how audio is mixed
+    const GOLDEN_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/test_data/offline_world.wav"
+    );
+
+    fn track(value: f32) -> Arc<Track> {
+        let mut bytes = Cursor::new(Vec::new());
+        ambient_audio::write_wav(&mut bytes, &vec![Frame::splat(value); 4410], 44100).unwrap();
+        Arc::new(Track::from_wav(bytes.into_inner()).unwrap())
+    }
+
+    fn play(audio: &OfflineAudio, uid: u32, value: f32, bus: Option<&str>) {
+        audio
+            .sender()
+            .send(AudioMessage::Track {
+                track: track(value),
+                url: AbsAssetUrl::parse("http://localhost/sound.wav").unwrap(),
+                uid,
+                looping: false,
+                start: Duration::ZERO,
+                controls: SoundControls::new(1.0, 1.0, Default::default()),
+                spatial: None,
+                bus: bus.map(Into::into),
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn render() {
+        let mut audio = OfflineAudio::new(44100);
+        audio.stream().mixer().set_limiter(None);
+        audio
+            .sender()
+            .send(AudioMessage::UpdateBuses(HashMap::from([(
+                "music".to_string(),
+                BusSettings {
+                    volume: 0.5,
+                    ..Default::default()
+                },
+            )])))
+            .unwrap();
+        play(&audio, 0, 0.25, None);
+        play(&audio, 1, 0.5, Some("music"));
+
+        let frames = audio.render(Duration::from_millis(200));
+        assert_eq!(frames.len(), 8820);
+        assert_eq!(frames[0], Frame::splat(0.5));
+        assert_eq!(frames[4409], Frame::splat(0.5));
+        assert_eq!(frames[4410], Frame::ZERO);
+
+        // Stopping a sound takes effect at the next render
+        play(&audio, 2, 0.25, None);
+        audio.sender().send(AudioMessage::StopById(2)).unwrap();
+        assert!(audio
+            .render(Duration::from_millis(100))
+            .iter()
+            .all(|v| *v == Frame::ZERO));
+    }
+
+    #[test]
+    fn removed_buses_are_removed_from_the_mixer() {
+        let mut audio = OfflineAudio::new(44100);
+        audio.stream().mixer().set_limiter(None);
+        let sender = audio.sender().clone();
+        let update_buses = |buses: HashMap<String, BusSettings>| {
+            sender.send(AudioMessage::UpdateBuses(buses)).unwrap()
+        };
+        update_buses(HashMap::from([(
+            "music".to_string(),
+            BusSettings {
+                volume: 0.5,
+                ..Default::default()
+            },
+        )]));
+        play(&audio, 0, 0.5, Some("music"));
+        assert_eq!(
+            audio.render(Duration::from_millis(10))[0],
+            Frame::splat(0.25)
+        );
+
+        update_buses(HashMap::new());
+        assert_eq!(
+            audio.render(Duration::from_millis(10))[0],
+            Frame::splat(0.5)
+        );
+        assert_eq!(audio.stream().mixer().bus_settings("music"), None);
+    }
+
+    #[test]
+    fn render_world() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        crate::init_components();
+
+        let mut world = World::new_with_config("test", false);
+        let mut audio = OfflineAudio::new(44100);
+        audio.stream().mixer().set_limiter(None);
+        audio.install(&mut world);
+        world.resource_mut(audio_buses()).insert(
+            "music".to_string(),
+            BusSettings {
+                volume: 0.5,
+                ..Default::default()
+            },
+        );
+
+        let mut systems = audio_bus_systems();
+        let mut frames = Vec::new();
+        for step in 0..8 {
+            if step == 2 {
+                world
+                    .resource_mut(audio_buses())
+                    .get_mut("music")
+                    .unwrap()
+                    .volume = 0.25;
+            }
+            systems.run(&mut world, &FrameEvent);
+            world.next_frame();
+
+            match step {
+                0 => {
+                    play(&audio, 0, 0.25, None);
+                    play(&audio, 1, 0.5, Some("music"));
+                }
+                4 => world
+                    .resource(audio_sender())
+                    .send(AudioMessage::StopById(0))
+                    .unwrap(),
+                _ => {}
+            }
+            frames.extend(audio.render(Duration::from_millis(20)));
+        }
+
+        if std::env::var_os("AMBIENT_BLESS_GOLDEN").is_some() {
+            let file = std::fs::File::create(GOLDEN_PATH).unwrap();
+            ambient_audio::write_wav(std::io::BufWriter::new(file), &frames, 44100).unwrap();
+            return;
+        }
+
+        let golden = Track::from_wav(std::fs::read(GOLDEN_PATH).unwrap())
+            .unwrap()
+            .decode()
+            .unwrap()
+            .samples_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(frames.len(), golden.len());
+        for (i, (actual, expected)) in frames.iter().zip(&golden).enumerate() {
+            assert!(
+                actual.abs_diff_eq(*expected, 1e-5),
+                "frame {i} is {actual}, expected {expected}"
+            );
+        }
+    }
+}
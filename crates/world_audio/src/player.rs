@@ -0,0 +1,106 @@
+use std::collections::{HashMap, HashSet};
+
+use ambient_audio::AudioMixer;
+
+use crate::{AudioMessage, SoundInfo};
+
+/// Plays the sounds requested through [AudioMessage]s on a mixer
+pub struct AudioPlayer {
+    mixer: AudioMixer,
+    sounds: HashMap<u32, SoundInfo>,
+    /// The buses of the last [AudioMessage::UpdateBuses]
+    buses: HashSet<String>,
+}
+
+impl AudioPlayer {
+    pub fn new(mixer: AudioMixer) -> Self {
+        Self {
+            mixer,
+            sounds: HashMap::new(),
+            buses: HashSet::new(),
+        }
+    }
+
+    pub fn mixer(&self) -> &AudioMixer {
+        &self.mixer
+    }
+
+    pub fn handle(&mut self, message: AudioMessage) {
+        match message {
+            AudioMessage::Spatial(source) => {
+                self.mixer.play(source);
+            }
+            AudioMessage::Track {
+                track,
+                url,
+                uid,
+                looping,
+                start,
+                controls,
+                spatial,
+                bus,
+            } => {
+                let mut sound_info = SoundInfo {
+                    url,
+                    track,
+                    looping,
+                    controls,
+                    spatial,
+                    bus,
+                    id: Default::default(),
+                };
+                sound_info.play(&self.mixer, start);
+                self.sounds.insert(uid, sound_info);
+            }
+            AudioMessage::UpdateVolume(target_url, amp) => {
+                for info in self.sounds.values_mut() {
+                    if info.url == target_url {
+                        *info.controls.gain.lock() = amp;
+                    }
+                }
+            }
+            AudioMessage::UpdateBuses(buses) => {
+                for name in &self.buses {
+                    if !buses.contains_key(name) {
+                        self.mixer.remove_bus(name);
+                    }
+                }
+                self.buses = buses.keys().cloned().collect();
+                for (name, settings) in buses {
+                    self.mixer.set_bus(&name, settings);
+                }
+            }
+            AudioMessage::Seek(uid, position) => {
+                let Some(info) = self.sounds.get_mut(&uid) else {
+                    log::error!("No sound with id {}", uid);
+                    return;
+                };
+                self.mixer.stop(info.id);
+                info.play(&self.mixer, position);
+            }
+            AudioMessage::Stop(target_url) => {
+                let keys_to_remove = self
+                    .sounds
+                    .iter()
+                    .filter(|(_, info)| info.url == target_url)
+                    .map(|(&key, _)| key)
+                    .collect::<Vec<_>>();
+
+                for key in keys_to_remove {
+                    if let Some(info) = self.sounds.remove(&key) {
+                        self.mixer.stop(info.id);
+                        info.controls.progress.set_finished();
+                    }
+                }
+            }
+            AudioMessage::StopById(uid) => {
+                let Some(info) = self.sounds.remove(&uid) else {
+                    log::error!("No sound with id {}", uid);
+                    return;
+                };
+                self.mixer.stop(info.id);
+                info.controls.progress.set_finished();
+            }
+        }
+    }
+}
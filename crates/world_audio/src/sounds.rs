@@ -1,8 +1,11 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use ambient_audio::{
-    hrtf::HrtfLib, track::TrackDecodeStream, Attenuation, AudioEmitter, AudioListener, AudioMixer,
-    Sound, SoundId, Source, Spatial,
+    blt::Filter,
+    hrtf::HrtfLib,
+    track::{Track, TrackDecodeStream},
+    Attenuation, AudioEmitter, AudioListener, AudioMixer, BusSettings, ProgressState, Sound,
+    SoundId, Source, Spatial,
 };
 use ambient_ecs::{components, query, EntityId, Resource, World};
 use ambient_element::ElementComponentExt;
@@ -29,16 +32,23 @@ components!("audio", {
     audio_sender: Arc<flume::Sender<AudioMessage>>,
     @[Resource]
     audio_mixer: AudioMixer,
+    @[Resource]
+    sound_controls: Arc<Mutex<HashMap<u32, SoundControls>>>,
+    @[Resource]
+    audio_buses: HashMap<String, BusSettings>,
 });
 
 pub enum AudioMessage {
-    Track(
-        Arc<ambient_audio::track::Track>,
-        bool,
-        f32,
-        AbsAssetUrl,
-        u32,
-    ),
+    Track {
+        track: Arc<Track>,
+        url: AbsAssetUrl,
+        uid: u32,
+        looping: bool,
+        start: Duration,
+        controls: SoundControls,
+        spatial: Option<SpatialSound>,
+        bus: Option<String>,
+    },
     Spatial(
         Spatial<
             TrackDecodeStream,
@@ -47,17 +57,109 @@ pub enum AudioMessage {
         >,
     ),
     UpdateVolume(AbsAssetUrl, f32),
+    UpdateBuses(HashMap<String, BusSettings>),
+    Seek(u32, Duration),
     Stop(AbsAssetUrl),
     StopById(u32),
 }
 
 pub struct SoundInfo {
     pub url: AbsAssetUrl,
+    pub track: Arc<Track>,
     pub looping: bool,
-    pub gain: Arc<Mutex<f32>>,
+    pub controls: SoundControls,
+    pub spatial: Option<SpatialSound>,
+    /// The mixer bus to play the sound on
+    pub bus: Option<String>,
     pub id: SoundId,
 }
 
+impl SoundInfo {
+    /// Plays the sound on its bus, starting `start` into the track
+    pub fn play(&mut self, mixer: &AudioMixer, start: Duration) {
+        let source = match self.source(start) {
+            Ok(source) => source,
+            Err(err) => {
+                log::error!("Failed to play {}: {err}", self.url);
+                return;
+            }
+        };
+        self.id = match &self.bus {
+            Some(bus) => mixer.play_on_bus(bus, source).id,
+            None => mixer.play(source).id,
+        };
+    }
+
+    /// Builds the source for this sound, starting `start` into the track
+    pub fn source(&self, start: Duration) -> ambient_audio::Result<Box<dyn Source>> {
+        let decoded = self.track.decode()?;
+        let duration = decoded.duration();
+
+        // Make sure that skipping does not go past the end of the track
+        let start = match duration {
+            Some(duration) if self.looping && !duration.is_zero() => {
+                Duration::from_nanos((start.as_nanos() % duration.as_nanos()) as u64)
+            }
+            Some(duration) => start.min(duration),
+            None => start,
+        };
+
+        let source: Box<dyn Source> = if self.looping {
+            Box::new(decoded.repeat().skip(start))
+        } else {
+            Box::new(decoded.skip(start))
+        };
+
+        let controls = &self.controls;
+        let source = source.progress(controls.progress.clone(), start);
+        if let (true, Some(duration)) = (self.looping, duration) {
+            controls.progress.set_period(duration);
+        }
+
+        let source = source
+            .blt(controls.filter.clone())
+            .playback_rate(controls.playback_rate.clone())
+            .gain(controls.gain.clone());
+
+        Ok(match &self.spatial {
+            Some(spatial) => Box::new(source.spatial(
+                &spatial.hrtf_lib,
+                spatial.listener.clone(),
+                spatial.emitter.clone(),
+            )),
+            None => Box::new(source),
+        })
+    }
+}
+
+/// Controls for a sound played by a module, which can be changed while the sound is playing
+#[derive(Debug, Clone)]
+pub struct SoundControls {
+    pub gain: Arc<Mutex<f32>>,
+    pub playback_rate: Arc<Mutex<f32>>,
+    pub filter: Arc<Mutex<Filter>>,
+    pub progress: Arc<ProgressState>,
+}
+
+impl SoundControls {
+    pub fn new(gain: f32, playback_rate: f32, filter: Filter) -> Self {
+        Self {
+            gain: Arc::new(Mutex::new(gain)),
+            playback_rate: Arc::new(Mutex::new(playback_rate)),
+            filter: Arc::new(Mutex::new(filter)),
+            progress: Default::default(),
+        }
+    }
+}
+
+/// The resources needed to play a sound from an emitter in the world
+#[derive(Clone)]
+pub struct SpatialSound {
+    pub hrtf_lib: Arc<HrtfLib>,
+    pub listener: Arc<Mutex<AudioListener>>,
+    pub emitter: Arc<Mutex<AudioEmitter>>,
+}
+
 /// TODO: hook this into the Attenuation inside ambient_audio
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, DerefMut, Deref, From, Into)]
 pub struct AttenuationEditorVisual(Attenuation);
@@ -5,7 +5,7 @@ use ambient_core::transform::local_to_world;
 use ambient_ecs::{query, SystemGroup, World};
 use glam::{vec4, Mat4};
 
-use crate::{audio_emitter, audio_listener, hrtf_lib};
+use crate::{audio_buses, audio_emitter, audio_listener, audio_sender, hrtf_lib, AudioMessage};
 
 /// Initializes the HRTF sphere and adds the appropriate resources
 ///
@@ -52,6 +52,20 @@ pub fn spatial_audio_systems() -> SystemGroup {
     )
 }
 
+/// Sends changes to the [audio_buses] resource to the audio thread
+pub fn audio_bus_systems() -> SystemGroup {
+    SystemGroup::new(
+        "audio_buses",
+        vec![query(audio_buses().changed()).to_system(|q, world, qs, _| {
+            for (_, buses) in q.iter(world, qs) {
+                if let Some(sender) = world.resource_opt(audio_sender()) {
+                    sender.send(AudioMessage::UpdateBuses(buses.clone())).ok();
+                }
+            }
+        })],
+    )
+}
+
 pub fn client_systems() -> SystemGroup {
     SystemGroup::new("Spatial audio", vec![Box::new(spatial_audio_systems())])
 }
@@ -239,7 +239,19 @@ export type Pipeline = {
     /// Will import supported audio file formats and produce Ogg Vorbis or WAV files to be used by the runtime.
     type: "Audio",
     /// Whether or not the audio should be converted to Ogg Vorbis.
+    ///
+    /// FLAC and MP3 files are always converted.
     convert?: boolean,
+    /// The quality of the Ogg Vorbis encoding, from -0.1 (smallest files) to 1.0 (best quality).
+    /// Defaults to 0.9.
+    quality?: f32,
+    /// The sample rate to resample converted audio to, in Hz.
+    /// By default, the sample rate of the source file is kept.
+    sample_rate?: u32,
+    /// The integrated loudness to normalize converted audio to, in LUFS (e.g. -16).
+    /// The gain is reduced if the peaks of the audio would otherwise exceed -1 dBFS.
+    /// By default, the loudness is left unchanged.
+    normalize_loudness?: f32,
   },
   /// Filter the sources used to feed this pipeline.
   /// This is a list of glob patterns for accepted files.
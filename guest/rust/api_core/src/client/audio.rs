@@ -1,4 +1,7 @@
-use crate::internal::wit;
+use crate::{
+    global::EntityId,
+    internal::{conversion::IntoBindgen, wit},
+};
 
 /// Load an audio file from `url`, and return an [AudioTrack] that can be used to play the audio.
 pub fn load(url: String) -> AudioTrack {
@@ -8,6 +11,189 @@ pub fn load(url: String) -> AudioTrack {
         name: actuall_url,
         looping: false,
         volume: 1.0,
+        playback_rate: 1.0,
+        filter: AudioFilter::None,
+        start_position: 0.0,
+        emitter: None,
+        bus: None,
+    }
+}
+
+/// A filter that can be applied to the audio of a track.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AudioFilter {
+    /// The audio is not filtered.
+    #[default]
+    None,
+    /// Removes the frequencies above `freq`. This can be used to muffle audio, e.g. when underwater.
+    LowPass {
+        /// The cutoff frequency, in Hz
+        freq: f32,
+        /// The bandwidth of the filter, in octaves
+        bandwidth: f32,
+    },
+    /// Removes the frequencies below `freq`.
+    HighPass {
+        /// The cutoff frequency, in Hz
+        freq: f32,
+        /// The bandwidth of the filter, in octaves
+        bandwidth: f32,
+    },
+    /// Removes the frequencies that are not close to `freq`.
+    BandPass {
+        /// The center frequency, in Hz
+        freq: f32,
+        /// The bandwidth of the filter, in octaves
+        bandwidth: f32,
+    },
+}
+impl IntoBindgen for AudioFilter {
+    type Item = wit::client_audio::Filter;
+
+    fn into_bindgen(self) -> Self::Item {
+        use wit::client_audio::{Filter, FilterKind};
+
+        let (kind, freq, bandwidth) = match self {
+            AudioFilter::None => (FilterKind::None, 0.0, 0.0),
+            AudioFilter::LowPass { freq, bandwidth } => (FilterKind::LowPass, freq, bandwidth),
+            AudioFilter::HighPass { freq, bandwidth } => (FilterKind::HighPass, freq, bandwidth),
+            AudioFilter::BandPass { freq, bandwidth } => (FilterKind::BandPass, freq, bandwidth),
+        };
+        Filter {
+            kind,
+            freq,
+            bandwidth,
+        }
+    }
+}
+
+/// An effect that can be applied to everything played on an [AudioBus].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AudioEffect {
+    /// Filters the audio. See [AudioFilter].
+    Filter(AudioFilter),
+    /// Repeats the audio as a series of decaying echoes.
+    Delay {
+        /// The time between each echo, in seconds
+        delay: f32,
+        /// How much of each echo is fed back into the next one, in `0..1`
+        feedback: f32,
+        /// The volume of the echoes relative to the input
+        wet: f32,
+    },
+    /// Simulates the reflections of a room.
+    Reverb {
+        /// The size of the room in `0..1`. Larger rooms have a longer tail.
+        room_size: f32,
+        /// How quickly high frequencies are absorbed, in `0..1`
+        damping: f32,
+        /// The volume of the reverberated audio
+        wet: f32,
+        /// The volume of the unprocessed audio
+        dry: f32,
+        /// The stereo width of the reverb in `0..1`
+        width: f32,
+    },
+    /// Reduces the volume of the audio when it is above `threshold`.
+    Compressor {
+        /// The level above which the audio is compressed, in dBFS
+        threshold: f32,
+        /// How much the audio above the threshold is reduced, e.g. `4.0` for 4:1. Use `f32::INFINITY` for a limiter.
+        ratio: f32,
+        /// How quickly the compressor reacts, in seconds
+        attack: f32,
+        /// How quickly the compressor recovers, in seconds
+        release: f32,
+        /// Gain applied after compression, in dB
+        makeup_gain: f32,
+    },
+}
+impl IntoBindgen for AudioEffect {
+    type Item = wit::client_audio::BusEffect;
+
+    fn into_bindgen(self) -> Self::Item {
+        use wit::client_audio::{BusEffect, CompressorEffect, DelayEffect, ReverbEffect};
+
+        match self {
+            AudioEffect::Filter(filter) => BusEffect::Filter(filter.into_bindgen()),
+            AudioEffect::Delay {
+                delay,
+                feedback,
+                wet,
+            } => BusEffect::Delay(DelayEffect {
+                delay,
+                feedback,
+                wet,
+            }),
+            AudioEffect::Reverb {
+                room_size,
+                damping,
+                wet,
+                dry,
+                width,
+            } => BusEffect::Reverb(ReverbEffect {
+                room_size,
+                damping,
+                wet,
+                dry,
+                width,
+            }),
+            AudioEffect::Compressor {
+                threshold,
+                ratio,
+                attack,
+                release,
+                makeup_gain,
+            } => BusEffect::Compressor(CompressorEffect {
+                threshold,
+                ratio,
+                attack,
+                release,
+                makeup_gain,
+            }),
+        }
+    }
+}
+
+/// A named group of audio tracks which share a volume and effects, such as `"music"` or `"sfx"`.
+///
+/// Buses are created when they are first used.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AudioBus {
+    /// The name of the bus
+    pub name: String,
+}
+
+impl AudioBus {
+    /// Refer to the bus called `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    /// Set the volume of everything played on the bus.
+    pub fn set_volume(&self, volume: f32) {
+        wit::client_audio::set_bus_volume(&self.name, volume.max(0.));
+    }
+
+    /// Mute or unmute the bus.
+    pub fn set_muted(&self, muted: bool) {
+        wit::client_audio::set_bus_muted(&self.name, muted);
+    }
+
+    /// Replace the effects of the bus. The effects are applied in order.
+    pub fn set_effects(&self, effects: &[AudioEffect]) {
+        let effects: Vec<_> = effects.iter().map(|v| v.into_bindgen()).collect();
+        wit::client_audio::set_bus_effects(&self.name, &effects);
+    }
+
+    /// Lower the volume of this bus to `gain` while anything is playing on `trigger`, e.g. to
+    /// duck music under dialogue. Passing `None` disables ducking.
+    pub fn set_ducking(&self, trigger: Option<&AudioBus>, gain: f32) {
+        wit::client_audio::set_bus_ducking(
+            &self.name,
+            trigger.map(|bus| bus.name.as_str()),
+            gain.max(0.),
+        );
     }
 }
 
@@ -20,6 +206,16 @@ pub struct AudioTrack {
     pub looping: bool,
     /// The volume of the audio
     pub volume: f32,
+    /// The speed at which the audio is played. This also changes its pitch.
+    pub playback_rate: f32,
+    /// The filter applied to the audio
+    pub filter: AudioFilter,
+    /// The position to start playing from, in seconds
+    pub start_position: f32,
+    /// The entity to play the audio from. If set, the audio will be spatialized around the entity.
+    pub emitter: Option<EntityId>,
+    /// The bus to play the audio on. If not set, the audio is played directly on the mixer.
+    pub bus: Option<AudioBus>,
 }
 
 impl AudioTrack {
@@ -36,10 +232,53 @@ impl AudioTrack {
         self
     }
 
+    /// Set the playback rate of the track. A rate of `2.0` plays the track twice as fast, and an octave higher.
+    /// Rates above `16.0` are clamped.
+    pub fn playback_rate(&mut self, playback_rate: f32) -> &mut Self {
+        self.playback_rate = playback_rate.max(0.);
+        self
+    }
+
+    /// Set the filter applied to the track.
+    pub fn filter(&mut self, filter: AudioFilter) -> &mut Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Set the position to start playing the track from, in seconds.
+    pub fn start_position(&mut self, start_position: f32) -> &mut Self {
+        self.start_position = start_position.max(0.);
+        self
+    }
+
+    /// Play the track from `emitter`, spatializing it around the entity as it moves.
+    pub fn emitter(&mut self, emitter: Option<EntityId>) -> &mut Self {
+        self.emitter = emitter;
+        self
+    }
+
+    /// Play the track on `bus`.
+    pub fn bus(&mut self, bus: Option<AudioBus>) -> &mut Self {
+        self.bus = bus;
+        self
+    }
+
     /// Play the track.
     pub fn play(&self) -> AudioTrackId {
         let uid = rand::random::<u32>();
-        wit::client_audio::play(&self.name, self.looping, self.volume, uid);
+        wit::client_audio::play_with_options(
+            &self.name,
+            wit::client_audio::PlayOptions {
+                looping: self.looping,
+                volume: self.volume,
+                playback_rate: self.playback_rate,
+                filter: self.filter.into_bindgen(),
+                start_position: self.start_position,
+                emitter: self.emitter.into_bindgen(),
+                bus: self.bus.as_ref().map(|bus| bus.name.as_str()),
+            },
+            uid,
+        );
         AudioTrackId { uid }
     }
 
@@ -61,4 +300,36 @@ impl AudioTrackId {
     pub fn stop(&self) {
         wit::client_audio::stop_by_id(self.uid);
     }
+
+    /// Set the volume of the playing track.
+    pub fn set_volume(&self, volume: f32) {
+        wit::client_audio::set_volume_by_id(self.uid, volume.max(0.));
+    }
+
+    /// Set the playback rate of the playing track. This also changes its pitch. Rates above `16.0` are clamped.
+    pub fn set_playback_rate(&self, playback_rate: f32) {
+        wit::client_audio::set_playback_rate(self.uid, playback_rate.max(0.));
+    }
+
+    /// Set the filter applied to the playing track.
+    pub fn set_filter(&self, filter: AudioFilter) {
+        wit::client_audio::set_filter(self.uid, filter.into_bindgen());
+    }
+
+    /// Move the playing track to `position`, in seconds.
+    pub fn seek(&self, position: f32) {
+        wit::client_audio::seek(self.uid, position.max(0.));
+    }
+
+    /// Get the playback position of the track, in seconds.
+    ///
+    /// Returns `None` if the track is not known to the host.
+    pub fn position(&self) -> Option<f32> {
+        wit::client_audio::get_position(self.uid)
+    }
+
+    /// Returns true if the track has finished playing or has been stopped.
+    pub fn is_finished(&self) -> bool {
+        wit::client_audio::is_finished(self.uid)
+    }
 }
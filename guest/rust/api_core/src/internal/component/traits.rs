@@ -8,8 +8,9 @@ use crate::{
         conversion::{FromBindgen, IntoBindgen},
         wit,
     },
+    message::MessageSerde,
 };
-use ambient_shared_types::primitive_component_definitions;
+use ambient_shared_types::{primitive_component_definitions, StructValue};
 use glam::{UVec2, UVec3, UVec4};
 use std::time::Duration;
 
@@ -74,3 +75,140 @@ macro_rules! define_component_types {
 }
 
 primitive_component_definitions!(define_component_types);
+
+/// How values of a [GeneratedType] are stored by the runtime.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeneratedTypeStorage {
+    /// Enums without fields are stored as their discriminant, which allows them to be used in
+    /// `Vec` and `Option` components.
+    Discriminant,
+    /// Enums with fields are stored as their serialized bytes. `Vec` and `Option` components of
+    /// them are serialized as a whole, and stored as bytes as well.
+    Bytes,
+    /// Structs are stored as a [StructValue], and can be used in containers.
+    Struct,
+}
+
+/// Implemented by all enums and structs generated from `ambient.toml`.
+pub trait GeneratedType: MessageSerde {
+    #[doc(hidden)]
+    const STORAGE: GeneratedTypeStorage;
+
+    /// Converts this value to its discriminant, if it is an enum without fields.
+    fn to_u32(&self) -> Option<u32> {
+        None
+    }
+    /// Converts a discriminant to a value, if this is an enum without fields.
+    fn from_u32(_value: u32) -> Option<Self> {
+        None
+    }
+}
+
+fn serialize_generated_type<T: MessageSerde>(value: &T) -> Vec<u8> {
+    let mut output = vec![];
+    value
+        .serialize_message_part(&mut output)
+        .expect("failed to serialize generated type");
+    output
+}
+
+fn deserialize_generated_type<T: MessageSerde>(data: &[u8]) -> Option<T> {
+    T::deserialize_message_part(&mut &data[..]).ok()
+}
+
+fn generated_type_to_u32<T: GeneratedType>(value: &T) -> u32 {
+    value
+        .to_u32()
+        .expect("only enums without fields are stored as their discriminant")
+}
+
+impl<T: GeneratedType> SupportedValue for T {
+    fn from_result(result: wit::component::Value) -> Option<Self> {
+        use wit::component::{Value as V, VecValue as VV};
+        match (T::STORAGE, result) {
+            (GeneratedTypeStorage::Discriminant, V::TypeU32(v)) => T::from_u32(v),
+            (GeneratedTypeStorage::Bytes, V::TypeVec(VV::TypeU8(v))) => {
+                deserialize_generated_type(&v)
+            }
+            (GeneratedTypeStorage::Struct, V::TypeStruct(v)) => deserialize_generated_type(&v.data),
+            _ => None,
+        }
+    }
+
+    fn into_result(self) -> wit::component::Value {
+        use wit::component::{Value as V, VecValue as VV};
+        match T::STORAGE {
+            GeneratedTypeStorage::Discriminant => V::TypeU32(generated_type_to_u32(&self)),
+            GeneratedTypeStorage::Bytes => V::TypeVec(VV::TypeU8(serialize_generated_type(&self))),
+            GeneratedTypeStorage::Struct => {
+                V::TypeStruct(StructValue(serialize_generated_type(&self)).into_bindgen())
+            }
+        }
+    }
+}
+impl<T: GeneratedType> SupportedValue for Vec<T> {
+    fn from_result(result: wit::component::Value) -> Option<Self> {
+        use wit::component::{Value as V, VecValue as VV};
+        match (T::STORAGE, result) {
+            (GeneratedTypeStorage::Discriminant, V::TypeVec(VV::TypeU32(v))) => {
+                v.into_iter().map(T::from_u32).collect()
+            }
+            (GeneratedTypeStorage::Bytes, V::TypeVec(VV::TypeU8(v))) => {
+                deserialize_generated_type(&v)
+            }
+            (GeneratedTypeStorage::Struct, V::TypeVec(VV::TypeStruct(v))) => v
+                .iter()
+                .map(|v| deserialize_generated_type(&v.data))
+                .collect(),
+            _ => None,
+        }
+    }
+
+    fn into_result(self) -> wit::component::Value {
+        use wit::component::{Value as V, VecValue as VV};
+        match T::STORAGE {
+            GeneratedTypeStorage::Discriminant => V::TypeVec(VV::TypeU32(
+                self.iter().map(generated_type_to_u32).collect(),
+            )),
+            GeneratedTypeStorage::Bytes => V::TypeVec(VV::TypeU8(serialize_generated_type(&self))),
+            GeneratedTypeStorage::Struct => V::TypeVec(VV::TypeStruct(
+                self.iter()
+                    .map(|v| StructValue(serialize_generated_type(v)).into_bindgen())
+                    .collect(),
+            )),
+        }
+    }
+}
+impl<T: GeneratedType> SupportedValue for Option<T> {
+    fn from_result(result: wit::component::Value) -> Option<Self> {
+        use wit::component::{OptionValue as OV, Value as V, VecValue as VV};
+        match (T::STORAGE, result) {
+            (GeneratedTypeStorage::Discriminant, V::TypeOption(OV::TypeU32(v))) => match v {
+                Some(v) => Some(Some(T::from_u32(v)?)),
+                None => Some(None),
+            },
+            (GeneratedTypeStorage::Bytes, V::TypeVec(VV::TypeU8(v))) => {
+                deserialize_generated_type(&v)
+            }
+            (GeneratedTypeStorage::Struct, V::TypeOption(OV::TypeStruct(v))) => match v {
+                Some(v) => Some(Some(deserialize_generated_type(&v.data)?)),
+                None => Some(None),
+            },
+            _ => None,
+        }
+    }
+
+    fn into_result(self) -> wit::component::Value {
+        use wit::component::{OptionValue as OV, Value as V, VecValue as VV};
+        match T::STORAGE {
+            GeneratedTypeStorage::Discriminant => {
+                V::TypeOption(OV::TypeU32(self.as_ref().map(generated_type_to_u32)))
+            }
+            GeneratedTypeStorage::Bytes => V::TypeVec(VV::TypeU8(serialize_generated_type(&self))),
+            GeneratedTypeStorage::Struct => V::TypeOption(OV::TypeStruct(
+                self.map(|v| StructValue(serialize_generated_type(&v)).into_bindgen()),
+            )),
+        }
+    }
+}
@@ -7,7 +7,7 @@ use crate::{
     },
     internal::wit,
 };
-use ambient_shared_types::procedural_storage_handle_definitions;
+use ambient_shared_types::{procedural_storage_handle_definitions, StructValue};
 use glam::{UVec2, UVec3, UVec4};
 use paste::paste;
 
@@ -184,6 +184,19 @@ impl FromBindgen for wit::types::Duration {
     }
 }
 
+impl IntoBindgen for StructValue {
+    type Item = wit::types::StructValue;
+    fn into_bindgen(self) -> Self::Item {
+        wit::types::StructValue { data: self.0 }
+    }
+}
+impl FromBindgen for wit::types::StructValue {
+    type Item = StructValue;
+    fn from_bindgen(self) -> Self::Item {
+        StructValue(self.data)
+    }
+}
+
 macro_rules! bindgen_passthrough {
     ($type:ty) => {
         impl IntoBindgen for $type {
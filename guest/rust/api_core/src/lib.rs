@@ -27,6 +27,8 @@ pub mod global;
 pub mod message;
 /// Player-specific functionality.
 pub mod player;
+/// Client-side prediction of the entities controlled by players.
+pub mod prediction;
 
 /// Helpful imports that almost all Ambient projects will use.
 pub mod prelude;
@@ -37,6 +39,8 @@ pub mod animation;
 /// Internal implementation details.
 mod internal;
 
+mod input;
+
 pub use ambient_api_macros::main;
 
 pub use internal::generated::*;
@@ -0,0 +1,32 @@
+use crate::{
+    global::EntityId,
+    internal::{conversion::FromBindgen, wit},
+    message::RuntimeMessage,
+    messages::PredictionStep,
+};
+
+pub use crate::input::{Input, InputDelta, KeyCode, MouseButton};
+
+/// Makes `model` the prediction model, which moves the entities with the `predicted` component
+/// according to the input of the player who owns them. It is called with the entity, the input of
+/// one frame, and how many seconds that frame lasted.
+///
+/// Each client applies the model to the entities of its player as soon as the input is sampled,
+/// and the server applies it when the input arrives. The model must therefore be set to the same
+/// function by a client module and a server module, and should only change the translation and
+/// rotation of the entity, as only those are corrected when the server's state arrives.
+pub fn set_model(mut model: impl FnMut(EntityId, &Input, f32) + 'static) {
+    wit::prediction::set_model();
+    PredictionStep::subscribe(move |step: PredictionStep| {
+        if let Some(input) = get_input() {
+            model(step.entity, &input, step.dt);
+        }
+    });
+}
+
+/// Gets the input which the prediction model is applying.
+///
+/// Returns `None` outside of the prediction model.
+pub fn get_input() -> Option<Input> {
+    wit::prediction::get_input().from_bindgen()
+}
@@ -0,0 +1,110 @@
+use std::{cell::RefCell, rc::Rc};
+
+use thiserror::Error;
+
+use crate::{global::block_until, internal::wit, message::RuntimeMessage, messages::HttpResponse};
+
+/// The response to an HTTP request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// The status code of the response.
+    pub status: u16,
+    /// The headers of the response, with lowercase names.
+    pub headers: Vec<(String, String)>,
+    /// The body of the response.
+    pub body: Vec<u8>,
+}
+impl Response {
+    /// Returns the value of the first header named `name`, if there is one.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Error, Debug)]
+/// Errors that can occur when sending an HTTP request.
+pub enum HttpError {
+    #[error("URL not allowed: {0}")]
+    /// The URL is not in the `[http]` allowlist of this ember's `ambient.toml`.
+    NotAllowed(String),
+    #[error("Invalid request: {0}")]
+    /// The URL or the headers are invalid.
+    InvalidRequest(String),
+    #[error("Request failed: {0}")]
+    /// The request could not be completed, such as when the server could not be reached or did not respond in time.
+    Failed(String),
+}
+impl From<wit::server_http::HttpError> for HttpError {
+    fn from(value: wit::server_http::HttpError) -> Self {
+        match value {
+            wit::server_http::HttpError::NotAllowed(err) => HttpError::NotAllowed(err),
+            wit::server_http::HttpError::InvalidRequest(err) => HttpError::InvalidRequest(err),
+        }
+    }
+}
+
+/// Sends a GET request to `url` with the given `headers`, and waits for the response.
+///
+/// The URL must be in the `[http]` allowlist of this ember's `ambient.toml`.
+///
+/// This must be used with `.await` in either an `async fn` or an `async` block.
+pub async fn get(url: impl AsRef<str>, headers: &[(&str, &str)]) -> Result<Response, HttpError> {
+    send(wit::server_http::Method::Get, url.as_ref(), headers, &[]).await
+}
+
+/// Sends a POST request with `body` to `url` with the given `headers`, and waits for the response.
+///
+/// The URL must be in the `[http]` allowlist of this ember's `ambient.toml`.
+///
+/// This must be used with `.await` in either an `async fn` or an `async` block.
+pub async fn post(
+    url: impl AsRef<str>,
+    headers: &[(&str, &str)],
+    body: impl AsRef<[u8]>,
+) -> Result<Response, HttpError> {
+    send(
+        wit::server_http::Method::Post,
+        url.as_ref(),
+        headers,
+        body.as_ref(),
+    )
+    .await
+}
+
+async fn send(
+    method: wit::server_http::Method,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Result<Response, HttpError> {
+    let request_id = wit::server_http::send(method, url, headers, body)?;
+
+    let response = Rc::new(RefCell::new(None));
+    let listener = HttpResponse::subscribe({
+        let response = response.clone();
+        move |message: HttpResponse| {
+            if message.request_id == request_id {
+                *response.borrow_mut() = Some(message);
+            }
+        }
+    });
+    block_until(|| response.borrow().is_some()).await;
+    listener.stop();
+
+    let response = response.borrow_mut().take().unwrap();
+    if let Some(err) = response.error {
+        return Err(HttpError::Failed(err));
+    }
+    Ok(Response {
+        status: response.status as u16,
+        headers: response
+            .header_names
+            .into_iter()
+            .zip(response.header_values)
+            .collect(),
+        body: response.body,
+    })
+}
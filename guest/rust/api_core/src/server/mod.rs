@@ -1,2 +1,8 @@
+/// **\[Server-only\]** Outbound HTTP requests to the URLs allowed by the `[http]` section of `ambient.toml`.
+pub mod http;
+/// **\[Server-only\]** Loading and unloading mods, which are other built embers, while the server is running.
+pub mod mods;
 /// **\[Server-only\]** Physics-related functionality, including applying forces, changing physical properties, and more.
 pub mod physics;
+/// **\[Server-only\]** Persistent key-value storage, which is kept across restarts of the server.
+pub mod storage;
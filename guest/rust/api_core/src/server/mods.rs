@@ -0,0 +1,41 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{global::block_until, internal::wit, message::RuntimeMessage, messages::ModLoad};
+
+/// Loads the built ember at `url` into the world as a mod, and waits until its modules have been
+/// spawned. `url` is either the URL or the path of the ember, which must have been built and be of
+/// type `Mod`. Loading a mod that is already loaded reloads it.
+///
+/// Returns the ember ID of the mod, or why it could not be loaded. Only the modules of the project
+/// can load mods.
+///
+/// This must be used with `.await` in either an `async fn` or an `async` block.
+pub async fn load(url: impl AsRef<str>) -> Result<String, String> {
+    let request_id = wit::server_mod::load(url.as_ref())?;
+
+    let result = Rc::new(RefCell::new(None));
+    let listener = ModLoad::subscribe({
+        let result = result.clone();
+        move |message: ModLoad| {
+            if message.request_id == request_id {
+                *result.borrow_mut() = Some(message);
+            }
+        }
+    });
+    block_until(|| result.borrow().is_some()).await;
+    listener.stop();
+
+    let result = result.borrow_mut().take().unwrap();
+    match result.error {
+        Some(err) => Err(err),
+        None => Ok(result.ember_id),
+    }
+}
+
+/// Unloads the mod with the ember ID `ember_id` on the next frame, despawning its modules and
+/// everything they spawned. Does nothing if the mod is not loaded.
+///
+/// Only the modules of the project can unload mods.
+pub fn unload(ember_id: impl AsRef<str>) -> Result<(), String> {
+    wit::server_mod::unload(ember_id.as_ref())
+}
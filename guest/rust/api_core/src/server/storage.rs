@@ -0,0 +1,69 @@
+use thiserror::Error;
+
+use crate::internal::wit;
+
+#[derive(Error, Debug)]
+/// Errors that can occur when accessing the storage.
+pub enum StorageError {
+    #[error("Storage is not available on this server")]
+    /// The server was not started with a storage directory.
+    Unavailable,
+    #[error("Invalid name: {0}")]
+    /// The namespace or key is empty, or too long.
+    InvalidName(String),
+    #[error("Storage limit of {0} bytes exceeded")]
+    /// Storing the value would take this ember over the amount of data it can store, in bytes.
+    LimitExceeded(u64),
+    #[error("I/O error: {0}")]
+    /// The storage could not be read from or written to.
+    Io(String),
+}
+impl From<wit::server_storage::StorageError> for StorageError {
+    fn from(value: wit::server_storage::StorageError) -> Self {
+        match value {
+            wit::server_storage::StorageError::Unavailable => StorageError::Unavailable,
+            wit::server_storage::StorageError::InvalidName(err) => StorageError::InvalidName(err),
+            wit::server_storage::StorageError::LimitExceeded(limit) => {
+                StorageError::LimitExceeded(limit)
+            }
+            wit::server_storage::StorageError::Io(err) => StorageError::Io(err),
+        }
+    }
+}
+
+/// Gets the value stored under `key` in `namespace`, if there is one.
+///
+/// The storage is shared by all of the server modules of this ember, and is kept across restarts
+/// of the server. Namespaces and keys can be any non-empty string of up to 100 bytes.
+pub fn get(
+    namespace: impl AsRef<str>,
+    key: impl AsRef<str>,
+) -> Result<Option<Vec<u8>>, StorageError> {
+    Ok(wit::server_storage::get(namespace.as_ref(), key.as_ref())?)
+}
+
+/// Stores `value` under `key` in `namespace`, replacing any value that was already there.
+pub fn set(
+    namespace: impl AsRef<str>,
+    key: impl AsRef<str>,
+    value: impl AsRef<[u8]>,
+) -> Result<(), StorageError> {
+    Ok(wit::server_storage::set(
+        namespace.as_ref(),
+        key.as_ref(),
+        value.as_ref(),
+    )?)
+}
+
+/// Deletes the value stored under `key` in `namespace`, returning whether there was one.
+pub fn delete(namespace: impl AsRef<str>, key: impl AsRef<str>) -> Result<bool, StorageError> {
+    Ok(wit::server_storage::delete(
+        namespace.as_ref(),
+        key.as_ref(),
+    )?)
+}
+
+/// Lists the keys which have a value in `namespace`, in sorted order.
+pub fn list(namespace: impl AsRef<str>) -> Result<Vec<String>, StorageError> {
+    Ok(wit::server_storage::list(namespace.as_ref())?)
+}
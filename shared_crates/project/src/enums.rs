@@ -0,0 +1,26 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CamelCaseIdentifier, ComponentType, Identifier};
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Serialize)]
+pub struct Enum {
+    pub description: Option<String>,
+    /// The variants of the enum. Their discriminants are assigned in declaration order.
+    pub variants: Vec<EnumVariant>,
+}
+impl Enum {
+    /// Returns true if any of the variants of this enum carry data.
+    pub fn has_payloads(&self) -> bool {
+        self.variants.iter().any(|v| !v.fields.is_empty())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Serialize)]
+pub struct EnumVariant {
+    pub name: CamelCaseIdentifier,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub fields: BTreeMap<Identifier, ComponentType>,
+}
@@ -177,9 +177,73 @@ impl ToTokens for Identifier {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct CamelCaseIdentifier(pub(super) String);
+impl CamelCaseIdentifier {
+    pub fn new(id: impl Into<String>) -> Result<Self, &'static str> {
+        Self::new_impl(id.into())
+    }
+
+    fn new_impl(id: String) -> Result<Self, &'static str> {
+        Self::validate(&id)?;
+        Ok(Self(id))
+    }
+
+    pub fn validate(id: &str) -> Result<&str, &'static str> {
+        if id.is_empty() {
+            return Err("identifier must not be empty");
+        }
+
+        if !id.starts_with(|c: char| c.is_ascii_uppercase()) {
+            return Err("identifier must start with an uppercase ASCII character");
+        }
+
+        if !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err("identifier must be camel-case ASCII");
+        }
+
+        Ok(id)
+    }
+}
+impl Serialize for CamelCaseIdentifier {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        String::serialize(&self.0, serializer)
+    }
+}
+impl<'de> Deserialize<'de> for CamelCaseIdentifier {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        CamelCaseIdentifier::new_impl(String::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+}
+impl AsRef<str> for CamelCaseIdentifier {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+impl Display for CamelCaseIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+impl ToTokens for CamelCaseIdentifier {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append(syn::Ident::new(
+            self.as_ref(),
+            proc_macro2::Span::call_site(),
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{Identifier, IdentifierPathBuf};
+    use crate::{CamelCaseIdentifier, Identifier, IdentifierPathBuf};
 
     #[test]
     fn can_validate_identifiers() {
@@ -217,6 +281,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_validate_camel_case_identifiers() {
+        use CamelCaseIdentifier as CI;
+
+        assert_eq!(CI::new(""), Err("identifier must not be empty"));
+        assert_eq!(
+            CI::new("5Asd"),
+            Err("identifier must start with an uppercase ASCII character")
+        );
+        assert_eq!(
+            CI::new("playerState"),
+            Err("identifier must start with an uppercase ASCII character")
+        );
+        assert_eq!(
+            CI::new("Player_State"),
+            Err("identifier must be camel-case ASCII")
+        );
+
+        assert_eq!(CI::new("PlayerState"), Ok(CI("PlayerState".to_string())));
+        assert_eq!(CI::new("Idle2"), Ok(CI("Idle2".to_string())));
+    }
+
     #[test]
     fn can_validate_identifier_paths() {
         use Identifier as I;
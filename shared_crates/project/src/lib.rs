@@ -2,10 +2,14 @@ mod component;
 pub use component::*;
 mod concept;
 pub use concept::*;
+mod enums;
+pub use enums::*;
 mod identifier;
 pub use identifier::*;
 mod manifest;
 pub use manifest::*;
+mod structs;
+pub use structs::*;
 mod version;
 pub use version::*;
 mod message;
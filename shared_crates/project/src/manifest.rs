@@ -1,8 +1,16 @@
-use std::{collections::BTreeMap, fs, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use crate::{Component, Concept, Identifier, IdentifierPathBuf, Message, Version};
+use crate::{
+    CamelCaseIdentifier, Component, Concept, Enum, Identifier, IdentifierPathBuf, Message, Struct,
+    Version,
+};
 use anyhow::Context;
 
 #[derive(Deserialize, Clone, Debug, Default, PartialEq, Serialize)]
@@ -18,6 +26,14 @@ pub struct Manifest {
     pub concepts: BTreeMap<IdentifierPathBuf, NamespaceOr<Concept>>,
     #[serde(default)]
     pub messages: BTreeMap<IdentifierPathBuf, NamespaceOr<Message>>,
+    #[serde(default)]
+    pub enums: BTreeMap<CamelCaseIdentifier, Enum>,
+    #[serde(default)]
+    pub structs: BTreeMap<CamelCaseIdentifier, Struct>,
+    #[serde(default)]
+    pub dependencies: BTreeMap<Identifier, Dependency>,
+    #[serde(default)]
+    pub http: Http,
 }
 impl Manifest {
     pub fn parse(manifest: &str) -> Result<Self, toml::de::Error> {
@@ -33,10 +49,30 @@ impl Manifest {
             &fs::read_to_string(path.as_ref())
                 .context(format!("Failed to read file: {:?}", path.as_ref()))?,
         )?;
-        res.resolve_imports(path.as_ref().parent().context("No parent directory")?)?;
+        let directory = path.as_ref().parent().context("No parent directory")?;
+        res.resolve_imports(directory)?;
+        res.resolve_dependencies(
+            directory,
+            &mut vec![(res.project_path(), canonical_path(path.as_ref()))],
+            &mut BTreeMap::new(),
+        )?;
         Ok(res)
     }
 
+    /// Loads the manifests of this manifest's dependencies, whose paths are relative to
+    /// `directory`, the directory of this manifest's `ambient.toml`. [Manifest::from_file] does
+    /// this already; this is for manifests which were parsed from elsewhere, such as a build.
+    pub fn resolve_all_dependencies(&mut self, directory: &Path) -> anyhow::Result<()> {
+        self.resolve_dependencies(
+            directory,
+            &mut vec![(
+                self.project_path(),
+                canonical_path(&directory.join("ambient.toml")),
+            )],
+            &mut BTreeMap::new(),
+        )
+    }
+
     pub fn project_path(&self) -> IdentifierPathBuf {
         self.ember
             .organization
@@ -54,10 +90,113 @@ impl Manifest {
             self.components.extend(manifest.components);
             self.concepts.extend(manifest.concepts);
             self.messages.extend(manifest.messages);
+            self.enums.extend(manifest.enums);
+            self.structs.extend(manifest.structs);
         }
         self.ember.includes.extend(new_includes);
         Ok(())
     }
+
+    /// Loads the manifests of all of this manifest's dependencies (and their dependencies in turn),
+    /// storing them in [Dependency::manifest].
+    ///
+    /// `stack` is the chain of embers currently being resolved, used to detect cycles, and
+    /// `resolved` is every ember encountered so far, used to detect version conflicts.
+    fn resolve_dependencies(
+        &mut self,
+        directory: &Path,
+        stack: &mut Vec<(IdentifierPathBuf, PathBuf)>,
+        resolved: &mut BTreeMap<IdentifierPathBuf, (Version, PathBuf)>,
+    ) -> anyhow::Result<()> {
+        for (id, dependency) in &mut self.dependencies {
+            let dependency_directory = directory.join(&dependency.path);
+            let manifest_path = canonical_path(&dependency_directory.join("ambient.toml"));
+
+            if let Some(index) = stack.iter().position(|(_, path)| *path == manifest_path) {
+                let mut cycle: Vec<_> = stack[index..].iter().map(|(id, _)| id.clone()).collect();
+                cycle.push(stack[index].0.clone());
+                return Err(DependencyError::Cycle(cycle).into());
+            }
+
+            let mut manifest = Manifest::parse(
+                &fs::read_to_string(&manifest_path)
+                    .context(format!("Failed to read file: {manifest_path:?}"))?,
+            )?;
+            manifest.resolve_imports(&dependency_directory)?;
+
+            if manifest.ember.id != *id {
+                return Err(DependencyError::IdMismatch {
+                    expected: id.clone(),
+                    found: manifest.ember.id,
+                }
+                .into());
+            }
+
+            let project_path = manifest.project_path();
+            let version = manifest.ember.version.clone();
+            if let Some(required) = &dependency.version {
+                if !version.is_compatible_with(required) {
+                    return Err(DependencyError::IncompatibleVersion {
+                        ember: project_path,
+                        required: required.clone(),
+                        found: version,
+                    }
+                    .into());
+                }
+            }
+            match resolved.get(&project_path) {
+                Some((existing, _)) if *existing != version => {
+                    return Err(DependencyError::VersionConflict {
+                        ember: project_path,
+                        first: existing.clone(),
+                        second: version,
+                    }
+                    .into());
+                }
+                _ => {
+                    resolved.insert(project_path.clone(), (version, manifest_path.clone()));
+                }
+            }
+
+            stack.push((project_path, manifest_path));
+            manifest.resolve_dependencies(&dependency_directory, stack, resolved)?;
+            stack.pop();
+
+            dependency.manifest = Some(Box::new(manifest));
+        }
+        Ok(())
+    }
+
+    /// Returns the resolved manifests of all direct and transitive dependencies, with each ember
+    /// appearing only once.
+    pub fn all_dependencies(&self) -> Vec<&Manifest> {
+        fn visit<'a>(
+            manifest: &'a Manifest,
+            seen: &mut Vec<IdentifierPathBuf>,
+            result: &mut Vec<&'a Manifest>,
+        ) {
+            for dependency in manifest.dependencies.values() {
+                let Some(dependency) = dependency.manifest.as_deref() else {
+                    continue;
+                };
+                let project_path = dependency.project_path();
+                if seen.contains(&project_path) {
+                    continue;
+                }
+                seen.push(project_path);
+                result.push(dependency);
+                visit(dependency, seen, result);
+            }
+        }
+
+        let mut result = vec![];
+        visit(self, &mut vec![self.project_path()], &mut result);
+        result
+    }
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
 }
 
 #[derive(Deserialize, Clone, Debug, PartialEq, Default, Serialize)]
@@ -97,6 +236,42 @@ pub enum Category {
     Racing,
 }
 
+#[derive(Deserialize, Clone, Debug, PartialEq, Serialize)]
+pub struct Dependency {
+    /// The path to the directory containing the dependency's `ambient.toml`, relative to this manifest.
+    pub path: PathBuf,
+    /// The version of the dependency that is required. Any version with the same major version
+    /// (or minor version, for `0.x` versions) that is not older than this is accepted.
+    #[serde(default)]
+    pub version: Option<Version>,
+    /// The manifest of the dependency. This is populated by [Manifest::from_file].
+    #[serde(skip)]
+    pub manifest: Option<Box<Manifest>>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DependencyError {
+    #[error("dependency cycle detected: {}", .0.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" -> "))]
+    Cycle(Vec<IdentifierPathBuf>),
+    #[error("dependency `{expected}` points to an ember with the id `{found}`")]
+    IdMismatch {
+        expected: Identifier,
+        found: Identifier,
+    },
+    #[error("dependency `{ember}` requires version {required}, but version {found} was found")]
+    IncompatibleVersion {
+        ember: IdentifierPathBuf,
+        required: Version,
+        found: Version,
+    },
+    #[error("dependency `{ember}` is required at both version {first} and version {second}")]
+    VersionConflict {
+        ember: IdentifierPathBuf,
+        first: Version,
+        second: Version,
+    },
+}
+
 #[derive(Deserialize, Clone, Debug, PartialEq, Default, Serialize)]
 pub struct Build {
     #[serde(default)]
@@ -116,6 +291,14 @@ impl Default for BuildRust {
     }
 }
 
+#[derive(Deserialize, Clone, Debug, PartialEq, Default, Serialize)]
+pub struct Http {
+    /// The URLs that the server modules of the ember can send requests to. A request is allowed
+    /// if its URL has the same scheme, host and port as one of these, and a path within its path.
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
 #[derive(Deserialize, Debug, Clone, PartialEq, Serialize)]
 pub struct Namespace {
     pub name: Option<String>,
@@ -165,8 +348,9 @@ mod tests {
     use std::collections::BTreeMap;
 
     use crate::{
-        Build, BuildRust, Component, ComponentType, Concept, Ember, Identifier, IdentifierPathBuf,
-        Manifest, Namespace, Version, VersionSuffix,
+        Build, BuildRust, CamelCaseIdentifier, Component, ComponentType, Concept, Dependency,
+        DependencyError, Ember, Enum, EnumVariant, Http, Identifier, IdentifierPathBuf, Manifest,
+        Namespace, Struct, Version, VersionSuffix,
     };
 
     #[test]
@@ -272,6 +456,10 @@ mod tests {
                     .into()
                 )]),
                 messages: BTreeMap::new(),
+                enums: BTreeMap::new(),
+                structs: BTreeMap::new(),
+                dependencies: BTreeMap::new(),
+                http: Http::default(),
             })
         )
     }
@@ -305,6 +493,10 @@ mod tests {
                 components: BTreeMap::new(),
                 concepts: BTreeMap::new(),
                 messages: BTreeMap::new(),
+                enums: BTreeMap::new(),
+                structs: BTreeMap::new(),
+                dependencies: BTreeMap::new(),
+                http: Http::default(),
             })
         )
     }
@@ -369,6 +561,10 @@ mod tests {
                 ]),
                 concepts: BTreeMap::new(),
                 messages: BTreeMap::new(),
+                enums: BTreeMap::new(),
+                structs: BTreeMap::new(),
+                dependencies: BTreeMap::new(),
+                http: Http::default(),
             })
         )
     }
@@ -501,7 +697,284 @@ mod tests {
                     )
                 ]),
                 messages: BTreeMap::new(),
+                enums: BTreeMap::new(),
+                structs: BTreeMap::new(),
+                dependencies: BTreeMap::new(),
+                http: Http::default(),
             })
         )
     }
+
+    #[test]
+    fn can_parse_enums() {
+        const TOML: &str = r#"
+        [ember]
+        id = "my_project"
+        name = "My Project"
+        version = "0.0.1"
+
+        [enums.PlayerState]
+        description = "The state of the player."
+        variants = [
+            { name = "Idle", description = "Standing still." },
+            { name = "Running", fields = { speed = "F32" } },
+        ]
+        "#;
+
+        assert_eq!(
+            Manifest::parse(TOML).map(|m| m.enums),
+            Ok(BTreeMap::from_iter([(
+                CamelCaseIdentifier::new("PlayerState").unwrap(),
+                Enum {
+                    description: Some("The state of the player.".to_string()),
+                    variants: vec![
+                        EnumVariant {
+                            name: CamelCaseIdentifier::new("Idle").unwrap(),
+                            description: Some("Standing still.".to_string()),
+                            fields: BTreeMap::new(),
+                        },
+                        EnumVariant {
+                            name: CamelCaseIdentifier::new("Running").unwrap(),
+                            description: None,
+                            fields: BTreeMap::from_iter([(
+                                Identifier::new("speed").unwrap(),
+                                ComponentType::String("F32".to_string())
+                            )]),
+                        },
+                    ],
+                }
+            )]))
+        )
+    }
+
+    #[test]
+    fn can_parse_structs() {
+        const TOML: &str = r#"
+        [ember]
+        id = "my_project"
+        name = "My Project"
+        version = "0.0.1"
+
+        [structs.Damage]
+        description = "Damage dealt to an entity."
+        fields = { amount = "F32", source = "EntityId" }
+        "#;
+
+        assert_eq!(
+            Manifest::parse(TOML).map(|m| m.structs),
+            Ok(BTreeMap::from_iter([(
+                CamelCaseIdentifier::new("Damage").unwrap(),
+                Struct {
+                    description: Some("Damage dealt to an entity.".to_string()),
+                    fields: BTreeMap::from_iter([
+                        (
+                            Identifier::new("amount").unwrap(),
+                            ComponentType::String("F32".to_string())
+                        ),
+                        (
+                            Identifier::new("source").unwrap(),
+                            ComponentType::String("EntityId".to_string())
+                        ),
+                    ]),
+                }
+            )]))
+        )
+    }
+
+    #[test]
+    fn can_parse_http_allowlist() {
+        const TOML: &str = r#"
+        [ember]
+        id = "my_project"
+        name = "My Project"
+        version = "0.0.1"
+
+        [http]
+        allow = ["https://matchmaking.example.com", "https://example.com/analytics/"]
+        "#;
+
+        assert_eq!(
+            Manifest::parse(TOML).map(|m| m.http),
+            Ok(Http {
+                allow: vec![
+                    "https://matchmaking.example.com".to_string(),
+                    "https://example.com/analytics/".to_string()
+                ]
+            })
+        )
+    }
+
+    #[test]
+    fn can_parse_dependencies() {
+        const TOML: &str = r#"
+        [ember]
+        id = "my_project"
+        name = "My Project"
+        version = "0.0.1"
+
+        [dependencies]
+        deps_assets = { path = "../deps/assets" }
+        deps_code = { path = "../deps/code", version = "0.1" }
+        "#;
+
+        assert_eq!(
+            Manifest::parse(TOML).map(|m| m.dependencies),
+            Ok(BTreeMap::from_iter([
+                (
+                    Identifier::new("deps_assets").unwrap(),
+                    Dependency {
+                        path: "../deps/assets".into(),
+                        version: None,
+                        manifest: None,
+                    }
+                ),
+                (
+                    Identifier::new("deps_code").unwrap(),
+                    Dependency {
+                        path: "../deps/code".into(),
+                        version: Some(Version::new(0, 1, 0, VersionSuffix::Final)),
+                        manifest: None,
+                    }
+                ),
+            ]))
+        )
+    }
+
+    fn write_embers(name: &str, embers: &[(&str, &str)]) -> std::path::PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("ambient_project_{name}_{}", std::process::id()));
+        for (id, toml) in embers {
+            let dir = root.join(id);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("ambient.toml"), toml).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn can_resolve_dependencies() {
+        let root = write_embers(
+            "resolve",
+            &[
+                (
+                    "game",
+                    r#"
+                    [ember]
+                    id = "game"
+                    version = "0.1.0"
+
+                    [dependencies]
+                    physics = { path = "../physics", version = "0.2.0" }
+                    "#,
+                ),
+                (
+                    "physics",
+                    r#"
+                    [ember]
+                    id = "physics"
+                    version = "0.2.3"
+
+                    [components]
+                    mass = { type = "F32" }
+                    "#,
+                ),
+            ],
+        );
+
+        let manifest = Manifest::from_file(root.join("game/ambient.toml")).unwrap();
+        let dependencies = manifest.all_dependencies();
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(
+            dependencies[0].ember.id,
+            Identifier::new("physics").unwrap()
+        );
+        assert!(dependencies[0]
+            .components
+            .contains_key(&IdentifierPathBuf::new("mass").unwrap()));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn will_error_on_dependency_cycle() {
+        let root = write_embers(
+            "cycle",
+            &[
+                (
+                    "a",
+                    r#"
+                    [ember]
+                    id = "a"
+                    version = "0.1.0"
+
+                    [dependencies]
+                    b = { path = "../b" }
+                    "#,
+                ),
+                (
+                    "b",
+                    r#"
+                    [ember]
+                    id = "b"
+                    version = "0.1.0"
+
+                    [dependencies]
+                    a = { path = "../a" }
+                    "#,
+                ),
+            ],
+        );
+
+        let error = Manifest::from_file(root.join("a/ambient.toml")).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<DependencyError>(),
+            Some(&DependencyError::Cycle(vec![
+                IdentifierPathBuf::new("a").unwrap(),
+                IdentifierPathBuf::new("b").unwrap(),
+                IdentifierPathBuf::new("a").unwrap(),
+            ]))
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn will_error_on_incompatible_dependency_version() {
+        let root = write_embers(
+            "version",
+            &[
+                (
+                    "game",
+                    r#"
+                    [ember]
+                    id = "game"
+                    version = "0.1.0"
+
+                    [dependencies]
+                    physics = { path = "../physics", version = "0.3.0" }
+                    "#,
+                ),
+                (
+                    "physics",
+                    r#"
+                    [ember]
+                    id = "physics"
+                    version = "0.2.3"
+                    "#,
+                ),
+            ],
+        );
+
+        let error = Manifest::from_file(root.join("game/ambient.toml")).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<DependencyError>(),
+            Some(&DependencyError::IncompatibleVersion {
+                ember: IdentifierPathBuf::new("physics").unwrap(),
+                required: Version::new(0, 3, 0, VersionSuffix::Final),
+                found: Version::new(0, 2, 3, VersionSuffix::Final),
+            })
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
 }
@@ -0,0 +1,12 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ComponentType, Identifier};
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Serialize)]
+pub struct Struct {
+    pub description: Option<String>,
+    /// The fields of the struct. They are serialized in alphabetical order.
+    pub fields: BTreeMap<Identifier, ComponentType>,
+}
@@ -61,6 +61,19 @@ impl Version {
             suffix,
         })
     }
+
+    /// Returns whether this version can be used to satisfy a requirement for `required`.
+    ///
+    /// This follows the usual semantic versioning rules: the major version must match (or the minor
+    /// version, if the major version is zero), and this version must not be older than `required`.
+    pub fn is_compatible_with(&self, required: &Version) -> bool {
+        let same_series = if required.major == 0 {
+            self.major == 0 && self.minor == required.minor
+        } else {
+            self.major == required.major
+        };
+        same_series && self >= required
+    }
 }
 impl Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -222,6 +235,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_check_version_compatibility() {
+        use Version as V;
+        use VersionSuffix as VS;
+
+        let compatible = [
+            (V::new(1, 0, 0, VS::Final), V::new(1, 0, 0, VS::Final)),
+            (V::new(1, 2, 3, VS::Final), V::new(1, 0, 0, VS::Final)),
+            (V::new(1, 0, 0, VS::Final), V::new(1, 0, 0, VS::Dev)),
+            (V::new(0, 1, 5, VS::Final), V::new(0, 1, 2, VS::Final)),
+        ];
+        for (version, required) in compatible {
+            assert!(
+                version.is_compatible_with(&required),
+                "{version} should be compatible with {required}"
+            );
+        }
+
+        let incompatible = [
+            (V::new(2, 0, 0, VS::Final), V::new(1, 0, 0, VS::Final)),
+            (V::new(1, 0, 0, VS::Final), V::new(1, 2, 0, VS::Final)),
+            (V::new(1, 0, 0, VS::Dev), V::new(1, 0, 0, VS::Final)),
+            (V::new(0, 2, 0, VS::Final), V::new(0, 1, 0, VS::Final)),
+        ];
+        for (version, required) in incompatible {
+            assert!(
+                !version.is_compatible_with(&required),
+                "{version} should not be compatible with {required}"
+            );
+        }
+    }
+
     #[test]
     fn can_sort_versions() {
         use Version as V;
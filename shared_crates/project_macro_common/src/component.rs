@@ -1,8 +1,11 @@
 use super::{
     tree::{Tree, TreeNode, TreeNodeInner, TreeNodeNamespace},
-    util, Context,
+    util::{self, TypeDefinitions},
+    Context,
+};
+use ambient_project::{
+    CamelCaseIdentifier, Component, ComponentType, IdentifierPath, IdentifierPathBuf,
 };
-use ambient_project::{Component, ComponentType, IdentifierPath, IdentifierPathBuf};
 use proc_macro2::TokenStream;
 use quote::quote;
 use thiserror::Error;
@@ -11,6 +14,7 @@ pub fn tree_to_token_stream(
     tree: &Tree<Component>,
     context: &Context,
     project_path: IdentifierPath,
+    types: TypeDefinitions,
 ) -> anyhow::Result<proc_macro2::TokenStream> {
     let tree_output = to_token_stream(
         tree.root(),
@@ -21,19 +25,24 @@ pub fn tree_to_token_stream(
                 quote! {
                     use std::time::Duration;
                     use glam::{Vec2, Vec3, Vec4, UVec2, UVec3, UVec4, Mat4, Quat};
-                    use crate::{EntityId, Debuggable, Networked, Store, Resource, MaybeResource, Name, Description};
+                    use crate::{EntityId, Debuggable, Networked, Store, Resource, MaybeResource, Name, Description, Quantized};
                     use ambient_shared_types::{ProceduralMeshHandle, ProceduralTextureHandle, ProceduralSamplerHandle, ProceduralMaterialHandle};
                     crate::components!(#namespace_path, {
                         #ts
                     });
                 }
             }
-            Context::Guest { api_path, .. } => quote! {
-                use #api_path::{once_cell::sync::Lazy, ecs::{Component, __internal_get_component}};
-                #ts
-            },
+            Context::Guest { api_path, .. } => {
+                let types_import = util::types_import(ns, types);
+                quote! {
+                    use #api_path::{once_cell::sync::Lazy, ecs::{Component, __internal_get_component}};
+                    #types_import
+                    #ts
+                }
+            }
         },
         project_path,
+        types,
     )?;
 
     match context {
@@ -84,16 +93,17 @@ fn to_token_stream(
     context: &Context,
     wrapper: impl Fn(&Context, &TreeNode<Component>, TokenStream) -> TokenStream + Copy,
     project_path: IdentifierPath,
+    types: TypeDefinitions,
 ) -> anyhow::Result<TokenStream> {
     util::tree_to_token_stream(
         node,
         context,
         wrapper,
-        |node, context, wrapper| to_token_stream(node, context, wrapper, project_path),
+        |node, context, wrapper| to_token_stream(node, context, wrapper, project_path, types),
         |name, component, context| {
             let name_ident: syn::Path = syn::parse_str(name)?;
             let name_uppercase_ident: syn::Path = syn::parse_str(&name.to_ascii_uppercase())?;
-            let component_ty = type_to_token_stream(&component.type_, context, false)?;
+            let component_ty = type_to_token_stream(&component.type_, context, false, types)?;
 
             let mut doc_comment = format!(
                 "**{}**",
@@ -121,10 +131,12 @@ fn to_token_stream(
 
             match context {
                 Context::Host => {
+                    // Attributes with parameters, such as `Quantized[0.01]`, are written as they are
                     let attrs = component
                         .attributes
                         .iter()
-                        .map(|a| syn::Ident::new(a, proc_macro2::Span::call_site()));
+                        .map(|a| a.parse::<TokenStream>().map_err(|e| anyhow::anyhow!("{e}")))
+                        .collect::<anyhow::Result<Vec<_>>>()?;
 
                     let description = &component.description;
 
@@ -153,16 +165,26 @@ pub enum TypeTokenStreamError {
     InvalidContainerType,
     #[error("invalid element type")]
     InvalidElementType,
+    #[error("enums with payloads can only be used in guest code")]
+    InvalidPayloadEnumType,
 }
 
+/// Converts a [ComponentType] to the Rust type used to represent it.
+///
+/// Types that are not primitive are looked up in `types`. In guest code, they refer to the
+/// generated type in the `enums` or `structs` module; on the host, enums without payloads are stored
+/// as their `u32` discriminant, and structs are stored as a [StructValue](ambient_shared_types::StructValue).
 pub fn type_to_token_stream(
     ty: &ComponentType,
     context: &Context,
     with_turbofish: bool,
+    types: TypeDefinitions,
 ) -> Result<proc_macro2::TokenStream, TypeTokenStreamError> {
     match ty {
         ComponentType::String(ty) => convert_primitive_type_to_rust_type(ty, context)
-            .ok_or(TypeTokenStreamError::InvalidPrimitiveType),
+            .map(Ok)
+            .or_else(|| convert_defined_type_to_rust_type(ty, context, types))
+            .unwrap_or(Err(TypeTokenStreamError::InvalidPrimitiveType)),
         ComponentType::ContainerType {
             type_,
             element_type,
@@ -172,7 +194,9 @@ pub fn type_to_token_stream(
                     .ok_or(TypeTokenStreamError::InvalidContainerType)?;
 
                 let element_ty = convert_primitive_type_to_rust_type(element_type, context)
-                    .ok_or(TypeTokenStreamError::InvalidElementType)?;
+                    .map(Ok)
+                    .or_else(|| convert_defined_type_to_rust_type(element_type, context, types))
+                    .unwrap_or(Err(TypeTokenStreamError::InvalidElementType))?;
 
                 if with_turbofish {
                     Ok(quote! { #container_ty :: < #element_ty > })
@@ -180,13 +204,43 @@ pub fn type_to_token_stream(
                     Ok(quote! { #container_ty < #element_ty > })
                 }
             } else {
-                Ok(convert_primitive_type_to_rust_type(type_, context)
-                    .ok_or(TypeTokenStreamError::InvalidPrimitiveType)?)
+                type_to_token_stream(
+                    &ComponentType::String(type_.clone()),
+                    context,
+                    with_turbofish,
+                    types,
+                )
             }
         }
     }
 }
 
+/// Returns `None` if `ty` does not name an enum or a struct.
+fn convert_defined_type_to_rust_type(
+    ty: &str,
+    context: &Context,
+    types: TypeDefinitions,
+) -> Option<Result<proc_macro2::TokenStream, TypeTokenStreamError>> {
+    let ty = CamelCaseIdentifier::new(ty).ok()?;
+
+    if let Some((name, _)) = types.structs.get_key_value(&ty) {
+        return Some(Ok(match context {
+            Context::Host => quote! { ambient_shared_types::StructValue },
+            Context::Guest { .. } => quote! { structs::#name },
+        }));
+    }
+
+    let (name, enum_) = types.enums.get_key_value(&ty)?;
+    if enum_.has_payloads() && matches!(context, Context::Host) {
+        return Some(Err(TypeTokenStreamError::InvalidPayloadEnumType));
+    }
+
+    Some(Ok(match context {
+        Context::Host => quote! { u32 },
+        Context::Guest { .. } => quote! { enums::#name },
+    }))
+}
+
 fn convert_primitive_type_to_rust_type(
     ty: &str,
     context: &Context,
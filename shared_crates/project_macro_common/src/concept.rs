@@ -1,10 +1,12 @@
 use super::{
     component::type_to_token_stream,
     tree::{Tree, TreeNode},
-    util, Context,
+    util::{self, TypeDefinitions},
+    Context,
 };
 use ambient_project::{
-    Component, ComponentType, Concept, Identifier, IdentifierPath, IdentifierPathBuf,
+    CamelCaseIdentifier, Component, ComponentType, Concept, Enum, Identifier, IdentifierPath,
+    IdentifierPathBuf, Struct,
 };
 use anyhow::Context as AnyhowContext;
 use proc_macro2::TokenStream;
@@ -14,25 +16,31 @@ pub fn tree_to_token_stream(
     concept_tree: &Tree<Concept>,
     components_tree: &Tree<Component>,
     context: &Context,
+    types: TypeDefinitions,
 ) -> anyhow::Result<proc_macro2::TokenStream> {
     to_token_stream(
         concept_tree.root(),
         context,
-        |context, _ns, ts| match context {
+        |context, ns, ts| match context {
             Context::Host => quote! {
                 use super::components;
                 use glam::{Vec2, Vec3, Vec4, UVec2, UVec3, UVec4, Mat4, Quat};
                 use crate::{EntityId, Entity, Component};
                 #ts
             },
-            Context::Guest { api_path, .. } => quote! {
-                use super::components;
-                use #api_path::prelude::*;
-                #ts
-            },
+            Context::Guest { api_path, .. } => {
+                let types_import = util::types_import(ns, types);
+                quote! {
+                    use super::components;
+                    use #api_path::prelude::*;
+                    #types_import
+                    #ts
+                }
+            }
         },
         concept_tree,
         components_tree,
+        types,
     )
 }
 
@@ -42,20 +50,22 @@ fn to_token_stream(
     wrapper: impl Fn(&Context, &TreeNode<Concept>, TokenStream) -> TokenStream + Copy,
     concept_tree: &Tree<Concept>,
     components_tree: &Tree<Component>,
+    types: TypeDefinitions,
 ) -> anyhow::Result<proc_macro2::TokenStream> {
     util::tree_to_token_stream(
         node,
         context,
         wrapper,
         |node, context, wrapper| {
-            to_token_stream(node, context, wrapper, concept_tree, components_tree)
+            to_token_stream(node, context, wrapper, concept_tree, components_tree, types)
         },
         |name, concept, context| {
             let make_concept =
-                generate_make(concept_tree, components_tree, context, name, concept)?;
-            let is_concept = generate_is(concept_tree, components_tree, context, name, concept)?;
+                generate_make(concept_tree, components_tree, context, name, concept, types)?;
+            let is_concept =
+                generate_is(concept_tree, components_tree, context, name, concept, types)?;
             let concept_fn =
-                generate_concept(concept_tree, components_tree, context, name, concept)?;
+                generate_concept(concept_tree, components_tree, context, name, concept, types)?;
             Ok(quote! {
                 #make_concept
                 #is_concept
@@ -71,12 +81,13 @@ fn generate_make(
     context: &Context,
     name: &str,
     concept: &Concept,
+    types: TypeDefinitions,
 ) -> anyhow::Result<TokenStream> {
     let make_comment = format!(
         "Makes a *{}*.\n\n{}\n\n{}",
         concept.name.as_ref().map(|x| x as &str).unwrap_or(name),
         concept.description.as_ref().unwrap_or(&"".to_string()),
-        generate_component_list_doc_comment(concept_tree, component_tree, context, concept)?
+        generate_component_list_doc_comment(concept_tree, component_tree, context, concept, types)?
     );
     let make_ident = quote::format_ident!("make_{}", name);
 
@@ -108,6 +119,8 @@ fn generate_make(
                 component.0.as_path(),
                 &manifest_component.type_,
                 component.1,
+                context,
+                types,
             )?;
 
             Ok(quote! { with(#full_path(), #default) })
@@ -131,12 +144,13 @@ fn generate_is(
     context: &Context,
     name: &str,
     concept: &Concept,
+    types: TypeDefinitions,
 ) -> anyhow::Result<TokenStream> {
     let is_comment = format!(
         "Checks if the entity is a *{}*.\n\n{}\n\n{}",
         concept.name.as_ref().map(|x| x as &str).unwrap_or(name),
         concept.description.as_ref().unwrap_or(&"".to_string()),
-        generate_component_list_doc_comment(concept_tree, component_tree, context, concept)?,
+        generate_component_list_doc_comment(concept_tree, component_tree, context, concept, types)?,
     );
     let is_ident = quote::format_ident!("is_{}", name);
 
@@ -189,12 +203,13 @@ fn generate_concept(
     context: &Context,
     name: &str,
     concept: &Concept,
+    types: TypeDefinitions,
 ) -> anyhow::Result<TokenStream> {
     let fn_comment = format!(
         "Returns the components that comprise *{}* as a tuple.\n\n{}\n\n{}",
         concept.name.as_ref().map(|x| x as &str).unwrap_or(name),
         concept.description.as_ref().unwrap_or(&"".to_string()),
-        generate_component_list_doc_comment(concept_tree, component_tree, context, concept)?,
+        generate_component_list_doc_comment(concept_tree, component_tree, context, concept, types)?,
     );
     let fn_ident = quote::format_ident!("{}", name);
 
@@ -244,7 +259,7 @@ fn generate_concept(
 
     let fn_ret = components
         .iter()
-        .map(|c| Ok(type_to_token_stream(&c.1.type_, context, false)?))
+        .map(|c| Ok(type_to_token_stream(&c.1.type_, context, false, types)?))
         .collect::<anyhow::Result<Vec<_>>>()?;
 
     Ok(quote! {
@@ -263,9 +278,13 @@ fn toml_value_to_tokens(
     path: IdentifierPath,
     ty: &ComponentType,
     value: &toml::Value,
+    context: &Context,
+    types: TypeDefinitions,
 ) -> anyhow::Result<TokenStream> {
     match ty {
-        ComponentType::String(ty) => toml_value_to_tokens_primitive(path, ty, value),
+        ComponentType::String(ty) => {
+            toml_value_to_tokens_primitive(path, ty, value, context, types)
+        }
         ComponentType::ContainerType {
             type_,
             element_type,
@@ -279,7 +298,15 @@ fn toml_value_to_tokens(
                     "Vec" => {
                         let values = values
                             .iter()
-                            .map(|v| toml_value_to_tokens_primitive(path, element_type, v))
+                            .map(|v| {
+                                toml_value_to_tokens_primitive(
+                                    path,
+                                    element_type,
+                                    v,
+                                    context,
+                                    types,
+                                )
+                            })
                             .collect::<anyhow::Result<Vec<_>>>()?;
 
                         Ok(quote! { vec![ #(#values),* ] })
@@ -288,15 +315,20 @@ fn toml_value_to_tokens(
                         if values.is_empty() {
                             Ok(quote! { None })
                         } else {
-                            let value =
-                                toml_value_to_tokens_primitive(path, element_type, &values[0])?;
+                            let value = toml_value_to_tokens_primitive(
+                                path,
+                                element_type,
+                                &values[0],
+                                context,
+                                types,
+                            )?;
                             Ok(quote! { Some(#value) })
                         }
                     }
                     _ => anyhow::bail!("unsupported container `{type_}` for component `{path}`"),
                 }
             } else {
-                toml_value_to_tokens_primitive(path, type_, value)
+                toml_value_to_tokens_primitive(path, type_, value, context, types)
             }
         }
     }
@@ -306,7 +338,18 @@ fn toml_value_to_tokens_primitive(
     path: IdentifierPath,
     ty: &str,
     value: &toml::Value,
+    context: &Context,
+    types: TypeDefinitions,
 ) -> anyhow::Result<TokenStream> {
+    if let Ok(ty) = CamelCaseIdentifier::new(ty) {
+        if let Some((name, enum_)) = types.enums.get_key_value(&ty) {
+            return toml_value_to_tokens_enum(path, name, enum_, value, context);
+        }
+        if let Some((name, struct_)) = types.structs.get_key_value(&ty) {
+            return toml_value_to_tokens_struct(path, name, struct_, value, context);
+        }
+    }
+
     Ok(match (ty, value) {
         ("Empty", toml::Value::Table(t)) if t.is_empty() => quote! {()},
         ("Bool", toml::Value::Boolean(b)) => quote! {#b},
@@ -355,6 +398,80 @@ fn toml_value_to_tokens_primitive(
     })
 }
 
+fn toml_value_to_tokens_enum(
+    path: IdentifierPath,
+    name: &CamelCaseIdentifier,
+    enum_: &Enum,
+    value: &toml::Value,
+    context: &Context,
+) -> anyhow::Result<TokenStream> {
+    let variant_name = value
+        .as_str()
+        .with_context(|| format!("expected a variant name of `{name}` for component `{path}`"))?;
+    let (discriminant, variant) = enum_
+        .variants
+        .iter()
+        .enumerate()
+        .find(|(_, v)| v.name.as_ref() == variant_name)
+        .with_context(|| {
+            format!("`{variant_name}` is not a variant of `{name}` for component `{path}`")
+        })?;
+    if !variant.fields.is_empty() {
+        anyhow::bail!(
+            "the variant `{variant_name}` of `{name}` has fields, so it cannot be used as a default for component `{path}`"
+        );
+    }
+
+    let variant_name = &variant.name;
+    let discriminant = discriminant as u32;
+    Ok(match context {
+        Context::Host => quote! { #discriminant },
+        Context::Guest { .. } => quote! { enums::#name::#variant_name },
+    })
+}
+
+fn toml_value_to_tokens_struct(
+    path: IdentifierPath,
+    name: &CamelCaseIdentifier,
+    struct_: &Struct,
+    value: &toml::Value,
+    context: &Context,
+) -> anyhow::Result<TokenStream> {
+    if let Context::Host = context {
+        anyhow::bail!("struct defaults are not supported on the host for component `{path}`");
+    }
+
+    let table = value.as_table().with_context(|| {
+        format!("expected a table initializer of `{name}` for component `{path}`")
+    })?;
+    if let Some(field) = table.keys().find(|k| {
+        !struct_
+            .fields
+            .keys()
+            .any(|field| field.as_ref() == k.as_str())
+    }) {
+        anyhow::bail!("`{field}` is not a field of `{name}` for component `{path}`");
+    }
+
+    let fields = struct_
+        .fields
+        .iter()
+        .filter_map(|(field, ty)| Some((field, ty, table.get(field.as_ref())?)))
+        .map(|(field, ty, value)| {
+            // Struct fields may only contain primitive types.
+            let value = toml_value_to_tokens(path, ty, value, context, TypeDefinitions::empty())?;
+            Ok(quote! { #field: #value })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        structs::#name {
+            #(#fields,)*
+            ..Default::default()
+        }
+    })
+}
+
 fn toml_array_f32_to_array_tokens(
     path: IdentifierPath,
     array: &toml::value::Array,
@@ -382,6 +499,7 @@ pub fn generate_component_list_doc_comment(
     component_tree: &Tree<Component>,
     context: &Context,
     concept: &Concept,
+    types: TypeDefinitions,
 ) -> anyhow::Result<String> {
     let mut output = "*Definition*:\n\n```ignore\n{\n".to_string();
 
@@ -390,6 +508,7 @@ pub fn generate_component_list_doc_comment(
         components: &Tree<Component>,
         context: &Context,
         concept: &Concept,
+        types: TypeDefinitions,
         output: &mut String,
         level: usize,
     ) -> anyhow::Result<()> {
@@ -406,8 +525,14 @@ pub fn generate_component_list_doc_comment(
             writeln!(
                 output,
                 "{padding}\"{component_path}\": {} = {},",
-                SemiprettyTokenStream(type_to_token_stream(&ty, context, false)?),
-                SemiprettyTokenStream(toml_value_to_tokens(component_path.as_path(), &ty, value)?)
+                SemiprettyTokenStream(type_to_token_stream(&ty, context, false, types)?),
+                SemiprettyTokenStream(toml_value_to_tokens(
+                    component_path.as_path(),
+                    &ty,
+                    value,
+                    context,
+                    types
+                )?)
             )?;
         }
         for concept_path in &concept.extends {
@@ -416,7 +541,15 @@ pub fn generate_component_list_doc_comment(
                 .with_context(|| format!("no definition found for {concept_path}"))?;
 
             writeln!(output, "{padding}\"{concept_path}\": {{ // Concept.")?;
-            write_level(concepts, components, context, concept, output, level + 1)?;
+            write_level(
+                concepts,
+                components,
+                context,
+                concept,
+                types,
+                output,
+                level + 1,
+            )?;
             writeln!(output, "{padding}}},")?;
         }
 
@@ -434,6 +567,7 @@ pub fn generate_component_list_doc_comment(
             },
         },
         concept,
+        types,
         &mut output,
         1,
     )?;
@@ -480,7 +614,7 @@ mod tests {
 
     use ambient_project::{Component, ComponentType, Concept, IdentifierPathBuf};
 
-    use crate::{tests::guest_context, tree::Tree};
+    use crate::{tests::guest_context, tree::Tree, util::TypeDefinitions};
 
     #[test]
     fn can_generate_nested_doc_comment_for_concepts() {
@@ -582,6 +716,7 @@ mod tests {
             concept_tree
                 .get(IdentifierPathBuf::new("concept3").unwrap().as_path())
                 .unwrap(),
+            TypeDefinitions::empty(),
         )
         .unwrap();
 
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, HashSet};
+
+use super::{component::type_to_token_stream, util::TypeDefinitions, Context};
+use ambient_project::{CamelCaseIdentifier, Enum};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub fn to_token_stream(
+    enums: &BTreeMap<CamelCaseIdentifier, Enum>,
+    context: &Context,
+) -> anyhow::Result<TokenStream> {
+    let api_path = match context {
+        // The host stores enums as their discriminant, so there is nothing to generate.
+        Context::Host => return Ok(quote! {}),
+        Context::Guest { api_path, .. } => api_path,
+    };
+
+    let enums = enums
+        .iter()
+        .map(|(name, enum_)| enum_to_token_stream(name, enum_, context))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        use #api_path::{ecs::{GeneratedType, GeneratedTypeStorage}, message::{MessageSerde, MessageSerdeError}};
+        #(#enums)*
+    })
+}
+
+fn enum_to_token_stream(
+    name: &CamelCaseIdentifier,
+    enum_: &Enum,
+    context: &Context,
+) -> anyhow::Result<TokenStream> {
+    if enum_.variants.is_empty() {
+        anyhow::bail!("the enum `{name}` must have at least one variant");
+    }
+
+    let mut seen = HashSet::new();
+    for variant in &enum_.variants {
+        if !seen.insert(&variant.name) {
+            anyhow::bail!(
+                "the enum `{name}` has more than one variant named `{}`",
+                variant.name
+            );
+        }
+    }
+
+    let doc_comment = match &enum_.description {
+        Some(desc) if !desc.is_empty() => format!("**{}**: {}", name, desc.replace('\n', "\n\n")),
+        _ => format!("**{}**", name),
+    };
+
+    // Payloads may only contain primitive types, so we resolve their types without any definitions.
+    let types = TypeDefinitions::empty();
+
+    let variants = enum_
+        .variants
+        .iter()
+        .map(|variant| {
+            let variant_name = &variant.name;
+            let doc_comment = match &variant.description {
+                Some(desc) if !desc.is_empty() => format!("**{}**: {}", variant_name, desc),
+                _ => format!("**{}**", variant_name),
+            };
+
+            if variant.fields.is_empty() {
+                return Ok(quote! {
+                    #[doc = #doc_comment]
+                    #variant_name
+                });
+            }
+
+            let fields = variant
+                .fields
+                .iter()
+                .map(|(field, ty)| {
+                    let ty = type_to_token_stream(ty, context, false, types)?;
+                    Ok(quote! { #field: #ty })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            Ok(quote! {
+                #[doc = #doc_comment]
+                #variant_name { #(#fields,)* }
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let default_variant = {
+        let variant = &enum_.variants[0];
+        let variant_name = &variant.name;
+        if variant.fields.is_empty() {
+            quote! { Self::#variant_name }
+        } else {
+            let fields = variant.fields.keys();
+            quote! { Self::#variant_name { #(#fields: Default::default(),)* } }
+        }
+    };
+
+    let discriminants: Vec<_> = (0..enum_.variants.len() as u32).collect();
+
+    let serialize_arms =
+        enum_
+            .variants
+            .iter()
+            .zip(&discriminants)
+            .map(|(variant, discriminant)| {
+                let variant_name = &variant.name;
+                let fields: Vec<_> = variant.fields.keys().collect();
+                quote! {
+                    Self::#variant_name { #(#fields,)* } => {
+                        #discriminant.serialize_message_part(output)?;
+                        #(#fields.serialize_message_part(output)?;)*
+                    }
+                }
+            });
+
+    let deserialize_arms = enum_
+        .variants
+        .iter()
+        .zip(&discriminants)
+        .map(|(variant, discriminant)| {
+            let variant_name = &variant.name;
+            let fields = variant
+                .fields
+                .iter()
+                .map(|(field, ty)| {
+                    let ty = type_to_token_stream(ty, context, true, types)?;
+                    Ok(quote! { #field: #ty::deserialize_message_part(input)? })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            Ok(quote! {
+                #discriminant => Self::#variant_name { #(#fields,)* }
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let (derives, generated_type_impl) = if enum_.has_payloads() {
+        (
+            quote! { #[derive(Clone, Debug, PartialEq)] },
+            quote! {
+                const STORAGE: GeneratedTypeStorage = GeneratedTypeStorage::Bytes;
+            },
+        )
+    } else {
+        let variant_names: Vec<_> = enum_.variants.iter().map(|v| &v.name).collect();
+        (
+            quote! { #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)] },
+            quote! {
+                const STORAGE: GeneratedTypeStorage = GeneratedTypeStorage::Discriminant;
+                fn to_u32(&self) -> Option<u32> {
+                    Some(match self {
+                        #(Self::#variant_names => #discriminants,)*
+                    })
+                }
+                fn from_u32(value: u32) -> Option<Self> {
+                    Some(match value {
+                        #(#discriminants => Self::#variant_names,)*
+                        _ => return None,
+                    })
+                }
+            },
+        )
+    };
+
+    Ok(quote! {
+        #derives
+        #[doc = #doc_comment]
+        pub enum #name {
+            #(#variants,)*
+        }
+        impl Default for #name {
+            fn default() -> Self {
+                #default_variant
+            }
+        }
+        impl MessageSerde for #name {
+            fn serialize_message_part(&self, output: &mut Vec<u8>) -> Result<(), MessageSerdeError> {
+                match self {
+                    #(#serialize_arms)*
+                }
+                Ok(())
+            }
+            fn deserialize_message_part(input: &mut dyn std::io::Read) -> Result<Self, MessageSerdeError> {
+                Ok(match u32::deserialize_message_part(input)? {
+                    #(#deserialize_arms,)*
+                    _ => return Err(MessageSerdeError::InvalidValue),
+                })
+            }
+        }
+        impl GeneratedType for #name {
+            #generated_type_impl
+        }
+    })
+}
@@ -1,18 +1,21 @@
 extern crate proc_macro;
 
-use ambient_project::{IdentifierPathBuf, Manifest};
+use ambient_project::{Identifier, IdentifierPathBuf, Manifest};
 use quote::quote;
 
 use proc_macro2::Ident;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tree::Tree;
+use util::TypeDefinitions;
 
 #[cfg(test)]
 mod tests;
 
 mod component;
 mod concept;
+mod enums;
 mod message;
+mod structs;
 mod tree;
 mod util;
 
@@ -33,13 +36,8 @@ impl ManifestSource {
         match self {
             Self::Path(file_path) => {
                 let manifest = Manifest::from_file(file_path)?;
-                let mut file_paths = vec![file_path.to_str().unwrap().to_string()];
-                let dir = file_path.parent().unwrap();
-                for include in &manifest.ember.includes {
-                    let path = dir.join(include);
-                    let file_path = path.to_str().unwrap().to_string();
-                    file_paths.push(file_path);
-                }
+                let mut file_paths = vec![];
+                collect_manifest_paths(&manifest, file_path, &mut file_paths);
                 let force_reload = file_paths.into_iter().enumerate().map(|(i, file_path)| {
                     let name = Ident::new(
                         &format!("_PROJECT_MANIFEST_{}", i),
@@ -54,6 +52,31 @@ impl ManifestSource {
     }
 }
 
+/// Collects the paths of the manifest, its includes and all of its dependencies' manifests,
+/// so that the generated code is rebuilt when any of them change.
+fn collect_manifest_paths(manifest: &Manifest, file_path: &Path, file_paths: &mut Vec<String>) {
+    let file_path_str = file_path.to_str().unwrap().to_string();
+    if file_paths.contains(&file_path_str) {
+        return;
+    }
+    file_paths.push(file_path_str);
+
+    let dir = file_path.parent().unwrap();
+    for include in &manifest.ember.includes {
+        let path = dir.join(include);
+        file_paths.push(path.to_str().unwrap().to_string());
+    }
+    for dependency in manifest.dependencies.values() {
+        if let Some(dependency_manifest) = &dependency.manifest {
+            collect_manifest_paths(
+                dependency_manifest,
+                &dir.join(&dependency.path).join("ambient.toml"),
+                file_paths,
+            );
+        }
+    }
+}
+
 pub fn generate_code(
     manifest: ManifestSource,
     context: Context,
@@ -68,19 +91,145 @@ pub fn generate_code(
         IdentifierPathBuf::empty()
     };
 
+    let items = generate_manifest_items(
+        &manifest,
+        &context,
+        project_path,
+        is_api_manifest,
+        validate_namespaces_documented,
+    )?;
+
+    // Dependencies which belong to an organization are nested in a module named after it, so that
+    // embers with the same ID from different organizations do not collide
+    let mut dependencies = vec![];
+    let mut organizations: Vec<(&Identifier, Vec<proc_macro2::TokenStream>)> = vec![];
+    let all_dependencies = manifest.all_dependencies();
+    for dependency in &all_dependencies {
+        let id = &dependency.ember.id;
+        let doc_comment = format!(
+            "Auto-generated definitions for the `{}` dependency.",
+            dependency.project_path()
+        );
+        let items = generate_manifest_items(
+            dependency,
+            &context,
+            dependency.project_path(),
+            false,
+            validate_namespaces_documented,
+        )?;
+        let module = quote! {
+            #[doc = #doc_comment]
+            pub mod #id {
+                #items
+            }
+        };
+
+        match &dependency.ember.organization {
+            Some(organization) => {
+                match organizations.iter_mut().find(|(o, _)| *o == organization) {
+                    Some((_, modules)) => modules.push(module),
+                    None => organizations.push((organization, vec![module])),
+                }
+            }
+            None => dependencies.push(module),
+        }
+    }
+    for (organization, modules) in organizations {
+        if let Some(dependency) = all_dependencies.iter().find(|dependency| {
+            dependency.ember.organization.is_none() && dependency.ember.id == *organization
+        }) {
+            anyhow::bail!(
+                "the dependency `{}` has the same name as the organization `{organization}` of other dependencies",
+                dependency.ember.id
+            );
+        }
+
+        let doc_comment = format!(
+            "Auto-generated definitions for the dependencies from the `{organization}` organization."
+        );
+        dependencies.push(quote! {
+            #[doc = #doc_comment]
+            pub mod #organization {
+                #(#modules)*
+            }
+        });
+    }
+    let dependencies = if dependencies.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            /// Auto-generated definitions for the dependencies of this project. These come from the `ambient.toml` of each dependency.
+            pub mod dependencies {
+                #(#dependencies)*
+            }
+        }
+    };
+
+    Ok(quote!(
+        #force_reload
+
+        #items
+        #dependencies
+    ))
+}
+
+fn generate_manifest_items(
+    manifest: &Manifest,
+    context: &Context,
+    project_path: IdentifierPathBuf,
+    is_api_manifest: bool,
+    validate_namespaces_documented: bool,
+) -> anyhow::Result<proc_macro2::TokenStream> {
+    if let Some(name) = manifest
+        .structs
+        .keys()
+        .find(|name| manifest.enums.contains_key(name))
+    {
+        anyhow::bail!("`{name}` is defined as both an enum and a struct");
+    }
+
+    let types = TypeDefinitions {
+        enums: &manifest.enums,
+        structs: &manifest.structs,
+    };
+
     let component_tree = Tree::new(&manifest.components, validate_namespaces_documented)?;
     let components_tokens =
-        component::tree_to_token_stream(&component_tree, &context, project_path.as_path())?;
+        component::tree_to_token_stream(&component_tree, context, project_path.as_path(), types)?;
 
     let concept_tree = Tree::new(&manifest.concepts, validate_namespaces_documented)?;
-    let concept_tokens = concept::tree_to_token_stream(&concept_tree, &component_tree, &context)?;
+    let concept_tokens =
+        concept::tree_to_token_stream(&concept_tree, &component_tree, context, types)?;
 
     let message_tree = Tree::new(&manifest.messages, validate_namespaces_documented)?;
-    let message_tokens = message::tree_to_token_stream(&message_tree, &context, is_api_manifest)?;
+    let message_tokens =
+        message::tree_to_token_stream(&message_tree, context, is_api_manifest, types)?;
 
-    Ok(quote!(
-        #force_reload
+    let enum_tokens = if manifest.enums.is_empty() || matches!(context, Context::Host) {
+        quote! {}
+    } else {
+        let enum_tokens = enums::to_token_stream(&manifest.enums, context)?;
+        quote! {
+            /// Auto-generated enum definitions. These come from `ambient.toml` in the root of the project.
+            pub mod enums {
+                #enum_tokens
+            }
+        }
+    };
 
+    let struct_tokens = if manifest.structs.is_empty() || matches!(context, Context::Host) {
+        quote! {}
+    } else {
+        let struct_tokens = structs::to_token_stream(&manifest.structs, context)?;
+        quote! {
+            /// Auto-generated struct definitions. These come from `ambient.toml` in the root of the project.
+            pub mod structs {
+                #struct_tokens
+            }
+        }
+    };
+
+    Ok(quote!(
         /// Auto-generated component definitions. These come from `ambient.toml` in the root of the project.
         pub mod components {
             #components_tokens
@@ -96,5 +245,7 @@ pub fn generate_code(
         pub mod messages {
             #message_tokens
         }
+        #enum_tokens
+        #struct_tokens
     ))
 }
@@ -1,7 +1,8 @@
 use super::{
     component::type_to_token_stream,
     tree::{Tree, TreeNode},
-    util, Context,
+    util::{self, TypeDefinitions},
+    Context,
 };
 use ambient_project::Message;
 use proc_macro2::{Span, TokenStream};
@@ -11,6 +12,7 @@ pub fn tree_to_token_stream(
     message_tree: &Tree<Message>,
     context: &Context,
     is_api_manifest: bool,
+    types: TypeDefinitions,
 ) -> anyhow::Result<TokenStream> {
     let runtime_message = if is_api_manifest {
         quote! { RuntimeMessage }
@@ -21,18 +23,23 @@ pub fn tree_to_token_stream(
         message_tree.root(),
         is_api_manifest,
         context,
-        |context, _ns, ts| match context {
+        |context, ns, ts| match context {
             Context::Host => quote! {
                 use ambient_project_rt::message_serde::{Message, MessageSerde, MessageSerdeError, #runtime_message};
                 use glam::{Vec2, Vec3, Vec4, UVec2, UVec3, UVec4, Mat4, Quat};
                 use crate::{EntityId, Entity};
                 #ts
             },
-            Context::Guest { api_path, .. } => quote! {
-                use #api_path::{prelude::*, message::{Message, MessageSerde, MessageSerdeError, #runtime_message}};
-                #ts
-            },
+            Context::Guest { api_path, .. } => {
+                let types_import = util::types_import(ns, types);
+                quote! {
+                    use #api_path::{prelude::*, message::{Message, MessageSerde, MessageSerdeError, #runtime_message}};
+                    #types_import
+                    #ts
+                }
+            }
         },
+        types,
     )
 }
 
@@ -41,12 +48,13 @@ fn to_token_stream(
     is_api_manifest: bool,
     context: &Context,
     wrapper: impl Fn(&Context, &TreeNode<Message>, TokenStream) -> TokenStream + Copy,
+    types: TypeDefinitions,
 ) -> anyhow::Result<TokenStream> {
     util::tree_to_token_stream(
         node,
         context,
         wrapper,
-        |n, c, w| to_token_stream(n, is_api_manifest, c, w),
+        |n, c, w| to_token_stream(n, is_api_manifest, c, w, types),
         |id, message, context| {
             let doc_comment = if let Some(desc) = &message.description {
                 format!("**{}**: {}", id, desc)
@@ -72,7 +80,7 @@ fn to_token_stream(
                 .iter()
                 .map(|f| {
                     let name = f.0;
-                    type_to_token_stream(f.1, context, false).map(|ty| {
+                    type_to_token_stream(f.1, context, false, types).map(|ty| {
                         quote! { pub #name: #ty }
                     })
                 })
@@ -83,7 +91,7 @@ fn to_token_stream(
                 .iter()
                 .map(|f| {
                     let name = f.0;
-                    type_to_token_stream(f.1, context, false).map(|ty| {
+                    type_to_token_stream(f.1, context, false, types).map(|ty| {
                         quote! { #name: impl Into<#ty> }
                     })
                 })
@@ -104,7 +112,7 @@ fn to_token_stream(
                 .iter()
                 .map(|f| {
                     let name = f.0;
-                    type_to_token_stream(f.1, context, true).map(|ty| {
+                    type_to_token_stream(f.1, context, true, types).map(|ty| {
                         quote! { #name: #ty ::deserialize_message_part(&mut input)? }
                     })
                 })
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use super::{component::type_to_token_stream, util::TypeDefinitions, Context};
+use ambient_project::{CamelCaseIdentifier, Struct};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub fn to_token_stream(
+    structs: &BTreeMap<CamelCaseIdentifier, Struct>,
+    context: &Context,
+) -> anyhow::Result<TokenStream> {
+    let api_path = match context {
+        // The host stores structs as their serialized fields, so there is nothing to generate.
+        Context::Host => return Ok(quote! {}),
+        Context::Guest { api_path, .. } => api_path,
+    };
+
+    let structs = structs
+        .iter()
+        .map(|(name, struct_)| struct_to_token_stream(name, struct_, context))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        use #api_path::{ecs::{GeneratedType, GeneratedTypeStorage}, message::{MessageSerde, MessageSerdeError}};
+        #(#structs)*
+    })
+}
+
+fn struct_to_token_stream(
+    name: &CamelCaseIdentifier,
+    struct_: &Struct,
+    context: &Context,
+) -> anyhow::Result<TokenStream> {
+    let doc_comment = match &struct_.description {
+        Some(desc) if !desc.is_empty() => format!("**{}**: {}", name, desc.replace('\n', "\n\n")),
+        _ => format!("**{}**", name),
+    };
+
+    // Fields may only contain primitive types, so we resolve their types without any definitions.
+    let types = TypeDefinitions::empty();
+
+    let field_names: Vec<_> = struct_.fields.keys().collect();
+    let field_types = struct_
+        .fields
+        .values()
+        .map(|ty| type_to_token_stream(ty, context, false, types))
+        .collect::<Result<Vec<_>, _>>()?;
+    let field_types_turbofish = struct_
+        .fields
+        .values()
+        .map(|ty| type_to_token_stream(ty, context, true, types))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(quote! {
+        #[derive(Clone, Debug, Default, PartialEq)]
+        #[doc = #doc_comment]
+        pub struct #name {
+            #(pub #field_names: #field_types,)*
+        }
+        impl MessageSerde for #name {
+            fn serialize_message_part(&self, output: &mut Vec<u8>) -> Result<(), MessageSerdeError> {
+                #(self.#field_names.serialize_message_part(output)?;)*
+                Ok(())
+            }
+            fn deserialize_message_part(input: &mut dyn std::io::Read) -> Result<Self, MessageSerdeError> {
+                Ok(Self {
+                    #(#field_names: #field_types_turbofish::deserialize_message_part(input)?,)*
+                })
+            }
+        }
+        impl GeneratedType for #name {
+            const STORAGE: GeneratedTypeStorage = GeneratedTypeStorage::Struct;
+        }
+    })
+}
@@ -1183,3 +1183,395 @@ fn can_generate_runtime_message() {
 
     assert_eq!(result.to_string(), expected_output.to_string());
 }
+
+#[test]
+fn can_generate_dependency_definitions() {
+    let root = std::env::temp_dir().join(format!(
+        "ambient_project_macro_dependencies_{}",
+        std::process::id()
+    ));
+    let game_dir = root.join("game");
+    let physics_dir = root.join("physics");
+    std::fs::create_dir_all(&game_dir).unwrap();
+    std::fs::create_dir_all(&physics_dir).unwrap();
+    std::fs::write(
+        game_dir.join("ambient.toml"),
+        indoc::indoc! {r#"
+        [ember]
+        id = "game"
+        name = "Game"
+        version = "0.0.1"
+
+        [dependencies]
+        physics = { path = "../physics" }
+        "#},
+    )
+    .unwrap();
+    std::fs::write(
+        physics_dir.join("ambient.toml"),
+        indoc::indoc! {r#"
+        [ember]
+        id = "physics"
+        name = "Physics"
+        version = "0.0.1"
+        organization = "acme"
+
+        [components]
+        mass = { name = "Mass", description = "", type = "F32" }
+        "#},
+    )
+    .unwrap();
+
+    let game_manifest_path = game_dir.join("ambient.toml");
+    let game_manifest_path_str = game_manifest_path.to_str().unwrap();
+    let physics_manifest_path = game_dir.join("../physics").join("ambient.toml");
+    let physics_manifest_path_str = physics_manifest_path.to_str().unwrap();
+
+    let expected_output = quote::quote! {
+        const _PROJECT_MANIFEST_0: &'static str = include_str!(#game_manifest_path_str);
+        const _PROJECT_MANIFEST_1: &'static str = include_str!(#physics_manifest_path_str);
+
+        #[doc = r" Auto-generated component definitions. These come from `ambient.toml` in the root of the project."]
+        pub mod components {
+        }
+        #[doc = r" Auto-generated concept definitions. Concepts are collections of components that describe some form of gameplay concept."]
+        #[doc = r""]
+        #[doc = r" They do not have any runtime representation outside of the components that compose them."]
+        pub mod concepts {
+        }
+        #[doc = r" Auto-generated message definitions. Messages are used to communicate with the runtime, the other side of the network,"]
+        #[doc = r" and with other modules."]
+        pub mod messages {
+        }
+        #[doc = r" Auto-generated definitions for the dependencies of this project. These come from the `ambient.toml` of each dependency."]
+        pub mod dependencies {
+            #[doc = "Auto-generated definitions for the dependencies from the `acme` organization."]
+            pub mod acme {
+                #[doc = "Auto-generated definitions for the `acme::physics` dependency."]
+                pub mod physics {
+                    #[doc = r" Auto-generated component definitions. These come from `ambient.toml` in the root of the project."]
+                    pub mod components {
+                        use ambient_api2::{once_cell::sync::Lazy, ecs::{Component, __internal_get_component}};
+                        static MASS: Lazy< Component<f32> > = Lazy::new(|| __internal_get_component("acme::physics::mass"));
+                        #[doc = "**Mass**"]
+                        pub fn mass() -> Component<f32> {
+                            *MASS
+                        }
+                    }
+                    #[doc = r" Auto-generated concept definitions. Concepts are collections of components that describe some form of gameplay concept."]
+                    #[doc = r""]
+                    #[doc = r" They do not have any runtime representation outside of the components that compose them."]
+                    pub mod concepts {
+                    }
+                    #[doc = r" Auto-generated message definitions. Messages are used to communicate with the runtime, the other side of the network,"]
+                    #[doc = r" and with other modules."]
+                    pub mod messages {
+                    }
+                }
+            }
+        }
+    };
+
+    let result = generate_code(
+        ManifestSource::Path(game_manifest_path.clone()),
+        guest_context(),
+        false,
+        true,
+    )
+    .unwrap();
+
+    std::fs::remove_dir_all(root).unwrap();
+
+    assert_eq!(result.to_string(), expected_output.to_string());
+}
+
+#[test]
+fn will_error_on_dependency_named_like_an_organization() {
+    let root = std::env::temp_dir().join(format!(
+        "ambient_project_macro_organization_collision_{}",
+        std::process::id()
+    ));
+    let manifests = [
+        (
+            "game",
+            indoc::indoc! {r#"
+            [ember]
+            id = "game"
+            name = "Game"
+            version = "0.0.1"
+
+            [dependencies]
+            acme = { path = "../acme" }
+            physics = { path = "../physics" }
+            "#},
+        ),
+        (
+            "acme",
+            indoc::indoc! {r#"
+            [ember]
+            id = "acme"
+            name = "Acme"
+            version = "0.0.1"
+            "#},
+        ),
+        (
+            "physics",
+            indoc::indoc! {r#"
+            [ember]
+            id = "physics"
+            name = "Physics"
+            version = "0.0.1"
+            organization = "acme"
+            "#},
+        ),
+    ];
+    for (dir, manifest) in manifests {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+        std::fs::write(root.join(dir).join("ambient.toml"), manifest).unwrap();
+    }
+
+    let result = generate_code(
+        ManifestSource::Path(root.join("game").join("ambient.toml")),
+        guest_context(),
+        false,
+        true,
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("`acme`"), "{err}");
+}
+
+#[test]
+fn can_generate_enums() {
+    let manifest = indoc::indoc! {r#"
+        [ember]
+        id = "my_project"
+        name = "My Project"
+        version = "0.0.1"
+
+        [enums.Stance]
+        description = "How the character is standing."
+        variants = [
+            { name = "Idle", description = "Standing still." },
+            { name = "Running" },
+        ]
+
+        [components]
+        stance = { name = "Stance", description = "", type = "Stance" }
+
+        [messages.stance_changed]
+        description = "The stance has changed."
+        [messages.stance_changed.fields]
+        stance = "Stance"
+    "#};
+
+    let expected_output = quote::quote! {
+        #[doc = r" Auto-generated component definitions. These come from `ambient.toml` in the root of the project."]
+        pub mod components {
+            use ambient_api2::{
+                once_cell::sync::Lazy,
+                ecs::{Component, __internal_get_component}
+            };
+            #[allow(unused_imports)]
+            use super::enums;
+            static STANCE: Lazy< Component<enums::Stance> > = Lazy::new(|| __internal_get_component("my_project::stance"));
+            #[doc = "**Stance**"]
+            pub fn stance() -> Component<enums::Stance> {
+                *STANCE
+            }
+        }
+        #[doc = r" Auto-generated concept definitions. Concepts are collections of components that describe some form of gameplay concept."]
+        #[doc = r""]
+        #[doc = r" They do not have any runtime representation outside of the components that compose them."]
+        pub mod concepts {
+        }
+        #[doc = r" Auto-generated message definitions. Messages are used to communicate with the runtime, the other side of the network,"]
+        #[doc = r" and with other modules."]
+        pub mod messages {
+            use ambient_api2::{prelude::*, message::{Message, MessageSerde, MessageSerdeError, ModuleMessage}};
+            #[allow(unused_imports)]
+            use super::enums;
+
+            #[derive(Clone, Debug)]
+            #[doc = "**stance_changed**: The stance has changed."]
+            pub struct StanceChanged {
+                pub stance: enums::Stance,
+            }
+            impl StanceChanged {
+                pub fn new(
+                    stance: impl Into<enums::Stance>,
+                ) -> Self {
+                    Self {
+                        stance: stance.into(),
+                    }
+                }
+            }
+            impl Message for StanceChanged {
+                fn id() -> &'static str {
+                    "stance_changed"
+                }
+                fn serialize_message(&self) -> Result<Vec<u8>, MessageSerdeError> {
+                    let mut output = vec![];
+                    self.stance.serialize_message_part(&mut output)?;
+                    Ok(output)
+                }
+                fn deserialize_message(mut input: &[u8]) -> Result<Self, MessageSerdeError> {
+                    Ok(Self {
+                        stance: enums::Stance::deserialize_message_part(&mut input)?,
+                    })
+                }
+            }
+            impl ModuleMessage for StanceChanged {}
+        }
+        #[doc = r" Auto-generated enum definitions. These come from `ambient.toml` in the root of the project."]
+        pub mod enums {
+            use ambient_api2::{ecs::{GeneratedType, GeneratedTypeStorage}, message::{MessageSerde, MessageSerdeError}};
+            #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+            #[doc = "**Stance**: How the character is standing."]
+            pub enum Stance {
+                #[doc = "**Idle**: Standing still."]
+                Idle,
+                #[doc = "**Running**"]
+                Running,
+            }
+            impl Default for Stance {
+                fn default() -> Self {
+                    Self::Idle
+                }
+            }
+            impl MessageSerde for Stance {
+                fn serialize_message_part(&self, output: &mut Vec<u8>) -> Result<(), MessageSerdeError> {
+                    match self {
+                        Self::Idle {} => {
+                            0u32.serialize_message_part(output)?;
+                        }
+                        Self::Running {} => {
+                            1u32.serialize_message_part(output)?;
+                        }
+                    }
+                    Ok(())
+                }
+                fn deserialize_message_part(input: &mut dyn std::io::Read) -> Result<Self, MessageSerdeError> {
+                    Ok(match u32::deserialize_message_part(input)? {
+                        0u32 => Self::Idle {},
+                        1u32 => Self::Running {},
+                        _ => return Err(MessageSerdeError::InvalidValue),
+                    })
+                }
+            }
+            impl GeneratedType for Stance {
+                const STORAGE: GeneratedTypeStorage = GeneratedTypeStorage::Discriminant;
+                fn to_u32(&self) -> Option<u32> {
+                    Some(match self {
+                        Self::Idle => 0u32,
+                        Self::Running => 1u32,
+                    })
+                }
+                fn from_u32(value: u32) -> Option<Self> {
+                    Some(match value {
+                        0u32 => Self::Idle,
+                        1u32 => Self::Running,
+                        _ => return None,
+                    })
+                }
+            }
+        }
+    };
+
+    let result = generate_code(
+        ManifestSource::String(manifest.to_string()),
+        guest_context(),
+        false,
+        true,
+    )
+    .unwrap();
+
+    assert_eq!(result.to_string(), expected_output.to_string());
+}
+
+#[test]
+fn can_generate_structs() {
+    let manifest = indoc::indoc! {r#"
+        [ember]
+        id = "my_project"
+        name = "My Project"
+        version = "0.0.1"
+
+        [structs.Damage]
+        description = "Damage dealt to an entity."
+        fields = { amount = "F32", source = "EntityId" }
+
+        [components]
+        damage = { name = "Damage", description = "", type = "Damage" }
+        damage_history = { name = "Damage history", description = "", type = { type = "Vec", element_type = "Damage" } }
+    "#};
+
+    let expected_output = quote::quote! {
+        #[doc = r" Auto-generated component definitions. These come from `ambient.toml` in the root of the project."]
+        pub mod components {
+            use ambient_api2::{
+                once_cell::sync::Lazy,
+                ecs::{Component, __internal_get_component}
+            };
+            #[allow(unused_imports)]
+            use super::structs;
+            static DAMAGE: Lazy< Component<structs::Damage> > = Lazy::new(|| __internal_get_component("my_project::damage"));
+            #[doc = "**Damage**"]
+            pub fn damage() -> Component<structs::Damage> {
+                *DAMAGE
+            }
+            static DAMAGE_HISTORY: Lazy< Component< Vec<structs::Damage> > > = Lazy::new(|| __internal_get_component("my_project::damage_history"));
+            #[doc = "**Damage history**"]
+            pub fn damage_history() -> Component< Vec<structs::Damage> > {
+                *DAMAGE_HISTORY
+            }
+        }
+        #[doc = r" Auto-generated concept definitions. Concepts are collections of components that describe some form of gameplay concept."]
+        #[doc = r""]
+        #[doc = r" They do not have any runtime representation outside of the components that compose them."]
+        pub mod concepts {
+        }
+        #[doc = r" Auto-generated message definitions. Messages are used to communicate with the runtime, the other side of the network,"]
+        #[doc = r" and with other modules."]
+        pub mod messages {
+        }
+        #[doc = r" Auto-generated struct definitions. These come from `ambient.toml` in the root of the project."]
+        pub mod structs {
+            use ambient_api2::{ecs::{GeneratedType, GeneratedTypeStorage}, message::{MessageSerde, MessageSerdeError}};
+            #[derive(Clone, Debug, Default, PartialEq)]
+            #[doc = "**Damage**: Damage dealt to an entity."]
+            pub struct Damage {
+                pub amount: f32,
+                pub source: ambient_api2::global::EntityId,
+            }
+            impl MessageSerde for Damage {
+                fn serialize_message_part(&self, output: &mut Vec<u8>) -> Result<(), MessageSerdeError> {
+                    self.amount.serialize_message_part(output)?;
+                    self.source.serialize_message_part(output)?;
+                    Ok(())
+                }
+                fn deserialize_message_part(input: &mut dyn std::io::Read) -> Result<Self, MessageSerdeError> {
+                    Ok(Self {
+                        amount: f32::deserialize_message_part(input)?,
+                        source: ambient_api2::global::EntityId::deserialize_message_part(input)?,
+                    })
+                }
+            }
+            impl GeneratedType for Damage {
+                const STORAGE: GeneratedTypeStorage = GeneratedTypeStorage::Struct;
+            }
+        }
+    };
+
+    let result = generate_code(
+        ManifestSource::String(manifest.to_string()),
+        guest_context(),
+        false,
+        true,
+    )
+    .unwrap();
+
+    assert_eq!(result.to_string(), expected_output.to_string());
+}
@@ -1,12 +1,58 @@
-use std::fmt::Debug;
+use std::{collections::BTreeMap, fmt::Debug};
 
 use super::{
     tree::{TreeNode, TreeNodeInner},
     Context,
 };
+use ambient_project::{CamelCaseIdentifier, Enum, Struct};
 use proc_macro2::TokenStream;
 use quote::quote;
 
+/// The enums and structs defined by a manifest, which component types can refer to by name.
+#[derive(Clone, Copy)]
+pub struct TypeDefinitions<'a> {
+    pub enums: &'a BTreeMap<CamelCaseIdentifier, Enum>,
+    pub structs: &'a BTreeMap<CamelCaseIdentifier, Struct>,
+}
+impl TypeDefinitions<'static> {
+    /// No enums or structs; used for types that may only contain primitives.
+    pub fn empty() -> Self {
+        static ENUMS: BTreeMap<CamelCaseIdentifier, Enum> = BTreeMap::new();
+        static STRUCTS: BTreeMap<CamelCaseIdentifier, Struct> = BTreeMap::new();
+        Self {
+            enums: &ENUMS,
+            structs: &STRUCTS,
+        }
+    }
+}
+
+/// Imports the generated `enums` and `structs` modules into the module generated for `node`, if they exist.
+///
+/// Generated modules are nested one level deeper than their node (e.g. within `components`), so
+/// the import has to walk up one more level than the node's path.
+pub fn types_import<T: Clone + Debug>(node: &TreeNode<T>, types: TypeDefinitions) -> TokenStream {
+    let supers: Vec<_> = std::iter::repeat(quote! { super })
+        .take(node.path.len() + 1)
+        .collect();
+
+    let enums = (!types.enums.is_empty()).then(|| {
+        quote! {
+            #[allow(unused_imports)]
+            use #(#supers::)* enums;
+        }
+    });
+    let structs = (!types.structs.is_empty()).then(|| {
+        quote! {
+            #[allow(unused_imports)]
+            use #(#supers::)* structs;
+        }
+    });
+    quote! {
+        #enums
+        #structs
+    }
+}
+
 /// Converts a tree to a token stream.
 pub fn tree_to_token_stream<
     T: Clone + Debug,
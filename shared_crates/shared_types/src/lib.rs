@@ -1,9 +1,15 @@
 use paste::paste;
 
 /// A mapping from enum names to Rust types. Instantiate this with a macro that takes `$(($value:ident, $type:ty)),*`.
+///
+/// These are the types that can be named as a component type in `ambient.toml`. The runtime also
+/// stores values with types that cannot be named; see [stored_component_definitions].
 #[macro_export]
 macro_rules! primitive_component_definitions {
     ($macro_to_instantiate:ident) => {
+        $crate::primitive_component_definitions!($macro_to_instantiate;);
+    };
+    ($macro_to_instantiate:ident; $($internal:tt)*) => {
         $macro_to_instantiate!(
             (Empty, ()),
             (Bool, bool),
@@ -28,10 +34,23 @@ macro_rules! primitive_component_definitions {
             (ProceduralTextureHandle, ProceduralTextureHandle),
             (ProceduralSamplerHandle, ProceduralSamplerHandle),
             (ProceduralMaterialHandle, ProceduralMaterialHandle)
+            $($internal)*
         );
     };
 }
 
+/// The [primitive_component_definitions], followed by the types that are only used to store values of
+/// types defined in `ambient.toml`. The latter cannot be named as a component type.
+///
+/// Instantiate this with a macro that takes
+/// `$(($value:ident, $type:ty)),*; $(($internal_value:ident, $internal_type:ty)),*`.
+#[macro_export]
+macro_rules! stored_component_definitions {
+    ($macro_to_instantiate:ident) => {
+        $crate::primitive_component_definitions!($macro_to_instantiate; ; (Struct, StructValue));
+    };
+}
+
 #[macro_export]
 macro_rules! procedural_storage_handle_definitions {
     ($macro_to_instantiate:ident) => {
@@ -881,3 +900,10 @@ macro_rules! make_procedural_storage_handles {
 }
 
 procedural_storage_handle_definitions!(make_procedural_storage_handles);
+
+/// The serialized fields of a struct defined in `ambient.toml`.
+///
+/// The runtime does not know the layout of these structs; their values are only interpreted by the
+/// code generated for guests.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StructValue(pub Vec<u8>);
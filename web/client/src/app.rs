@@ -20,6 +20,7 @@ pub fn MainApp(_hooks: &mut Hooks) -> Element {
         GameClientView {
             url,
             user_id: friendly_id(),
+            credential: None,
             systems_and_resources: cb(|| {
                 let mut resources = Entity::new();
 